@@ -0,0 +1,290 @@
+use core::pos::BiPos;
+
+pub use ir::hir::{AnnotationArg, BinaryOp, ReceiverKind, UnaryOp, Visibility};
+
+///The syntax tree of a file, as produced by the parser.
+///Names are not resolved yet: a `Type` is whatever was written, and a `Name` expression may
+///refer to a local or to a top level declaration. Both get resolved when lowering to HIR.
+#[derive(Debug, Clone)]
+pub struct Module {
+    pub name: String,
+    pub items: Vec<Item>,
+}
+
+///An `@name(args)` annotation in front of a declaration.
+#[derive(Debug, Clone)]
+pub struct Annotation {
+    pub name: String,
+    pub args: Vec<AnnotationArg>,
+    pub pos: BiPos,
+}
+
+///A declaration along with the annotations and visibility modifier in front of it.
+#[derive(Debug, Clone)]
+pub struct Item {
+    pub kind: ItemKind,
+    pub annotations: Vec<Annotation>,
+    ///The modifier and its position, if one was written
+    pub visibility: Option<(Visibility, BiPos)>,
+    pub pos: BiPos,
+}
+
+#[derive(Debug, Clone)]
+pub enum ItemKind {
+    ///A `val` or `var` declaration.
+    Property {
+        name: String,
+        mutable: bool,
+        ty: Option<Type>,
+        value: Expr,
+    },
+    ///A `const` declaration, whose value is computed at compile time.
+    Const {
+        name: String,
+        ty: Option<Type>,
+        value: Expr,
+    },
+    Fn(FnDecl),
+    ///A `native fun` declaration, which never has a body.
+    NativeFn(FnDecl),
+    Struct {
+        name: String,
+        type_params: Vec<TypeParam>,
+        fields: Vec<Field>,
+    },
+    Interface {
+        name: String,
+        methods: Vec<Item>,
+    },
+    ///An impl block, of an interface if one is named.
+    Impl {
+        interface: Option<String>,
+        self_type: Type,
+        methods: Vec<Item>,
+    },
+    ///A type alias such as `type UserId = Int`, which stands for the aliased type wherever it is used.
+    TypeAlias {
+        name: String,
+        ty: Type,
+    },
+    ///A newtype such as `newtype Meters(Float)`, a distinct type wrapping a value of another one.
+    Newtype {
+        name: String,
+        ty: Type,
+    },
+    ///An `import geometry`, which makes the public declarations of another module usable in this one.
+    Import(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct FnDecl {
+    pub name: String,
+    pub type_params: Vec<TypeParam>,
+    ///The type an extension function such as `fun String.shout()` is declared on
+    pub receiver: Option<Type>,
+    pub params: Vec<Param>,
+    ///The declared return type, `Unit` when left out
+    pub return_type: Option<Type>,
+    ///The statements of the body, or `None` for a required interface method or a native function
+    pub body: Option<Vec<Stmt>>,
+}
+
+///A generic type parameter such as `T: Show + Eq`.
+#[derive(Debug, Clone)]
+pub struct TypeParam {
+    pub name: String,
+    pub bounds: Vec<String>,
+    pub pos: BiPos,
+}
+
+#[derive(Debug, Clone)]
+pub struct Param {
+    pub name: String,
+    pub kind: ParamKind,
+    pub pos: BiPos,
+}
+
+#[derive(Debug, Clone)]
+pub enum ParamKind {
+    ///`self`, `&self` or `&mut self`.
+    Receiver(ReceiverKind),
+    ///A param such as `x: Int = 0` or `vararg xs: Int`.
+    Typed {
+        ty: Type,
+        default: Option<Expr>,
+        vararg: bool,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct Field {
+    pub name: String,
+    pub ty: Type,
+    pub visibility: Option<(Visibility, BiPos)>,
+    pub pos: BiPos,
+}
+
+#[derive(Debug, Clone)]
+pub struct Stmt {
+    pub kind: StmtKind,
+    pub pos: BiPos,
+}
+
+#[derive(Debug, Clone)]
+pub enum StmtKind {
+    ///A local variable such as `let mut x: Int = 1`.
+    Let {
+        name: String,
+        name_pos: BiPos,
+        mutable: bool,
+        ty: Option<Type>,
+        value: Expr,
+    },
+    ///A loop such as `for x in xs { ... }`.
+    For {
+        binding: String,
+        binding_pos: BiPos,
+        iterable: Expr,
+        body: Vec<Stmt>,
+    },
+    ///A conditional such as `if x > 0 { ... } else { ... }`.
+    ///An `else if` is an `else` branch holding a single `If` statement.
+    If {
+        cond: Expr,
+        then: Vec<Stmt>,
+        else_: Option<Vec<Stmt>>,
+    },
+    ///A loop such as `while x < 10 { ... }`.
+    While {
+        cond: Expr,
+        body: Vec<Stmt>,
+    },
+    ///A `loop { ... }`, which only ends with a `break`.
+    Loop(Vec<Stmt>),
+    Break,
+    Continue,
+    ///A `return`, with the returned value if one is given.
+    Return(Option<Expr>),
+    ///A block of statements with its own scope, such as `{ let x = 1 }`.
+    Block(Vec<Stmt>),
+    ///A declaration nested in a function body.
+    Item(Item),
+    Expr(Expr),
+}
+
+#[derive(Debug, Clone)]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub pos: BiPos,
+}
+
+#[derive(Debug, Clone)]
+pub enum ExprKind {
+    Integer(i32),
+    Float(f32),
+    String(String),
+    Bool(bool),
+    ///A name, which is either a local or something declared at the top level.
+    Name(String),
+    Call {
+        callee: Box<Expr>,
+        args: Vec<Expr>,
+    },
+    MethodCall {
+        receiver: Box<Expr>,
+        name: String,
+        args: Vec<Expr>,
+    },
+    ///An argument given by the name of its param, as in `f(x = 1)`.
+    ///These are only found among the arguments of a call.
+    NamedArg {
+        name: String,
+        value: Box<Expr>,
+    },
+    Field {
+        receiver: Box<Expr>,
+        name: String,
+    },
+    Array(Vec<Expr>),
+    Index {
+        collection: Box<Expr>,
+        index: Box<Expr>,
+    },
+    Slice {
+        collection: Box<Expr>,
+        start: Option<Box<Expr>>,
+        end: Option<Box<Expr>>,
+    },
+    Range {
+        start: Box<Expr>,
+        end: Box<Expr>,
+        inclusive: bool,
+    },
+    ///Propagation of the error of a result with `?`, as in `parse(s)?`.
+    Try(Box<Expr>),
+    Binary {
+        op: BinaryOp,
+        lhs: Box<Expr>,
+        rhs: Box<Expr>,
+    },
+    Unary {
+        op: UnaryOp,
+        operand: Box<Expr>,
+    },
+    ///An assignment such as `x = 1` or `p.x = 1`, whose target is a name or a field.
+    ///It is parsed like an expression, but can only be used as a statement.
+    Assign {
+        target: Box<Expr>,
+        value: Box<Expr>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct Type {
+    pub kind: TypeKind,
+    pub pos: BiPos,
+}
+
+#[derive(Debug, Clone)]
+pub enum TypeKind {
+    ///A named type such as `Int`, `T`, `Self` or `List<T>`.
+    Named { name: String, args: Vec<Type> },
+    ///An array type such as `[Int; 4]` or `[Int; SIZE]`.
+    Array(Box<Type>, ArrayLen),
+    ///A dynamically dispatched interface such as `dyn Show`.
+    Dyn(String),
+}
+
+///The length of an array type, either written out or the name of a constant of the module holding it.
+#[derive(Debug, Clone)]
+pub enum ArrayLen {
+    Literal(usize),
+    Const(String),
+}
+
+impl std::fmt::Display for ArrayLen {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArrayLen::Literal(len) => write!(f, "{}", len),
+            ArrayLen::Const(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+impl std::fmt::Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            TypeKind::Named { name, args } if args.is_empty() => write!(f, "{}", name),
+            TypeKind::Named { name, args } => {
+                let args = args
+                    .iter()
+                    .map(|a| a.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                write!(f, "{}<{}>", name, args)
+            }
+            TypeKind::Array(element, len) => write!(f, "[{}; {}]", element, len),
+            TypeKind::Dyn(interface) => write!(f, "dyn {}", interface),
+        }
+    }
+}
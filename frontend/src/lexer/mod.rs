@@ -12,13 +12,96 @@ lazy_static! {
         m.insert("let", tokens::TokenType::KwLet);
         m.insert("val", tokens::TokenType::KwVal);
         m.insert("var", tokens::TokenType::KwVar);
+        m.insert("const", tokens::TokenType::KwConst);
         m.insert("mut", tokens::TokenType::KwMut);
         m.insert("native", tokens::TokenType::KwNative);
+        m.insert("vararg", tokens::TokenType::KwVararg);
+        m.insert("public", tokens::TokenType::KwPublic);
+        m.insert("internal", tokens::TokenType::KwInternal);
+        m.insert("private", tokens::TokenType::KwPrivate);
         m.insert("fun", tokens::TokenType::KwFun);
+        m.insert("struct", tokens::TokenType::KwStruct);
+        m.insert("type", tokens::TokenType::KwType);
+        m.insert("newtype", tokens::TokenType::KwNewtype);
+        m.insert("interface", tokens::TokenType::KwInterface);
+        m.insert("trait", tokens::TokenType::KwInterface);
+        m.insert("impl", tokens::TokenType::KwImpl);
+        m.insert("import", tokens::TokenType::KwImport);
+        m.insert("for", tokens::TokenType::KwFor);
+        m.insert("in", tokens::TokenType::KwIn);
+        m.insert("if", tokens::TokenType::KwIf);
+        m.insert("else", tokens::TokenType::KwElse);
+        m.insert("while", tokens::TokenType::KwWhile);
+        m.insert("loop", tokens::TokenType::KwLoop);
+        m.insert("break", tokens::TokenType::KwBreak);
+        m.insert("continue", tokens::TokenType::KwContinue);
+        m.insert("return", tokens::TokenType::KwReturn);
+        m.insert("dyn", tokens::TokenType::KwDyn);
+        m.insert("true", tokens::TokenType::KwTrue);
+        m.insert("false", tokens::TokenType::KwFalse);
         m
     };
 }
 
+///The token a single character stands for when it separates other tokens.
+pub(crate) fn delimiter(c: char) -> Option<tokens::TokenType> {
+    match c {
+        '=' => Some(tokens::TokenType::Equal),
+        '(' => Some(tokens::TokenType::LParen),
+        ')' => Some(tokens::TokenType::RParen),
+        ']' => Some(tokens::TokenType::RBracket),
+        '[' => Some(tokens::TokenType::LBracket),
+        '{' => Some(tokens::TokenType::LCurly),
+        '}' => Some(tokens::TokenType::RCurly),
+        '|' => Some(tokens::TokenType::Pipe),
+        '/' => Some(tokens::TokenType::Slash),
+        '?' => Some(tokens::TokenType::QMark),
+        '\\' => Some(tokens::TokenType::Backslash),
+        ';' => Some(tokens::TokenType::Semicolon),
+        ':' => Some(tokens::TokenType::Colon),
+        '\'' => Some(tokens::TokenType::Apost),
+        '"' => Some(tokens::TokenType::Quote),
+        '>' => Some(tokens::TokenType::RAngle),
+        '<' => Some(tokens::TokenType::LAngle),
+        '.' => Some(tokens::TokenType::Dot),
+        ',' => Some(tokens::TokenType::Comma),
+        '-' => Some(tokens::TokenType::Minus),
+        '+' => Some(tokens::TokenType::Plus),
+        '_' => Some(tokens::TokenType::Underscore),
+        '*' => Some(tokens::TokenType::Star),
+        '%' => Some(tokens::TokenType::Percent),
+        '$' => Some(tokens::TokenType::Dollar),
+        '#' => Some(tokens::TokenType::Hash),
+        '@' => Some(tokens::TokenType::At),
+        '!' => Some(tokens::TokenType::Bang),
+        '&' => Some(tokens::TokenType::And),
+        '^' => Some(tokens::TokenType::Caret),
+        '`' => Some(tokens::TokenType::Tick),
+        _ => None,
+    }
+}
+
+///The operator token spelled by `c` followed by `next`, such as `==` or `&&`, if they make one.
+pub(crate) fn operator(c: char, next: Option<char>) -> Option<(tokens::TokenType, &'static str)> {
+    match (c, next?) {
+        ('=', '=') => Some((tokens::TokenType::EqualEqual, "==")),
+        ('!', '=') => Some((tokens::TokenType::BangEqual, "!=")),
+        ('<', '=') => Some((tokens::TokenType::LAngleEqual, "<=")),
+        ('>', '=') => Some((tokens::TokenType::RAngleEqual, ">=")),
+        ('&', '&') => Some((tokens::TokenType::AndAnd, "&&")),
+        ('|', '|') => Some((tokens::TokenType::PipePipe, "||")),
+        _ => None,
+    }
+}
+
+///The keyword token `identifier` spells, or `Identifier` if it isn't a keyword.
+pub(crate) fn keyword(identifier: &str) -> tokens::TokenType {
+    match IDENT_MAP.get(identifier) {
+        Some(token_type) => *token_type,
+        None => tokens::TokenType::Identifier,
+    }
+}
+
 pub struct Lexer<'a> {
     input: &'a str,
     source: Option<&'a str>,
@@ -75,6 +158,14 @@ impl<'a> Lexer<'a> {
         }
     }
 
+    ///The character after the one `peek` returns.
+    fn peek_next(&mut self) -> Option<char>{
+        match self.source{
+            Some(src) => src.chars().nth(1),
+            None => None
+        }
+    }
+
     fn advance(&mut self) -> Option<char> {
         match self.source{
             Some(src) => {
@@ -101,54 +192,19 @@ impl<'a> Lexer<'a> {
     }
 
     fn is_delimiter(&self, c: char) -> Option<tokens::TokenType> {
-        match c {
-            '=' => Some(tokens::TokenType::Equal),
-            '(' => Some(tokens::TokenType::LParen),
-            ')' => Some(tokens::TokenType::RParen),
-            ']' => Some(tokens::TokenType::RBracket),
-            '[' => Some(tokens::TokenType::LBracket),
-            '{' => Some(tokens::TokenType::LCurly),
-            '}' => Some(tokens::TokenType::RCurly),
-            '|' => Some(tokens::TokenType::Pipe),
-            '/' => Some(tokens::TokenType::Slash),
-            '?' => Some(tokens::TokenType::QMark),
-            '\\' => Some(tokens::TokenType::Backslash),
-            ';' => Some(tokens::TokenType::Semicolon),
-            ':' => Some(tokens::TokenType::Colon),
-            '\'' => Some(tokens::TokenType::Apost),
-            '"' => Some(tokens::TokenType::Quote),
-            '>' => Some(tokens::TokenType::RAngle),
-            '<' => Some(tokens::TokenType::LAngle),
-            '.' => Some(tokens::TokenType::Dot),
-            ',' => Some(tokens::TokenType::Comma),
-            '-' => Some(tokens::TokenType::Minus),
-            '+' => Some(tokens::TokenType::Plus),
-            '_' => Some(tokens::TokenType::Underscore),
-            '*' => Some(tokens::TokenType::Star),
-            '%' => Some(tokens::TokenType::Percent),
-            '$' => Some(tokens::TokenType::Dollar),
-            '#' => Some(tokens::TokenType::Hash),
-            '@' => Some(tokens::TokenType::At),
-            '!' => Some(tokens::TokenType::Bang),
-            '&' => Some(tokens::TokenType::And),
-            '^' => Some(tokens::TokenType::Caret),
-            '`' => Some(tokens::TokenType::Tick),
-            _ => None,
-        }
+        delimiter(c)
     }
 
     fn is_keyword(&self, identifier: &str) -> tokens::TokenType {
-        match IDENT_MAP.get(identifier) {
-            Some(token_type) => *token_type,
-            None => tokens::TokenType::Identifier,
-        }
+        keyword(identifier)
     }
 
     fn number(&mut self) -> Option<tokens::LexerToken<'a>> {
         let start_idx = self.char_idx;
         let mut is_float = false;
         while let Some(c) = self.peek() {
-            if c == '.' {
+            //A dot only makes a float if a digit follows it, so `1..3` stays a range
+            if c == '.' && !is_float && self.peek_next().is_some_and(|c| c.is_ascii_digit()) {
                 is_float = true;
                 self.advance_end();
             } else if c.is_digit(10) {
@@ -236,14 +292,19 @@ impl<'a> Lexer<'a> {
     }
 
     fn skip_whitespace(&mut self){
-        while match self.peek(){
-            Some(c) if c == '\n' => {
-                self.current_pos.next_line();
-                true
+        loop {
+            match self.peek(){
+                Some('\n') => self.current_pos.next_line(),
+                Some(c) if c.is_whitespace() => {}
+                //A line comment is skipped up to the newline ending it
+                Some('/') if self.peek_next() == Some('/') => {
+                    while !matches!(self.peek(), Some('\n') | None) {
+                        self.advance();
+                    }
+                    continue;
+                }
+                _ => break,
             }
-            Some(c) if c.is_whitespace() => true,
-            _ => false
-        }{
             self.advance();
         }
         self.current_pos.start = self.current_pos.end;
@@ -290,6 +351,31 @@ impl<'a> Lexer<'a> {
                         }
                     }
                     c if c.is_digit(10) => return self.number(),
+                    '.' if self.peek() == Some('.') => {
+                        self.advance_end();
+                        if self.peek() == Some('=') {
+                            self.advance_end();
+                            return Some(tokens::LexerToken {
+                                data: tokens::TokenData::Str("..="),
+                                type_: tokens::TokenType::DotDotEqual,
+                                pos: self.current_pos,
+                            });
+                        }
+                        return Some(tokens::LexerToken {
+                            data: tokens::TokenData::Str(".."),
+                            type_: tokens::TokenType::DotDot,
+                            pos: self.current_pos,
+                        });
+                    }
+                    c if operator(c, self.peek()).is_some() => {
+                        let (type_, op) = operator(c, self.peek()).unwrap();
+                        self.advance_end();
+                        return Some(tokens::LexerToken {
+                            data: tokens::TokenData::Str(op),
+                            type_,
+                            pos: self.current_pos,
+                        });
+                    }
                     c if self.is_delimiter(c).is_some() => {
                         return Some(tokens::LexerToken {
                             data: tokens::TokenData::String(c.to_string()),
@@ -14,6 +14,8 @@ pub enum TokenType {
 
     Comma,
     Dot,
+    DotDot,
+    DotDotEqual,
     Semicolon,
     Pipe,
 
@@ -29,6 +31,12 @@ pub enum TokenType {
 
     Bang,
     Equal,
+    EqualEqual,
+    BangEqual,
+    LAngleEqual,
+    RAngleEqual,
+    AndAnd,
+    PipePipe,
 
     Colon,
     Apost,
@@ -46,6 +54,7 @@ pub enum TokenType {
 
     KwVal,
     KwVar,
+    KwConst,
 
     KwFun,
 
@@ -53,16 +62,26 @@ pub enum TokenType {
     KwMut,
 
     KwStruct,
+    KwType,
+    KwNewtype,
+    KwInterface,
+    KwImpl,
+    KwDyn,
     KwReturn,
     KwMod,
+    KwImport,
     KwNative,
+    KwVararg,
     KwPublic,
+    KwInternal,
+    KwPrivate,
 
     KwIf,
     KwElse,
     KwLoop,
     KwWhile,
     KwFor,
+    KwIn,
     KwBreak,
     KwContinue,
 
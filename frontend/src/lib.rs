@@ -4,76 +4,320 @@ use std::path::Path;
 pub mod lexer;
 use lexer::tokens;
 
+pub mod lower;
 pub mod parser;
-use core::pos::BiPos;
-use ir::hir::HIR;
+pub mod syntax;
+use core::pos::{BiPos, Position};
+use ir::{
+    encoding,
+    hir::{HIRInstruction, VerifyError},
+    interface::ModuleInterface,
+    pass::{PassError, PassManager},
+    text::{self, TextError},
+};
 use parser::Parser;
+use syntax::{kind::SyntaxKind, red::SyntaxElement, SyntaxTree};
 
-use std::sync::mpsc::channel;
+use std::collections::HashMap;
+use std::sync::mpsc::{channel, Sender};
 
 use notices::{Notice, NoticeLevel};
-use typeck::TypeckVM;
+use lowering::{Lowerer, Ownership};
+use typeck::{Resolver, TypeckVM};
 
-pub struct Driver;
+///Compiles modules, running the passes of the compiler over each one after it is parsed.
+#[derive(Debug, Default)]
+pub struct Driver {
+    ///The passes before which each module is printed
+    pub dump_before: Vec<String>,
+    ///The passes after which each module is printed
+    pub dump_after: Vec<String>,
+}
+
+///Whether the module at `path` is written as HIR in its text syntax rather than in Beagle.
+fn is_hir_text(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension == "hir")
+}
+
+///The names of the modules imported by `source`, the source of the module at `path`, in the order of their imports.
+fn imported_modules(path: &Path, source: &str) -> Vec<String> {
+    if is_hir_text(path) {
+        //A module that can't be read imports nothing, and reports why when it is compiled
+        return text::parse_hir(source)
+            .map(|module| {
+                module
+                    .instructions
+                    .into_iter()
+                    .filter_map(|ins| match ins {
+                        HIRInstruction::Import(name) => Some(name),
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+    }
+    SyntaxTree::parse(source)
+        .root()
+        .children()
+        .filter(|item| item.kind() == SyntaxKind::Import)
+        .filter_map(|import| {
+            import.children_with_tokens().find_map(|element| match element {
+                SyntaxElement::Token(token) if token.kind() == SyntaxKind::Token(tokens::TokenType::Identifier) => {
+                    Some(token.text().to_string())
+                }
+                _ => None,
+            })
+        })
+        .collect()
+}
+
+///The ways in which `module`, as it came out of `pass`, is malformed, as notices to report.
+///This is only checked in debug builds.
+pub(crate) fn verify_hir(module: &ir::hir::Module, pass: &str) -> Vec<Notice> {
+    if !cfg!(debug_assertions) {
+        return Vec::new();
+    }
+    match ir::hir::verify(module) {
+        Ok(()) => Vec::new(),
+        Err(errors) => malformed_hir(module, pass, errors),
+    }
+}
+
+///The notice reporting `error`, which the HIR text of the module `file` has.
+fn unreadable_hir(file: &str, source: &str, error: TextError) -> Notice {
+    let len = source.lines().nth(error.line - 1).map_or(0, |line| line.len());
+    Notice {
+        from: "HIR reader".to_string(),
+        msg: error.msg,
+        pos: BiPos {
+            start: Position(error.line, 1),
+            end: Position(error.line, len + 1),
+        },
+        file: file.to_string(),
+        level: NoticeLevel::Error,
+        help: None,
+    }
+}
+
+///Notices reporting `errors`, the ways in which `module` is malformed after `pass`.
+///Malformed HIR is a bug of the compiler rather than of the module, which would otherwise show up
+///as confusing errors in the passes after it.
+fn malformed_hir(module: &ir::hir::Module, pass: &str, errors: Vec<VerifyError>) -> Vec<Notice> {
+    malformed(&format!("HIR verifier after {}", pass), &module.name, &module.positions, errors)
+}
+
+///Notices reporting `errors`, the ways in which the MIR `module` is malformed after `pass`.
+fn malformed_mir(module: &ir::mir::Module, pass: &str, errors: Vec<VerifyError>) -> Vec<Notice> {
+    malformed(&format!("MIR verifier after {}", pass), &module.name, &module.positions, errors)
+}
+
+///Notices from the verifier `from` reporting `errors` in the module `name`, whose instructions are at `positions`.
+fn malformed(from: &str, name: &str, positions: &[BiPos], errors: Vec<VerifyError>) -> Vec<Notice> {
+    errors
+        .into_iter()
+        .map(|error| Notice {
+            from: from.to_string(),
+            msg: error.msg,
+            pos: positions.get(error.idx).copied().unwrap_or_default(),
+            file: name.to_string(),
+            level: NoticeLevel::Error,
+            help: None,
+        })
+        .collect()
+}
 
 impl Driver {
-    pub async fn begin_parsing(&self, path: &Path) -> ir::hir::Module {
+    ///Compile the module at `path` to HIR and MIR and write its interface next to it, in a `.bmi` file.
+    ///The modules it imports are found next to it, and are only compiled again when their interface is missing or stale.
+    pub fn compile(&self, path: &Path) -> std::result::Result<(ir::hir::Module, ir::mir::Module), String> {
+        let (module, lowered, _) = self.compile_module(path, &mut Vec::new(), &mut HashMap::new())?;
+        Ok((module, lowered))
+    }
+
+    ///Compile the module at `path` against the interfaces of the modules it imports, and write its own interface.
+    ///`importing` holds the modules whose imports are being resolved, and `interfaces` the ones known to be up to date.
+    fn compile_module(
+        &self,
+        path: &Path,
+        importing: &mut Vec<String>,
+        interfaces: &mut HashMap<String, ModuleInterface>,
+    ) -> std::result::Result<(ir::hir::Module, ir::mir::Module, ModuleInterface), String> {
+        let source = std::fs::read_to_string(path).map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+        let imports = self.imports(path, &source, importing, interfaces)?;
+        let (module, lowered) = futures::executor::block_on(self.begin_parsing(path, &source, imports.iter().cloned().map(|i| (i.name.clone(), i)).collect()))?;
+        //Nothing comes out of the passes when the module has errors, which have been reported already
+        if module.instructions.is_empty() {
+            return Err(format!("Could not compile {}", path.display()));
+        }
+        let file = path.file_name().unwrap().to_string_lossy();
+        let interface = ModuleInterface::of(&module, &file, &source, &imports.iter().collect::<Vec<&ModuleInterface>>()).map_err(|e| e.msg)?;
+        let bmi = path.with_extension("bmi");
+        encoding::write(&interface, &bmi).map_err(|e| format!("Could not write {}: {}", bmi.display(), e))?;
+        Ok((module, lowered, interface))
+    }
+
+    ///The up to date interfaces of the modules imported by `source`, the source of the module at `path`.
+    fn imports(
+        &self,
+        path: &Path,
+        source: &str,
+        importing: &mut Vec<String>,
+        interfaces: &mut HashMap<String, ModuleInterface>,
+    ) -> std::result::Result<Vec<ModuleInterface>, String> {
+        let name = path.file_stem().unwrap().to_string_lossy().to_string();
+        importing.push(name);
+        let ret = imported_modules(path, source)
+            .into_iter()
+            .map(|import| {
+                //An imported module is in the same directory and has the same extension as the module importing it
+                let mut import_path = path.with_file_name(&import);
+                if let Some(extension) = path.extension() {
+                    import_path.set_extension(extension);
+                }
+                self.interface(&import, &import_path, importing, interfaces)
+            })
+            .collect();
+        importing.pop();
+        ret
+    }
+
+    ///The interface of the module `name` with its source at `path`, compiling the module again unless its `.bmi` file is up to date.
+    fn interface(
+        &self,
+        name: &str,
+        path: &Path,
+        importing: &mut Vec<String>,
+        interfaces: &mut HashMap<String, ModuleInterface>,
+    ) -> std::result::Result<ModuleInterface, String> {
+        if let Some(interface) = interfaces.get(name) {
+            return Ok(interface.clone());
+        }
+        if let Some(start) = importing.iter().position(|module| module == name) {
+            let mut cycle = importing[start..].to_vec();
+            cycle.push(name.to_string());
+            return Err(format!("Module {} imports itself: {}", name, cycle.join(" -> ")));
+        }
+        let source = std::fs::read_to_string(path).map_err(|e| format!("Could not read the imported module {} at {}: {}", name, path.display(), e))?;
+        let imports = self
+            .imports(path, &source, importing, interfaces)?
+            .into_iter()
+            .map(|import| (import.name, import.hash))
+            .collect::<Vec<(String, u64)>>();
+        let interface = match encoding::read::<ModuleInterface>(&path.with_extension("bmi")) {
+            Ok(interface) if interface.is_fresh(&source, &imports) => interface,
+            _ => self.compile_module(path, importing, interfaces)?.2,
+        };
+        interfaces.insert(name.to_string(), interface.clone());
+        Ok(interface)
+    }
+
+    ///The passes run over a module importing the modules whose interfaces are in `imports`, reporting through `notice_tx`.
+    fn passes(&self, notice_tx: Sender<Option<Notice>>, imports: HashMap<String, ModuleInterface>) -> std::result::Result<PassManager, String> {
+        let mut passes = PassManager::new();
+        passes.add_transform("resolve", Box::new(Resolver::new(notice_tx.clone(), imports.clone())));
+        passes.add_transform("typeck", Box::new(TypeckVM::new(notice_tx.clone(), imports.clone())));
+        passes.set_lowering("lower", Box::new(Lowerer::new(imports)));
+        passes.add_mir_pass("ownership", Box::new(Ownership::new(notice_tx)));
+        for pass in self.dump_before.iter() {
+            passes.dump_before(pass)?;
+        }
+        for pass in self.dump_after.iter() {
+            passes.dump_after(pass)?;
+        }
+        Ok(passes)
+    }
+
+    ///Parse and lower `source`, the source of the module at `path`, or read it if it is HIR text,
+    ///then run the passes over it against the interfaces of the modules it imports, which lower it to MIR.
+    ///The HIR and MIR modules have no instructions if the module could not be read or a pass failed.
+    pub async fn begin_parsing(
+        &self,
+        path: &Path,
+        source: &str,
+        imports: HashMap<String, ModuleInterface>,
+    ) -> std::result::Result<(ir::hir::Module, ir::mir::Module), String> {
         let name = path.file_stem().unwrap().to_str().unwrap().to_string();
-        let instr = std::fs::read_to_string(&path).unwrap();
 
-        let (token_tx, token_rx) = channel::<tokens::LexerToken>();
-        let (ir_tx, ir_rx) = channel::<Option<HIR>>();
+        //Notes about the declarations of an imported module are shown against its own source
+        let sources = imports
+            .values()
+            .filter_map(|import| {
+                let source = std::fs::read_to_string(path.with_file_name(&import.file)).ok()?;
+                Some((import.name.clone(), source))
+            })
+            .collect::<HashMap<String, String>>();
         let (notice_tx, notice_rx) = channel::<Option<Notice>>();
-        let (typeck_tx, typeck_rx) = channel::<Option<HIR>>();
-        
-        let mut lexer = lexer::Lexer::new(instr.as_str(), token_tx.clone()).unwrap();
-        let parser_task = Parser::parse(name.clone(), ir_tx, token_rx, notice_tx.clone());
-        let mut tir = ir::hir::Module::new(name.clone());
-        let typeck_task = TypeckVM::start_checking(name.clone(), ir_rx, notice_tx.clone(), typeck_tx);
+        let mut passes = self.passes(notice_tx.clone(), imports)?;
+
+        let read_task = {
+            let (name, notice_tx) = (name.clone(), notice_tx.clone());
+            async move {
+                //Written by hand rather than lowered by the compiler, HIR text is always verified
+                if is_hir_text(path) {
+                    let notices = match text::parse_hir_at_lines(source) {
+                        Ok(module) => match ir::hir::verify(&module) {
+                            Ok(()) => return Ok(module),
+                            Err(errors) => malformed_hir(&module, "reading", errors),
+                        },
+                        Err(error) => vec![unreadable_hir(&name, source, error)],
+                    };
+                    for notice in notices {
+                        notice_tx.send(Some(notice)).expect("Failed to send a notice through the notice channel.");
+                    }
+                    return Ok(ir::hir::Module::new(name));
+                }
+                let (token_tx, token_rx) = channel::<tokens::LexerToken>();
+                let mut lexer = lexer::Lexer::new(source, token_tx.clone()).unwrap();
+                let (lexer_result, parser_result) =
+                    futures::join!(lexer.start_tokenizing(), Parser::parse(name, token_rx, notice_tx.clone()));
+                lexer_result.unwrap();
+                parser_result
+            }
+        };
 
-        let lexer_task = lexer.start_tokenizing();
+        //The passes are dropped along with their senders once they have run, so that the notice channel then closes.
+        let pass_task = async move {
+            let mut module = read_task.await?;
+            if module.instructions.is_empty() {
+                return Ok((module, ir::mir::Module::new(name)));
+            }
+            match passes.run(&mut module) {
+                Ok(Some(lowered)) => return Ok((module, lowered)),
+                Ok(None) | Err(PassError::Failed(_)) => {}
+                Err(PassError::Malformed(pass, errors)) => {
+                    for notice in malformed_hir(&module, &pass, errors) {
+                        notice_tx.send(Some(notice)).expect("Failed to send a notice through the notice channel.");
+                    }
+                }
+                Err(PassError::MalformedMir(pass, lowered, errors)) => {
+                    for notice in malformed_mir(&lowered, &pass, errors) {
+                        notice_tx.send(Some(notice)).expect("Failed to send a notice through the notice channel.");
+                    }
+                }
+            }
+            Ok::<_, String>((ir::hir::Module::new(name.clone()), ir::mir::Module::new(name)))
+        };
 
         let notice_task = async {
             loop {
                 match notice_rx.recv() {
                     Ok(Some(n)) => {
                         match n.level {
-                            NoticeLevel::Halt => break,
-                            _ => n.report(Some(instr.clone().as_str())),
+                            NoticeLevel::Halt => continue,
+                            _ => {
+                                let source = sources.get(&n.file).map_or(source, |source| source.as_str());
+                                n.report(Some(source))
+                            }
                         };
                     }
                     Ok(None) => continue,
-                    Err(m) => {
-                        println!(
-                            "An error occurred while receiving notice from parser: {:?}",
-                            m.to_string()
-                        );
-                        break;
-                    }
-                };
-            }
-        };
-
-        let ir_task = async {
-            while let Ok(Some(ir)) = typeck_rx.recv() {
-                match ir.ins {
-                    ir::hir::HIRInstruction::Halt => {
-                        tir.push(ir.pos, ir.sig, ir.ins);
-                        break
-                    },
-                    _ => tir.push(ir.pos, ir.sig, ir.ins),
+                    Err(_) => break,
                 };
             }
         };
-        let (lexer_result, parser_result, typeck_result, _, _) =
-            futures::join!(lexer_task, parser_task,typeck_task, notice_task, ir_task);
-        // let (lexer_result, parser_result, _, _) =
-        //     futures::join!(lexer_task, parser_task, notice_task, ir_task);
 
-        lexer_result.unwrap();
-        parser_result.unwrap();
-        typeck_result.unwrap();
+        let (pass_result, _) = futures::join!(pass_task, notice_task);
 
-        tir
+        pass_result
     }
 }
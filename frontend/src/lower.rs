@@ -0,0 +1,738 @@
+use ast::{ArrayLen, Expr, ExprKind, FnDecl, Item, ItemKind, Param, ParamKind, Stmt, StmtKind, Type, TypeKind, TypeParam};
+
+use ir::{
+    consteval::{ConstEvaluator, ConstValue},
+    hir::{HIRInstruction, Module, ReceiverKind, HIR},
+    type_signature::{CallingConvention, FunctionSignature, GenericParam, Parameter, PrimitiveType, StructSignature, TypeSignature},
+};
+
+use core::pos::BiPos as Position;
+use notices::{Notice, NoticeLevel};
+
+use std::{
+    collections::HashMap,
+    sync::mpsc::{channel, Sender},
+};
+
+type LowerResult = Result<(), ()>;
+
+///Lowers the syntax tree of a module to its flat HIR.
+///This is where names get resolved: written types become type signatures, and each name
+///used in an expression becomes either a local or a global reference.
+pub struct Lowering {
+    notice_tx: Sender<Option<Notice>>,
+    module: Module,
+    ///The generic type params in scope of the declaration being lowered.
+    type_params: Vec<GenericParam>,
+    ///The names of the params and local variables declared so far in the function being lowered.
+    locals: Vec<String>,
+    ///The number of loops around the statement being lowered, within the function it is in.
+    loops: usize,
+    ///The type of `self` in the interface or impl block being lowered.
+    self_type: Option<TypeSignature>,
+    ///The type aliases declared at the top level of the module, with the type each stands for.
+    aliases: HashMap<String, (Type, Position)>,
+    ///The values of the constants of the module that could be computed, which array lengths can name.
+    constants: HashMap<String, ConstValue>,
+}
+
+impl Lowering {
+    pub fn lower(module: &ast::Module, notice_tx: Sender<Option<Notice>>) -> Result<Module, ()> {
+        let mut lowering = Lowering {
+            notice_tx,
+            module: Module::new(module.name.clone()),
+            type_params: Vec::new(),
+            locals: Vec::new(),
+            loops: 0,
+            self_type: None,
+            aliases: HashMap::new(),
+            constants: HashMap::new(),
+        };
+        //Aliases can be used before they are declared, so they are all collected first
+        for item in module.items.iter() {
+            if let ItemKind::TypeAlias { name, ty } = &item.kind {
+                if lowering.aliases.insert(name.clone(), (ty.clone(), item.pos)).is_some() {
+                    lowering.error(format!("Type alias {} is declared more than once.", name), item.pos);
+                    return Err(());
+                }
+            }
+        }
+        //So are the constants array lengths can name
+        lowering.constants = lowering.evaluate_constants(module);
+        lowering.emit(
+            Position::default(),
+            TypeSignature::None,
+            HIRInstruction::Module(module.name.clone()),
+        );
+        for item in module.items.iter() {
+            lowering.item(item)?;
+        }
+        lowering.emit(Position::default(), TypeSignature::None, HIRInstruction::EndModule);
+        Ok(lowering.module)
+    }
+
+    ///The values of the constants of `module`, computed from the HIR of its constants and functions.
+    ///The errors of those are reported when they are lowered, and the constants failing to be computed by the type checker,
+    ///so a constant which can't be computed is only left out.
+    fn evaluate_constants(&self, module: &ast::Module) -> HashMap<String, ConstValue> {
+        let names = module.items.iter().filter_map(|item| match &item.kind {
+            ItemKind::Const { name, .. } => Some(name.as_str()),
+            _ => None,
+        });
+        let names = names.collect::<Vec<&str>>();
+        if names.is_empty() {
+            return HashMap::new();
+        }
+        let (notice_tx, _notice_rx) = channel();
+        let mut lowering = Lowering {
+            notice_tx,
+            module: Module::new(module.name.clone()),
+            type_params: Vec::new(),
+            locals: Vec::new(),
+            loops: 0,
+            self_type: None,
+            aliases: self.aliases.clone(),
+            constants: HashMap::new(),
+        };
+        for item in module.items.iter().filter(|item| matches!(item.kind, ItemKind::Const { .. } | ItemKind::Fn(_))) {
+            let len = lowering.module.instructions.len();
+            if lowering.item(item).is_err() {
+                let module = &mut lowering.module;
+                module.instructions.truncate(len);
+                module.signatures.truncate(len);
+                module.positions.truncate(len);
+                module.symbols.truncate(len);
+            }
+        }
+        let mut evaluator = ConstEvaluator::new(&lowering.module.instructions);
+        names.into_iter().filter_map(|name| Some((name.to_string(), evaluator.constant(name).ok()?))).collect()
+    }
+
+    fn emit(&mut self, pos: Position, sig: TypeSignature, ins: HIRInstruction) {
+        self.module.push(pos, sig, ins);
+    }
+
+    fn error(&self, msg: String, pos: Position) {
+        let notice = Notice {
+            from: "Lowering".to_string(),
+            msg,
+            pos,
+            file: self.module.name.clone(),
+            level: NoticeLevel::Error,
+            help: None,
+        };
+
+        if let Err(e) = self.notice_tx.send(Some(notice)) {
+            eprintln!(
+                "{}Lowering notice send error: {}{}",
+                core::ansi::Fg::BrightRed,
+                e,
+                core::ansi::Fg::Reset
+            );
+        }
+    }
+
+    ///Lower a declaration, preceded by its annotations and then its visibility.
+    fn item(&mut self, item: &Item) -> LowerResult {
+        for annotation in item.annotations.iter() {
+            self.emit(
+                annotation.pos,
+                TypeSignature::None,
+                HIRInstruction::Annotation(annotation.name.clone(), annotation.args.clone()),
+            );
+        }
+        if let Some((visibility, pos)) = item.visibility {
+            self.emit(pos, TypeSignature::None, HIRInstruction::Visibility(visibility));
+        }
+        let pos = item.pos;
+        match &item.kind {
+            ItemKind::Property { name, mutable, ty, value } => {
+                let sig = self.optional_type(ty)?;
+                self.emit(pos, sig, HIRInstruction::Property(name.clone(), *mutable));
+                self.expr(value)
+            }
+            ItemKind::Const { name, ty, value } => {
+                let sig = self.optional_type(ty)?;
+                self.emit(pos, sig, HIRInstruction::Const(name.clone()));
+                self.expr(value)
+            }
+            ItemKind::Fn(decl) => self.function(pos, decl),
+            ItemKind::NativeFn(decl) => self.native_function(pos, decl),
+            ItemKind::Struct { name, type_params, fields } => self.struct_declaration(pos, name, type_params, fields),
+            ItemKind::Interface { name, methods } => {
+                self.emit(pos, TypeSignature::None, HIRInstruction::Interface(name.clone()));
+                let self_type = TypeSignature::Param(GenericParam {
+                    name: "Self".to_string(),
+                    bounds: vec![name.clone()],
+                });
+                self.methods(self_type, methods)?;
+                self.emit(pos, TypeSignature::None, HIRInstruction::EndInterface);
+                Ok(())
+            }
+            ItemKind::Impl { interface, self_type, methods } => {
+                let self_type = self.type_(self_type)?;
+                self.emit(pos, self_type.clone(), HIRInstruction::Impl(interface.clone()));
+                self.methods(self_type, methods)?;
+                self.emit(pos, TypeSignature::None, HIRInstruction::EndImpl);
+                Ok(())
+            }
+            ItemKind::TypeAlias { name, ty } => {
+                if is_builtin_type(name) {
+                    self.error(format!("Cannot declare a type alias named {}, which is a builtin type.", name), pos);
+                    return Err(());
+                }
+                let sig = self.resolve_type(ty, &mut vec![name.clone()])?;
+                self.emit(pos, sig, HIRInstruction::TypeAlias(name.clone()));
+                Ok(())
+            }
+            ItemKind::Newtype { name, ty } => {
+                if is_builtin_type(name) || self.aliases.contains_key(name) {
+                    self.error(format!("Cannot declare a newtype named {}, which is already the name of a type.", name), pos);
+                    return Err(());
+                }
+                let sig = self.type_(ty)?;
+                self.emit(pos, sig, HIRInstruction::Newtype(name.clone()));
+                Ok(())
+            }
+            ItemKind::Import(name) => {
+                self.emit(pos, TypeSignature::None, HIRInstruction::Import(name.clone()));
+                Ok(())
+            }
+        }
+    }
+
+    ///Lower the methods of an interface or impl block, with `self_type` being the type of `self`.
+    fn methods(&mut self, self_type: TypeSignature, methods: &[Item]) -> LowerResult {
+        let outer_self_type = self.self_type.replace(self_type);
+        let ret = methods.iter().try_for_each(|method| self.item(method));
+        self.self_type = outer_self_type;
+        ret
+    }
+
+    fn function(&mut self, pos: Position, decl: &FnDecl) -> LowerResult {
+        let type_params = decl.type_params.iter().map(generic_param).collect::<Vec<GenericParam>>();
+        let mut fn_type_params = self.type_params.clone();
+        fn_type_params.extend(type_params.iter().cloned());
+        let outer_type_params = std::mem::replace(&mut self.type_params, fn_type_params);
+        let outer_locals = std::mem::take(&mut self.locals);
+        let outer_loops = std::mem::take(&mut self.loops);
+        let ret = self.function_rest(pos, decl, type_params);
+        self.type_params = outer_type_params;
+        self.locals = outer_locals;
+        self.loops = outer_loops;
+        ret
+    }
+
+    ///Lower a function once its type params are in scope.
+    ///A function without a body is a required interface method.
+    fn function_rest(&mut self, pos: Position, decl: &FnDecl, type_params: Vec<GenericParam>) -> LowerResult {
+        let extended = match &decl.receiver {
+            Some(receiver) => Some(self.extension_receiver(pos, decl, receiver)?),
+            None => None,
+        };
+        let (params, param_ir) = self.parameters(decl)?;
+        let function_sig = TypeSignature::Function(FunctionSignature{
+            type_parameters: type_params,
+            parameters: params,
+            variadic: is_variadic(decl),
+            return_type_signature: Box::new(self.return_type(decl)?),
+            calling_convention: CallingConvention::Beagle,
+        });
+        let receiver = match param_ir.first().map(|ir| &ir.ins) {
+            Some(HIRInstruction::SelfParam(receiver)) => Some(*receiver),
+            _ => None,
+        };
+        let body = match &decl.body {
+            Some(body) => body,
+            None => {
+                let name = decl.name.clone();
+                if let Some(param) = decl.params.iter().find(|param| has_default(param)) {
+                    self.error(format!("Parameter {} of interface method {} cannot have a default value without a body.", param.name, name), param.pos);
+                    return Err(());
+                }
+                return match receiver {
+                    Some(receiver) => {
+                        self.emit(pos, function_sig, HIRInstruction::InterfaceMethod(name, receiver));
+                        Ok(())
+                    }
+                    None => {
+                        self.error(format!("Interface method {} must take 'self' as its first parameter.", name), pos);
+                        Err(())
+                    }
+                };
+            }
+        };
+        self.emit(pos, function_sig, HIRInstruction::Fn(decl.name.clone()));
+        for param in decl.type_params.iter() {
+            self.emit(param.pos, TypeSignature::Param(generic_param(param)), HIRInstruction::TypeParam(param.name.clone()));
+        }
+        if let Some(receiver) = extended {
+            self.module.push_ir(receiver);
+        }
+        for (param, ir) in decl.params.iter().zip(param_ir) {
+            self.module.push_ir(ir);
+            if let ParamKind::Typed { default: Some(default), .. } = &param.kind {
+                self.emit(default.pos, TypeSignature::None, HIRInstruction::DefaultArg);
+                self.expr(default)?;
+            }
+            self.locals.push(param.name.clone());
+        }
+        for stmt in body.iter() {
+            self.stmt(stmt)?;
+        }
+        self.emit(pos, TypeSignature::None, HIRInstruction::EndFn);
+        Ok(())
+    }
+
+    ///The implicit receiver of an extension function, which is declared as its `self` param.
+    ///Extension functions can only be declared at the top level of a module, so they never take `self` themselves.
+    fn extension_receiver(&mut self, pos: Position, decl: &FnDecl, receiver: &Type) -> Result<HIR, ()> {
+        if self.self_type.is_some() {
+            self.error(
+                format!("Extension function {} cannot be declared inside an interface or impl block.", decl.name),
+                pos,
+            );
+            return Err(());
+        }
+        if let Some(param) = decl.params.iter().find(|param| matches!(param.kind, ParamKind::Receiver(_))) {
+            self.error(
+                format!("Extension function {} cannot take 'self', which already refers to the {} it extends.", decl.name, receiver),
+                param.pos,
+            );
+            return Err(());
+        }
+        let sig = self.type_(receiver)?;
+        self.locals.push("self".to_string());
+        Ok(HIR {
+            pos: receiver.pos,
+            sig,
+            ins: HIRInstruction::SelfParam(ReceiverKind::Value),
+            symbol: None,
+        })
+    }
+
+    fn native_function(&mut self, pos: Position, decl: &FnDecl) -> LowerResult {
+        if let Some(param) = decl.params.iter().find(|param| has_default(param) || is_vararg(param)) {
+            self.error(format!("Native function {} cannot give its parameter {} a default value or make it a vararg.", decl.name, param.name), param.pos);
+            return Err(());
+        }
+        let (params, _) = self.parameters(decl)?;
+        let sig = TypeSignature::Function(FunctionSignature{
+            type_parameters: Vec::new(),
+            parameters: params,
+            variadic: false,
+            return_type_signature: Box::new(self.return_type(decl)?),
+            calling_convention: CallingConvention::C,
+        });
+        self.emit(pos, sig, HIRInstruction::NativeFn(decl.name.clone()));
+        Ok(())
+    }
+
+    ///Resolve the params of a function.
+    ///Returns the params other than the receiver, and the IR declaring every param without its default value.
+    fn parameters(&mut self, decl: &FnDecl) -> Result<(Vec<Parameter>, Vec<HIR>), ()> {
+        let mut params = Vec::<Parameter>::new();
+        let mut param_ir = Vec::<HIR>::new();
+        for (i, param) in decl.params.iter().enumerate() {
+            let (sig, ins) = match &param.kind {
+                ParamKind::Receiver(receiver) => (self.receiver_type(param.pos, param_ir.is_empty())?, HIRInstruction::SelfParam(*receiver)),
+                ParamKind::Typed { ty, default, vararg } => {
+                    if *vararg && i + 1 != decl.params.len() {
+                        self.error(format!("Only the last parameter of a function can be a vararg, but {} is not.", param.name), param.pos);
+                        return Err(());
+                    }
+                    if *vararg && default.is_some() {
+                        self.error(format!("The vararg parameter {} cannot have a default value.", param.name), param.pos);
+                        return Err(());
+                    }
+                    let sig = self.type_(ty)?;
+                    params.push(Parameter {
+                        default: default.is_some(),
+                        ..Parameter::new(&param.name, sig.clone())
+                    });
+                    //The arguments given for a vararg reach the function as a list
+                    let sig = if *vararg { TypeSignature::List(Box::new(sig)) } else { sig };
+                    (sig, HIRInstruction::FnParam(param.name.clone()))
+                }
+            };
+            param_ir.push(HIR{
+                pos: param.pos,
+                sig,
+                ins,
+                symbol: None,
+            });
+        }
+        Ok((params, param_ir))
+    }
+
+    ///The type of a `self` param, which must come first in an interface or impl block.
+    fn receiver_type(&self, pos: Position, first: bool) -> Result<TypeSignature, ()> {
+        match &self.self_type {
+            Some(self_type) if first => Ok(self_type.clone()),
+            Some(_) => {
+                self.error("The receiver 'self' must be the first parameter.".to_string(), pos);
+                Err(())
+            }
+            None => {
+                self.error("Found 'self' outside of an interface or impl block.".to_string(), pos);
+                Err(())
+            }
+        }
+    }
+
+    fn struct_declaration(&mut self, pos: Position, name: &str, type_params: &[TypeParam], fields: &[ast::Field]) -> LowerResult {
+        let params = type_params.iter().map(generic_param).collect::<Vec<GenericParam>>();
+        let outer_type_params = std::mem::replace(&mut self.type_params, params.clone());
+        let field_sigs = fields.iter().map(|field| self.type_(&field.ty)).collect::<Result<Vec<TypeSignature>, ()>>();
+        self.type_params = outer_type_params;
+        let field_sigs = field_sigs?;
+
+        let sig = TypeSignature::Struct(StructSignature {
+            name: name.to_string(),
+            type_arguments: params.into_iter().map(TypeSignature::Param).collect(),
+        });
+        self.emit(pos, sig, HIRInstruction::Struct(name.to_string()));
+        for param in type_params {
+            self.emit(param.pos, TypeSignature::Param(generic_param(param)), HIRInstruction::TypeParam(param.name.clone()));
+        }
+        for (field, sig) in fields.iter().zip(field_sigs) {
+            if let Some((visibility, pos)) = field.visibility {
+                self.emit(pos, TypeSignature::None, HIRInstruction::Visibility(visibility));
+            }
+            self.emit(field.pos, sig, HIRInstruction::StructField(field.name.clone()));
+        }
+        self.emit(pos, TypeSignature::None, HIRInstruction::EndStruct);
+        Ok(())
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) -> LowerResult {
+        match &stmt.kind {
+            StmtKind::Let { name, name_pos, mutable, ty, value } => {
+                let sig = self.optional_type(ty)?;
+                self.emit(*name_pos, sig, HIRInstruction::LocalVar(name.clone(), *mutable));
+                self.expr(value)?;
+                self.locals.push(name.clone());
+            }
+            StmtKind::For { binding, binding_pos, iterable, body } => {
+                self.emit(*binding_pos, TypeSignature::Untyped, HIRInstruction::For(binding.clone()));
+                self.expr(iterable)?;
+                let outer_locals = self.locals.len();
+                self.locals.push(binding.clone());
+                self.loop_body(body)?;
+                self.locals.truncate(outer_locals);
+                self.emit(stmt.pos, TypeSignature::None, HIRInstruction::EndFor);
+            }
+            StmtKind::If { cond, then, else_ } => {
+                self.emit(stmt.pos, TypeSignature::None, HIRInstruction::If(else_.is_some()));
+                self.expr(cond)?;
+                self.block(then)?;
+                if let Some(else_) = else_ {
+                    self.emit(stmt.pos, TypeSignature::None, HIRInstruction::Else);
+                    self.block(else_)?;
+                }
+                self.emit(stmt.pos, TypeSignature::None, HIRInstruction::EndIf);
+            }
+            StmtKind::While { cond, body } => {
+                self.emit(stmt.pos, TypeSignature::None, HIRInstruction::While);
+                self.expr(cond)?;
+                self.loop_body(body)?;
+                self.emit(stmt.pos, TypeSignature::None, HIRInstruction::EndWhile);
+            }
+            StmtKind::Loop(body) => {
+                self.emit(stmt.pos, TypeSignature::None, HIRInstruction::Loop);
+                self.loop_body(body)?;
+                self.emit(stmt.pos, TypeSignature::None, HIRInstruction::EndLoop);
+            }
+            StmtKind::Break | StmtKind::Continue if self.loops == 0 => {
+                let keyword = if let StmtKind::Break = stmt.kind { "break" } else { "continue" };
+                self.error(format!("'{}' can only be used inside a loop.", keyword), stmt.pos);
+                return Err(());
+            }
+            StmtKind::Break => self.emit(stmt.pos, TypeSignature::None, HIRInstruction::Break),
+            StmtKind::Continue => self.emit(stmt.pos, TypeSignature::None, HIRInstruction::Continue),
+            StmtKind::Return(value) => {
+                self.emit(stmt.pos, TypeSignature::Untyped, HIRInstruction::Return(value.is_some()));
+                if let Some(value) = value {
+                    self.expr(value)?;
+                }
+            }
+            StmtKind::Block(body) => {
+                self.emit(stmt.pos, TypeSignature::None, HIRInstruction::Block);
+                self.block(body)?;
+                self.emit(stmt.pos, TypeSignature::None, HIRInstruction::EndBlock);
+            }
+            StmtKind::Item(Item { kind: ItemKind::Fn(decl), pos, .. }) if decl.receiver.is_some() => {
+                self.error(format!("Extension function {} must be declared at the top level of the module.", decl.name), *pos);
+                return Err(());
+            }
+            StmtKind::Item(Item { kind: ItemKind::Const { name, .. }, pos, .. }) => {
+                self.error(format!("Constant {} must be declared at the top level of the module.", name), *pos);
+                return Err(());
+            }
+            StmtKind::Item(Item { kind: ItemKind::TypeAlias { name, .. }, pos, .. }) => {
+                self.error(format!("Type alias {} must be declared at the top level of the module.", name), *pos);
+                return Err(());
+            }
+            StmtKind::Item(Item { kind: ItemKind::Import(name), pos, .. }) => {
+                self.error(format!("Module {} must be imported at the top level of the module.", name), *pos);
+                return Err(());
+            }
+            StmtKind::Item(item) => self.item(item)?,
+            StmtKind::Expr(expr) => self.expr(expr)?,
+        }
+        Ok(())
+    }
+
+    ///Lower the statements of a nested block, whose locals go out of scope at its end.
+    fn block(&mut self, body: &[Stmt]) -> LowerResult {
+        let outer_locals = self.locals.len();
+        let ret = body.iter().try_for_each(|stmt| self.stmt(stmt));
+        self.locals.truncate(outer_locals);
+        ret
+    }
+
+    ///Lower the statements of a loop body, in which `break` and `continue` can be used.
+    ///Like any block, the locals declared in it go out of scope at its end.
+    fn loop_body(&mut self, body: &[Stmt]) -> LowerResult {
+        self.loops += 1;
+        let ret = self.block(body);
+        self.loops -= 1;
+        ret
+    }
+
+    ///Lower an expression in prefix order: the instruction of an operation comes before its operands.
+    fn expr(&mut self, expr: &Expr) -> LowerResult {
+        let pos = expr.pos;
+        let untyped = TypeSignature::Untyped;
+        match &expr.kind {
+            ExprKind::Integer(int) => self.emit(pos, TypeSignature::Primitive(PrimitiveType::Integer), HIRInstruction::Integer(*int)),
+            ExprKind::Float(float) => self.emit(pos, TypeSignature::Primitive(PrimitiveType::Float), HIRInstruction::Float(*float)),
+            ExprKind::String(s) => self.emit(pos, TypeSignature::Primitive(PrimitiveType::String), HIRInstruction::String(s.clone())),
+            ExprKind::Bool(b) => self.emit(pos, TypeSignature::Primitive(PrimitiveType::Bool), HIRInstruction::Bool(*b)),
+            ExprKind::Name(name) => {
+                let ins = if self.locals.contains(name) {
+                    HIRInstruction::LocalRef(name.clone())
+                } else {
+                    HIRInstruction::GlobalRef(name.clone())
+                };
+                self.emit(pos, untyped, ins);
+            }
+            ExprKind::Call { callee, args } => {
+                self.emit(pos, untyped, HIRInstruction::Call(args.len()));
+                self.expr(callee)?;
+                self.exprs(args)?;
+            }
+            ExprKind::MethodCall { receiver, name, args } => {
+                self.emit(pos, untyped, HIRInstruction::MethodCall(name.clone(), args.len()));
+                self.expr(receiver)?;
+                self.exprs(args)?;
+            }
+            ExprKind::NamedArg { name, value } => {
+                self.emit(pos, untyped, HIRInstruction::NamedArg(name.clone()));
+                self.expr(value)?;
+            }
+            ExprKind::Field { receiver, name } => {
+                self.emit(pos, untyped, HIRInstruction::Field(name.clone()));
+                self.expr(receiver)?;
+            }
+            ExprKind::Array(elements) => {
+                self.emit(pos, untyped, HIRInstruction::Array(elements.len()));
+                self.exprs(elements)?;
+            }
+            ExprKind::Index { collection, index } => {
+                self.emit(pos, untyped, HIRInstruction::Index);
+                self.expr(collection)?;
+                self.expr(index)?;
+            }
+            ExprKind::Slice { collection, start, end } => {
+                self.emit(pos, untyped, HIRInstruction::Slice(start.is_some(), end.is_some()));
+                self.expr(collection)?;
+                for bound in start.iter().chain(end.iter()) {
+                    self.expr(bound)?;
+                }
+            }
+            ExprKind::Range { start, end, inclusive } => {
+                self.emit(pos, untyped, HIRInstruction::Range(*inclusive));
+                self.expr(start)?;
+                self.expr(end)?;
+            }
+            ExprKind::Try(result) => {
+                self.emit(pos, untyped, HIRInstruction::Try);
+                self.expr(result)?;
+            }
+            ExprKind::Binary { op, lhs, rhs } => {
+                self.emit(pos, untyped, HIRInstruction::Binary(*op));
+                self.expr(lhs)?;
+                self.expr(rhs)?;
+            }
+            ExprKind::Unary { op, operand } => {
+                self.emit(pos, untyped, HIRInstruction::Unary(*op));
+                self.expr(operand)?;
+            }
+            ExprKind::Assign { target, value } => {
+                self.emit(pos, TypeSignature::Primitive(PrimitiveType::Unit), HIRInstruction::Assign);
+                self.expr(target)?;
+                self.expr(value)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn exprs(&mut self, exprs: &[Expr]) -> LowerResult {
+        exprs.iter().try_for_each(|expr| self.expr(expr))
+    }
+
+    ///Resolve a type that may have been left out, in which case it is inferred later.
+    fn optional_type(&self, ty: &Option<Type>) -> Result<TypeSignature, ()> {
+        match ty {
+            Some(ty) => self.type_(ty),
+            None => Ok(TypeSignature::Untyped),
+        }
+    }
+
+    ///The declared return type of a function, which is `Unit` when left out.
+    fn return_type(&self, decl: &FnDecl) -> Result<TypeSignature, ()> {
+        match &decl.return_type {
+            Some(ty) => self.type_(ty),
+            None => Ok(TypeSignature::Primitive(PrimitiveType::Unit)),
+        }
+    }
+
+    ///Resolve a written type to the signature it names.
+    ///The length of an array type, which can be a constant of the module holding a non-negative `Int`.
+    fn array_len(&self, len: &ArrayLen, pos: Position) -> Result<usize, ()> {
+        let name = match len {
+            ArrayLen::Literal(len) => return Ok(*len),
+            ArrayLen::Const(name) => name,
+        };
+        match self.constants.get(name) {
+            Some(ConstValue::Integer(len)) if *len >= 0 => Ok(*len as usize),
+            Some(value) => {
+                self.error(format!("The length of an array must be a non-negative Int, but {} is {}.", name, value), pos);
+                Err(())
+            }
+            None => {
+                self.error(format!("The length of an array must be a constant of this module whose value can be computed, which {} isn't.", name), pos);
+                Err(())
+            }
+        }
+    }
+
+    fn type_(&self, ty: &Type) -> Result<TypeSignature, ()> {
+        self.resolve_type(ty, &mut Vec::new())
+    }
+
+    ///Resolve a written type, where `expanding` holds the type aliases whose types are being resolved.
+    ///Those are resolved at the top level, where no type params or `Self` are in scope.
+    fn resolve_type(&self, ty: &Type, expanding: &mut Vec<String>) -> Result<TypeSignature, ()> {
+        let pos = ty.pos;
+        let (name, args) = match &ty.kind {
+            TypeKind::Dyn(interface) => return Ok(TypeSignature::Dyn(interface.clone())),
+            TypeKind::Array(element, len) => {
+                let element = self.resolve_type(element, expanding)?;
+                return Ok(TypeSignature::Array(Box::new(element), self.array_len(len, pos)?));
+            }
+            TypeKind::Named { name, args } => (name, args),
+        };
+        let in_alias = !expanding.is_empty();
+        if let Some(param) = self.type_params.iter().find(|param| param.name == *name && !in_alias) {
+            if !args.is_empty() {
+                self.error(format!("Type parameter {} does not take type arguments.", name), pos);
+                return Err(());
+            }
+            return Ok(TypeSignature::Param(param.clone()));
+        }
+        if name == "Self" && !in_alias {
+            return match &self.self_type {
+                Some(self_type) => Ok(self_type.clone()),
+                None => {
+                    self.error("Found 'Self' outside of an interface or impl block.".to_string(), pos);
+                    Err(())
+                }
+            };
+        }
+        if let Some((aliased, decl_pos)) = self.aliases.get(name) {
+            if !args.is_empty() {
+                self.error(format!("Type alias {} does not take type arguments.", name), pos);
+                return Err(());
+            }
+            if let Some(start) = expanding.iter().position(|alias| alias == name) {
+                let mut cycle = expanding[start..].to_vec();
+                cycle.push(name.clone());
+                self.error(format!("Type alias {} refers to itself: {}", name, cycle.join(" -> ")), *decl_pos);
+                return Err(());
+            }
+            expanding.push(name.clone());
+            let ret = self.resolve_type(aliased, expanding);
+            expanding.pop();
+            return ret;
+        }
+        let mut type_arguments = args.iter().map(|arg| self.resolve_type(arg, expanding)).collect::<Result<Vec<TypeSignature>, ()>>()?;
+        let builtin: Option<fn(Box<TypeSignature>) -> TypeSignature> = match name.as_str() {
+            "List" => Some(TypeSignature::List),
+            "Option" => Some(TypeSignature::Option),
+            "Range" => Some(TypeSignature::Range),
+            _ => None,
+        };
+        if let Some(builtin) = builtin {
+            if type_arguments.len() != 1 {
+                self.error(
+                    format!("Type {} expects 1 type argument but was given {}", name, type_arguments.len()),
+                    pos,
+                );
+                return Err(());
+            }
+            return Ok(builtin(Box::new(type_arguments.remove(0))));
+        }
+        if name == "Result" {
+            if type_arguments.len() != 2 {
+                self.error(
+                    format!("Type Result expects 2 type arguments but was given {}", type_arguments.len()),
+                    pos,
+                );
+                return Err(());
+            }
+            let error = type_arguments.pop().unwrap();
+            let value = type_arguments.pop().unwrap();
+            return Ok(TypeSignature::Result(Box::new(value), Box::new(error)));
+        }
+        match PrimitiveType::new(name.as_str()) {
+            PrimitiveType::None => Ok(TypeSignature::Struct(StructSignature {
+                name: name.clone(),
+                type_arguments,
+            })),
+            primitive => {
+                if !type_arguments.is_empty() {
+                    self.error(format!("Type {} does not take type arguments.", name), pos);
+                    return Err(());
+                }
+                Ok(TypeSignature::Primitive(primitive))
+            }
+        }
+    }
+}
+
+///Whether `name` is the name of a type built into the language, which can't be declared again.
+fn is_builtin_type(name: &str) -> bool {
+    matches!(name, "List" | "Option" | "Range" | "Result" | "Self") || PrimitiveType::new(name) != PrimitiveType::None
+}
+
+fn has_default(param: &Param) -> bool {
+    matches!(param.kind, ParamKind::Typed { default: Some(_), .. })
+}
+
+fn is_vararg(param: &Param) -> bool {
+    matches!(param.kind, ParamKind::Typed { vararg: true, .. })
+}
+
+///Whether the last param of the function is a vararg.
+fn is_variadic(decl: &FnDecl) -> bool {
+    decl.params.last().is_some_and(is_vararg)
+}
+
+fn generic_param(param: &TypeParam) -> GenericParam {
+    GenericParam {
+        name: param.name.clone(),
+        bounds: param.bounds.clone(),
+    }
+}
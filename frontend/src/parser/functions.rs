@@ -1,114 +1,258 @@
 use crate::{
-    parser::{functions, rules, ParseContext},
+    parser::{rules, rules::Precedence, ParseContext},
     tokens::{LexerToken, TokenData, TokenType},
     Parser,
 };
 
-use ir::{
-    hir::HIRInstruction,
-    type_signature::{PrimitiveType, TypeSignature},
+use ast::{
+    Annotation, AnnotationArg, ArrayLen, BinaryOp, Expr, ExprKind, Field, FnDecl, Item, ItemKind, Param, ParamKind, ReceiverKind,
+    Stmt, StmtKind, Type, TypeKind, TypeParam, UnaryOp, Visibility,
 };
 
 use core::pos::BiPos as Position;
 use notices::NoticeLevel;
+use std::convert::TryFrom;
 
-type IRError = Result<(), ()>;
+type ItemResult = Result<Item, ()>;
+pub(crate) type ExprResult = Result<Expr, ()>;
 
-pub(crate) fn nil_func<'a>(p: &mut Parser<'a>) -> IRError {
-    panic!("This is a placehold func for parse rules. This represents a null pointer function. This should not have been called.")
-}
-
-pub fn module<'a>(p: &mut Parser<'a>) -> IRError {
-    p.emit_ir(
-        Position::default(),
-        TypeSignature::None,
-        HIRInstruction::Module(p.name.clone()),
-    );
+pub fn module<'a>(p: &mut Parser<'a>) -> Result<ast::Module, ()> {
+    let mut items = Vec::new();
     while !p.check(TokenType::Eof) {
-        if let Err(()) = declaration_or_statement(p) {
-            return Err(());
-        }
-        // p.advance().unwrap();
+        items.push(declaration_or_statement(p)?);
     }
-    p.emit_ir(Position::default(), TypeSignature::None, HIRInstruction::EndModule);
-    Ok(())
+    Ok(ast::Module {
+        name: p.name.clone(),
+        items,
+    })
 }
 
-pub(crate) fn declaration_or_statement<'a>(p: &mut Parser<'a>) -> IRError {
+pub(crate) fn declaration_or_statement<'a>(p: &mut Parser<'a>) -> ItemResult {
     match p.current_token().type_ {
         TokenType::KwMod => mod_declaration(p),
         _ => statement(p),
     }
 }
 
-pub(crate) fn mod_declaration<'a>(p: &mut Parser<'a>) -> IRError {
-    Ok(())
+pub(crate) fn mod_declaration<'a>(p: &mut Parser<'a>) -> ItemResult {
+    p.emit_notice(
+        p.current_token().pos,
+        NoticeLevel::Error,
+        "Module declarations are not supported yet.".to_string(),
+    );
+    Err(())
+}
+
+///The keywords starting a declaration.
+const DECLARATION_KEYWORDS: [&str; 11] = [
+    "val", "var", "const", "fun", "native", "struct", "type", "newtype", "interface", "impl", "import",
+];
+
+///A note suggesting the declaration keyword `token` is probably a misspelling of, such as `fun` for `fucn`.
+fn declaration_keyword_help(token: &LexerToken) -> Option<String> {
+    match (token.type_, &token.data) {
+        (TokenType::Identifier, TokenData::Str(name)) => {
+            notices::closest_name(name, DECLARATION_KEYWORDS.iter().copied())
+                .map(|keyword| format!("Did you mean the keyword `{}`?", keyword))
+        }
+        _ => None,
+    }
 }
 
-pub(crate) fn statement<'a>(p: &mut Parser<'a>) -> IRError {
+///Parse a declaration along with the annotations and visibility modifier in front of it.
+pub(crate) fn statement<'a>(p: &mut Parser<'a>) -> ItemResult {
+    let annotations = annotations(p)?;
+    let visibility = visibility(p);
     let token = p.current_token();
-    match token.type_ {
+    let pos = token.pos;
+    let kind = match token.type_ {
         TokenType::KwVal => property(p)?,
         TokenType::KwVar => property(p)?,
-        TokenType::KwFun => function(p)?,
+        TokenType::KwConst => const_declaration(p)?,
+        TokenType::KwFun => ItemKind::Fn(function(p)?),
+        TokenType::KwNative => ItemKind::NativeFn(native_function(p)?),
+        TokenType::KwStruct => struct_declaration(p)?,
+        TokenType::KwType => type_alias(p)?,
+        TokenType::KwNewtype => newtype_declaration(p)?,
+        TokenType::KwInterface => interface_declaration(p)?,
+        TokenType::KwImpl if visibility.is_none() => impl_declaration(p)?,
+        TokenType::KwImport if visibility.is_none() => import(p)?,
+        type_ if visibility.is_some() => {
+            let help = declaration_keyword_help(token);
+            p.emit_notice_with_help(
+                pos,
+                NoticeLevel::Error,
+                format!("Expected a declaration after a visibility modifier but instead got {:?}", type_),
+                help,
+            );
+            return Err(());
+        }
         _ => {
-            p.emit_notice(
-                token.pos,
+            let help = declaration_keyword_help(token);
+            p.emit_notice_with_help(
+                pos,
                 NoticeLevel::Error,
                 format!("Unexpected token found: {:?}", token).to_string(),
+                help,
             );
             return Err(());
         }
-    }
-    Ok(())
+    };
+    Ok(Item {
+        kind,
+        annotations,
+        visibility,
+        pos,
+    })
 }
 
-pub(crate) fn property<'a>(p: &mut Parser<'a>) -> IRError {
-    let lpos = p.current_token().pos;
-    let mutable = if !p.check(TokenType::KwVal) {
-        if !p.check(TokenType::KwVar){
-            let message = format!(
-                "Expected a val or var keyword token, but instead got {}",
-                p.current_token()
-            );
-            p.emit_notice(lpos, NoticeLevel::Error, message);
-            return Err(());
+///Parse the annotations in front of a declaration, such as `@inline @deprecated("use bar")`.
+pub(crate) fn annotations<'a>(p: &mut Parser<'a>) -> Result<Vec<Annotation>, ()> {
+    let mut ret = Vec::new();
+    while p.check(TokenType::At) {
+        let mut pos = p.current_token().pos;
+        p.advance().unwrap();
+        let name = identifier(p)?;
+        pos.end = p.current_token().pos.end;
+        p.advance().unwrap();
+        let mut args = Vec::new();
+        if p.check_consume(TokenType::LParen) {
+            while !p.check(TokenType::RParen) {
+                let token = p.current_token();
+                let arg = match (token.type_, &token.data) {
+                    (TokenType::String, TokenData::Str(s)) => AnnotationArg::String(s.to_string()),
+                    (TokenType::Number, TokenData::Integer(i)) => AnnotationArg::Integer(int32(p, *i, token.pos)?),
+                    (TokenType::Number, TokenData::Float(f)) => AnnotationArg::Float(float32(p, *f, token.pos)?),
+                    (TokenType::Identifier, TokenData::Str(s)) => AnnotationArg::Name(s.to_string()),
+                    (type_, _) => {
+                        p.emit_notice(
+                            token.pos,
+                            NoticeLevel::Error,
+                            format!("Expected a literal or a name as an argument of @{} but instead got {:?}", name, type_),
+                        );
+                        return Err(());
+                    }
+                };
+                args.push(arg);
+                p.advance().unwrap();
+                if !p.check_consume(TokenType::Comma) {
+                    break;
+                }
+            }
+            pos.end = p.current_token().pos.end;
+            expect(p, TokenType::RParen)?;
         }
-        true
-    }else{
-        false
+        ret.push(Annotation { name, args, pos });
+    }
+    Ok(ret)
+}
+
+///The integer literal `int` at `pos` as an `Int`, which is 32 bits wide. One that doesn't fit is an error.
+fn int32<'a>(p: &mut Parser<'a>, int: isize, pos: Position) -> Result<i32, ()> {
+    i32::try_from(int).map_err(|_| {
+        p.emit_notice(
+            pos,
+            NoticeLevel::Error,
+            format!("The integer {} is out of the range of Int, from {} to {}", int, i32::MIN, i32::MAX),
+        );
+    })
+}
+
+///The float literal `float` at `pos` as a `Float`, which is 32 bits wide. One too large for it is an error.
+fn float32<'a>(p: &mut Parser<'a>, float: f64, pos: Position) -> Result<f32, ()> {
+    let narrowed = float as f32;
+    if narrowed.is_infinite() && float.is_finite() {
+        p.emit_notice(
+            pos,
+            NoticeLevel::Error,
+            format!("The float {} is out of the range of Float, whose largest value is {}", float, f32::MAX),
+        );
+        return Err(());
+    }
+    Ok(narrowed)
+}
+
+///Parse a `public`, `internal` or `private` modifier if there is one.
+pub(crate) fn visibility<'a>(p: &mut Parser<'a>) -> Option<(Visibility, Position)> {
+    let visibility = match p.current_token().type_ {
+        TokenType::KwPublic => Visibility::Public,
+        TokenType::KwInternal => Visibility::Internal,
+        TokenType::KwPrivate => Visibility::Private,
+        _ => return None,
     };
+    let pos = p.current_token().pos;
     p.advance().unwrap();
+    Some((visibility, pos))
+}
+
+///Get the name out of the current identifier token without advancing.
+pub(crate) fn identifier<'a>(p: &mut Parser<'a>) -> Result<String, ()> {
+    let pos = p.current_token().pos;
     if !p.check(TokenType::Identifier) {
         let message = format!(
             "Expected an identifier token, but instead got {}",
             p.current_token()
         );
-        p.emit_notice(lpos, NoticeLevel::Error, message);
+        p.emit_notice(pos, NoticeLevel::Error, message);
         return Err(());
     }
-    let name = match p.current_token().data {
-        TokenData::Str(s) => (*s).to_string(),
+    match p.current_token().data {
+        TokenData::Str(s) => Ok((*s).to_string()),
         _ => {
             p.emit_notice(
-                lpos,
+                pos,
                 NoticeLevel::Error,
                 "Failed to extract string data from identifier token.".to_string(),
             );
+            Err(())
+        }
+    }
+}
+
+///Consume a token of `type_`, or report what was found instead.
+pub(crate) fn expect<'a>(p: &mut Parser<'a>, type_: TokenType) -> Result<(), ()> {
+    if p.check_consume(type_) {
+        return Ok(());
+    }
+    p.emit_notice(
+        p.current_token().pos,
+        NoticeLevel::Error,
+        format!(
+            "Expected a '{:?}' but instead got {:?}",
+            type_,
+            p.current_token().type_
+        ),
+    );
+    Err(())
+}
+
+pub(crate) fn property<'a>(p: &mut Parser<'a>) -> Result<ItemKind, ()> {
+    let lpos = p.current_token().pos;
+    let mutable = if !p.check(TokenType::KwVal) {
+        if !p.check(TokenType::KwVar){
+            let message = format!(
+                "Expected a val or var keyword token, but instead got {}",
+                p.current_token()
+            );
+            p.emit_notice(lpos, NoticeLevel::Error, message);
             return Err(());
         }
+        true
+    }else{
+        false
     };
-    let signature = if p.check_consume(TokenType::Colon) {
+    p.advance().unwrap();
+    let name = identifier(p)?;
+    p.advance().unwrap();
+    let ty = if p.check_consume(TokenType::Colon) {
         if let Ok(t) = type_(p){
-            t
+            Some(t)
         }else{
             p.emit_notice(p.prev_token().pos, NoticeLevel::Error, "Could not create type signature for property.".to_string());
             return Err(())
         }
     } else {
-        p.advance()
-            .expect("Failed to advance parser to next token.");
-        TypeSignature::Untyped
+        None
     };
 
     if !p.check_consume(TokenType::Equal) {
@@ -132,12 +276,71 @@ pub(crate) fn property<'a>(p: &mut Parser<'a>) -> IRError {
         );
         return Err(());
     }
-    p.emit_ir(lpos, signature, HIRInstruction::Property(name, mutable));
-    expression(p).expect("Could not parse expression.");
-    Ok(())
+    let value = expression(p)?;
+    Ok(ItemKind::Property {
+        name,
+        mutable,
+        ty,
+        value,
+    })
+}
+
+///Parse a constant such as `const LIMIT: Int = 10 * 1024`.
+pub(crate) fn const_declaration<'a>(p: &mut Parser<'a>) -> Result<ItemKind, ()> {
+    expect(p, TokenType::KwConst)?;
+    let name = identifier(p)?;
+    p.advance().unwrap();
+    let ty = if p.check_consume(TokenType::Colon) {
+        Some(type_(p)?)
+    } else {
+        None
+    };
+    expect(p, TokenType::Equal)?;
+    let value = expression(p)?;
+    Ok(ItemKind::Const { name, ty, value })
+}
+
+///Parse a list of generic type parameters such as `<T, U: Show + Eq>`.
+///The current token is expected to be the opening angle bracket.
+pub(crate) fn type_params<'a>(p: &mut Parser<'a>) -> Result<Vec<TypeParam>, ()> {
+    expect(p, TokenType::LAngle)?;
+    let mut params = Vec::new();
+    loop {
+        let pos = p.current_token().pos;
+        let name = identifier(p)?;
+        p.advance().unwrap();
+        let mut bounds = Vec::new();
+        if p.check_consume(TokenType::Colon) {
+            loop {
+                bounds.push(identifier(p)?);
+                p.advance().unwrap();
+                if !p.check_consume(TokenType::Plus) {
+                    break;
+                }
+            }
+        }
+        if params.iter().any(|param: &TypeParam| param.name == name) {
+            p.emit_notice(
+                pos,
+                NoticeLevel::Error,
+                format!("Type parameter {} is declared more than once.", name),
+            );
+            return Err(());
+        }
+        params.push(TypeParam { name, bounds, pos });
+        if p.check_consume(TokenType::Comma) {
+            continue;
+        }
+        expect(p, TokenType::RAngle)?;
+        break;
+    }
+    Ok(params)
 }
 
-pub(crate) fn function<'a>(p: &mut Parser<'a>) -> IRError {
+///Parse a function declaration.
+///The body is either a block or `=` followed by a single expression, as in `fun String.shout(): String = self`.
+///Inside an interface, a method may leave out its body to make it a required method.
+pub(crate) fn function<'a>(p: &mut Parser<'a>) -> Result<FnDecl, ()> {
     let lpos = p.current_token().pos;
     if !p.check_consume(TokenType::KwFun) {
         let message = format!(
@@ -147,127 +350,415 @@ pub(crate) fn function<'a>(p: &mut Parser<'a>) -> IRError {
         p.emit_notice(lpos, NoticeLevel::Error, message);
         return Err(());
     }
-    if !p.check(TokenType::Identifier) {
-        let message = format!(
-            "Expected an identifier token, but instead got {}",
-            p.current_token()
+    let type_params = if p.check(TokenType::LAngle) {
+        type_params(p)?
+    } else {
+        Vec::new()
+    };
+    let receiver = if is_receiver(p) {
+        let receiver = type_(p)?;
+        expect(p, TokenType::Dot)?;
+        Some(receiver)
+    } else {
+        None
+    };
+    let name = identifier(p)?;
+    if p.advance().is_err(){
+        return Err(())
+    }
+    let (params, return_type) = parameters(p)?;
+    let body = if p.check(TokenType::Equal) {
+        let pos = p.current_token().pos;
+        p.advance().unwrap();
+        let outer_context = std::mem::replace(&mut p.context, ParseContext::Local);
+        let value = expression(p);
+        p.context = outer_context;
+        Some(vec![Stmt {
+            kind: StmtKind::Expr(value?),
+            pos,
+        }])
+    } else if p.context == ParseContext::Interface && !p.check(TokenType::LCurly) {
+        None
+    } else {
+        let outer_context = std::mem::replace(&mut p.context, ParseContext::Local);
+        let body = block(p, lpos, "function");
+        p.context = outer_context;
+        Some(body?)
+    };
+    Ok(FnDecl {
+        name,
+        type_params,
+        receiver,
+        params,
+        return_type,
+        body,
+    })
+}
+
+///Whether the function being parsed starts with the type it extends, as in `fun String.shout()`,
+///rather than with its name.
+fn is_receiver<'a>(p: &mut Parser<'a>) -> bool {
+    match p.current_token().type_ {
+        TokenType::KwDyn | TokenType::LBracket => true,
+        TokenType::Identifier => p.check_next(TokenType::Dot) || p.check_next(TokenType::LAngle),
+        _ => false,
+    }
+}
+
+///Parse the statements between curly braces, up to and including the closing one.
+///`what` names the construct being closed for the error at the end of the file.
+fn block<'a>(p: &mut Parser<'a>, lpos: Position, what: &str) -> Result<Vec<Stmt>, ()> {
+    expect(p, TokenType::LCurly)?;
+    let mut stmts = Vec::new();
+    while !p.check_consume(TokenType::RCurly){
+        if p.check(TokenType::Eof){
+            p.emit_notice(
+                lpos,
+                NoticeLevel::Error,
+                format!("Expected '}}' to close the {} body but reached the end of the file.", what),
+            );
+            return Err(())
+        }
+        stmts.push(local_statements(p)?);
+    }
+    Ok(stmts)
+}
+
+///Parse the declaration of a function implemented in C, such as `native fun puts(s: String): Int`.
+pub(crate) fn native_function<'a>(p: &mut Parser<'a>) -> Result<FnDecl, ()> {
+    expect(p, TokenType::KwNative)?;
+    if !p.check_consume(TokenType::KwFun) {
+        p.emit_notice(
+            p.current_token().pos,
+            NoticeLevel::Error,
+            format!("Expected 'fun' after 'native' but instead got {:?}", p.current_token().type_),
         );
-        p.emit_notice(lpos, NoticeLevel::Error, message);
         return Err(());
     }
-    let name = match p.current_token().data {
-        TokenData::Str(s) => (*s).to_string(),
-        _ => {
+    if p.check(TokenType::LAngle) {
+        p.emit_notice(p.current_token().pos, NoticeLevel::Error, "Native functions cannot take type parameters.".to_string());
+        return Err(());
+    }
+    let name = identifier(p)?;
+    p.advance().unwrap();
+    let (params, return_type) = parameters(p)?;
+    if p.check(TokenType::LCurly) {
+        p.emit_notice(
+            p.current_token().pos,
+            NoticeLevel::Error,
+            format!("Native function {} cannot have a body.", name),
+        );
+        return Err(());
+    }
+    Ok(FnDecl {
+        name,
+        type_params: Vec::new(),
+        receiver: None,
+        params,
+        return_type,
+        body: None,
+    })
+}
+
+///Parse the parameter list and return type of a function, starting at the opening paren.
+///A param may have a default value, as in `x: Int = 0`, or take any number of arguments, as in `vararg xs: Int`.
+fn parameters<'a>(p: &mut Parser<'a>) -> Result<(Vec<Param>, Option<Type>), ()> {
+    let mut params = Vec::new();
+    expect(p, TokenType::LParen)?;
+    while !p.check_consume(TokenType::RParen) {
+        let loc = p.current_token().pos;
+        let vararg = p.check_consume(TokenType::KwVararg);
+        let receiver = receiver_kind(p);
+        let param_name = identifier(p)?;
+        p.advance().unwrap();
+        let kind = if param_name == "self" && !vararg {
+            ParamKind::Receiver(receiver)
+        } else if receiver != ReceiverKind::Value {
+            p.emit_notice(loc, NoticeLevel::Error, format!("Expected 'self' after '&' but instead got {}", param_name));
+            return Err(());
+        } else {
+            expect(p, TokenType::Colon)?;
+            let ty = type_(p)?;
+            let default = if p.check_consume(TokenType::Equal) {
+                Some(expression(p)?)
+            } else {
+                None
+            };
+            ParamKind::Typed { ty, default, vararg }
+        };
+        params.push(Param {
+            name: param_name,
+            kind,
+            pos: loc,
+        });
+        if !p.check_consume(TokenType::Comma) && !p.check(TokenType::RParen) {
             p.emit_notice(
-                lpos,
+                p.current_token().pos,
                 NoticeLevel::Error,
-                "Failed to extract string data from identifier token.".to_string(),
+                format!("Expected ',' or ')' but instead got {:?}", p.current_token().type_),
             );
             return Err(());
         }
+    }
+    let return_type = if p.check_consume(TokenType::Colon){
+        Some(type_(p)?)
+    }else{
+        None
     };
-    if p.advance().is_err(){
-        return Err(())
+    Ok((params, return_type))
+}
+
+///Parse the `&` or `&mut` in front of a receiver, if there is one.
+///Returns the kind of receiver the parameter would be if it turns out to be `self`.
+fn receiver_kind<'a>(p: &mut Parser<'a>) -> ReceiverKind {
+    if !p.check_consume(TokenType::And) {
+        ReceiverKind::Value
+    } else if p.check_consume(TokenType::KwMut) {
+        ReceiverKind::MutRef
+    } else {
+        ReceiverKind::Ref
     }
-    let mut params = Vec::<TypeSignature>::new();
-    let mut param_ir = Vec::<ir::hir::HIR>::new();
-    if p.check(TokenType::LParen){
-        
-        loop{
-            if p.check(TokenType::RParen){
-                break;
-            }
-            let loc = p.next_token().pos;
-            let param_name = match p.consume(TokenType::Identifier).unwrap() {
-                TokenData::Str(s) => (*s).to_string(),
-                _ => {
-                    p.emit_notice(
-                        lpos,
-                        NoticeLevel::Error,
-                        "Failed to extract string data from identifier token.".to_string(),
-                    );
-                    return Err(());
-                }
-            };
-            let _ = p.consume(TokenType::Colon);
-            let param_typename = match p.consume(TokenType::Identifier).unwrap() {
-                TokenData::Str(s) => (*s).to_string(),
-                _ => {
-                    p.emit_notice(
-                        lpos,
-                        NoticeLevel::Error,
-                        "Failed to extract string data from identifier token.".to_string(),
-                    );
-                    return Err(());
-                }
-            };
-            let type_sig = ir::type_signature::TypeSignature::Primitive(PrimitiveType::new(param_typename.as_str()));
-            params.push(type_sig.clone());
-            param_ir.push(ir::hir::HIR{
-                pos: loc,
-                sig: type_sig,
-                ins: HIRInstruction::FnParam(param_name)
-            });
-            p.advance().unwrap();
+}
+
+///Parse the methods of an interface or impl block up to and including the closing curly brace.
+fn methods<'a>(p: &mut Parser<'a>, context: ParseContext) -> Result<Vec<Item>, ()> {
+    expect(p, TokenType::LCurly)?;
+    let outer_context = std::mem::replace(&mut p.context, context);
+    let ret = method_items(p);
+    p.context = outer_context;
+    ret
+}
+
+fn method_items<'a>(p: &mut Parser<'a>) -> Result<Vec<Item>, ()> {
+    let mut methods = Vec::new();
+    while !p.check_consume(TokenType::RCurly) {
+        let annotations = annotations(p)?;
+        let visibility = if p.context == ParseContext::Impl {
+            visibility(p)
+        } else {
+            None
+        };
+        let pos = p.current_token().pos;
+        if !p.check(TokenType::KwFun) {
+            p.emit_notice(
+                pos,
+                NoticeLevel::Error,
+                format!("Expected a method but instead got {:?}", p.current_token().type_),
+            );
+            return Err(());
         }
+        methods.push(Item {
+            kind: ItemKind::Fn(function(p)?),
+            annotations,
+            visibility,
+            pos,
+        });
     }
-    let typename = if p.check_consume_next(TokenType::Colon){
-        match p.consume(TokenType::Identifier).unwrap() {
-            TokenData::Str(s) => (*s).to_string(),
+    Ok(methods)
+}
+
+///Parse an interface declaration such as `interface Show { fun show(self): String }`.
+pub(crate) fn interface_declaration<'a>(p: &mut Parser<'a>) -> Result<ItemKind, ()> {
+    expect(p, TokenType::KwInterface)?;
+    let name = identifier(p)?;
+    p.advance().unwrap();
+    let methods = methods(p, ParseContext::Interface)?;
+    Ok(ItemKind::Interface { name, methods })
+}
+
+///Parse an impl block, either of an interface such as `impl Show for Point { ... }`
+///or of the type's own methods and associated functions such as `impl Point { ... }`.
+pub(crate) fn impl_declaration<'a>(p: &mut Parser<'a>) -> Result<ItemKind, ()> {
+    expect(p, TokenType::KwImpl)?;
+    let implemented = type_(p)?;
+    let (interface, self_type) = if p.check_consume(TokenType::KwFor) {
+        match implemented.kind {
+            TypeKind::Named { name, args } if args.is_empty() => (Some(name), type_(p)?),
             _ => {
-                p.emit_notice(
-                    lpos,
-                    NoticeLevel::Error,
-                    "Failed to extract string data from identifier token.".to_string(),
-                );
+                p.emit_notice(implemented.pos, NoticeLevel::Error, format!("Expected an interface name but instead got {}", implemented));
                 return Err(());
             }
         }
-    }else{
-        "Unit".to_string()
+    } else {
+        (None, implemented)
     };
-    let function_sig = ir::type_signature::TypeSignature::Function(ir::type_signature::FunctionSignature{
-        parameters: params,
-        return_type_signature: Box::new(TypeSignature::Primitive(PrimitiveType::new(typename.as_str())))
-    });
-    p.emit_ir(lpos, function_sig, ir::hir::HIRInstruction::Fn(name));
-    for ir in param_ir{
-        p.emit_ir(ir.pos, ir.sig, ir.ins);
-    }
-    if p.consume(TokenType::LCurly).is_err(){
-        return Err(())
-    }
+    let methods = methods(p, ParseContext::Impl)?;
+    Ok(ItemKind::Impl {
+        interface,
+        self_type,
+        methods,
+    })
+}
 
-    p.context = ParseContext::Local;
-    
-    while !p.check_consume(TokenType::RCurly){
-        if local_statements(p).is_err(){
-            return Err(())
+///Parse a struct declaration such as `struct Pair<A, B> { first: A, second: B }`.
+pub(crate) fn struct_declaration<'a>(p: &mut Parser<'a>) -> Result<ItemKind, ()> {
+    expect(p, TokenType::KwStruct)?;
+    let name = identifier(p)?;
+    p.advance().unwrap();
+    let type_params = if p.check(TokenType::LAngle) {
+        type_params(p)?
+    } else {
+        Vec::new()
+    };
+    let fields = struct_fields(p)?;
+    Ok(ItemKind::Struct {
+        name,
+        type_params,
+        fields,
+    })
+}
+
+///Parse a type alias such as `type UserId = Int`.
+pub(crate) fn type_alias<'a>(p: &mut Parser<'a>) -> Result<ItemKind, ()> {
+    expect(p, TokenType::KwType)?;
+    let name = identifier(p)?;
+    p.advance().unwrap();
+    expect(p, TokenType::Equal)?;
+    let ty = type_(p)?;
+    Ok(ItemKind::TypeAlias { name, ty })
+}
+
+///Parse an import of another module such as `import geometry`.
+pub(crate) fn import<'a>(p: &mut Parser<'a>) -> Result<ItemKind, ()> {
+    expect(p, TokenType::KwImport)?;
+    let name = identifier(p)?;
+    p.advance().unwrap();
+    Ok(ItemKind::Import(name))
+}
+
+///Parse a newtype such as `newtype Meters(Float)`.
+pub(crate) fn newtype_declaration<'a>(p: &mut Parser<'a>) -> Result<ItemKind, ()> {
+    expect(p, TokenType::KwNewtype)?;
+    let name = identifier(p)?;
+    p.advance().unwrap();
+    expect(p, TokenType::LParen)?;
+    let ty = type_(p)?;
+    expect(p, TokenType::RParen)?;
+    Ok(ItemKind::Newtype { name, ty })
+}
+
+fn struct_fields<'a>(p: &mut Parser<'a>) -> Result<Vec<Field>, ()> {
+    let mut fields = Vec::<Field>::new();
+    expect(p, TokenType::LCurly)?;
+    while !p.check_consume(TokenType::RCurly) {
+        let visibility = visibility(p);
+        let pos = p.current_token().pos;
+        let name = identifier(p)?;
+        if fields.iter().any(|f| f.name == name) {
+            p.emit_notice(
+                pos,
+                NoticeLevel::Error,
+                format!("Field {} is declared more than once.", name),
+            );
+            return Err(());
         }
+        p.advance().unwrap();
+        expect(p, TokenType::Colon)?;
+        let ty = type_(p)?;
+        fields.push(Field {
+            name,
+            ty,
+            visibility,
+            pos,
+        });
+        p.check_consume(TokenType::Comma);
     }
-    p.emit_ir(lpos, TypeSignature::None, ir::hir::HIRInstruction::EndFn);
+    Ok(fields)
+}
 
-    Ok(())
+pub(crate) fn local_statements<'a>(p: &mut Parser<'a>) -> Result<Stmt, ()>{
+    let pos = p.current_token().pos;
+    let kind = match p.current_token().type_{
+        TokenType::KwLet => local_var(p)?,
+        TokenType::KwVal
+        | TokenType::KwVar
+        | TokenType::KwConst
+        | TokenType::KwFun
+        | TokenType::KwStruct
+        | TokenType::KwType
+        | TokenType::KwNewtype
+        | TokenType::KwInterface
+        | TokenType::KwImpl
+        | TokenType::KwImport => StmtKind::Item(statement(p)?),
+        TokenType::KwFor => for_loop(p)?,
+        TokenType::KwIf => if_statement(p)?,
+        TokenType::KwWhile => while_loop(p)?,
+        TokenType::KwLoop => {
+            p.advance().unwrap();
+            StmtKind::Loop(block(p, pos, "loop")?)
+        }
+        TokenType::KwBreak => {
+            p.advance().unwrap();
+            StmtKind::Break
+        }
+        TokenType::KwContinue => {
+            p.advance().unwrap();
+            StmtKind::Continue
+        }
+        TokenType::KwReturn => return_statement(p)?,
+        TokenType::LCurly => StmtKind::Block(block(p, pos, "block")?),
+        _ => StmtKind::Expr(expression(p)?),
+    };
+    Ok(Stmt { kind, pos })
 }
 
-pub(crate) fn local_statements<'a>(p: &mut Parser<'a>) -> IRError{
+///Parse a loop such as `for x in 0..10 { ... }`.
+pub(crate) fn for_loop<'a>(p: &mut Parser<'a>) -> Result<StmtKind, ()> {
+    let lpos = p.current_token().pos;
+    expect(p, TokenType::KwFor)?;
+    let binding_pos = p.current_token().pos;
+    let binding = identifier(p)?;
     p.advance().unwrap();
-    match p.current_token().type_{
-        TokenType::RCurly => return Ok(()),
-        TokenType::KwLet => local_var(p)?,
-        TokenType::Identifier => {
-            match p.next_token().type_{
-                TokenType::Equal => unimplemented!(),
-                _ => unimplemented!()
-            };
-        },
-        _ => statement(p)?
+    expect(p, TokenType::KwIn)?;
+    let iterable = expression(p)?;
+    let body = block(p, lpos, "loop")?;
+    Ok(StmtKind::For {
+        binding,
+        binding_pos,
+        iterable,
+        body,
+    })
+}
+
+///Parse a conditional such as `if x > 0 { ... } else if x < 0 { ... } else { ... }`.
+pub(crate) fn if_statement<'a>(p: &mut Parser<'a>) -> Result<StmtKind, ()> {
+    let lpos = p.current_token().pos;
+    expect(p, TokenType::KwIf)?;
+    let cond = expression(p)?;
+    let then = block(p, lpos, "if")?;
+    let else_ = if p.check_consume(TokenType::KwElse) {
+        let pos = p.current_token().pos;
+        if p.check(TokenType::KwIf) {
+            let kind = if_statement(p)?;
+            Some(vec![Stmt { kind, pos }])
+        } else {
+            Some(block(p, lpos, "else")?)
+        }
+    } else {
+        None
     };
-    // p.advance().unwrap();
-    Ok(())
+    Ok(StmtKind::If { cond, then, else_ })
+}
+
+///Parse a loop such as `while x < 10 { ... }`.
+pub(crate) fn while_loop<'a>(p: &mut Parser<'a>) -> Result<StmtKind, ()> {
+    let lpos = p.current_token().pos;
+    expect(p, TokenType::KwWhile)?;
+    let cond = expression(p)?;
+    let body = block(p, lpos, "loop")?;
+    Ok(StmtKind::While { cond, body })
 }
 
-pub(crate) fn local_var<'a>(p: &mut Parser<'a>) -> IRError {
+///Parse a `return`, which has no value when it is the last statement of its block.
+pub(crate) fn return_statement<'a>(p: &mut Parser<'a>) -> Result<StmtKind, ()> {
+    expect(p, TokenType::KwReturn)?;
+    if p.check(TokenType::RCurly) {
+        return Ok(StmtKind::Return(None));
+    }
+    Ok(StmtKind::Return(Some(expression(p)?)))
+}
+
+pub(crate) fn local_var<'a>(p: &mut Parser<'a>) -> Result<StmtKind, ()> {
     if p.context != ParseContext::Local{
         p.emit_notice(p.current_token().pos, NoticeLevel::Error, "Found 'let' outside of local context.".to_string());
         return Err(())
@@ -279,39 +770,20 @@ pub(crate) fn local_var<'a>(p: &mut Parser<'a>) -> IRError {
             "Expected keyword 'let' for defining an local variable.".to_string(),
         );
     }
+    let mutable = p.check_consume(TokenType::KwMut);
     let pos = p.current_token().pos;
-    if !p.check(TokenType::Identifier) {
-        let message = format!(
-            "Expected an identifier token, but instead got {}",
-            p.current_token()
-        );
-        p.emit_notice(pos, NoticeLevel::Error, message);
-        return Err(());
-    }
-    let name = match p.current_token().data {
-        TokenData::Str(s) => (*s).to_string(),
-        _ => {
-            p.emit_notice(
-                pos,
-                NoticeLevel::Error,
-                "Failed to extract string data from identifier token.".to_string(),
-            );
-            return Err(());
-        }
-    };
-    let signature = if p.next_token().type_ == TokenType::Colon {
+    let name = identifier(p)?;
+    p.advance().unwrap();
+    let ty = if p.check_consume(TokenType::Colon) {
         if let Ok(t) = type_(p){
-            t
+            Some(t)
         }else{
             p.emit_notice(p.current_token().pos, NoticeLevel::Error, "Could not create type signature for local variable.".to_string());
             return Err(())
         }
     } else {
-        p.advance()
-            .expect("Failed to advance parser to next token.");
-        TypeSignature::Untyped
+        None
     };
-    p.emit_ir(pos, signature, HIRInstruction::LocalVar(name.clone(), false));
 
     if !p.check_consume(TokenType::Equal) {
         p.emit_notice(
@@ -335,50 +807,66 @@ pub(crate) fn local_var<'a>(p: &mut Parser<'a>) -> IRError {
         return Err(());
     }
 
-    if expression(p).is_err() {
-        p.emit_notice(
-            pos,
-            NoticeLevel::Error,
-            format!("Local variable {} cannot go uninitialized.", name),
-        );
-    }
-    Ok(())
+    let value = match expression(p) {
+        Ok(expr) => expr,
+        Err(()) => {
+            p.emit_notice(
+                pos,
+                NoticeLevel::Error,
+                format!("Local variable {} cannot go uninitialized.", name),
+            );
+            return Err(());
+        }
+    };
+    Ok(StmtKind::Let {
+        name,
+        name_pos: pos,
+        mutable,
+        ty,
+        value,
+    })
 }
 
-fn expression<'a>(p: &mut Parser<'a>) -> IRError {
+pub(crate) fn expression<'a>(p: &mut Parser<'a>) -> ExprResult {
+    parse_precedence(p, Precedence::Assignment)
+}
+
+///Parse an expression made of operators that bind at least as tightly as `precedence`.
+pub(crate) fn parse_precedence<'a>(p: &mut Parser<'a>, precedence: Precedence) -> ExprResult {
     let token = p.current_token();
-    let rule = rules::PARSER_RULE_TABLE
+    let prefix = match rules::PARSER_RULE_TABLE
         .get(&token.type_)
-        .expect(format!("Could not find parse rule for current token: {:?}", token).as_str());
-    let infix = rule.infix;
-    if infix as usize == nil_func as usize {
-        p.emit_notice(
-            token.pos,
-            NoticeLevel::Error,
-            "Expected an infix function, but instead got nil_func".to_string(),
-        );
-        return Err(());
+        .and_then(|rule| rule.prefix)
+    {
+        Some(prefix) => prefix,
+        None => {
+            p.emit_notice(
+                token.pos,
+                NoticeLevel::Error,
+                format!("Expected an expression but instead got {:?}", token.type_),
+            );
+            return Err(());
+        }
+    };
+    let mut expr = prefix(p)?;
+    while let Some(rule) = rules::PARSER_RULE_TABLE.get(&p.current_token().type_) {
+        match rule.infix {
+            Some(infix) if precedence <= rule.precedence => expr = infix(p, expr)?,
+            _ => break,
+        }
     }
-    infix(p).unwrap();
-    p.advance().unwrap();
-    Ok(())
+    Ok(expr)
 }
 
-pub(crate) fn literal<'a>(p: &mut Parser<'a>) -> IRError {
-    // p.advance();
+pub(crate) fn literal<'a>(p: &mut Parser<'a>) -> ExprResult {
     let current_token = p.current_token();
     let pos = current_token.pos;
     let token_type = current_token.type_;
     let token_data = current_token.data.clone();
-    match token_type {
+    let kind = match token_type {
         TokenType::Number => match current_token.data {
-            TokenData::Integer(int) => {
-                p.emit_ir(
-                    pos,
-                    TypeSignature::Primitive(PrimitiveType::Integer),
-                    HIRInstruction::Integer(int as i32),
-                );
-            }
+            TokenData::Integer(int) => ExprKind::Integer(int32(p, int, pos)?),
+            TokenData::Float(float) => ExprKind::Float(float32(p, float, pos)?),
             _ => {
                 p.emit_notice(
                     pos,
@@ -392,34 +880,305 @@ pub(crate) fn literal<'a>(p: &mut Parser<'a>) -> IRError {
             }
         },
         TokenType::String => match token_data {
-            TokenData::Str(s) => p.emit_ir(
-                pos,
-                TypeSignature::Primitive(PrimitiveType::String),
-                HIRInstruction::String(s.to_string().clone()),
-            ),
+            TokenData::Str(s) => ExprKind::String(s.to_string()),
             _ => unimplemented!(),
         },
+        TokenType::KwTrue => ExprKind::Bool(true),
+        TokenType::KwFalse => ExprKind::Bool(false),
         _ => unimplemented!(),
+    };
+    p.advance().unwrap();
+    Ok(Expr { kind, pos })
+}
+
+///A reference to a local variable, parameter, or something declared at the top level.
+pub(crate) fn reference<'a>(p: &mut Parser<'a>) -> ExprResult {
+    let pos = p.current_token().pos;
+    let name = identifier(p)?;
+    p.advance().unwrap();
+    Ok(Expr {
+        kind: ExprKind::Name(name),
+        pos,
+    })
+}
+
+///A parenthesized expression.
+pub(crate) fn grouping<'a>(p: &mut Parser<'a>) -> ExprResult {
+    expect(p, TokenType::LParen)?;
+    let expr = expression(p)?;
+    expect(p, TokenType::RParen)?;
+    Ok(expr)
+}
+
+///Parse comma separated elements up to and including the `closing` token, each one with `element`.
+///Returns them along with the position of the closing token.
+fn arguments<'a>(p: &mut Parser<'a>, closing: TokenType, element: fn(&mut Parser<'a>) -> ExprResult) -> Result<(Vec<Expr>, Position), ()> {
+    let mut args = Vec::new();
+    while !p.check(closing) {
+        args.push(element(p)?);
+        if !p.check_consume(TokenType::Comma) {
+            break;
+        }
     }
-    Ok(())
+    let end = p.current_token().pos;
+    expect(p, closing)?;
+    Ok((args, end))
 }
 
-fn type_<'a>(p: &mut Parser<'a>) -> Result<TypeSignature, ()> {
-    if p.advance().is_err(){
-        p.emit_notice(p.current_token().pos, NoticeLevel::Error, "Could not advance parser.".to_string());
-        return Err(())
+///An argument of a call, which may be given by the name of its param as in `x = 1`.
+fn argument<'a>(p: &mut Parser<'a>) -> ExprResult {
+    if !(p.check(TokenType::Identifier) && p.check_next(TokenType::Equal)) {
+        return expression(p);
+    }
+    let mut pos = p.current_token().pos;
+    let name = identifier(p)?;
+    p.advance().unwrap();
+    p.advance().unwrap();
+    let value = expression(p)?;
+    pos.end = value.pos.end;
+    Ok(Expr {
+        kind: ExprKind::NamedArg {
+            name,
+            value: Box::new(value),
+        },
+        pos,
+    })
+}
+
+///A call of the already parsed `callee`, with the current token being the opening paren.
+pub(crate) fn call<'a>(p: &mut Parser<'a>, callee: Expr) -> ExprResult {
+    let mut pos = callee.pos;
+    expect(p, TokenType::LParen)?;
+    let (args, end) = arguments(p, TokenType::RParen, argument)?;
+    pos.end = end.end;
+    Ok(Expr {
+        kind: ExprKind::Call {
+            callee: Box::new(callee),
+            args,
+        },
+        pos,
+    })
+}
+
+///A range such as `0..10` or `1..=n` whose start has already been parsed, with the current token being the dots.
+pub(crate) fn range<'a>(p: &mut Parser<'a>, start: Expr) -> ExprResult {
+    let mut pos = start.pos;
+    let inclusive = p.check(TokenType::DotDotEqual);
+    p.advance().unwrap();
+    let end = parse_precedence(p, Precedence::Or)?;
+    pos.end = p.prev_token().pos.end;
+    Ok(Expr {
+        kind: ExprKind::Range {
+            start: Box::new(start),
+            end: Box::new(end),
+            inclusive,
+        },
+        pos,
+    })
+}
+
+///A binary operation such as `a + b` whose left operand has already been parsed, with the current token being the operator.
+///Operators are left associative, so the right operand only takes operators that bind more tightly.
+pub(crate) fn binary<'a>(p: &mut Parser<'a>, lhs: Expr) -> ExprResult {
+    let mut pos = lhs.pos;
+    let token = p.current_token().type_;
+    let op = match token {
+        TokenType::Plus => BinaryOp::Add,
+        TokenType::Minus => BinaryOp::Sub,
+        TokenType::Star => BinaryOp::Mul,
+        TokenType::Slash => BinaryOp::Div,
+        TokenType::Percent => BinaryOp::Rem,
+        TokenType::EqualEqual => BinaryOp::Eq,
+        TokenType::BangEqual => BinaryOp::Ne,
+        TokenType::LAngle => BinaryOp::Lt,
+        TokenType::LAngleEqual => BinaryOp::Le,
+        TokenType::RAngle => BinaryOp::Gt,
+        TokenType::RAngleEqual => BinaryOp::Ge,
+        TokenType::AndAnd => BinaryOp::And,
+        TokenType::PipePipe => BinaryOp::Or,
+        _ => unreachable!(),
+    };
+    let precedence = rules::PARSER_RULE_TABLE.get(&token).unwrap().precedence;
+    p.advance().unwrap();
+    let rhs = parse_precedence(p, precedence.next())?;
+    pos.end = p.prev_token().pos.end;
+    Ok(Expr {
+        kind: ExprKind::Binary {
+            op,
+            lhs: Box::new(lhs),
+            rhs: Box::new(rhs),
+        },
+        pos,
+    })
+}
+
+///An assignment to the already parsed `target`, with the current token being the `=`.
+///The target must be a name or a field, and the value takes any expression, so `a = b = c` assigns `b = c` to `a`.
+pub(crate) fn assignment<'a>(p: &mut Parser<'a>, target: Expr) -> ExprResult {
+    let mut pos = target.pos;
+    if !matches!(target.kind, ExprKind::Name(_) | ExprKind::Field { .. }) {
+        p.emit_notice(target.pos, NoticeLevel::Error, "Only a name or a field can be assigned to".to_string());
+        return Err(());
     }
-    let current_token = &p.current_token();
-    let ret = match (&current_token.type_, &current_token.data) {
-        (TokenType::Identifier, TokenData::Str(s)) => {
-            Ok(TypeSignature::Primitive(PrimitiveType::new(s)))
+    expect(p, TokenType::Equal)?;
+    let value = expression(p)?;
+    pos.end = p.prev_token().pos.end;
+    Ok(Expr {
+        kind: ExprKind::Assign {
+            target: Box::new(target),
+            value: Box::new(value),
+        },
+        pos,
+    })
+}
+
+///Propagation of the error of the already parsed `result` with a postfix `?`, the current token.
+pub(crate) fn try_<'a>(p: &mut Parser<'a>, result: Expr) -> ExprResult {
+    let mut pos = result.pos;
+    pos.end = p.current_token().pos.end;
+    expect(p, TokenType::QMark)?;
+    Ok(Expr {
+        kind: ExprKind::Try(Box::new(result)),
+        pos,
+    })
+}
+
+///A unary operation such as `-x` or `!done`.
+pub(crate) fn unary<'a>(p: &mut Parser<'a>) -> ExprResult {
+    let mut pos = p.current_token().pos;
+    let op = match p.current_token().type_ {
+        TokenType::Minus => UnaryOp::Neg,
+        _ => UnaryOp::Not,
+    };
+    p.advance().unwrap();
+    let operand = parse_precedence(p, Precedence::Unary)?;
+    pos.end = p.prev_token().pos.end;
+    Ok(Expr {
+        kind: ExprKind::Unary {
+            op,
+            operand: Box::new(operand),
+        },
+        pos,
+    })
+}
+
+///An array literal such as `[1, 2, 3]`.
+pub(crate) fn array<'a>(p: &mut Parser<'a>) -> ExprResult {
+    let mut pos = p.current_token().pos;
+    expect(p, TokenType::LBracket)?;
+    let (elements, end) = arguments(p, TokenType::RBracket, expression)?;
+    pos.end = end.end;
+    Ok(Expr {
+        kind: ExprKind::Array(elements),
+        pos,
+    })
+}
+
+///Indexing such as `xs[i]` or slicing such as `xs[1..3]` of the already parsed `collection`,
+///with the current token being the opening bracket.
+pub(crate) fn index<'a>(p: &mut Parser<'a>, collection: Expr) -> ExprResult {
+    let mut pos = collection.pos;
+    expect(p, TokenType::LBracket)?;
+    //The bounds are parsed above range precedence so the `..` of the slice isn't taken for a range
+    let start = if p.check(TokenType::DotDot) {
+        None
+    } else {
+        Some(Box::new(parse_precedence(p, Precedence::Or)?))
+    };
+    let collection = Box::new(collection);
+    let kind = if p.check_consume(TokenType::DotDot) {
+        let end = if p.check(TokenType::RBracket) {
+            None
+        } else {
+            Some(Box::new(parse_precedence(p, Precedence::Or)?))
+        };
+        ExprKind::Slice { collection, start, end }
+    } else {
+        match start {
+            Some(index) => ExprKind::Index { collection, index },
+            None => unreachable!(),
         }
-        _ => Err(()),
     };
-    if ret.is_ok() {
+    pos.end = p.current_token().pos.end;
+    expect(p, TokenType::RBracket)?;
+    Ok(Expr { kind, pos })
+}
+
+///A method call or field access on the already parsed `receiver`, with the current token being the dot.
+pub(crate) fn method_call<'a>(p: &mut Parser<'a>, receiver: Expr) -> ExprResult {
+    let mut pos = receiver.pos;
+    expect(p, TokenType::Dot)?;
+    let name = identifier(p)?;
+    pos.end = p.current_token().pos.end;
+    p.advance().unwrap();
+    let receiver = Box::new(receiver);
+    if !p.check_consume(TokenType::LParen) {
+        return Ok(Expr {
+            kind: ExprKind::Field { receiver, name },
+            pos,
+        });
+    }
+    let (args, end) = arguments(p, TokenType::RParen, argument)?;
+    pos.end = end.end;
+    Ok(Expr {
+        kind: ExprKind::MethodCall { receiver, name, args },
+        pos,
+    })
+}
+
+///Parse a type such as `Int`, `List<T>`, `[Int; 4]`, `[Int; SIZE]` or `dyn Show`, leaving the parser on the token after it.
+pub(crate) fn type_<'a>(p: &mut Parser<'a>) -> Result<Type, ()> {
+    let pos = p.current_token().pos;
+    if p.check_consume(TokenType::KwDyn) {
+        let interface = identifier(p)?;
         p.advance().unwrap();
-        ret
-    } else {
-        Err(())
+        return Ok(Type {
+            kind: TypeKind::Dyn(interface),
+            pos,
+        });
+    }
+    if p.check_consume(TokenType::LBracket) {
+        let element = type_(p)?;
+        expect(p, TokenType::Semicolon)?;
+        let len = match (p.current_token().type_, &p.current_token().data) {
+            (TokenType::Number, TokenData::Integer(len)) if *len >= 0 => ArrayLen::Literal(*len as usize),
+            (TokenType::Identifier, TokenData::Str(name)) => ArrayLen::Const(name.to_string()),
+            _ => {
+                p.emit_notice(
+                    p.current_token().pos,
+                    NoticeLevel::Error,
+                    format!("Expected the length of the array but instead got {:?}", p.current_token().type_),
+                );
+                return Err(());
+            }
+        };
+        p.advance().unwrap();
+        expect(p, TokenType::RBracket)?;
+        return Ok(Type {
+            kind: TypeKind::Array(Box::new(element), len),
+            pos,
+        });
+    }
+    let name = match identifier(p) {
+        Ok(name) => name,
+        Err(()) => {
+            p.emit_notice(pos, NoticeLevel::Error, "Expected a type.".to_string());
+            return Err(());
+        }
+    };
+    p.advance().unwrap();
+    let mut args = Vec::new();
+    if p.check_consume(TokenType::LAngle) {
+        loop {
+            args.push(type_(p)?);
+            if !p.check_consume(TokenType::Comma) {
+                break;
+            }
+        }
+        expect(p, TokenType::RAngle)?;
     }
+    Ok(Type {
+        kind: TypeKind::Named { name, args },
+        pos,
+    })
 }
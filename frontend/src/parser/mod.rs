@@ -4,17 +4,14 @@ use core::ansi::{Bg, Fg};
 
 use std::sync::mpsc::{Receiver, Sender};
 
-use ir::{
-    hir::{HIR, HIRInstruction},
-    type_signature::TypeSignature,
-};
+use ir::hir::Module;
 
 use core::pos::BiPos as Position;
 
-use std::sync::{Arc, Mutex};
-
 use notices::{Notice, NoticeLevel};
 
+use crate::lower::Lowering;
+
 pub mod functions;
 pub mod rules;
 
@@ -25,12 +22,13 @@ const NEXT_TOKEN: usize = 2;
 #[derive(Debug, PartialEq)]
 pub enum ParseContext{
     TopLevel,
-    Local
+    Local,
+    Interface,
+    Impl
 }
 
 pub struct Parser<'a> {
     pub name: String,
-    pub ir_tx: Arc<Mutex<Sender<Option<HIR>>>>,
     pub token_rx: Receiver<LexerToken<'a>>,
     pub notice_tx: Sender<Option<Notice>>,
     pub context: ParseContext,
@@ -41,13 +39,11 @@ pub struct Parser<'a> {
 impl<'a> Parser<'a> {
     pub fn new(
         name: String,
-        ir_tx: Sender<Option<HIR>>,
         token_rx: Receiver<LexerToken<'a>>,
         notice_tx: Sender<Option<Notice>>,
     ) -> Self {
         Parser {
             name,
-            ir_tx: Arc::new(Mutex::new(ir_tx)),
             token_rx,
             notice_tx,
             context: ParseContext::TopLevel,
@@ -90,27 +86,17 @@ impl<'a> Parser<'a> {
     }
 
     pub fn emit_notice(&self, pos: Position, level: NoticeLevel, msg: String) {
-        if level == NoticeLevel::Error {
-            if let Err(e) = self.ir_tx.lock().unwrap().send(Some(HIR {
-                pos,
-                sig: TypeSignature::None,
-                ins: HIRInstruction::Halt,
-            })) {
-                eprintln!(
-                    "{}Parser notice send error: {}{}",
-                    core::ansi::Fg::BrightRed,
-                    e,
-                    core::ansi::Fg::Reset
-                );
-            }
-        }
+        self.emit_notice_with_help(pos, level, msg, None)
+    }
 
+    pub fn emit_notice_with_help(&self, pos: Position, level: NoticeLevel, msg: String, help: Option<String>) {
         let notice = Notice {
             from: "Parser".to_string(),
             msg,
             pos,
             file: self.name.clone(),
             level,
+            help,
         };
 
         if let Err(e) = self.notice_tx.send(Some(notice)) {
@@ -133,16 +119,6 @@ impl<'a> Parser<'a> {
         self.next_token().type_ == type_
     }
 
-    #[inline]
-    pub fn emit_ir(&mut self, pos: Position, sig: TypeSignature, ins: HIRInstruction) {
-        let ir = HIR { pos, sig, ins };
-        self.ir_tx
-            .lock()
-            .expect("Failed to acquire lock on ir_tx sender.")
-            .send(Some(ir))
-            .expect(format!("Failed to send IR through IR channel.").as_str())
-    }
-
     #[inline]
     pub fn check_consume(&mut self, type_: TokenType) -> bool {
         if self.check(type_) {
@@ -181,25 +157,34 @@ impl<'a> Parser<'a> {
         }
     }
 
+    ///Parse the whole module into a syntax tree, reporting any syntax errors as notices.
+    pub fn parse_module(&mut self) -> Result<ast::Module, ()> {
+        self.advance().unwrap();
+        self.advance().unwrap();
+        functions::module(self)
+    }
+
+    ///Parse the module and lower it to HIR.
     pub async fn parse<'p>(
         name: String,
-        ir_tx: Sender<Option<HIR>>,
         token_rx: Receiver<LexerToken<'p>>,
         notice_tx: Sender<Option<Notice>>,
-    ) -> Result<(), String> {
-        let mut parser = Parser::new(name, ir_tx, token_rx, notice_tx);
-        parser.advance().unwrap();
-        parser.advance().unwrap();
-        match functions::module(&mut parser) {
-            Ok(()) => {
-                parser.emit_notice(Position::default(), NoticeLevel::Halt, "Halt".to_string());
-
-                return Ok(());
-            }
-            Err(_) => {
-                parser.emit_notice(Position::default(), NoticeLevel::Halt, "Halt".to_string());
-                return Err("An error occurred while parsing module".to_string());
-            }
-        }
+    ) -> Result<Module, String> {
+        let mut parser = Parser::new(name, token_rx, notice_tx.clone());
+        let hir = parser
+            .parse_module()
+            .and_then(|module| Lowering::lower(&module, notice_tx))
+            .and_then(|hir| {
+                let notices = crate::verify_hir(&hir, "lowering");
+                if notices.is_empty() {
+                    return Ok(hir);
+                }
+                for notice in notices {
+                    parser.notice_tx.send(Some(notice)).expect("Failed to send a notice through the notice channel.");
+                }
+                Err(())
+            });
+        parser.emit_notice(Position::default(), NoticeLevel::Halt, "Halt".to_string());
+        hir.map_err(|_| "An error occurred while parsing module".to_string())
     }
 }
@@ -1,21 +1,65 @@
 use crate::{
     lexer::tokens::TokenType,
     parser::{
-        functions::{literal, nil_func},
+        functions::{
+            array, assignment, binary, call, grouping, index, literal, method_call, range, reference, try_, unary, ExprResult,
+        },
         Parser,
     },
 };
 
+use ast::Expr;
+
 use lazy_static::lazy_static;
 
 use std::collections::HashMap;
 
-pub(crate) type ParseFn = fn(&mut Parser) -> Result<(), ()>;
+///Parses an expression starting at the current token.
+pub(crate) type PrefixFn = fn(&mut Parser) -> ExprResult;
+///Parses the rest of an expression whose left hand side has already been parsed.
+pub(crate) type InfixFn = fn(&mut Parser, Expr) -> ExprResult;
+
+///How tightly an infix rule binds, from loosest to tightest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Precedence {
+    None,
+    Assignment,
+    Range,
+    Or,
+    And,
+    Equality,
+    Comparison,
+    Term,
+    Factor,
+    Unary,
+    Call,
+    Primary,
+}
+
+impl Precedence {
+    ///The precedence binding one step more tightly.
+    pub fn next(self) -> Precedence {
+        match self {
+            Precedence::None => Precedence::Assignment,
+            Precedence::Assignment => Precedence::Range,
+            Precedence::Range => Precedence::Or,
+            Precedence::Or => Precedence::And,
+            Precedence::And => Precedence::Equality,
+            Precedence::Equality => Precedence::Comparison,
+            Precedence::Comparison => Precedence::Term,
+            Precedence::Term => Precedence::Factor,
+            Precedence::Factor => Precedence::Unary,
+            Precedence::Unary => Precedence::Call,
+            Precedence::Call | Precedence::Primary => Precedence::Primary,
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct ParseRule {
-    pub(crate) prefix: ParseFn,
-    pub(crate) infix: ParseFn,
+    pub(crate) prefix: Option<PrefixFn>,
+    pub(crate) infix: Option<InfixFn>,
+    pub(crate) precedence: Precedence,
 }
 
 lazy_static! {
@@ -24,15 +68,124 @@ lazy_static! {
         m.insert(
             &TokenType::String,
             ParseRule {
-                prefix: nil_func,
-                infix: literal,
+                prefix: Some(literal),
+                infix: None,
+                precedence: Precedence::None,
             },
         );
         m.insert(
             &TokenType::Number,
             ParseRule {
-                prefix: nil_func,
-                infix: literal,
+                prefix: Some(literal),
+                infix: None,
+                precedence: Precedence::None,
+            },
+        );
+        m.insert(
+            &TokenType::Identifier,
+            ParseRule {
+                prefix: Some(reference),
+                infix: None,
+                precedence: Precedence::None,
+            },
+        );
+        m.insert(
+            &TokenType::LParen,
+            ParseRule {
+                prefix: Some(grouping),
+                infix: Some(call),
+                precedence: Precedence::Call,
+            },
+        );
+        m.insert(
+            &TokenType::LBracket,
+            ParseRule {
+                prefix: Some(array),
+                infix: Some(index),
+                precedence: Precedence::Call,
+            },
+        );
+        m.insert(
+            &TokenType::Equal,
+            ParseRule {
+                prefix: None,
+                infix: Some(assignment),
+                precedence: Precedence::Assignment,
+            },
+        );
+        m.insert(
+            &TokenType::DotDot,
+            ParseRule {
+                prefix: None,
+                infix: Some(range),
+                precedence: Precedence::Range,
+            },
+        );
+        m.insert(
+            &TokenType::DotDotEqual,
+            ParseRule {
+                prefix: None,
+                infix: Some(range),
+                precedence: Precedence::Range,
+            },
+        );
+        m.insert(
+            &TokenType::QMark,
+            ParseRule {
+                prefix: None,
+                infix: Some(try_),
+                precedence: Precedence::Call,
+            },
+        );
+        m.insert(
+            &TokenType::Dot,
+            ParseRule {
+                prefix: None,
+                infix: Some(method_call),
+                precedence: Precedence::Call,
+            },
+        );
+        for literal_token in [&TokenType::KwTrue, &TokenType::KwFalse] {
+            m.insert(
+                literal_token,
+                ParseRule {
+                    prefix: Some(literal),
+                    infix: None,
+                    precedence: Precedence::None,
+                },
+            );
+        }
+        let operators = [
+            (&TokenType::Minus, Precedence::Term),
+            (&TokenType::Plus, Precedence::Term),
+            (&TokenType::Star, Precedence::Factor),
+            (&TokenType::Slash, Precedence::Factor),
+            (&TokenType::Percent, Precedence::Factor),
+            (&TokenType::EqualEqual, Precedence::Equality),
+            (&TokenType::BangEqual, Precedence::Equality),
+            (&TokenType::LAngle, Precedence::Comparison),
+            (&TokenType::LAngleEqual, Precedence::Comparison),
+            (&TokenType::RAngle, Precedence::Comparison),
+            (&TokenType::RAngleEqual, Precedence::Comparison),
+            (&TokenType::AndAnd, Precedence::And),
+            (&TokenType::PipePipe, Precedence::Or),
+        ];
+        for (operator, precedence) in operators.iter() {
+            m.insert(
+                *operator,
+                ParseRule {
+                    prefix: if **operator == TokenType::Minus { Some(unary) } else { None },
+                    infix: Some(binary),
+                    precedence: *precedence,
+                },
+            );
+        }
+        m.insert(
+            &TokenType::Bang,
+            ParseRule {
+                prefix: Some(unary),
+                infix: None,
+                precedence: Precedence::None,
             },
         );
         m
@@ -0,0 +1,743 @@
+use crate::{
+    lexer::tokens::TokenType,
+    parser::rules::Precedence,
+    syntax::{
+        green::{GreenElement, GreenNode, GreenToken},
+        kind::SyntaxKind,
+        lex::tokenize,
+        SyntaxError,
+    },
+};
+
+///Where parsing of a piece of text starts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Entry {
+    Module,
+    Block,
+    ItemList,
+    FieldList,
+}
+
+///Parse `text` into a green node of the kind `entry` produces.
+///Errors are reported relative to the start of `text`.
+pub(crate) fn parse(text: &str, entry: Entry) -> (GreenNode, Vec<SyntaxError>) {
+    let mut p = CstParser {
+        tokens: tokenize(text),
+        pos: 0,
+        offset: 0,
+        stack: vec![Vec::new()],
+        errors: Vec::new(),
+    };
+    match entry {
+        Entry::Module => {
+            p.start();
+            while !p.at_end() {
+                p.bump_trivia();
+                let before = p.pos;
+                item(&mut p);
+                if p.pos == before {
+                    p.error_bump_any();
+                }
+            }
+            p.bump_trivia();
+            p.finish(SyntaxKind::Module);
+        }
+        Entry::Block => block(&mut p),
+        Entry::ItemList => item_list(&mut p),
+        Entry::FieldList => field_list(&mut p),
+    }
+    //Anything left over after a nested entry means the text isn't a single node of that kind
+    if !p.at_end() {
+        p.error("Expected the end of the text");
+        while !p.at_end() {
+            p.error_bump_any();
+        }
+    }
+    p.bump_trivia();
+    let mut children = p.stack.pop().unwrap_or_default();
+    let node = match children.len() {
+        1 => match children.remove(0) {
+            GreenElement::Node(node) => (*node).clone(),
+            token => GreenNode::new(SyntaxKind::Error, vec![token]),
+        },
+        _ => GreenNode::new(SyntaxKind::Error, children),
+    };
+    (node, p.errors)
+}
+
+///Builds the green tree while walking the tokens, keeping each one including trivia.
+struct CstParser<'t> {
+    tokens: Vec<(SyntaxKind, &'t str)>,
+    pos: usize,
+    ///The byte offset of the next token
+    offset: usize,
+    ///The children of each node that has been started but not finished yet
+    stack: Vec<Vec<GreenElement>>,
+    errors: Vec<SyntaxError>,
+}
+
+///The number of children the innermost unfinished node had, to wrap the ones added since in a node.
+type Checkpoint = usize;
+
+impl<'t> CstParser<'t> {
+    ///The kind of the next token that isn't trivia, or `Eof`.
+    fn current(&self) -> SyntaxKind {
+        self.nth(0)
+    }
+
+    fn nth(&self, n: usize) -> SyntaxKind {
+        self.tokens[self.pos..]
+            .iter()
+            .filter(|(kind, _)| !kind.is_trivia())
+            .nth(n)
+            .map_or(SyntaxKind::Token(TokenType::Eof), |(kind, _)| *kind)
+    }
+
+    fn at(&self, type_: TokenType) -> bool {
+        self.current() == SyntaxKind::Token(type_)
+    }
+
+    fn at_end(&self) -> bool {
+        self.at(TokenType::Eof)
+    }
+
+    fn bump_trivia(&mut self) {
+        while let Some((kind, text)) = self.tokens.get(self.pos) {
+            if !kind.is_trivia() {
+                break;
+            }
+            self.push_token(*kind, text);
+        }
+    }
+
+    fn push_token(&mut self, kind: SyntaxKind, text: &str) {
+        self.stack
+            .last_mut()
+            .unwrap()
+            .push(GreenToken::new(kind, text).into());
+        self.pos += 1;
+        self.offset += text.len();
+    }
+
+    ///Add the next token to the innermost node, after the trivia in front of it.
+    fn bump(&mut self) {
+        self.bump_trivia();
+        if let Some((kind, text)) = self.tokens.get(self.pos) {
+            self.push_token(*kind, text);
+        }
+    }
+
+    fn eat(&mut self, type_: TokenType) -> bool {
+        if self.at(type_) {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, type_: TokenType) -> bool {
+        if self.eat(type_) {
+            return true;
+        }
+        self.error(&format!("Expected a '{:?}' but instead got {}", type_, self.current()));
+        false
+    }
+
+    ///Report an error at the next token without consuming it.
+    fn error(&mut self, message: &str) {
+        self.bump_trivia();
+        let len = self.tokens.get(self.pos).map_or(0, |(_, text)| text.len());
+        self.errors.push(SyntaxError {
+            message: message.to_string(),
+            range: self.offset..self.offset + len,
+        });
+    }
+
+    ///Report an error and wrap the next token in an error node, unless it is a closing
+    ///delimiter, in which case it is left for whatever it closes.
+    fn error_bump(&mut self, message: &str) {
+        self.error(message);
+        if !self.at(TokenType::RCurly) && !self.at(TokenType::RParen) && !self.at(TokenType::RBracket) {
+            self.error_bump_any();
+        }
+    }
+
+    fn error_bump_any(&mut self) {
+        self.start();
+        self.bump();
+        self.finish(SyntaxKind::Error);
+    }
+
+    ///Start a node, leaving the trivia in front of it to the enclosing node.
+    fn start(&mut self) {
+        self.bump_trivia();
+        self.stack.push(Vec::new());
+    }
+
+    fn finish(&mut self, kind: SyntaxKind) {
+        let children = self.stack.pop().unwrap();
+        self.stack
+            .last_mut()
+            .unwrap()
+            .push(GreenNode::new(kind, children).into());
+    }
+
+    fn checkpoint(&mut self) -> Checkpoint {
+        self.bump_trivia();
+        self.stack.last().unwrap().len()
+    }
+
+    ///Wrap everything added to the innermost node since `checkpoint` in a node of `kind`.
+    fn wrap(&mut self, checkpoint: Checkpoint, kind: SyntaxKind) {
+        let children = self.stack.last_mut().unwrap().split_off(checkpoint);
+        //Trivia after the last token belongs outside the node
+        let trailing = children
+            .iter()
+            .rev()
+            .take_while(|child| child.kind().is_trivia())
+            .count();
+        let mut children = children;
+        let trailing = children.split_off(children.len() - trailing);
+        let parent = self.stack.last_mut().unwrap();
+        parent.push(GreenNode::new(kind, children).into());
+        parent.extend(trailing);
+    }
+}
+
+fn at_item(p: &CstParser) -> bool {
+    matches!(
+        p.current(),
+        SyntaxKind::Token(TokenType::At)
+            | SyntaxKind::Token(TokenType::KwPublic)
+            | SyntaxKind::Token(TokenType::KwInternal)
+            | SyntaxKind::Token(TokenType::KwPrivate)
+            | SyntaxKind::Token(TokenType::KwVal)
+            | SyntaxKind::Token(TokenType::KwVar)
+            | SyntaxKind::Token(TokenType::KwConst)
+            | SyntaxKind::Token(TokenType::KwFun)
+            | SyntaxKind::Token(TokenType::KwNative)
+            | SyntaxKind::Token(TokenType::KwStruct)
+            | SyntaxKind::Token(TokenType::KwType)
+            | SyntaxKind::Token(TokenType::KwNewtype)
+            | SyntaxKind::Token(TokenType::KwInterface)
+            | SyntaxKind::Token(TokenType::KwImpl)
+            | SyntaxKind::Token(TokenType::KwImport)
+    )
+}
+
+///Parse a declaration along with its annotations and visibility modifier.
+fn item(p: &mut CstParser) {
+    if !at_item(p) {
+        p.error_bump("Expected a declaration");
+        return;
+    }
+    let checkpoint = p.checkpoint();
+    while p.at(TokenType::At) {
+        annotation(p);
+    }
+    if matches!(
+        p.current(),
+        SyntaxKind::Token(TokenType::KwPublic) | SyntaxKind::Token(TokenType::KwInternal) | SyntaxKind::Token(TokenType::KwPrivate)
+    ) {
+        p.start();
+        p.bump();
+        p.finish(SyntaxKind::Visibility);
+    }
+    let kind = match p.current() {
+        SyntaxKind::Token(TokenType::KwVal) | SyntaxKind::Token(TokenType::KwVar) => {
+            property(p);
+            SyntaxKind::Property
+        }
+        SyntaxKind::Token(TokenType::KwConst) => {
+            property(p);
+            SyntaxKind::Const
+        }
+        SyntaxKind::Token(TokenType::KwFun) => {
+            function(p);
+            SyntaxKind::Fn
+        }
+        SyntaxKind::Token(TokenType::KwNative) => {
+            p.bump();
+            p.expect(TokenType::KwFun);
+            p.expect(TokenType::Identifier);
+            signature(p);
+            SyntaxKind::NativeFn
+        }
+        SyntaxKind::Token(TokenType::KwStruct) => {
+            p.bump();
+            p.expect(TokenType::Identifier);
+            if p.at(TokenType::LAngle) {
+                type_params(p);
+            }
+            field_list(p);
+            SyntaxKind::Struct
+        }
+        SyntaxKind::Token(TokenType::KwType) => {
+            p.bump();
+            p.expect(TokenType::Identifier);
+            p.expect(TokenType::Equal);
+            type_(p);
+            SyntaxKind::TypeAlias
+        }
+        SyntaxKind::Token(TokenType::KwNewtype) => {
+            p.bump();
+            p.expect(TokenType::Identifier);
+            p.expect(TokenType::LParen);
+            type_(p);
+            p.expect(TokenType::RParen);
+            SyntaxKind::Newtype
+        }
+        SyntaxKind::Token(TokenType::KwInterface) => {
+            p.bump();
+            p.expect(TokenType::Identifier);
+            item_list(p);
+            SyntaxKind::Interface
+        }
+        SyntaxKind::Token(TokenType::KwImpl) => {
+            p.bump();
+            type_(p);
+            if p.eat(TokenType::KwFor) {
+                type_(p);
+            }
+            item_list(p);
+            SyntaxKind::Impl
+        }
+        SyntaxKind::Token(TokenType::KwImport) => {
+            p.bump();
+            p.expect(TokenType::Identifier);
+            SyntaxKind::Import
+        }
+        _ => {
+            p.error("Expected a declaration after its annotations and visibility");
+            SyntaxKind::Error
+        }
+    };
+    p.wrap(checkpoint, kind);
+}
+
+fn annotation(p: &mut CstParser) {
+    p.start();
+    p.bump();
+    p.expect(TokenType::Identifier);
+    if p.eat(TokenType::LParen) {
+        while !p.at(TokenType::RParen) && !p.at_end() {
+            match p.current() {
+                SyntaxKind::Token(TokenType::String)
+                | SyntaxKind::Token(TokenType::Number)
+                | SyntaxKind::Token(TokenType::Identifier) => p.bump(),
+                _ => p.error_bump("Expected a literal or a name as an annotation argument"),
+            }
+            if !p.eat(TokenType::Comma) {
+                break;
+            }
+        }
+        p.expect(TokenType::RParen);
+    }
+    p.finish(SyntaxKind::Annotation);
+}
+
+///Parse a `val`, `var` or `const` declaration.
+fn property(p: &mut CstParser) {
+    p.bump();
+    p.expect(TokenType::Identifier);
+    if p.eat(TokenType::Colon) {
+        type_(p);
+    }
+    p.expect(TokenType::Equal);
+    expression(p);
+}
+
+///Parse a function from its `fun` keyword. The body is left out by required interface methods.
+fn function(p: &mut CstParser) {
+    p.bump();
+    if p.at(TokenType::LAngle) {
+        type_params(p);
+    }
+    let receiver = match p.current() {
+        SyntaxKind::Token(TokenType::KwDyn) | SyntaxKind::Token(TokenType::LBracket) => true,
+        SyntaxKind::Token(TokenType::Identifier) => matches!(
+            p.nth(1),
+            SyntaxKind::Token(TokenType::Dot) | SyntaxKind::Token(TokenType::LAngle)
+        ),
+        _ => false,
+    };
+    if receiver {
+        p.start();
+        type_(p);
+        p.expect(TokenType::Dot);
+        p.finish(SyntaxKind::Receiver);
+    }
+    p.expect(TokenType::Identifier);
+    signature(p);
+    if p.eat(TokenType::Equal) {
+        expression(p);
+    } else if p.at(TokenType::LCurly) {
+        block(p);
+    }
+}
+
+///Parse the parameter list and return type of a function.
+fn signature(p: &mut CstParser) {
+    p.start();
+    if p.expect(TokenType::LParen) {
+        while !p.at(TokenType::RParen) && !p.at_end() {
+            if !param(p) {
+                break;
+            }
+            if !p.eat(TokenType::Comma) {
+                break;
+            }
+        }
+        p.expect(TokenType::RParen);
+    }
+    p.finish(SyntaxKind::ParamList);
+    if p.eat(TokenType::Colon) {
+        type_(p);
+    }
+}
+
+fn param(p: &mut CstParser) -> bool {
+    p.start();
+    p.eat(TokenType::KwVararg);
+    if p.eat(TokenType::And) {
+        p.eat(TokenType::KwMut);
+    }
+    let ok = p.expect(TokenType::Identifier);
+    if ok && p.eat(TokenType::Colon) {
+        type_(p);
+        if p.eat(TokenType::Equal) {
+            expression(p);
+        }
+    }
+    p.finish(SyntaxKind::Param);
+    ok
+}
+
+fn type_params(p: &mut CstParser) {
+    p.start();
+    p.bump();
+    while !p.at(TokenType::RAngle) && !p.at_end() {
+        p.start();
+        let ok = p.expect(TokenType::Identifier);
+        if ok && p.eat(TokenType::Colon) {
+            p.expect(TokenType::Identifier);
+            while p.eat(TokenType::Plus) {
+                p.expect(TokenType::Identifier);
+            }
+        }
+        p.finish(SyntaxKind::TypeParam);
+        if !ok || !p.eat(TokenType::Comma) {
+            break;
+        }
+    }
+    p.expect(TokenType::RAngle);
+    p.finish(SyntaxKind::TypeParams);
+}
+
+///Parse `{` followed by elements up to and including `}`, recovering from errors inside.
+fn delimited(p: &mut CstParser, kind: SyntaxKind, mut element: impl FnMut(&mut CstParser)) {
+    p.start();
+    if p.expect(TokenType::LCurly) {
+        while !p.at(TokenType::RCurly) && !p.at_end() {
+            p.bump_trivia();
+            let before = p.pos;
+            element(p);
+            if p.pos == before {
+                p.error_bump_any();
+            }
+        }
+        p.expect(TokenType::RCurly);
+    }
+    p.finish(kind);
+}
+
+fn item_list(p: &mut CstParser) {
+    delimited(p, SyntaxKind::ItemList, item);
+}
+
+fn field_list(p: &mut CstParser) {
+    delimited(p, SyntaxKind::FieldList, |p| {
+        p.start();
+        if matches!(
+            p.current(),
+            SyntaxKind::Token(TokenType::KwPublic) | SyntaxKind::Token(TokenType::KwInternal) | SyntaxKind::Token(TokenType::KwPrivate)
+        ) {
+            p.start();
+            p.bump();
+            p.finish(SyntaxKind::Visibility);
+        }
+        if p.expect(TokenType::Identifier) && p.expect(TokenType::Colon) {
+            type_(p);
+        }
+        p.eat(TokenType::Comma);
+        p.finish(SyntaxKind::Field);
+    });
+}
+
+fn block(p: &mut CstParser) {
+    delimited(p, SyntaxKind::Block, statement);
+}
+
+fn statement(p: &mut CstParser) {
+    match p.current() {
+        SyntaxKind::Token(TokenType::KwLet) => {
+            p.start();
+            p.bump();
+            p.eat(TokenType::KwMut);
+            p.expect(TokenType::Identifier);
+            if p.eat(TokenType::Colon) {
+                type_(p);
+            }
+            p.expect(TokenType::Equal);
+            expression(p);
+            p.finish(SyntaxKind::Let);
+        }
+        SyntaxKind::Token(TokenType::KwFor) => {
+            p.start();
+            p.bump();
+            p.expect(TokenType::Identifier);
+            p.expect(TokenType::KwIn);
+            expression(p);
+            block(p);
+            p.finish(SyntaxKind::For);
+        }
+        SyntaxKind::Token(TokenType::KwIf) => if_(p),
+        SyntaxKind::Token(TokenType::KwWhile) => {
+            p.start();
+            p.bump();
+            expression(p);
+            block(p);
+            p.finish(SyntaxKind::While);
+        }
+        SyntaxKind::Token(TokenType::KwLoop) => {
+            p.start();
+            p.bump();
+            block(p);
+            p.finish(SyntaxKind::Loop);
+        }
+        SyntaxKind::Token(TokenType::KwBreak) => {
+            p.start();
+            p.bump();
+            p.finish(SyntaxKind::Break);
+        }
+        SyntaxKind::Token(TokenType::KwContinue) => {
+            p.start();
+            p.bump();
+            p.finish(SyntaxKind::Continue);
+        }
+        SyntaxKind::Token(TokenType::KwReturn) => {
+            p.start();
+            p.bump();
+            if !p.at(TokenType::RCurly) {
+                expression(p);
+            }
+            p.finish(SyntaxKind::Return);
+        }
+        SyntaxKind::Token(TokenType::LCurly) => block(p),
+        _ if at_item(p) => item(p),
+        _ => expression(p),
+    }
+}
+
+fn if_(p: &mut CstParser) {
+    p.start();
+    p.bump();
+    expression(p);
+    block(p);
+    if p.at(TokenType::KwElse) {
+        p.start();
+        p.bump();
+        if p.at(TokenType::KwIf) {
+            if_(p);
+        } else {
+            block(p);
+        }
+        p.finish(SyntaxKind::Else);
+    }
+    p.finish(SyntaxKind::If);
+}
+
+fn expression(p: &mut CstParser) {
+    expression_bp(p, Precedence::Assignment);
+}
+
+///The precedence of the infix rule a token starts, mirroring the parser's rule table.
+fn infix_precedence(kind: SyntaxKind) -> Option<Precedence> {
+    match kind {
+        SyntaxKind::Token(TokenType::LParen)
+        | SyntaxKind::Token(TokenType::LBracket)
+        | SyntaxKind::Token(TokenType::Dot)
+        | SyntaxKind::Token(TokenType::QMark) => Some(Precedence::Call),
+        SyntaxKind::Token(TokenType::DotDot) | SyntaxKind::Token(TokenType::DotDotEqual) => Some(Precedence::Range),
+        SyntaxKind::Token(TokenType::Star) | SyntaxKind::Token(TokenType::Slash) | SyntaxKind::Token(TokenType::Percent) => {
+            Some(Precedence::Factor)
+        }
+        SyntaxKind::Token(TokenType::Plus) | SyntaxKind::Token(TokenType::Minus) => Some(Precedence::Term),
+        SyntaxKind::Token(TokenType::LAngle)
+        | SyntaxKind::Token(TokenType::LAngleEqual)
+        | SyntaxKind::Token(TokenType::RAngle)
+        | SyntaxKind::Token(TokenType::RAngleEqual) => Some(Precedence::Comparison),
+        SyntaxKind::Token(TokenType::EqualEqual) | SyntaxKind::Token(TokenType::BangEqual) => Some(Precedence::Equality),
+        SyntaxKind::Token(TokenType::AndAnd) => Some(Precedence::And),
+        SyntaxKind::Token(TokenType::PipePipe) => Some(Precedence::Or),
+        _ => None,
+    }
+}
+
+///Parse an expression made of operators that bind at least as tightly as `precedence`.
+fn expression_bp(p: &mut CstParser, precedence: Precedence) {
+    let checkpoint = p.checkpoint();
+    match p.current() {
+        SyntaxKind::Token(TokenType::Number)
+        | SyntaxKind::Token(TokenType::String)
+        | SyntaxKind::Token(TokenType::KwTrue)
+        | SyntaxKind::Token(TokenType::KwFalse) => {
+            p.bump();
+            p.wrap(checkpoint, SyntaxKind::Literal);
+        }
+        SyntaxKind::Token(TokenType::Minus) | SyntaxKind::Token(TokenType::Bang) => {
+            p.bump();
+            expression_bp(p, Precedence::Unary);
+            p.wrap(checkpoint, SyntaxKind::Unary);
+        }
+        SyntaxKind::Token(TokenType::Identifier) => {
+            p.bump();
+            p.wrap(checkpoint, SyntaxKind::Name);
+        }
+        SyntaxKind::Token(TokenType::LParen) => {
+            p.bump();
+            expression(p);
+            p.expect(TokenType::RParen);
+            p.wrap(checkpoint, SyntaxKind::Paren);
+        }
+        SyntaxKind::Token(TokenType::LBracket) => {
+            arguments(p, TokenType::RBracket);
+            p.wrap(checkpoint, SyntaxKind::Array);
+        }
+        kind => {
+            p.error_bump(&format!("Expected an expression but instead got {}", kind));
+            return;
+        }
+    }
+    while let Some(rule) = infix_precedence(p.current()) {
+        if precedence > rule {
+            break;
+        }
+        let kind = match p.current() {
+            SyntaxKind::Token(TokenType::LParen) => {
+                p.start();
+                arguments(p, TokenType::RParen);
+                p.finish(SyntaxKind::ArgList);
+                SyntaxKind::Call
+            }
+            SyntaxKind::Token(TokenType::LBracket) => {
+                p.bump();
+                let mut kind = SyntaxKind::Index;
+                if !p.at(TokenType::DotDot) {
+                    expression_bp(p, Precedence::Or);
+                }
+                if p.eat(TokenType::DotDot) {
+                    kind = SyntaxKind::Slice;
+                    if !p.at(TokenType::RBracket) {
+                        expression_bp(p, Precedence::Or);
+                    }
+                }
+                p.expect(TokenType::RBracket);
+                kind
+            }
+            SyntaxKind::Token(TokenType::Dot) => {
+                p.bump();
+                p.expect(TokenType::Identifier);
+                if p.at(TokenType::LParen) {
+                    p.start();
+                    arguments(p, TokenType::RParen);
+                    p.finish(SyntaxKind::ArgList);
+                    SyntaxKind::MethodCall
+                } else {
+                    SyntaxKind::FieldAccess
+                }
+            }
+            SyntaxKind::Token(TokenType::QMark) => {
+                p.bump();
+                SyntaxKind::Try
+            }
+            SyntaxKind::Token(TokenType::DotDot) | SyntaxKind::Token(TokenType::DotDotEqual) => {
+                p.bump();
+                expression_bp(p, Precedence::Or);
+                SyntaxKind::Range
+            }
+            _ => {
+                p.bump();
+                expression_bp(p, rule.next());
+                SyntaxKind::Binary
+            }
+        };
+        p.wrap(checkpoint, kind);
+    }
+}
+
+///Parse the opening token followed by comma separated expressions up to and including `closing`.
+///The arguments of a call can also be given by name, as in `x = 1`.
+fn arguments(p: &mut CstParser, closing: TokenType) {
+    p.bump();
+    while !p.at(closing) && !p.at_end() {
+        let before = p.pos;
+        if closing == TokenType::RParen && p.at(TokenType::Identifier) && p.nth(1) == SyntaxKind::Token(TokenType::Equal) {
+            p.start();
+            p.bump();
+            p.bump();
+            expression(p);
+            p.finish(SyntaxKind::NamedArg);
+        } else {
+            expression(p);
+        }
+        if p.pos == before || !p.eat(TokenType::Comma) {
+            break;
+        }
+    }
+    p.expect(closing);
+}
+
+fn type_(p: &mut CstParser) {
+    p.start();
+    let kind = match p.current() {
+        SyntaxKind::Token(TokenType::KwDyn) => {
+            p.bump();
+            p.expect(TokenType::Identifier);
+            SyntaxKind::DynType
+        }
+        SyntaxKind::Token(TokenType::LBracket) => {
+            p.bump();
+            type_(p);
+            p.expect(TokenType::Semicolon);
+            //The length is a number or the name of a constant
+            if !p.eat(TokenType::Identifier) {
+                p.expect(TokenType::Number);
+            }
+            p.expect(TokenType::RBracket);
+            SyntaxKind::ArrayType
+        }
+        SyntaxKind::Token(TokenType::Identifier) => {
+            p.bump();
+            if p.at(TokenType::LAngle) {
+                p.start();
+                p.bump();
+                loop {
+                    type_(p);
+                    if !p.eat(TokenType::Comma) {
+                        break;
+                    }
+                }
+                p.expect(TokenType::RAngle);
+                p.finish(SyntaxKind::TypeArgs);
+            }
+            SyntaxKind::NamedType
+        }
+        _ => {
+            p.error("Expected a type");
+            SyntaxKind::Error
+        }
+    };
+    p.finish(kind);
+}
@@ -0,0 +1,99 @@
+use crate::lexer::tokens::TokenType;
+
+///The kind of a token or node in the concrete syntax tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SyntaxKind {
+    Whitespace,
+    ///A `//` comment up to the end of its line.
+    Comment,
+    ///A token of the language, as the lexer classifies it.
+    ///Characters the lexer rejects are kept as `Token(TokenType::Err)`.
+    Token(TokenType),
+
+    Module,
+    ///An `@name(args)` annotation in front of a declaration.
+    Annotation,
+    Visibility,
+    Property,
+    Const,
+    Fn,
+    ///The `Type.` in front of the name of an extension function.
+    Receiver,
+    NativeFn,
+    Struct,
+    TypeAlias,
+    Newtype,
+    Interface,
+    Impl,
+    ///An `import` of another module.
+    Import,
+    TypeParams,
+    TypeParam,
+    ParamList,
+    Param,
+    ///The `{ ... }` statements of a function, loop, conditional or nested block.
+    Block,
+    ///The `{ ... }` methods of an interface or impl block.
+    ItemList,
+    ///The `{ ... }` fields of a struct.
+    FieldList,
+    Field,
+    Let,
+    For,
+    If,
+    ///The `else` branch of a conditional, holding either a block or another `If`.
+    Else,
+    While,
+    Loop,
+    Break,
+    Continue,
+    Return,
+
+    Literal,
+    Name,
+    Paren,
+    Call,
+    ArgList,
+    ///An argument given by the name of its param, as in `x = 1`.
+    NamedArg,
+    MethodCall,
+    FieldAccess,
+    Array,
+    Index,
+    Slice,
+    Range,
+    ///An error propagated with a postfix `?`.
+    Try,
+    Binary,
+    Unary,
+
+    NamedType,
+    TypeArgs,
+    ArrayType,
+    DynType,
+
+    ///Tokens that could not be parsed as part of anything else.
+    Error,
+}
+
+impl SyntaxKind {
+    ///Whether tokens of this kind carry no meaning for the compiler.
+    pub fn is_trivia(self) -> bool {
+        matches!(self, SyntaxKind::Whitespace | SyntaxKind::Comment)
+    }
+}
+
+impl From<TokenType> for SyntaxKind {
+    fn from(type_: TokenType) -> Self {
+        SyntaxKind::Token(type_)
+    }
+}
+
+impl std::fmt::Display for SyntaxKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SyntaxKind::Token(type_) => write!(f, "{:?}", type_),
+            kind => write!(f, "{:?}", kind),
+        }
+    }
+}
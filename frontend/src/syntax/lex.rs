@@ -0,0 +1,52 @@
+use crate::{
+    lexer::{delimiter, keyword, operator, tokens::TokenType},
+    syntax::kind::SyntaxKind,
+};
+
+///Split `text` into tokens that together spell it out exactly, whitespace and comments included.
+///Tokens are classified the same way the compiler's lexer does, except that nothing is ever
+///dropped: an unterminated string or a character the lexer rejects becomes an error token.
+pub fn tokenize(text: &str) -> Vec<(SyntaxKind, &str)> {
+    let mut tokens = Vec::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let (kind, len) = token(c, rest);
+        tokens.push((kind, &rest[..len]));
+        rest = &rest[len..];
+    }
+    tokens
+}
+
+///The kind and length in bytes of the token at the start of `rest`, which starts with `c`.
+fn token(c: char, rest: &str) -> (SyntaxKind, usize) {
+    let len_while = |skip: usize, f: &dyn Fn(char) -> bool| {
+        skip + rest[skip..].find(|c: char| !f(c)).unwrap_or(rest.len() - skip)
+    };
+    match c {
+        c if c.is_whitespace() => (SyntaxKind::Whitespace, len_while(0, &|c| c.is_whitespace())),
+        '/' if rest[1..].starts_with('/') => (SyntaxKind::Comment, len_while(0, &|c| c != '\n')),
+        c if c.is_alphabetic() => {
+            let len = len_while(c.len_utf8(), &|c| delimiter(c).is_none() && !c.is_whitespace());
+            (keyword(&rest[..len]).into(), len)
+        }
+        '"' => match rest[1..].find('"') {
+            Some(end) => (TokenType::String.into(), end + 2),
+            None => (TokenType::Err.into(), rest.len()),
+        },
+        c if c.is_ascii_digit() => {
+            let mut len = len_while(0, &|c| c.is_ascii_digit());
+            //A dot only makes a float if a digit follows it, so `1..3` stays a range
+            if rest[len..].starts_with('.') && rest[len + 1..].starts_with(|c: char| c.is_ascii_digit()) {
+                len = len_while(len + 1, &|c| c.is_ascii_digit());
+            }
+            (TokenType::Number.into(), len)
+        }
+        '.' if rest.starts_with("..=") => (TokenType::DotDotEqual.into(), 3),
+        '.' if rest.starts_with("..") => (TokenType::DotDot.into(), 2),
+        c => match (operator(c, rest[c.len_utf8()..].chars().next()), delimiter(c)) {
+            (Some((type_, op)), _) => (type_.into(), op.len()),
+            (None, Some(type_)) => (type_.into(), 1),
+            (None, None) => (TokenType::Err.into(), c.len_utf8()),
+        },
+    }
+}
@@ -0,0 +1,257 @@
+use std::sync::Arc;
+
+pub mod green;
+mod grammar;
+pub mod kind;
+pub mod lex;
+pub mod red;
+
+use grammar::Entry;
+use green::{GreenNode, GreenToken};
+use kind::SyntaxKind;
+use lex::tokenize;
+use red::{SyntaxElement, SyntaxNode, TextRange};
+
+use crate::lexer::tokens::TokenType;
+
+///A lossless concrete syntax tree of a file, for tools that need to reproduce or rewrite the
+///source exactly: every token, comment and bit of whitespace is kept, and input that does not
+///parse is kept in `Error` nodes instead of stopping the parse.
+#[derive(Clone)]
+pub struct SyntaxTree {
+    green: Arc<GreenNode>,
+    errors: Vec<SyntaxError>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxError {
+    pub message: String,
+    pub range: TextRange,
+}
+
+///A change to the text of a file: `range` gets replaced by `insert`.
+#[derive(Debug, Clone)]
+pub struct TextEdit {
+    pub range: TextRange,
+    pub insert: String,
+}
+
+impl TextEdit {
+    ///Apply the edit to `text`, whose start is at `offset` in the file being edited.
+    fn apply(&self, text: &str, offset: usize) -> String {
+        let mut ret = text.to_string();
+        ret.replace_range(self.range.start - offset..self.range.end - offset, &self.insert);
+        ret
+    }
+
+    ///How much longer the text gets.
+    fn delta(&self) -> isize {
+        self.insert.len() as isize - self.range.len() as isize
+    }
+}
+
+impl SyntaxTree {
+    pub fn parse(text: &str) -> Self {
+        let (green, errors) = grammar::parse(text, Entry::Module);
+        SyntaxTree {
+            green: Arc::new(green),
+            errors,
+        }
+    }
+
+    pub fn root(&self) -> SyntaxNode {
+        SyntaxNode::new_root(self.green.clone())
+    }
+
+    pub fn errors(&self) -> &[SyntaxError] {
+        &self.errors
+    }
+
+    ///The exact text the tree was parsed from.
+    pub fn text(&self) -> String {
+        self.green.to_string()
+    }
+
+    ///The tree of the text after `edit`, which is the same tree a full parse of the edited
+    ///text would give. Only the smallest token or `{ ... }` node around the edit gets
+    ///reparsed when possible; the rest of the green tree is shared with this one.
+    pub fn reparse(&self, edit: &TextEdit) -> SyntaxTree {
+        let element = self.root().covering_element(edit.range.clone());
+        if let SyntaxElement::Token(token) = &element {
+            if let Some(tree) = self.relex_token(token, edit) {
+                return tree;
+            }
+        }
+        if let Some(tree) = self.reparse_block(element.node(), edit) {
+            return tree;
+        }
+        SyntaxTree::parse(&edit.apply(&self.text(), 0))
+    }
+
+    ///Relex a token the edit falls in, which works as long as it stays a single token of the
+    ///same kind that can't change how the tokens around it parse.
+    fn relex_token(&self, token: &red::SyntaxToken, edit: &TextEdit) -> Option<SyntaxTree> {
+        let relexable = matches!(
+            token.kind(),
+            SyntaxKind::Whitespace
+                | SyntaxKind::Comment
+                | SyntaxKind::Token(TokenType::Identifier)
+                | SyntaxKind::Token(TokenType::Number)
+                | SyntaxKind::Token(TokenType::String)
+        );
+        if !relexable {
+            return None;
+        }
+        let range = token.text_range();
+        let text = edit.apply(token.text(), range.start);
+        let tokens = tokenize(&text);
+        if tokens.len() != 1 || tokens[0].0 != token.kind() {
+            return None;
+        }
+        //The token must not merge with the tokens around it, such as a number running into the `.5`
+        //after it, or whitespace losing the newline that ends the comment in front of it. A float
+        //spans up to three tokens, so two on each side are checked.
+        let before = self.neighbours(range.start, -1);
+        let after = self.neighbours(range.end, 1);
+        let texts = before
+            .iter()
+            .rev()
+            .map(|token| token.text())
+            .chain(std::iter::once(text.as_str()))
+            .chain(after.iter().map(|token| token.text()))
+            .collect::<Vec<&str>>();
+        let lens = tokenize(&texts.concat()).iter().map(|(_, t)| t.len()).collect::<Vec<usize>>();
+        let expected = texts.iter().map(|text| text.len()).filter(|len| *len > 0).collect::<Vec<usize>>();
+        if lens != expected {
+            return None;
+        }
+        let green = token.replace_with(GreenToken::new(token.kind(), &text));
+        Some(SyntaxTree {
+            green,
+            errors: self.shift_errors(range, Vec::new(), edit.delta()),
+        })
+    }
+
+    ///The two tokens next to `offset`, going forward from it when `step` is positive and back otherwise.
+    fn neighbours(&self, offset: usize, step: isize) -> Vec<red::SyntaxToken> {
+        let mut tokens = Vec::new();
+        let mut offset = offset;
+        while tokens.len() < 2 {
+            let token = if step > 0 {
+                self.token_at(offset)
+            } else {
+                offset.checked_sub(1).and_then(|offset| self.token_at(offset))
+            };
+            let token = match token {
+                Some(token) => token,
+                None => break,
+            };
+            let range = token.text_range();
+            offset = if step > 0 { range.end } else { range.start };
+            tokens.push(token);
+        }
+        tokens
+    }
+
+    ///The token containing the byte at `offset`.
+    fn token_at(&self, offset: usize) -> Option<red::SyntaxToken> {
+        match self.root().covering_element(offset..offset + 1) {
+            SyntaxElement::Token(token) => Some(token),
+            SyntaxElement::Node(_) => None,
+        }
+    }
+
+    ///Reparse the innermost `{ ... }` node containing the edit, when its braces stay balanced.
+    fn reparse_block(&self, node: SyntaxNode, edit: &TextEdit) -> Option<SyntaxTree> {
+        let node = node.ancestors().find(|node| {
+            let range = node.text_range();
+            reparse_entry(node.kind()).is_some() && range.start < edit.range.start && edit.range.end < range.end
+        })?;
+        let entry = reparse_entry(node.kind())?;
+        //The closing brace has to be the node's own: a `}` ending a nested node means this one is unclosed
+        let closed = node.children_with_tokens().filter(|child| !child.kind().is_trivia()).last();
+        if !matches!(closed, Some(SyntaxElement::Token(token)) if token.kind() == SyntaxKind::Token(TokenType::RCurly)) {
+            return None;
+        }
+        let range = node.text_range();
+        let text = edit.apply(&node.text(), range.start);
+        if !is_balanced(&text) {
+            return None;
+        }
+        let (green, errors) = grammar::parse(&text, entry);
+        if green.kind() != node.kind() {
+            return None;
+        }
+        let new_errors = errors
+            .into_iter()
+            .map(|error| SyntaxError {
+                message: error.message,
+                range: error.range.start + range.start..error.range.end + range.start,
+            })
+            .collect();
+        Some(SyntaxTree {
+            green: node.replace_with(green),
+            errors: self.shift_errors(range, new_errors, edit.delta()),
+        })
+    }
+
+    ///The errors after reparsing `replaced`, whose errors get swapped for `added`.
+    ///Errors reported at the start of `replaced` came from whatever encloses it, so they are kept,
+    ///and errors after it move along with the text by `delta`.
+    fn shift_errors(&self, replaced: TextRange, added: Vec<SyntaxError>, delta: isize) -> Vec<SyntaxError> {
+        let shift = |offset: usize| (offset as isize + delta) as usize;
+        let mut errors = Vec::new();
+        for error in self.errors.iter() {
+            let range = if error.range.start <= replaced.start {
+                let end = if error.range.end >= replaced.end {
+                    shift(error.range.end)
+                } else {
+                    error.range.end
+                };
+                error.range.start..end
+            } else if error.range.start >= replaced.end {
+                shift(error.range.start)..shift(error.range.end)
+            } else {
+                continue;
+            };
+            errors.push(SyntaxError {
+                message: error.message.clone(),
+                range,
+            });
+        }
+        errors.extend(added);
+        errors.sort_by_key(|error| error.range.start);
+        errors
+    }
+}
+
+///The grammar entry to reparse a node of `kind` with, if it can be reparsed on its own.
+fn reparse_entry(kind: SyntaxKind) -> Option<Entry> {
+    match kind {
+        SyntaxKind::Block => Some(Entry::Block),
+        SyntaxKind::ItemList => Some(Entry::ItemList),
+        SyntaxKind::FieldList => Some(Entry::FieldList),
+        _ => None,
+    }
+}
+
+///Whether `text` is a single `{ ... }` group: it starts with `{` and its matching `}` is the last token.
+fn is_balanced(text: &str) -> bool {
+    let tokens = tokenize(text);
+    let mut tokens = tokens.iter().map(|(kind, _)| *kind).filter(|kind| !kind.is_trivia());
+    if tokens.next() != Some(SyntaxKind::Token(TokenType::LCurly)) {
+        return false;
+    }
+    let mut depth = 1;
+    for kind in tokens {
+        if depth == 0 {
+            return false;
+        }
+        match kind {
+            SyntaxKind::Token(TokenType::LCurly) => depth += 1,
+            SyntaxKind::Token(TokenType::RCurly) => depth -= 1,
+            _ => {}
+        }
+    }
+    depth == 0
+}
@@ -0,0 +1,243 @@
+use crate::syntax::{
+    green::{GreenElement, GreenNode, GreenToken},
+    kind::SyntaxKind,
+};
+
+use std::{ops::Range, rc::Rc, sync::Arc};
+
+///A range of byte offsets into the source text.
+pub type TextRange = Range<usize>;
+
+///A node of the red tree, which is a view of a green node at a position in the file.
+///Red nodes are created on demand while walking down from the root, and know their parent
+///and offset so they can answer questions about ranges and ancestry.
+#[derive(Clone)]
+pub struct SyntaxNode(Rc<NodeData>);
+
+struct NodeData {
+    green: Arc<GreenNode>,
+    parent: Option<SyntaxNode>,
+    ///The index of this node among the children of its parent
+    index: usize,
+    offset: usize,
+}
+
+#[derive(Clone)]
+pub struct SyntaxToken {
+    green: Arc<GreenToken>,
+    parent: SyntaxNode,
+    index: usize,
+    offset: usize,
+}
+
+#[derive(Clone, Debug)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+impl SyntaxNode {
+    pub fn new_root(green: Arc<GreenNode>) -> Self {
+        SyntaxNode(Rc::new(NodeData {
+            green,
+            parent: None,
+            index: 0,
+            offset: 0,
+        }))
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.0.green.kind()
+    }
+
+    pub fn green(&self) -> &Arc<GreenNode> {
+        &self.0.green
+    }
+
+    pub fn text_range(&self) -> TextRange {
+        self.0.offset..self.0.offset + self.0.green.text_len()
+    }
+
+    ///The exact source text of the node, including its comments and whitespace.
+    pub fn text(&self) -> String {
+        self.0.green.to_string()
+    }
+
+    pub fn parent(&self) -> Option<SyntaxNode> {
+        self.0.parent.clone()
+    }
+
+    ///This node followed by each of its ancestors up to the root.
+    pub fn ancestors(&self) -> impl Iterator<Item = SyntaxNode> {
+        std::iter::successors(Some(self.clone()), SyntaxNode::parent)
+    }
+
+    pub fn children_with_tokens(&self) -> impl Iterator<Item = SyntaxElement> {
+        let parent = self.clone();
+        let green = self.0.green.clone();
+        let mut offset = self.0.offset;
+        (0..green.children().len()).map(move |index| {
+            let child = &green.children()[index];
+            let start = offset;
+            offset += child.text_len();
+            match child {
+                GreenElement::Node(node) => SyntaxElement::Node(SyntaxNode(Rc::new(NodeData {
+                    green: node.clone(),
+                    parent: Some(parent.clone()),
+                    index,
+                    offset: start,
+                }))),
+                GreenElement::Token(token) => SyntaxElement::Token(SyntaxToken {
+                    green: token.clone(),
+                    parent: parent.clone(),
+                    index,
+                    offset: start,
+                }),
+            }
+        })
+    }
+
+    pub fn children(&self) -> impl Iterator<Item = SyntaxNode> {
+        self.children_with_tokens().filter_map(|child| match child {
+            SyntaxElement::Node(node) => Some(node),
+            SyntaxElement::Token(_) => None,
+        })
+    }
+
+    ///This node and every node below it, in preorder.
+    pub fn descendants(&self) -> impl Iterator<Item = SyntaxNode> {
+        let mut stack = vec![self.clone()];
+        std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            let mut children = node.children().collect::<Vec<SyntaxNode>>();
+            children.reverse();
+            stack.extend(children);
+            Some(node)
+        })
+    }
+
+    ///The first token of the node that isn't whitespace or a comment.
+    pub fn first_token(&self) -> Option<SyntaxToken> {
+        self.children_with_tokens().find_map(|child| match child {
+            SyntaxElement::Node(node) => node.first_token(),
+            SyntaxElement::Token(token) if !token.kind().is_trivia() => Some(token),
+            SyntaxElement::Token(_) => None,
+        })
+    }
+
+    ///The last token of the node that isn't whitespace or a comment.
+    pub fn last_token(&self) -> Option<SyntaxToken> {
+        let children = self.children_with_tokens().collect::<Vec<SyntaxElement>>();
+        children.into_iter().rev().find_map(|child| match child {
+            SyntaxElement::Node(node) => node.last_token(),
+            SyntaxElement::Token(token) if !token.kind().is_trivia() => Some(token),
+            SyntaxElement::Token(_) => None,
+        })
+    }
+
+    ///The deepest node or token whose range contains all of `range`.
+    pub fn covering_element(&self, range: TextRange) -> SyntaxElement {
+        let mut node = self.clone();
+        loop {
+            let child = node.children_with_tokens().find(|child| {
+                let child_range = child.text_range();
+                child_range.start <= range.start && range.end <= child_range.end && !child_range.is_empty()
+            });
+            match child {
+                Some(SyntaxElement::Node(child)) => node = child,
+                Some(token) => return token,
+                None => return SyntaxElement::Node(node),
+            }
+        }
+    }
+
+    ///Build the green tree of the whole file with this node replaced by `green`.
+    ///Returns the new root, which shares every subtree not on the path to this node.
+    pub fn replace_with(&self, green: GreenNode) -> Arc<GreenNode> {
+        match &self.0.parent {
+            Some(parent) => parent.replace_with(parent.green().replace_child(self.0.index, green.into())),
+            None => Arc::new(green),
+        }
+    }
+}
+
+impl SyntaxToken {
+    pub fn kind(&self) -> SyntaxKind {
+        self.green.kind()
+    }
+
+    pub fn text(&self) -> &str {
+        self.green.text()
+    }
+
+    pub fn text_range(&self) -> TextRange {
+        self.offset..self.offset + self.green.text_len()
+    }
+
+    pub fn parent(&self) -> SyntaxNode {
+        self.parent.clone()
+    }
+
+    ///Build the green tree of the whole file with this token replaced by `green`.
+    pub fn replace_with(&self, green: GreenToken) -> Arc<GreenNode> {
+        self.parent
+            .replace_with(self.parent.green().replace_child(self.index, green.into()))
+    }
+}
+
+impl SyntaxElement {
+    pub fn kind(&self) -> SyntaxKind {
+        match self {
+            SyntaxElement::Node(node) => node.kind(),
+            SyntaxElement::Token(token) => token.kind(),
+        }
+    }
+
+    pub fn text_range(&self) -> TextRange {
+        match self {
+            SyntaxElement::Node(node) => node.text_range(),
+            SyntaxElement::Token(token) => token.text_range(),
+        }
+    }
+
+    ///The node itself, or the node containing the token.
+    pub fn node(&self) -> SyntaxNode {
+        match self {
+            SyntaxElement::Node(node) => node.clone(),
+            SyntaxElement::Token(token) => token.parent(),
+        }
+    }
+}
+
+impl PartialEq for SyntaxNode {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0.green, &other.0.green) && self.0.offset == other.0.offset
+    }
+}
+
+///Writes the tree with one node or token per line, indented by depth, such as `Name@4..7`.
+impl std::fmt::Debug for SyntaxNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let depth = self.ancestors().count() - 1;
+        writeln!(f, "{:indent$}{:?}@{:?}", "", self.kind(), self.text_range(), indent = depth * 2)?;
+        for child in self.children_with_tokens() {
+            match child {
+                SyntaxElement::Node(node) => write!(f, "{:?}", node)?,
+                SyntaxElement::Token(token) => writeln!(f, "{:indent$}{:?}", "", token, indent = depth * 2 + 2)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for SyntaxToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}@{:?} {:?}", self.kind(), self.text_range(), self.text())
+    }
+}
+
+impl std::fmt::Display for SyntaxNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.green)
+    }
+}
@@ -0,0 +1,467 @@
+use crate::hir::{expression_end, BinaryOp, HIRInstruction, UnaryOp};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter, Result as FmtResult},
+};
+
+///How many calls of pure functions can be nested while evaluating a constant.
+const MAX_CALL_DEPTH: usize = 64;
+
+///A value computed at compile time.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ConstValue {
+    Integer(i32),
+    Float(f32),
+    Bool(bool),
+    String(String),
+}
+
+///Why an expression could not be evaluated at compile time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConstError {
+    pub msg: String,
+    ///The index of the instruction that could not be evaluated
+    pub idx: usize,
+}
+
+type EvalResult<T> = Result<T, ConstError>;
+
+///Evaluates the initializers of `Const` declarations in a type checked module.
+///Initializers may use literals, the arithmetic, comparison and logical operators, string concatenation,
+///other constants and calls of pure functions: top level functions whose body only binds locals and
+///computes a value from their params, without loops or calls of anything that isn't pure itself.
+pub struct ConstEvaluator<'a> {
+    instructions: &'a [HIRInstruction],
+    ///The index of each top level function by name
+    functions: HashMap<&'a str, usize>,
+    ///The index of each constant by name
+    constants: HashMap<&'a str, usize>,
+    ///The values of the constants evaluated so far
+    values: HashMap<String, ConstValue>,
+    ///The constants being evaluated, outermost first, to catch constants that depend on themselves
+    evaluating: Vec<String>,
+    ///The params and locals of each call being evaluated, innermost last
+    frames: Vec<Vec<(String, ConstValue)>>,
+}
+
+impl<'a> ConstEvaluator<'a> {
+    pub fn new(instructions: &'a [HIRInstruction]) -> Self {
+        let mut functions = HashMap::new();
+        let mut constants = HashMap::new();
+        let mut depth = 0;
+        for (idx, ins) in instructions.iter().enumerate() {
+            match ins {
+                HIRInstruction::Fn(name) => {
+                    if depth == 0 && !is_extension(instructions, idx) {
+                        functions.insert(name.as_str(), idx);
+                    }
+                    depth += 1;
+                }
+                HIRInstruction::Interface(_) | HIRInstruction::Impl(_) => depth += 1,
+                HIRInstruction::EndFn | HIRInstruction::EndInterface | HIRInstruction::EndImpl => depth -= 1,
+                HIRInstruction::Const(name) if depth == 0 => {
+                    constants.insert(name.as_str(), idx);
+                }
+                _ => {}
+            }
+        }
+        ConstEvaluator {
+            instructions,
+            functions,
+            constants,
+            values: HashMap::new(),
+            evaluating: Vec::new(),
+            frames: Vec::new(),
+        }
+    }
+
+    ///Make the constant `name` of an imported module usable, with the value recorded in the interface of that module.
+    pub fn import(&mut self, name: &str, value: ConstValue) {
+        self.values.insert(name.to_string(), value);
+    }
+
+    ///The value of the constant `name`, which must be declared in the module or imported.
+    ///It gets evaluated along with the constants it uses unless that has been done already.
+    pub fn constant(&mut self, name: &str) -> EvalResult<ConstValue> {
+        if let Some(value) = self.values.get(name) {
+            return Ok(value.clone());
+        }
+        let idx = self.constants[name];
+        if let Some(start) = self.evaluating.iter().position(|constant| constant == name) {
+            let mut cycle = self.evaluating[start..].to_vec();
+            cycle.push(name.to_string());
+            return Err(ConstError {
+                msg: format!("Constant {} depends on itself: {}", name, cycle.join(" -> ")),
+                idx,
+            });
+        }
+        //A constant used from a function being evaluated can't see the locals of that function
+        let frames = std::mem::take(&mut self.frames);
+        self.evaluating.push(name.to_string());
+        let ret = self.expr(idx + 1);
+        self.evaluating.pop();
+        self.frames = frames;
+        let (value, _) = ret?;
+        self.values.insert(name.to_string(), value.clone());
+        Ok(value)
+    }
+
+    ///Evaluate the expression starting at `idx`, returning its value and the index after it.
+    pub fn expr(&mut self, idx: usize) -> EvalResult<(ConstValue, usize)> {
+        let value = match &self.instructions[idx] {
+            HIRInstruction::Integer(int) => ConstValue::Integer(*int),
+            HIRInstruction::Float(float) => ConstValue::Float(*float),
+            HIRInstruction::Bool(b) => ConstValue::Bool(*b),
+            HIRInstruction::String(s) => ConstValue::String(s.clone()),
+            HIRInstruction::LocalRef(name) => {
+                let local = self.frames.last().and_then(|frame| frame.iter().rev().find(|(local, _)| local == name));
+                match local {
+                    Some((_, value)) => value.clone(),
+                    None => return error(format!("{} cannot be used in a constant expression", name), idx),
+                }
+            }
+            HIRInstruction::GlobalRef(name) if self.values.contains_key(name.as_str()) => self.values[name.as_str()].clone(),
+            HIRInstruction::GlobalRef(name) if self.constants.contains_key(name.as_str()) => {
+                let name = name.clone();
+                self.constant(&name)?
+            }
+            HIRInstruction::GlobalRef(name) => {
+                return error(format!("{} is not a constant, so it cannot be used in a constant expression", name), idx)
+            }
+            HIRInstruction::Binary(op) => return self.binary(*op, idx),
+            HIRInstruction::Unary(op) => {
+                let (operand, next) = self.expr(idx + 1)?;
+                return Ok((unary(*op, operand, idx)?, next));
+            }
+            HIRInstruction::Call(argc) => return self.call(idx, *argc),
+            _ => return error("This expression cannot be evaluated at compile time".to_string(), idx),
+        };
+        Ok((value, idx + 1))
+    }
+
+    ///Evaluate the binary operation at `idx`. `&&` and `||` leave out their right operand when the left one decides the result.
+    fn binary(&mut self, op: BinaryOp, idx: usize) -> EvalResult<(ConstValue, usize)> {
+        let (lhs, next) = self.expr(idx + 1)?;
+        match (op, &lhs) {
+            (BinaryOp::And, ConstValue::Bool(false)) | (BinaryOp::Or, ConstValue::Bool(true)) => {
+                return Ok((lhs, expression_end(self.instructions, next)))
+            }
+            _ => {}
+        }
+        let (rhs, next) = self.expr(next)?;
+        Ok((binary(op, lhs, rhs, idx)?, next))
+    }
+
+    ///Evaluate the call at `idx`, which has to call a pure function by name.
+    fn call(&mut self, idx: usize, argc: usize) -> EvalResult<(ConstValue, usize)> {
+        let name = match &self.instructions[idx + 1] {
+            HIRInstruction::GlobalRef(name) => name.clone(),
+            _ => return error("Only calls of functions by name can be evaluated at compile time".to_string(), idx),
+        };
+        let decl = match self.functions.get(name.as_str()) {
+            Some(decl) => *decl,
+            None => return error(format!("{} is not a pure function, so it cannot be called in a constant expression", name), idx + 1),
+        };
+        let mut next = idx + 2;
+        let mut positional = Vec::new();
+        let mut named = Vec::new();
+        for _ in 0..argc {
+            match &self.instructions[next] {
+                HIRInstruction::NamedArg(param) => {
+                    let param = param.clone();
+                    let (value, arg_next) = self.expr(next + 1)?;
+                    named.push((param, value));
+                    next = arg_next;
+                }
+                _ => {
+                    let (value, arg_next) = self.expr(next)?;
+                    positional.push(value);
+                    next = arg_next;
+                }
+            }
+        }
+        if self.frames.len() >= MAX_CALL_DEPTH {
+            return error(format!("Evaluating {} at compile time nests calls too deeply", name), idx);
+        }
+        self.frames.push(Vec::new());
+        let ret = self.function_body(&name, decl, positional, named);
+        self.frames.pop();
+        Ok((ret?, next))
+    }
+
+    ///Bind the arguments of a call to the params of the function declared at `decl` and evaluate its body.
+    fn function_body(&mut self, name: &str, decl: usize, positional: Vec<ConstValue>, named: Vec<(String, ConstValue)>) -> EvalResult<ConstValue> {
+        let mut positional = positional.into_iter();
+        let mut idx = decl + 1;
+        let mut ret = None;
+        loop {
+            match &self.instructions[idx] {
+                HIRInstruction::EndFn if positional.next().is_some() => {
+                    return error(format!("{} takes a vararg parameter, so it cannot be called in a constant expression", name), decl)
+                }
+                HIRInstruction::EndFn => break,
+                HIRInstruction::TypeParam(_) => idx += 1,
+                HIRInstruction::FnParam(param) => {
+                    let given = named
+                        .iter()
+                        .find(|(named, _)| named == param)
+                        .map(|(_, value)| value.clone())
+                        .or_else(|| positional.next());
+                    let param = param.clone();
+                    idx += 1;
+                    let value = match (given, &self.instructions[idx]) {
+                        (Some(value), HIRInstruction::DefaultArg) => {
+                            idx = expression_end(self.instructions, idx + 1);
+                            value
+                        }
+                        (Some(value), _) => value,
+                        (None, HIRInstruction::DefaultArg) => {
+                            let (value, next) = self.expr(idx + 1)?;
+                            idx = next;
+                            value
+                        }
+                        (None, _) => return error(format!("Missing an argument for parameter {}", param), idx - 1),
+                    };
+                    self.frames.last_mut().unwrap().push((param, value));
+                }
+                HIRInstruction::LocalVar(local, _) | HIRInstruction::Property(local, _) => {
+                    let local = local.clone();
+                    let (value, next) = self.expr(idx + 1)?;
+                    self.frames.last_mut().unwrap().push((local, value));
+                    idx = next;
+                }
+                HIRInstruction::Return(true) => {
+                    let (value, _) = self.expr(idx + 1)?;
+                    return Ok(value);
+                }
+                HIRInstruction::SelfParam(_)
+                | HIRInstruction::For(_)
+                | HIRInstruction::If(_)
+                | HIRInstruction::While
+                | HIRInstruction::Loop
+                | HIRInstruction::Return(false)
+                | HIRInstruction::Block
+                | HIRInstruction::Fn(_)
+                | HIRInstruction::Struct(_) => {
+                    return error(
+                        format!("{} cannot be called in a constant expression, because its body is not a pure expression", name),
+                        idx,
+                    )
+                }
+                _ => {
+                    let (value, next) = self.expr(idx)?;
+                    ret = Some(value);
+                    idx = next;
+                }
+            }
+        }
+        match ret {
+            Some(value) => Ok(value),
+            None => error(format!("{} does not compute a value, so it cannot be called in a constant expression", name), decl),
+        }
+    }
+}
+
+///Whether the function declared at `idx` is an extension function, which is never called by name.
+pub(crate) fn is_extension(instructions: &[HIRInstruction], idx: usize) -> bool {
+    instructions[idx + 1..]
+        .iter()
+        .find(|ins| !matches!(ins, HIRInstruction::TypeParam(_)))
+        .is_some_and(|ins| matches!(ins, HIRInstruction::SelfParam(_)))
+}
+
+fn error<T>(msg: String, idx: usize) -> EvalResult<T> {
+    Err(ConstError { msg, idx })
+}
+
+fn unary(op: UnaryOp, operand: ConstValue, idx: usize) -> EvalResult<ConstValue> {
+    match (op, operand) {
+        (UnaryOp::Neg, ConstValue::Integer(int)) => match int.checked_neg() {
+            Some(int) => Ok(ConstValue::Integer(int)),
+            None => error(format!("Integer overflow in constant expression -({})", int), idx),
+        },
+        (UnaryOp::Neg, ConstValue::Float(float)) => Ok(ConstValue::Float(-float)),
+        (UnaryOp::Not, ConstValue::Bool(b)) => Ok(ConstValue::Bool(!b)),
+        (op, operand) => error(format!("Operator {} cannot be applied to {}", op, operand), idx),
+    }
+}
+
+fn binary(op: BinaryOp, lhs: ConstValue, rhs: ConstValue, idx: usize) -> EvalResult<ConstValue> {
+    use ConstValue::*;
+    if op.is_comparison() {
+        let ordering = match (&lhs, &rhs) {
+            (Integer(a), Integer(b)) => a.partial_cmp(b),
+            (Float(a), Float(b)) => a.partial_cmp(b),
+            (String(a), String(b)) => a.partial_cmp(b),
+            (Bool(a), Bool(b)) if matches!(op, BinaryOp::Eq | BinaryOp::Ne) => a.partial_cmp(b),
+            _ => return error(format!("Operator {} cannot be applied to {} and {}", op, lhs, rhs), idx),
+        };
+        let holds = match ordering {
+            Some(ordering) => match op {
+                BinaryOp::Eq => ordering.is_eq(),
+                BinaryOp::Ne => ordering.is_ne(),
+                BinaryOp::Lt => ordering.is_lt(),
+                BinaryOp::Le => ordering.is_le(),
+                BinaryOp::Gt => ordering.is_gt(),
+                _ => ordering.is_ge(),
+            },
+            //Only NaN is unordered, and it only differs from everything
+            None => op == BinaryOp::Ne,
+        };
+        return Ok(Bool(holds));
+    }
+    match (&lhs, &rhs) {
+        (Integer(a), Integer(b)) => {
+            if matches!(op, BinaryOp::Div | BinaryOp::Rem) && *b == 0 {
+                return error(format!("Division by zero in constant expression {} {} {}", lhs, op, rhs), idx);
+            }
+            let result = match op {
+                BinaryOp::Add => a.checked_add(*b),
+                BinaryOp::Sub => a.checked_sub(*b),
+                BinaryOp::Mul => a.checked_mul(*b),
+                BinaryOp::Div => a.checked_div(*b),
+                BinaryOp::Rem => a.checked_rem(*b),
+                _ => return error(format!("Operator {} cannot be applied to {} and {}", op, lhs, rhs), idx),
+            };
+            match result {
+                Some(int) => Ok(Integer(int)),
+                None => error(format!("Integer overflow in constant expression {} {} {}", lhs, op, rhs), idx),
+            }
+        }
+        (Float(a), Float(b)) => {
+            if matches!(op, BinaryOp::Div | BinaryOp::Rem) && *b == 0.0 {
+                return error(format!("Division by zero in constant expression {} {} {}", lhs, op, rhs), idx);
+            }
+            let result = match op {
+                BinaryOp::Add => a + b,
+                BinaryOp::Sub => a - b,
+                BinaryOp::Mul => a * b,
+                BinaryOp::Div => a / b,
+                BinaryOp::Rem => a % b,
+                _ => return error(format!("Operator {} cannot be applied to {} and {}", op, lhs, rhs), idx),
+            };
+            if result.is_infinite() {
+                return error(format!("Float overflow in constant expression {} {} {}", lhs, op, rhs), idx);
+            }
+            Ok(Float(result))
+        }
+        (String(a), String(b)) if op == BinaryOp::Add => Ok(String(format!("{}{}", a, b))),
+        (Bool(a), Bool(b)) if op == BinaryOp::And => Ok(Bool(*a && *b)),
+        (Bool(a), Bool(b)) if op == BinaryOp::Or => Ok(Bool(*a || *b)),
+        _ => error(format!("Operator {} cannot be applied to {} and {}", op, lhs, rhs), idx),
+    }
+}
+
+impl Display for ConstValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Integer(int) => write!(f, "{}", int),
+            Self::Float(float) => write!(f, "{:?}", float),
+            Self::Bool(b) => write!(f, "{}", b),
+            Self::String(s) => write!(f, "{:?}", s),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use HIRInstruction::*;
+
+    fn constant(instructions: &[HIRInstruction], name: &str) -> EvalResult<ConstValue> {
+        ConstEvaluator::new(instructions).constant(name)
+    }
+
+    #[test]
+    fn calls_pure_functions() {
+        let instructions = vec![
+            Module("test".to_string()),
+            Const("SIZE".to_string()),
+            Binary(BinaryOp::Add),
+            Call(1),
+            GlobalRef("twice".to_string()),
+            Integer(2),
+            Integer(1),
+            Fn("twice".to_string()),
+            FnParam("x".to_string()),
+            Binary(BinaryOp::Mul),
+            LocalRef("x".to_string()),
+            Integer(2),
+            EndFn,
+            EndModule,
+        ];
+        assert_eq!(constant(&instructions, "SIZE"), Ok(ConstValue::Integer(5)));
+    }
+
+    #[test]
+    fn uses_other_constants() {
+        let instructions = vec![
+            Module("test".to_string()),
+            Const("GREETING".to_string()),
+            Binary(BinaryOp::Add),
+            GlobalRef("HELLO".to_string()),
+            String(" world".to_string()),
+            Const("HELLO".to_string()),
+            String("hello".to_string()),
+            EndModule,
+        ];
+        assert_eq!(constant(&instructions, "GREETING"), Ok(ConstValue::String("hello world".to_string())));
+    }
+
+    #[test]
+    fn rejects_constants_depending_on_themselves() {
+        let instructions = vec![
+            Module("test".to_string()),
+            Const("A".to_string()),
+            Binary(BinaryOp::Add),
+            GlobalRef("B".to_string()),
+            Integer(1),
+            Const("B".to_string()),
+            GlobalRef("A".to_string()),
+            EndModule,
+        ];
+        let error = constant(&instructions, "A").unwrap_err();
+        assert_eq!(error.msg, "Constant A depends on itself: A -> B -> A");
+        assert_eq!(error.idx, 1);
+    }
+
+    #[test]
+    fn rejects_overflow_and_division_by_zero() {
+        let overflow = vec![
+            Module("test".to_string()),
+            Const("BIG".to_string()),
+            Binary(BinaryOp::Add),
+            Integer(i32::MAX),
+            Integer(1),
+            EndModule,
+        ];
+        let error = constant(&overflow, "BIG").unwrap_err();
+        assert_eq!(error.msg, format!("Integer overflow in constant expression {} + 1", i32::MAX));
+        assert_eq!(error.idx, 2);
+        let division = vec![
+            Module("test".to_string()),
+            Const("NONE".to_string()),
+            Binary(BinaryOp::Div),
+            Integer(1),
+            Integer(0),
+            EndModule,
+        ];
+        assert!(constant(&division, "NONE").unwrap_err().msg.starts_with("Division by zero"));
+    }
+
+    #[test]
+    fn rejects_globals_that_are_not_constants() {
+        let instructions = vec![
+            Module("test".to_string()),
+            Property("counter".to_string(), false),
+            Integer(0),
+            Const("COPY".to_string()),
+            GlobalRef("counter".to_string()),
+            EndModule,
+        ];
+        let error = constant(&instructions, "COPY").unwrap_err();
+        assert_eq!(error.msg, "counter is not a constant, so it cannot be used in a constant expression");
+        assert_eq!(error.idx, 4);
+    }
+}
@@ -0,0 +1,260 @@
+use crate::{hir, interface::ModuleInterface, mir};
+
+use bincode::Options;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use std::{
+    fmt::{Display, Formatter},
+    path::Path,
+};
+
+///The version of the layout of encoded modules, bumped whenever an instruction or type signature changes shape.
+///Modules encoded with another version are rejected instead of being misread.
+pub const FORMAT_VERSION: u32 = 6;
+
+///The bytes every module in the binary format starts with.
+const MAGIC: &[u8; 4] = b"BGIR";
+
+///A module of one of the intermediate representations, or the interface of a module, which can be written to disk and read back.
+pub trait IrModule: Serialize + DeserializeOwned {
+    ///The name of the representation, recorded along with the module
+    const KIND: &'static str;
+}
+
+impl IrModule for hir::Module {
+    const KIND: &'static str = "hir";
+}
+
+impl IrModule for mir::Module {
+    const KIND: &'static str = "mir";
+}
+
+impl IrModule for ModuleInterface {
+    const KIND: &'static str = "bmi";
+}
+
+///How a module is laid out on disk.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    ///Human readable JSON, for tools that do not link the compiler.
+    Json,
+    ///A compact binary encoding, starting with `BGIR`.
+    Binary,
+}
+
+impl Format {
+    ///The format of the file at `path`: JSON for a `.json` file, and binary otherwise.
+    pub fn of_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => Format::Json,
+            _ => Format::Binary,
+        }
+    }
+}
+
+///Why a module could not be encoded or decoded.
+#[derive(Debug)]
+pub enum EncodingError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Binary(bincode::Error),
+    ///The data does not start with the magic bytes of the binary format.
+    NotAModule,
+    ///The module was encoded with another version of the format.
+    UnsupportedVersion(u32),
+    ///The module is of another representation than the one being read.
+    WrongKind {
+        expected: &'static str,
+        found: String,
+    },
+}
+
+impl Display for EncodingError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{}", e),
+            Self::Json(e) => write!(f, "Invalid JSON module: {}", e),
+            Self::Binary(e) => write!(f, "Invalid binary module: {}", e),
+            Self::NotAModule => write!(f, "Not a binary module: it does not start with {:?}", std::str::from_utf8(MAGIC).unwrap()),
+            Self::UnsupportedVersion(version) => write!(
+                f,
+                "The module was encoded with version {} of the format, but only version {} is supported",
+                version, FORMAT_VERSION
+            ),
+            Self::WrongKind { expected, found } => write!(f, "Expected a {} module but found a {} module", expected, found),
+        }
+    }
+}
+
+impl From<std::io::Error> for EncodingError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for EncodingError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Json(e)
+    }
+}
+
+impl From<bincode::Error> for EncodingError {
+    fn from(e: bincode::Error) -> Self {
+        Self::Binary(e)
+    }
+}
+
+///What precedes the module itself, so readers can tell what they were given before decoding the rest.
+#[derive(Serialize, Deserialize)]
+struct Header {
+    version: u32,
+    kind: String,
+}
+
+///A module along with its header, as it is laid out in either format.
+///In JSON this is an object with the fields `version`, `kind` and `module`.
+#[derive(Serialize)]
+struct Encoded<'a, M> {
+    version: u32,
+    kind: &'a str,
+    module: &'a M,
+}
+
+///The module of a JSON encoding, whose header has already been checked.
+#[derive(Deserialize)]
+struct Decoded<M> {
+    module: M,
+}
+
+fn binary_options() -> impl Options {
+    bincode::DefaultOptions::new()
+}
+
+///Encode `module` in `format`.
+pub fn encode<M: IrModule>(module: &M, format: Format) -> Result<Vec<u8>, EncodingError> {
+    let encoded = Encoded {
+        version: FORMAT_VERSION,
+        kind: M::KIND,
+        module,
+    };
+    match format {
+        Format::Json => Ok(serde_json::to_vec_pretty(&encoded)?),
+        Format::Binary => {
+            let mut bytes = MAGIC.to_vec();
+            binary_options().serialize_into(&mut bytes, &encoded)?;
+            Ok(bytes)
+        }
+    }
+}
+
+///The header of the module encoded in `bytes`, checking that it is of a supported version.
+fn header(bytes: &[u8], format: Format) -> Result<Header, EncodingError> {
+    let header = match format {
+        Format::Json => serde_json::from_slice(bytes)?,
+        Format::Binary => match bytes.strip_prefix(&MAGIC[..]) {
+            //The header is a prefix of the whole encoding, so bincode stops reading right after it
+            Some(rest) => binary_options().allow_trailing_bytes().deserialize(rest)?,
+            None => return Err(EncodingError::NotAModule),
+        },
+    };
+    match header {
+        Header { version, .. } if version != FORMAT_VERSION => Err(EncodingError::UnsupportedVersion(version)),
+        header => Ok(header),
+    }
+}
+
+///The kind of module encoded in `bytes`, such as `hir` or `mir`.
+pub fn kind_of(bytes: &[u8], format: Format) -> Result<String, EncodingError> {
+    Ok(header(bytes, format)?.kind)
+}
+
+///Decode a module encoded in `format` by `encode`.
+pub fn decode<M: IrModule>(bytes: &[u8], format: Format) -> Result<M, EncodingError> {
+    let kind = kind_of(bytes, format)?;
+    if kind != M::KIND {
+        return Err(EncodingError::WrongKind {
+            expected: M::KIND,
+            found: kind,
+        });
+    }
+    match format {
+        Format::Json => Ok(serde_json::from_slice::<Decoded<M>>(bytes)?.module),
+        //The binary encoding has no field names, so the header is just the fields that come before the module
+        Format::Binary => Ok(binary_options().deserialize::<(Header, M)>(&bytes[MAGIC.len()..])?.1),
+    }
+}
+
+///Write `module` to the file at `path`, in the format its extension calls for.
+pub fn write<M: IrModule>(module: &M, path: &Path) -> Result<(), EncodingError> {
+    let bytes = encode(module, Format::of_path(path))?;
+    std::fs::write(path, bytes)?;
+    Ok(())
+}
+
+///Read a module written by `write` from the file at `path`.
+pub fn read<M: IrModule>(path: &Path) -> Result<M, EncodingError> {
+    decode(&std::fs::read(path)?, Format::of_path(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text;
+
+    const HIR: &str = r#"hir test after resolve typeck
+Module test #3
+    Fn main #1 : fun() -> Int @ 1:2 1:4
+        LocalVar s #2 : String @ 2:10 2:10
+        String "a \"quoted\"\n" : String @ 2:14 2:27
+        Binary Add : Int @ 3:6 3:10
+        Integer 1 : Int @ 3:6 3:6
+        Float 2.5 : Float @ 3:10 3:12
+    EndFn @ 1:2 1:4
+EndModule
+"#;
+
+    const MIR: &str = r#"mir test
+Module test
+    Fun main : fun() -> Int @ 1:2 1:4
+        Return value : Int @ 2:6 2:6
+        Call 1 : Int @ 2:6 2:9
+        Global f : fun(a: Int, b: Int = default) -> Int @ 2:6 2:6
+        Integer 1 : Int @ 2:8 2:8
+    EndFun @ 1:2 1:4
+EndModule
+"#;
+
+    #[test]
+    fn modules_round_trip_in_both_formats() {
+        let hir = text::parse_hir(HIR).unwrap();
+        let mir = text::parse_mir(MIR).unwrap();
+        for format in [Format::Json, Format::Binary] {
+            let decoded = decode::<hir::Module>(&encode(&hir, format).unwrap(), format).unwrap();
+            assert_eq!(decoded.passes, hir.passes);
+            assert_eq!(text::print_hir(&decoded), HIR);
+            let decoded = decode::<mir::Module>(&encode(&mir, format).unwrap(), format).unwrap();
+            assert_eq!(text::print_mir(&decoded), MIR);
+        }
+    }
+
+    #[test]
+    fn rejects_other_kinds_of_modules() {
+        let bytes = encode(&text::parse_mir(MIR).unwrap(), Format::Binary).unwrap();
+        assert_eq!(kind_of(&bytes, Format::Binary).unwrap(), "mir");
+        match decode::<hir::Module>(&bytes, Format::Binary) {
+            Err(EncodingError::WrongKind { expected: "hir", found }) => assert_eq!(found, "mir"),
+            ret => panic!("Expected a WrongKind error, but got {:?}", ret.map(|module| module.name)),
+        }
+    }
+
+    #[test]
+    fn rejects_other_versions_and_data() {
+        let bytes = encode(&text::parse_hir(HIR).unwrap(), Format::Json).unwrap();
+        let json = std::str::from_utf8(&bytes).unwrap();
+        let old = json.replacen(&format!("\"version\": {}", FORMAT_VERSION), "\"version\": 1", 1);
+        assert!(matches!(decode::<hir::Module>(old.as_bytes(), Format::Json), Err(EncodingError::UnsupportedVersion(1))));
+        assert!(matches!(decode::<hir::Module>(b"not a module", Format::Binary), Err(EncodingError::NotAModule)));
+        let truncated = &encode(&text::parse_hir(HIR).unwrap(), Format::Binary).unwrap()[..20];
+        assert!(matches!(decode::<hir::Module>(truncated, Format::Binary), Err(EncodingError::Binary(_))));
+    }
+}
@@ -1,7 +1,12 @@
-use super::type_signature::TypeSignature;
+use super::{
+    symbols::{SymbolId, SymbolTable},
+    type_signature::TypeSignature,
+};
 use core::pos::BiPos as Position;
 use serde::{Deserialize, Serialize};
 
+pub use crate::verify::{verify, VerifyError};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Module {
     /// The name of the module
@@ -12,6 +17,12 @@ pub struct Module {
     pub signatures: Vec<TypeSignature>,
     /// The positions in code of each ir instruction
     pub positions: Vec<Position>,
+    /// The symbol each ir instruction declares or refers to, once names are resolved
+    pub symbols: Vec<Option<SymbolId>>,
+    /// The symbols of the module and the scopes they are declared in, once names are resolved
+    pub symbol_table: SymbolTable,
+    /// The HIR passes that have run over the module, in order. A module read back from disk resumes after them
+    pub passes: Vec<String>,
 }
 
 impl Module {
@@ -21,11 +32,15 @@ impl Module {
             instructions: Vec::new(),
             signatures: Vec::new(),
             positions: Vec::new(),
+            symbols: Vec::new(),
+            symbol_table: SymbolTable::new(),
+            passes: Vec::new(),
         }
     }
 
     pub fn push_ir(&mut self, ir: HIR) {
-        self.push(ir.pos, ir.sig, ir.ins)
+        self.push(ir.pos, ir.sig, ir.ins);
+        *self.symbols.last_mut().unwrap() = ir.symbol;
     }
 
     /// Push an instruction into the module
@@ -33,6 +48,7 @@ impl Module {
         self.positions.push(pos);
         self.signatures.push(sig);
         self.instructions.push(ins);
+        self.symbols.push(None);
     }
 }
 
@@ -41,6 +57,7 @@ pub struct HIR {
     pub pos: Position,
     pub sig: TypeSignature,
     pub ins: HIRInstruction,
+    pub symbol: Option<SymbolId>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -48,17 +65,145 @@ pub enum HIRInstruction {
     //The module being parsed, which needs a name.
     Module(String),
     EndModule,
+    //The visibility of the declaration that immediately follows: a function, property, struct, struct field or interface.
+    //Declarations without one are internal.
+    Visibility(Visibility),
+    //An annotation such as `@deprecated("use bar")` with its name and arguments.
+    //Annotations apply to the declaration that follows them and come before its visibility marker, if it has one.
+    Annotation(String, Vec<AnnotationArg>),
     //The start of a function. The name of the function is expected to follow.
     Fn(String),
     EndFn,
+    //A function implemented in C, declared with `native fun`. Linking isn't implemented yet, so nothing provides it.
+    //It has no params or body in the HIR, and its signature uses the C calling convention.
+    NativeFn(String),
     //The start of a param. The name and type of the param must follow.
+    //The signature of a `vararg` param is a list of its type, holding every argument given for it.
     FnParam(String),
+    //The default value of the `FnParam` right before it, given by the expression that follows.
+    //It is used when a call leaves the param out, and can refer to the params declared before it.
+    DefaultArg,
+    //A generic type parameter of the enclosing function or struct. Its signature holds the bounds.
+    //These come directly after the `Fn` or `Struct` they belong to, before any `FnParam` or `StructField`.
+    TypeParam(String),
     //The return type of the function
     FnType(String),
     //A property which must be given a name and whether it is mutable or not. An expression must follow.
     Property(String, bool),
+    //A compile-time constant with the given name. Its initializer expression follows, and the signature is its type.
+    //The initializer may only use literals, operators, other constants and calls of pure functions,
+    //and is evaluated by the type checker.
+    Const(String),
     //A local variable which must be given a name and whether it is mutable or not. An expression must follow.
     LocalVar(String, bool),
+    //The start of a struct declaration. Type params and then fields follow.
+    Struct(String),
+    //A field of the enclosing struct. The signature is the type of the field.
+    StructField(String),
+    EndStruct,
+    //A type alias with the given name. The signature is the aliased type.
+    //Aliases are transparent: every use of one has already been replaced by the type it stands for.
+    TypeAlias(String),
+    //A newtype with the given name, such as `newtype Meters(Float)`. The signature is the wrapped type.
+    //A newtype is a distinct type with the representation of the type it wraps. It is built by calling it
+    //like a function, as in `Meters(1.5)`, and the wrapped value is its `value` field.
+    Newtype(String),
+    //The start of an interface declaration. Its methods follow until `EndInterface`.
+    //Default methods are given as regular `Fn` declarations.
+    Interface(String),
+    //A method an implementation of the enclosing interface must provide, along with how it takes its receiver.
+    //The signature is the method's signature.
+    InterfaceMethod(String, ReceiverKind),
+    EndInterface,
+    //The start of an implementation block for the type in the signature.
+    //The name of the implemented interface is given if there is one. The methods of the block follow until `EndImpl`.
+    Impl(Option<String>),
+    EndImpl,
+    //The receiver of a method and how it is passed.
+    //This comes right after the `Fn` or `TypeParam`s of the method, before any `FnParam`. The signature is the type of `self`.
+    //A function declared in an impl block without a receiver is an associated function.
+    //A top level function with a receiver is an extension function of the type of its `self`, such as `fun String.shout()`,
+    //which always takes its receiver by value.
+    SelfParam(ReceiverKind),
+    //An import of the module with the given name, which only comes at the top level.
+    //The public functions, structs and constants of the module can be used as if they were declared in this one.
+    //They are checked against the interface of the module, without its source.
+    Import(String),
+
+    //A reference to a local variable or function parameter.
+    LocalRef(String),
+    //A reference to a top level property or function.
+    GlobalRef(String),
+    //A call with the given number of arguments.
+    //The callee expression follows, then each argument expression in order.
+    //The signature is the type of the value returned by the call.
+    Call(usize),
+    //An argument of the enclosing call given to the param with this name, as in `f(x = 1)`.
+    //The argument expression follows. Named arguments come after every positional one.
+    NamedArg(String),
+    //Access of the field with the given name. The expression for the struct value follows.
+    Field(String),
+    //A call of the method with the given name and number of arguments.
+    //The receiver expression follows, then each argument expression in order.
+    //The signature is the type of the value returned by the call.
+    MethodCall(String, usize),
+    //An array literal with the given number of elements.
+    //Each element expression follows in order.
+    Array(usize),
+    //Indexing of a collection. The collection expression follows, then the index expression.
+    //Indexing is bounds checked at runtime.
+    Index,
+    //Slicing of a collection into a new list, such as `xs[1..3]`.
+    //The collection expression follows, then the start index if the first flag is set, then the end index if the second flag is set.
+    //A missing start is 0 and a missing end is the length of the collection.
+    Slice(bool, bool),
+    //Propagation of an error with `?`. The expression that follows gives a `Result`.
+    //If it is `Ok`, the wrapped value is the value of the expression and the signature is its type.
+    //If it is `Err`, the enclosing function returns right away with that same `Err`.
+    Try,
+    //A binary operation. The left operand expression follows, then the right one.
+    //The signature is the type of the result.
+    Binary(BinaryOp),
+    //A unary operation. The operand expression follows.
+    Unary(UnaryOp),
+    //A range from the start expression that follows to the end expression after it.
+    //The flag is set when the end is part of the range, as in `a..=b`.
+    Range(bool),
+    //A loop binding each value produced by an iterable to the local with the given name.
+    //The iterable expression follows, then the statements of the body up to the matching `EndFor`.
+    //The signature is the type of the values. Ranges, arrays and lists are iterable, and so is any
+    //type with a method `next(&mut self): Option<T>`, which lowering calls until it returns `None`.
+    For(String),
+    EndFor,
+    //A conditional. The condition expression follows, then the statements run when it is true.
+    //If the flag is set, those statements are closed by an `Else` and the statements run otherwise come after it.
+    //Either way the conditional ends with `EndIf`. An `else if` is an `If` that is the only statement after the `Else`.
+    If(bool),
+    Else,
+    EndIf,
+    //A loop running its body for as long as the condition expression that follows is true.
+    //The statements of the body come after the condition, up to the matching `EndWhile`.
+    While,
+    EndWhile,
+    //A loop running the statements that follow, up to the matching `EndLoop`, until a `Break` leaves it.
+    Loop,
+    EndLoop,
+    //Leave the innermost `For`, `While` or `Loop` enclosing this instruction.
+    Break,
+    //Go on to the next iteration of the innermost `For`, `While` or `Loop` enclosing this instruction.
+    //A `While` checks its condition again first.
+    Continue,
+    //An assignment. The target follows, which is a `LocalRef` or `GlobalRef` of a mutable local or property,
+    //or a `Field` of such a target, then the expression giving its new value.
+    //It is a statement rather than an operand, and the value the target held is dropped once the new one is computed.
+    Assign,
+    //Return from the enclosing function. If the flag is set, the expression that follows gives the returned value,
+    //otherwise the function returns `Unit`. The signature is the type of the returned value.
+    Return(bool),
+    //A nested block of statements, up to the matching `EndBlock`.
+    //The locals declared in the block go out of scope at its end.
+    Block,
+    EndBlock,
 
     Integer(i32),
     Float(f32),
@@ -67,12 +212,179 @@ pub enum HIRInstruction {
     Halt,
 }
 
+///Where a declaration can be used from.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub enum Visibility {
+    ///Usable from any module, and part of the module's exported interface
+    Public,
+    ///Usable from anywhere within the module it is declared in
+    #[default]
+    Internal,
+    ///A top level declaration usable only within its own file,
+    ///or a field or method usable only within the impl blocks of its type
+    Private,
+}
+
+///The visibility given to the declaration at `idx` by the marker before it, if any.
+pub fn visibility_of(instructions: &[HIRInstruction], idx: usize) -> Visibility {
+    match idx.checked_sub(1).map(|prev| &instructions[prev]) {
+        Some(HIRInstruction::Visibility(visibility)) => *visibility,
+        _ => Visibility::default(),
+    }
+}
+
+///The annotations of the declaration at `idx`, in source order.
+pub fn annotations_of(instructions: &[HIRInstruction], idx: usize) -> Vec<(&str, &[AnnotationArg])> {
+    let mut annotations = instructions[..idx]
+        .iter()
+        .rev()
+        .skip_while(|ins| matches!(ins, HIRInstruction::Visibility(_)))
+        .map_while(|ins| match ins {
+            HIRInstruction::Annotation(name, args) => Some((name.as_str(), args.as_slice())),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    annotations.reverse();
+    annotations
+}
+
+///The index after the expression starting at `idx`, whose operands follow it in prefix order.
+pub fn expression_end(instructions: &[HIRInstruction], idx: usize) -> usize {
+    let operands = match &instructions[idx] {
+        HIRInstruction::Call(argc) | HIRInstruction::MethodCall(_, argc) => argc + 1,
+        HIRInstruction::Array(len) => *len,
+        HIRInstruction::Index | HIRInstruction::Range(_) | HIRInstruction::Binary(_) | HIRInstruction::Assign => 2,
+        HIRInstruction::Slice(start, end) => 1 + *start as usize + *end as usize,
+        HIRInstruction::Field(_) | HIRInstruction::Unary(_) | HIRInstruction::NamedArg(_) | HIRInstruction::Try => 1,
+        _ => 0,
+    };
+    (0..operands).fold(idx + 1, |next, _| expression_end(instructions, next))
+}
+
+///An argument given to an annotation.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum AnnotationArg {
+    String(String),
+    Integer(i32),
+    Float(f32),
+    ///A bare name, as in `@inline(always)`
+    Name(String),
+}
+
+///An operator taking two operands.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    ///`&&`, which only evaluates the right operand when the left one is true
+    And,
+    ///`||`, which only evaluates the right operand when the left one is false
+    Or,
+}
+
+impl BinaryOp {
+    ///Whether the operator compares its operands, giving a `Bool`.
+    pub fn is_comparison(self) -> bool {
+        matches!(self, Self::Eq | Self::Ne | Self::Lt | Self::Le | Self::Gt | Self::Ge)
+    }
+}
+
+///An operator taking a single operand.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum UnaryOp {
+    ///`-x`
+    Neg,
+    ///`!x`
+    Not,
+}
+
+///How the receiver of a method is passed to it.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum ReceiverKind {
+    ///`self`
+    Value,
+    ///`&self`
+    Ref,
+    ///`&mut self`
+    MutRef,
+}
+
 use std::fmt::{
     Display,
     Formatter,
     Result
 };
 
+impl Display for Visibility {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Self::Public => write!(f, "public"),
+            Self::Internal => write!(f, "internal"),
+            Self::Private => write!(f, "private"),
+        }
+    }
+}
+
+impl Display for AnnotationArg {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Self::String(s) => write!(f, "{:?}", s),
+            Self::Integer(i) => write!(f, "{}", i),
+            Self::Float(x) => write!(f, "{}", x),
+            Self::Name(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+impl Display for BinaryOp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let op = match self {
+            Self::Add => "+",
+            Self::Sub => "-",
+            Self::Mul => "*",
+            Self::Div => "/",
+            Self::Rem => "%",
+            Self::Eq => "==",
+            Self::Ne => "!=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+            Self::And => "&&",
+            Self::Or => "||",
+        };
+        write!(f, "{}", op)
+    }
+}
+
+impl Display for UnaryOp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Self::Neg => write!(f, "-"),
+            Self::Not => write!(f, "!"),
+        }
+    }
+}
+
+impl Display for ReceiverKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Self::Value => write!(f, "self"),
+            Self::Ref => write!(f, "&self"),
+            Self::MutRef => write!(f, "&mut self"),
+        }
+    }
+}
+
 use core::ansi;
 use super::{
     fmt_tab,
@@ -122,6 +434,37 @@ impl Display for Module {
                         ansi::Fg::Reset
                     )?;
                 },
+                Visibility(visibility) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}Visibility {}{}",
+                        ansi::Fg::Green,
+                        visibility,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                Annotation(name, args) => {
+                    fmt_tab(f, depth)?;
+                    write!(f, "{}Annotation @{}", ansi::Fg::Green, name)?;
+                    if !args.is_empty() {
+                        let args = args.iter().map(|arg| arg.to_string()).collect::<Vec<std::string::String>>();
+                        write!(f, "({})", args.join(", "))?;
+                    }
+                    writeln!(f, "{}", ansi::Fg::Reset)?;
+                },
+                NativeFn(name) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}Native function{} {}: {}{}",
+                        ansi::Fg::Cyan,
+                        ansi::Fg::Red,
+                        name,
+                        sig,
+                        ansi::Fg::Reset
+                    )?;
+                },
                 EndFn => {
                     depth -= 1;
                     fmt_tab(f, depth)?;
@@ -145,6 +488,26 @@ impl Display for Module {
                         ansi::Fg::Reset
                     )?;
                 },
+                DefaultArg => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}Default{}",
+                        ansi::Fg::Cyan,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                NamedArg(name) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}NamedArg{} {}{}",
+                        ansi::Fg::Magenta,
+                        ansi::Fg::White,
+                        name,
+                        ansi::Fg::Reset
+                    )?;
+                },
                 Property(name, mutable) => {
                     fmt_tab(f, depth)?;
                     writeln!(
@@ -164,6 +527,19 @@ impl Display for Module {
                         ansi::Fg::Reset
                     )?;
                 }
+                Const(name) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}Const {}{}{}: {}{}",
+                        ansi::Fg::Cyan,
+                        ansi::Fg::White,
+                        name,
+                        ansi::Fg::Blue,
+                        sig,
+                        ansi::Fg::Reset
+                    )?;
+                }
                 LocalVar(name, mutable) => {
                     fmt_tab(f, depth)?;
                     writeln!(
@@ -172,9 +548,9 @@ impl Display for Module {
                         ansi::Fg::Cyan,
                         ansi::Fg::Green,
                         if *mutable {
-                            "value"
-                        }else{
                             "variable"
+                        }else{
+                            "value"
                         },
                         ansi::Fg::White,
                         name,
@@ -215,13 +591,497 @@ impl Display for Module {
                         ansi::Fg::Reset
                     )?;
                 },
-                Unit => {
+                Bool(b) => {
                     fmt_tab(f, depth)?;
                     writeln!(
                         f,
-                        "{}Unit{}",
+                        "{}Bool {}{}{}",
                         ansi::Fg::Cyan,
-                        ansi::Fg::Reset,
+                        ansi::Fg::Green,
+                        b,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                FnType(name) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}Returns{} {}{}",
+                        ansi::Fg::Cyan,
+                        ansi::Fg::Yellow,
+                        name,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                TypeParam(_) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}TypeParam{} {}{}",
+                        ansi::Fg::Cyan,
+                        ansi::Fg::Yellow,
+                        sig,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                Struct(name) => {
+                    fmt_tab(f, depth)?;
+                    depth += 1;
+                    writeln!(
+                        f,
+                        "{}Struct{} {}{}",
+                        ansi::Fg::Cyan,
+                        ansi::Fg::Red,
+                        name,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                TypeAlias(name) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}TypeAlias{} {} = {}{}",
+                        ansi::Fg::Cyan,
+                        ansi::Fg::Red,
+                        name,
+                        sig,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                Newtype(name) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}Newtype{} {}({}){}",
+                        ansi::Fg::Cyan,
+                        ansi::Fg::Red,
+                        name,
+                        sig,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                StructField(name) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}Field{} {}: {}{}{}",
+                        ansi::Fg::Cyan,
+                        ansi::Fg::White,
+                        name,
+                        ansi::Fg::Yellow,
+                        sig,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                EndStruct => {
+                    depth -= 1;
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}EndStruct{}",
+                        ansi::Fg::Cyan,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                Interface(name) => {
+                    fmt_tab(f, depth)?;
+                    depth += 1;
+                    writeln!(
+                        f,
+                        "{}Interface{} {}{}",
+                        ansi::Fg::Cyan,
+                        ansi::Fg::Red,
+                        name,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                InterfaceMethod(name, receiver) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}Method{} {}({}) {}{}",
+                        ansi::Fg::Cyan,
+                        ansi::Fg::Red,
+                        name,
+                        receiver,
+                        sig,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                EndInterface => {
+                    depth -= 1;
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}EndInterface{}",
+                        ansi::Fg::Cyan,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                Impl(interface) => {
+                    fmt_tab(f, depth)?;
+                    depth += 1;
+                    match interface{
+                        Some(interface) => writeln!(
+                            f,
+                            "{}Impl{} {} {}for{} {}{}",
+                            ansi::Fg::Cyan,
+                            ansi::Fg::Red,
+                            interface,
+                            ansi::Fg::Cyan,
+                            ansi::Fg::Red,
+                            sig,
+                            ansi::Fg::Reset
+                        )?,
+                        None => writeln!(
+                            f,
+                            "{}Impl{} {}{}",
+                            ansi::Fg::Cyan,
+                            ansi::Fg::Red,
+                            sig,
+                            ansi::Fg::Reset
+                        )?,
+                    };
+                },
+                EndImpl => {
+                    depth -= 1;
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}EndImpl{}",
+                        ansi::Fg::Cyan,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                SelfParam(receiver) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}Receiver{} {}: {}{}{}",
+                        ansi::Fg::Cyan,
+                        ansi::Fg::White,
+                        receiver,
+                        ansi::Fg::Yellow,
+                        sig,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                Import(name) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}Import{} {}{}",
+                        ansi::Fg::Cyan,
+                        ansi::Fg::White,
+                        name,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                Field(name) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}GetField {}{}{}: {}{}",
+                        ansi::Fg::Magenta,
+                        ansi::Fg::White,
+                        name,
+                        ansi::Fg::Blue,
+                        sig,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                MethodCall(name, argc) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}MethodCall {}{}{}({}){}: {}{}",
+                        ansi::Fg::Magenta,
+                        ansi::Fg::White,
+                        name,
+                        ansi::Fg::Magenta,
+                        argc,
+                        ansi::Fg::Blue,
+                        sig,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                Try => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}Try{}: {}{}",
+                        ansi::Fg::Magenta,
+                        ansi::Fg::Blue,
+                        sig,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                Binary(op) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}Binary {}{}{}: {}{}",
+                        ansi::Fg::Magenta,
+                        ansi::Fg::White,
+                        op,
+                        ansi::Fg::Blue,
+                        sig,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                Unary(op) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}Unary {}{}{}: {}{}",
+                        ansi::Fg::Magenta,
+                        ansi::Fg::White,
+                        op,
+                        ansi::Fg::Blue,
+                        sig,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                Range(inclusive) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}Range {}{}{}: {}{}",
+                        ansi::Fg::Magenta,
+                        ansi::Fg::White,
+                        if *inclusive { "..=" } else { ".." },
+                        ansi::Fg::Blue,
+                        sig,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                For(name) => {
+                    fmt_tab(f, depth)?;
+                    depth += 1;
+                    writeln!(
+                        f,
+                        "{}For {}{}{}: {}{}",
+                        ansi::Fg::Cyan,
+                        ansi::Fg::White,
+                        name,
+                        ansi::Fg::Blue,
+                        sig,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                EndFor => {
+                    depth -= 1;
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}EndFor{}",
+                        ansi::Fg::Cyan,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                If(has_else) => {
+                    fmt_tab(f, depth)?;
+                    depth += 1;
+                    writeln!(
+                        f,
+                        "{}If{}{}",
+                        ansi::Fg::Cyan,
+                        if *has_else { " with else" } else { "" },
+                        ansi::Fg::Reset
+                    )?;
+                },
+                Else => {
+                    fmt_tab(f, depth - 1)?;
+                    writeln!(
+                        f,
+                        "{}Else{}",
+                        ansi::Fg::Cyan,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                EndIf => {
+                    depth -= 1;
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}EndIf{}",
+                        ansi::Fg::Cyan,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                While => {
+                    fmt_tab(f, depth)?;
+                    depth += 1;
+                    writeln!(
+                        f,
+                        "{}While{}",
+                        ansi::Fg::Cyan,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                EndWhile => {
+                    depth -= 1;
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}EndWhile{}",
+                        ansi::Fg::Cyan,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                Loop => {
+                    fmt_tab(f, depth)?;
+                    depth += 1;
+                    writeln!(
+                        f,
+                        "{}Loop{}",
+                        ansi::Fg::Cyan,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                EndLoop => {
+                    depth -= 1;
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}EndLoop{}",
+                        ansi::Fg::Cyan,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                Break => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}Break{}",
+                        ansi::Fg::Cyan,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                Continue => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}Continue{}",
+                        ansi::Fg::Cyan,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                Assign => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}Assign{}",
+                        ansi::Fg::Cyan,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                Return(_) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}Return{}: {}{}",
+                        ansi::Fg::Cyan,
+                        ansi::Fg::Blue,
+                        sig,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                Block => {
+                    fmt_tab(f, depth)?;
+                    depth += 1;
+                    writeln!(
+                        f,
+                        "{}Block{}",
+                        ansi::Fg::Cyan,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                EndBlock => {
+                    depth -= 1;
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}EndBlock{}",
+                        ansi::Fg::Cyan,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                Array(len) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}Array({}){}: {}{}",
+                        ansi::Fg::Magenta,
+                        len,
+                        ansi::Fg::Blue,
+                        sig,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                Index => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}Index{}: {}{}",
+                        ansi::Fg::Magenta,
+                        ansi::Fg::Blue,
+                        sig,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                Slice(start, end) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}Slice {}{}..{}{}: {}{}",
+                        ansi::Fg::Magenta,
+                        ansi::Fg::White,
+                        if *start { "start" } else { "" },
+                        if *end { "end" } else { "" },
+                        ansi::Fg::Blue,
+                        sig,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                LocalRef(name) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}LocalRef {}{}{}: {}{}",
+                        ansi::Fg::Magenta,
+                        ansi::Fg::White,
+                        name,
+                        ansi::Fg::Blue,
+                        sig,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                GlobalRef(name) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}GlobalRef {}{}{}: {}{}",
+                        ansi::Fg::Magenta,
+                        ansi::Fg::White,
+                        name,
+                        ansi::Fg::Blue,
+                        sig,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                Call(argc) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}Call({}{}{}){}: {}{}",
+                        ansi::Fg::Magenta,
+                        ansi::Fg::White,
+                        argc,
+                        ansi::Fg::Magenta,
+                        ansi::Fg::Blue,
+                        sig,
+                        ansi::Fg::Reset
                     )?;
                 }
             }
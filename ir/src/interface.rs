@@ -0,0 +1,253 @@
+use crate::{
+    consteval::{is_extension, ConstError, ConstEvaluator, ConstValue},
+    hir::{visibility_of, HIRInstruction, Module, Visibility},
+    type_signature::{GenericParam, TypeSignature},
+};
+use core::pos::BiPos as Position;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+///What a compiled module exports, which modules importing it are type checked against instead of its source.
+///The driver writes it to a `.bmi` file next to the module's source.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModuleInterface {
+    pub name: String,
+    ///The file name of the module's source, which notes about its declarations are reported against
+    pub file: String,
+    ///The hash of the source of the module along with the hashes of the interfaces it was compiled against.
+    ///The interface is stale once the module has to be compiled against anything else.
+    pub hash: u64,
+    ///The modules the module imports, with the hash of the interface of each it was compiled against
+    pub imports: Vec<(String, u64)>,
+    ///The public top level functions, including native ones
+    pub functions: Vec<FunctionExport>,
+    ///The public structs and newtypes
+    pub structs: Vec<StructLayout>,
+    ///The public constants along with their values
+    pub constants: Vec<ConstExport>,
+    ///The top level functions, structs, newtypes and constants that aren't public,
+    ///so that a module using one is told it can't rather than that it doesn't exist
+    pub hidden: Vec<HiddenDecl>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionExport {
+    pub name: String,
+    ///The signature of the function, whose params record which of them have a default value
+    pub sig: TypeSignature,
+}
+
+///The fields of a struct in declaration order, which is the order they are laid out in.
+///A newtype is a struct with the single field `value`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StructLayout {
+    pub name: String,
+    pub params: Vec<GenericParam>,
+    ///Every field of the struct, since they all take space in it. Only the public ones can be used by importers.
+    pub fields: Vec<FieldLayout>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldLayout {
+    pub name: String,
+    pub sig: TypeSignature,
+    pub visibility: Visibility,
+    ///Where the field is declared in the source of the module
+    pub pos: Position,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HiddenDecl {
+    pub name: String,
+    pub visibility: Visibility,
+    ///Where the item is declared in the source of the module
+    pub pos: Position,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConstExport {
+    pub name: String,
+    pub sig: TypeSignature,
+    pub value: ConstValue,
+}
+
+///FNV-1a, which unlike the hasher of the standard library is guaranteed to give the same hash on every build.
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+impl ModuleInterface {
+    ///The hash of a module with the source `source`, compiled against the interfaces of `imports`.
+    pub fn hash_of(source: &str, imports: &[(String, u64)]) -> u64 {
+        let mut hash = fnv1a(0xcbf29ce484222325, source.as_bytes());
+        for (name, import) in imports.iter() {
+            //The name is terminated so that two lists of imports never hash the same bytes
+            hash = fnv1a(hash, name.as_bytes());
+            hash = fnv1a(hash, &[0]);
+            hash = fnv1a(hash, &import.to_le_bytes());
+        }
+        hash
+    }
+
+    ///Whether this interface is still the one of the module with the source `source`,
+    ///given the current interfaces of the modules it imports.
+    pub fn is_fresh(&self, source: &str, imports: &[(String, u64)]) -> bool {
+        self.hash == Self::hash_of(source, imports)
+    }
+
+    ///The interface of the type checked `module` with the source `source` read from `file`,
+    ///compiled against the interfaces of `imports`.
+    ///Fails if the value of one of its constants can't be computed, which the type checker would have reported already.
+    pub fn of(module: &Module, file: &str, source: &str, imports: &[&ModuleInterface]) -> Result<Self, ConstError> {
+        let instructions = &module.instructions;
+        let mut evaluator = ConstEvaluator::new(instructions);
+        for constant in imports.iter().flat_map(|import| import.constants.iter()) {
+            evaluator.import(&constant.name, constant.value.clone());
+        }
+        let imports = imports
+            .iter()
+            .map(|import| (import.name.clone(), import.hash))
+            .collect::<Vec<(String, u64)>>();
+        let mut interface = ModuleInterface {
+            name: module.name.clone(),
+            file: file.to_string(),
+            hash: Self::hash_of(source, &imports),
+            imports,
+            functions: Vec::new(),
+            structs: Vec::new(),
+            constants: Vec::new(),
+            hidden: Vec::new(),
+        };
+        let mut depth = 0;
+        for (idx, ins) in instructions.iter().enumerate() {
+            let sig = &module.signatures[idx];
+            let visibility = visibility_of(instructions, idx);
+            let exported = depth == 0 && visibility == Visibility::Public;
+            let hidden = depth == 0 && !exported;
+            match ins {
+                //Extension functions are only found by the method calls of the module that declares them
+                HIRInstruction::Fn(name) => {
+                    if exported && !is_extension(instructions, idx) {
+                        interface.functions.push(FunctionExport {
+                            name: name.clone(),
+                            sig: sig.clone(),
+                        });
+                    } else if hidden && !is_extension(instructions, idx) {
+                        interface.hidden.push(HiddenDecl {
+                            name: name.clone(),
+                            visibility,
+                            pos: module.positions[idx],
+                        });
+                    }
+                    depth += 1;
+                }
+                HIRInstruction::NativeFn(name) if exported => interface.functions.push(FunctionExport {
+                    name: name.clone(),
+                    sig: sig.clone(),
+                }),
+                HIRInstruction::Struct(name) if exported => interface.structs.push(struct_layout(module, idx, name)),
+                HIRInstruction::Newtype(name) if exported => interface.structs.push(StructLayout {
+                    name: name.clone(),
+                    params: Vec::new(),
+                    fields: vec![FieldLayout {
+                        name: "value".to_string(),
+                        sig: sig.clone(),
+                        visibility: Visibility::Public,
+                        pos: module.positions[idx],
+                    }],
+                }),
+                HIRInstruction::Const(name) if exported => interface.constants.push(ConstExport {
+                    name: name.clone(),
+                    sig: sig.clone(),
+                    value: evaluator.constant(name)?,
+                }),
+                HIRInstruction::NativeFn(name)
+                | HIRInstruction::Struct(name)
+                | HIRInstruction::Newtype(name)
+                | HIRInstruction::Const(name)
+                    if hidden =>
+                {
+                    interface.hidden.push(HiddenDecl {
+                        name: name.clone(),
+                        visibility,
+                        pos: module.positions[idx],
+                    })
+                }
+                HIRInstruction::Interface(_) | HIRInstruction::Impl(_) => depth += 1,
+                HIRInstruction::EndFn | HIRInstruction::EndInterface | HIRInstruction::EndImpl => depth -= 1,
+                _ => {}
+            }
+        }
+        Ok(interface)
+    }
+}
+
+///The layout of the struct `name` declared at `idx`.
+fn struct_layout(module: &Module, idx: usize, name: &str) -> StructLayout {
+    let params = match &module.signatures[idx] {
+        TypeSignature::Struct(s) => s
+            .type_arguments
+            .iter()
+            .filter_map(|arg| match arg {
+                TypeSignature::Param(param) => Some(param.clone()),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    let fields = module.instructions[idx + 1..]
+        .iter()
+        .enumerate()
+        .take_while(|(_, ins)| **ins != HIRInstruction::EndStruct)
+        .filter_map(|(i, ins)| match ins {
+            HIRInstruction::StructField(field) => Some(FieldLayout {
+                name: field.clone(),
+                sig: module.signatures[idx + 1 + i].clone(),
+                visibility: visibility_of(&module.instructions, idx + 1 + i),
+                pos: module.positions[idx + 1 + i],
+            }),
+            _ => None,
+        })
+        .collect();
+    StructLayout {
+        name: name.to_string(),
+        params,
+        fields,
+    }
+}
+
+impl Display for ModuleInterface {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        writeln!(f, "Interface {} {:016x} from {}", self.name, self.hash, self.file)?;
+        for (name, hash) in self.imports.iter() {
+            writeln!(f, "    import {} {:016x}", name, hash)?;
+        }
+        for function in self.functions.iter() {
+            writeln!(f, "    fun {}: {}", function.name, function.sig)?;
+        }
+        for layout in self.structs.iter() {
+            let params = layout.params.iter().map(|param| param.name.as_str()).collect::<Vec<&str>>();
+            let fields = layout
+                .fields
+                .iter()
+                .map(|field| format!("{} {}: {}", field.visibility, field.name, field.sig))
+                .collect::<Vec<String>>();
+            if params.is_empty() {
+                writeln!(f, "    struct {} {{ {} }}", layout.name, fields.join(", "))?;
+            } else {
+                writeln!(f, "    struct {}<{}> {{ {} }}", layout.name, params.join(", "), fields.join(", "))?;
+            }
+        }
+        for constant in self.constants.iter() {
+            writeln!(f, "    const {}: {} = {}", constant.name, constant.sig, constant.value)?;
+        }
+        for hidden in self.hidden.iter() {
+            writeln!(f, "    {} {}", hidden.visibility, hidden.name)?;
+        }
+        Ok(())
+    }
+}
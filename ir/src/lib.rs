@@ -2,9 +2,17 @@ use serde::{Deserialize, Serialize};
 
 use core::pos::BiPos;
 
+pub mod consteval;
+pub mod encoding;
 pub mod hir;
+pub mod interface;
 pub mod type_signature;
 pub mod mir;
+pub mod mono;
+pub mod pass;
+pub mod symbols;
+pub mod text;
+mod verify;
 
 pub const TAB_WIDTH: usize = 5;
 
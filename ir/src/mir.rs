@@ -1,10 +1,13 @@
 use core::pos::BiPos;
+use crate::hir::{BinaryOp, UnaryOp};
 use crate::type_signature::{
     TypeSignature
 };
 
 use serde::{Serialize, Deserialize};
 
+pub use crate::verify::{verify_mir as verify, VerifyError};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum MIRInstruction{
     ///Module start
@@ -19,6 +22,7 @@ pub enum MIRInstruction{
     ///Drop `name`. This can either be a value or a reference.
     ///The drop mechanism is smart. If what is being dropped is a reference, 
     ///the reference counter will decrement the count for object `name`.
+    ///An object moved on only some of the paths to its drop is only dropped when it wasn't moved.
     Drop(String),
     ///Function start. 
     ///At the beginning is where local variable preallocation will occur.
@@ -26,10 +30,15 @@ pub enum MIRInstruction{
     ///End function.
     ///This is where all drops to local variables and any references or values passed as arguments will occur.
     EndFun,
+    ///Declaration of the C function `name`, which is left for the linker to resolve.
+    ///Linking isn't implemented yet, so nothing provides the function, not even when it is one of libc.
+    NativeFun(String),
 
     //Function param.
     //The call to the containing function will handle the pass-by.
     FunParam(String),
+    ///The default value of the `FunParam` right before it, given by the expression that follows.
+    DefaultArg,
 
     //Literals
     ///Integer literal
@@ -49,10 +58,13 @@ pub enum MIRInstruction{
     Ref(String),
     ///Move `name`.
     ///A single reference to a local variables will result in this instruction.
+    ///Once moved, the object can't be used again until it is given a new value.
     Move(String),
     ///Copy `name`.
     ///Where n is the number of references to a local variable, all references until n-1 will result in this instruction,
     /// whereas the final reference to a local variable will result in a Move instruction.
+    ///Only values of trivially copyable types can be copied, so the ownership pass reports the other references
+    ///to a move-only local that aren't its last.
     Copy(String),
     ///Heap allocation of `size`
     HeapAlloc(usize),
@@ -68,15 +80,133 @@ pub enum MIRInstruction{
     Lateinit(usize),
     ///Mutate object `name`.
     ///An expression must proceed this instruction.
+    ///An assignment to a local or property computes its new value first, then drops the old one and mutates it:
+    ///     ObjInit tmp
+    ///         ...value
+    ///     Drop x
+    ///     ObjMut x
+    ///         Copy tmp
     ObjMut(String),
+    ///Mutate field `name` of an object. The object follows, as a `Ref` of a local or property or a `Field` of one,
+    ///then the new value of the field. The value the field held is dropped once the new one is computed.
+    FieldMut(String),
+
+    //Expressions
+    ///Top level function `name`, or a builtin such as `Some`, `None` or `Range.next`.
+    ///The methods of a type are known as `Type.method`, or `Type.Interface.method` for those implementing an interface,
+    ///the default methods of an interface as `Interface.method` and extension functions as `ext Type.method`.
+    Global(String),
+    ///Call with `argc` arguments. The callee follows, then each argument in order.
+    ///A method is called with its receiver as the first argument.
+    Call(usize),
+    ///The argument that follows is given to the param `name` of the enclosing call.
+    ///Named arguments come after every positional one, and the params left out take their default value.
+    NamedArg(String),
+    ///Field `name` of the object that follows.
+    Field(String),
+    ///Binary operation. The left operand follows, then the right one.
+    ///`And` and `Or` only evaluate the right operand when it decides the result.
+    Binary(BinaryOp),
+    ///Unary operation on the operand that follows.
+    Unary(UnaryOp),
+
+    //Dynamic dispatch
+    ///Virtual method table of interface `interface` (first) implemented by type `type` (second).
+    ///One `VTableEntry` per method of the interface follows, in the order the interface declares its methods,
+    ///and the table is closed by `EndVTable`.
+    VTable(String, String),
+    ///The function implementing the next method of the enclosing vtable.
+    VTableEntry(String),
+    ///End vtable.
+    EndVTable,
+    ///Turn the value that follows into a trait object of interface `interface`.
+    ///The trait object is the value paired with the vtable of its type for `interface`.
+    MakeDyn(String),
+    ///Call the method in slot `slot` (first) of the vtable of the trait object that follows, with `argc` (second) arguments.
+    ///The trait object is passed as the receiver and the arguments follow it in order.
+    DynCall(usize, usize),
+
+    //Collections
+    ///Array of `len` elements, initialized by the `len` values that follow in order.
+    ArrayInit(usize),
+    ///List initialized by the `len` values that follow in order.
+    ///The list owns a heap buffer that grows as elements are pushed.
+    ListInit(usize),
+    ///Copy the elements of the array that follows into a new list.
+    ArrayToList,
+    ///Check the index that follows against the length of the collection after it.
+    ///If the index is negative or not less than the length, the program halts with an out of bounds error.
+    ///Every `Index` is preceded by a `BoundsCheck` of the same collection and index.
+    BoundsCheck,
+    ///Element of the collection that follows at the index after it.
+    ///The index has already been checked by a `BoundsCheck`.
+    Index,
+    ///Copy the elements of the collection that follows from the start index up to but excluding the end index into a new list.
+    ///The start and end follow the collection. The program halts with an out of bounds error
+    ///unless `0 <= start <= end <= len`.
+    Slice,
+    ///Length of the collection that follows.
+    Len,
+    ///Push onto the end of the list that follows the value after it, growing the buffer of the list if it is full.
+    ListPush,
+
+    //Loops and iteration
+    ///Loop start. The instructions up to the matching `EndLoop` run until a `Break` leaves the loop.
+    Loop,
+    ///End loop. Jumps back to the matching `Loop`.
+    EndLoop,
+    ///Leave the innermost loop, continuing after its `EndLoop`.
+    Break,
+    ///Go back to the start of the innermost loop.
+    Continue,
+    ///Range from the start value that follows to the end value after it.
+    ///The flag is set when the end is part of the range.
+    RangeInit(bool),
+    ///Leave the innermost loop if the optional `name` is `None`.
+    ///A `for` loop lowers to a loop around a call of the iterator's `next`, followed by this and an `Unwrap` of the result:
+    ///     Loop
+    ///         next = iter.next()
+    ///         BreakIfNone next
+    ///         x = Unwrap next
+    ///         ...body
+    ///         Drop x
+    ///         Drop next
+    ///     EndLoop
+    ///Arrays and lists are iterated through a hidden index instead, and ranges by advancing their start.
+    ///Nothing of the body is dropped when it breaks, since `name` is `None` and the rest is declared after it.
+    BreakIfNone(String),
+    ///Value held by the optional `name`, which is known to be `Some`, or by the result `name`, known to be `Ok`.
+    Unwrap(String),
+    ///Whether the result `name` is an `Err`.
+    ///A `?` on a result lowers to a return of its error when it is one, dropping what the function declared before it:
+    ///     result = ...
+    ///     If IsErr result
+    ///         Drop ...
+    ///         Return Err(UnwrapErr result)
+    ///     EndIf
+    ///     ...Unwrap result
+    IsErr(String),
+    ///Error held by the result `name`, which is known to be `Err`.
+    UnwrapErr(String),
+
+    //Control flow
+    ///Conditional. The condition follows, then the instructions run when it is true.
+    ///If the flag is set, they are closed by an `Else` and the instructions run otherwise come after it, up to the `EndIf`.
+    If(bool),
+    Else,
+    EndIf,
+    ///Return from the function. Every local declared before it is dropped before it, once the returned value is computed.
+    ///If the flag is set, the returned value follows.
+    Return(bool),
     ///Halt compiler
     Halt
 }
 
+#[derive(Debug, Clone)]
 pub struct MIR{
-    pos: BiPos,
-    sig: TypeSignature,
-    ins: MIRInstruction
+    pub pos: BiPos,
+    pub sig: TypeSignature,
+    pub ins: MIRInstruction
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -114,6 +244,20 @@ impl Module {
     }
 }
 
+///The index after the expression starting at `idx`, whose operands follow it in prefix order.
+pub fn expression_end(instructions: &[MIRInstruction], idx: usize) -> usize {
+    use MIRInstruction::*;
+    let operands = match &instructions[idx] {
+        Call(argc) | DynCall(_, argc) => argc + 1,
+        ArrayInit(len) | ListInit(len) => *len,
+        Binary(_) | BoundsCheck | Index | ListPush | RangeInit(_) | FieldMut(_) => 2,
+        Slice => 3,
+        NamedArg(_) | Field(_) | Unary(_) | MakeDyn(_) | ArrayToList | Len => 1,
+        _ => 0,
+    };
+    (0..operands).fold(idx + 1, |next, _| expression_end(instructions, next))
+}
+
 use std::fmt::{
     Display,
     Formatter,
@@ -166,7 +310,19 @@ impl Display for Module {
                         ansi::Fg::Reset
                     )?;
                 },
-                EndFn => {
+                NativeFun(name) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}Native function{} {}: {}{}",
+                        ansi::Fg::Cyan,
+                        ansi::Fg::Red,
+                        name,
+                        sig,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                EndFun => {
                     depth -= 1;
                     fmt_tab(f, depth)?;
                     writeln!(
@@ -240,6 +396,17 @@ impl Display for Module {
                         ansi::Fg::Reset
                     )?;
                 },
+                Bool(b) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}Bool {}{}{}",
+                        ansi::Fg::Cyan,
+                        ansi::Fg::Green,
+                        b,
+                        ansi::Fg::Reset
+                    )?;
+                },
                 Unit => {
                     fmt_tab(f, depth)?;
                     writeln!(
@@ -304,6 +471,367 @@ impl Display for Module {
                         ansi::Fg::Reset
                     )?;
                 },
+                Lateinit(size) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}Lateinit({}{}){}",
+                        ansi::Fg::Blue,
+                        ansi::Fg::White,
+                        size,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                ObjMut(name) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}Mut {}{}{}",
+                        ansi::Fg::BrightRed,
+                        ansi::Fg::White,
+                        name,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                FieldMut(name) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}MutField {}{}{}",
+                        ansi::Fg::BrightRed,
+                        ansi::Fg::White,
+                        name,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                VTable(interface, type_) => {
+                    fmt_tab(f, depth)?;
+                    depth += 1;
+                    writeln!(
+                        f,
+                        "{}VTable{} {} {}for{} {}{}",
+                        ansi::Fg::Cyan,
+                        ansi::Fg::Red,
+                        interface,
+                        ansi::Fg::Cyan,
+                        ansi::Fg::Red,
+                        type_,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                VTableEntry(name) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}Entry {}{}{}",
+                        ansi::Fg::Cyan,
+                        ansi::Fg::White,
+                        name,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                EndVTable => {
+                    depth -= 1;
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}EndVTable{}",
+                        ansi::Fg::Cyan,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                MakeDyn(interface) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}MakeDyn {}{}{}",
+                        ansi::Fg::Magenta,
+                        ansi::Fg::White,
+                        interface,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                DynCall(slot, argc) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}DynCall{} slot {} ({}){}: {}{}",
+                        ansi::Fg::Magenta,
+                        ansi::Fg::White,
+                        slot,
+                        argc,
+                        ansi::Fg::Blue,
+                        sig,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                ListInit(len) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}ListInit({}){}: {}{}",
+                        ansi::Fg::Magenta,
+                        len,
+                        ansi::Fg::Blue,
+                        sig,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                ArrayInit(len) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}ArrayInit({}){}: {}{}",
+                        ansi::Fg::Magenta,
+                        len,
+                        ansi::Fg::Blue,
+                        sig,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                ArrayToList => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}ArrayToList{}: {}{}",
+                        ansi::Fg::Magenta,
+                        ansi::Fg::Blue,
+                        sig,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                BoundsCheck => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}BoundsCheck{}: {}{}",
+                        ansi::Fg::Magenta,
+                        ansi::Fg::Blue,
+                        sig,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                Index => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}Index{}: {}{}",
+                        ansi::Fg::Magenta,
+                        ansi::Fg::Blue,
+                        sig,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                Slice => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}Slice{}: {}{}",
+                        ansi::Fg::Magenta,
+                        ansi::Fg::Blue,
+                        sig,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                Len => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}Len{}: {}{}",
+                        ansi::Fg::Magenta,
+                        ansi::Fg::Blue,
+                        sig,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                ListPush => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}ListPush{}: {}{}",
+                        ansi::Fg::Magenta,
+                        ansi::Fg::Blue,
+                        sig,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                Loop => {
+                    fmt_tab(f, depth)?;
+                    depth += 1;
+                    writeln!(f, "{}Loop{}", ansi::Fg::Cyan, ansi::Fg::Reset)?;
+                },
+                EndLoop => {
+                    depth -= 1;
+                    fmt_tab(f, depth)?;
+                    writeln!(f, "{}EndLoop{}", ansi::Fg::Cyan, ansi::Fg::Reset)?;
+                },
+                Break => {
+                    fmt_tab(f, depth)?;
+                    writeln!(f, "{}Break{}", ansi::Fg::Cyan, ansi::Fg::Reset)?;
+                },
+                RangeInit(inclusive) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}RangeInit{}{}: {}{}",
+                        ansi::Fg::Magenta,
+                        if *inclusive { " inclusive" } else { "" },
+                        ansi::Fg::Blue,
+                        sig,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                BreakIfNone(name) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}BreakIfNone {}{}{}",
+                        ansi::Fg::Magenta,
+                        ansi::Fg::White,
+                        name,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                Unwrap(name) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}Unwrap {}{}{}",
+                        ansi::Fg::Magenta,
+                        ansi::Fg::White,
+                        name,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                IsErr(name) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}IsErr {}{}{}",
+                        ansi::Fg::Magenta,
+                        ansi::Fg::White,
+                        name,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                UnwrapErr(name) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}UnwrapErr {}{}{}",
+                        ansi::Fg::Magenta,
+                        ansi::Fg::White,
+                        name,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                DefaultArg => {
+                    fmt_tab(f, depth)?;
+                    writeln!(f, "{}Default{}", ansi::Fg::Cyan, ansi::Fg::Reset)?;
+                },
+                Global(name) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}Global {}{}{}: {}{}",
+                        ansi::Fg::Cyan,
+                        ansi::Fg::White,
+                        name,
+                        ansi::Fg::Blue,
+                        sig,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                Call(argc) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}Call{} ({}){}: {}{}",
+                        ansi::Fg::Magenta,
+                        ansi::Fg::White,
+                        argc,
+                        ansi::Fg::Blue,
+                        sig,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                NamedArg(name) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}Arg {}{}{}",
+                        ansi::Fg::Magenta,
+                        ansi::Fg::White,
+                        name,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                Field(name) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}Field {}{}{}: {}{}",
+                        ansi::Fg::Magenta,
+                        ansi::Fg::White,
+                        name,
+                        ansi::Fg::Blue,
+                        sig,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                Binary(op) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}{:?}{}: {}{}",
+                        ansi::Fg::Magenta,
+                        op,
+                        ansi::Fg::Blue,
+                        sig,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                Unary(op) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}{:?}{}: {}{}",
+                        ansi::Fg::Magenta,
+                        op,
+                        ansi::Fg::Blue,
+                        sig,
+                        ansi::Fg::Reset
+                    )?;
+                },
+                Continue => {
+                    fmt_tab(f, depth)?;
+                    writeln!(f, "{}Continue{}", ansi::Fg::Cyan, ansi::Fg::Reset)?;
+                },
+                If(_) => {
+                    fmt_tab(f, depth)?;
+                    depth += 1;
+                    writeln!(f, "{}If{}", ansi::Fg::Cyan, ansi::Fg::Reset)?;
+                },
+                Else => {
+                    fmt_tab(f, depth.saturating_sub(1))?;
+                    writeln!(f, "{}Else{}", ansi::Fg::Cyan, ansi::Fg::Reset)?;
+                },
+                EndIf => {
+                    depth -= 1;
+                    fmt_tab(f, depth)?;
+                    writeln!(f, "{}EndIf{}", ansi::Fg::Cyan, ansi::Fg::Reset)?;
+                },
+                Return(_) => {
+                    fmt_tab(f, depth)?;
+                    writeln!(
+                        f,
+                        "{}Return{}: {}{}",
+                        ansi::Fg::Cyan,
+                        ansi::Fg::Blue,
+                        sig,
+                        ansi::Fg::Reset
+                    )?;
+                },
                 StackAlloc(size) => {
                     fmt_tab(f, depth)?;
                     writeln!(
@@ -0,0 +1,487 @@
+use crate::{
+    hir::{HIRInstruction, Module, ReceiverKind, HIR},
+    type_signature::{Parameter, StructSignature, TypeSignature},
+};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+///A generic declaration found in the module, which gets copied once per set of type arguments used.
+struct GenericDecl {
+    ///The index of the opening `Fn` or `Struct` instruction
+    start: usize,
+    ///The index of the matching `EndFn` or `EndStruct` instruction
+    end: usize,
+    ///The names of the type params, in declaration order
+    params: Vec<String>,
+    ///The generic signature of the declaration
+    sig: TypeSignature,
+    ///The name the declaration is referred to by, such as `Point.new` for an associated function.
+    ///Methods and extension functions are only called through their receiver, so they have none.
+    global: Option<String>,
+    ///The type of the receiver of a method or extension function
+    receiver: Option<TypeSignature>,
+}
+
+enum Instantiation {
+    ///The generic function at this index of `functions`
+    Function(usize, HashMap<String, TypeSignature>),
+    Struct(String, HashMap<String, TypeSignature>),
+}
+
+struct Monomorphizer<'m> {
+    module: &'m Module,
+    functions: Vec<GenericDecl>,
+    structs: HashMap<String, GenericDecl>,
+    ///The indices of the `Fn` and `EndFn` of the default methods of each interface
+    defaults: HashMap<String, Vec<(usize, usize)>>,
+    queue: VecDeque<Instantiation>,
+    ///The start of the declaration and the mangled name of every instantiation queued so far
+    seen: HashSet<(usize, String)>,
+}
+
+///Produce a copy of `module` where every generic function and struct has been replaced by
+///one specialized copy per distinct set of type arguments it is used with.
+///
+///This expects a type checked module: each `GlobalRef` to a generic function must carry the
+///concrete function signature it was instantiated with, and each `MethodCall` the type it returns.
+///Specialized copies are named after the generic declaration and its type arguments, e.g. `first<Int>`,
+///and are placed at the end of the module. Their instructions keep the symbols of the ones they are copied from.
+///The default methods of an interface are generic over the type implementing it, so they are copied into every
+///impl block of the interface not defining them, and the interface only keeps their signature.
+pub fn monomorphize(module: &Module) -> Module {
+    let mut mono = Monomorphizer {
+        module,
+        functions: Vec::new(),
+        structs: HashMap::new(),
+        defaults: HashMap::new(),
+        queue: VecDeque::new(),
+        seen: HashSet::new(),
+    };
+    mono.collect_generics();
+
+    let mut ret = Module::new(module.name.clone());
+    ret.symbol_table = module.symbol_table.clone();
+    ret.passes = module.passes.clone();
+    let mut impl_block = None;
+    let mut idx = 0;
+    while idx < module.instructions.len() {
+        if let Some(end) = mono.generic_decl_end(idx) {
+            idx = end + 1;
+            continue;
+        }
+        //The annotations and visibility of a generic declaration go along with it
+        let decl = module.instructions[idx..]
+            .iter()
+            .position(|ins| !matches!(ins, HIRInstruction::Visibility(_) | HIRInstruction::Annotation(_, _)))
+            .map_or(idx, |offset| idx + offset);
+        if decl != idx && mono.generic_decl_end(decl).is_some() {
+            idx = decl;
+            continue;
+        }
+        match &module.instructions[idx] {
+            HIRInstruction::Impl(_) => impl_block = Some(idx),
+            HIRInstruction::EndImpl => {
+                if let Some(start) = impl_block.take() {
+                    mono.default_methods(start, idx, &mut ret);
+                }
+            }
+            HIRInstruction::Fn(name) => {
+                if let Some(end) = mono.default_method_end(idx) {
+                    ret.push_ir(HIR {
+                        pos: module.positions[idx],
+                        sig: module.signatures[idx].clone(),
+                        ins: HIRInstruction::InterfaceMethod(name.clone(), receiver_of(module, idx).unwrap().0),
+                        symbol: module.symbols.get(idx).copied().flatten(),
+                    });
+                    idx = end + 1;
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        let ir = mono.rewrite(idx, &HashMap::new());
+        ret.push_ir(ir);
+        idx += 1;
+    }
+
+    let mut specialized = Module::new(module.name.clone());
+    while let Some(inst) = mono.queue.pop_front() {
+        mono.instantiate(inst, &mut specialized);
+    }
+
+    let insert_at = ret
+        .instructions
+        .iter()
+        .rposition(|ins| *ins == HIRInstruction::EndModule)
+        .unwrap_or(ret.instructions.len());
+    ret.instructions
+        .splice(insert_at..insert_at, specialized.instructions);
+    ret.signatures.splice(insert_at..insert_at, specialized.signatures);
+    ret.positions.splice(insert_at..insert_at, specialized.positions);
+    ret.symbols.splice(insert_at..insert_at, specialized.symbols);
+    ret
+}
+
+///The name given to the copy of `name` specialized for `args`.
+pub fn mangle(name: &str, args: &[TypeSignature]) -> String {
+    let args = args
+        .iter()
+        .map(|a| a.to_string())
+        .collect::<Vec<String>>()
+        .join(", ");
+    format!("{}<{}>", name, args)
+}
+
+///How the function declared at `idx` takes its receiver and the type of the receiver, if it has one.
+fn receiver_of(module: &Module, idx: usize) -> Option<(ReceiverKind, &TypeSignature)> {
+    module.instructions[idx + 1..]
+        .iter()
+        .zip(module.signatures[idx + 1..].iter())
+        .find(|(ins, _)| !matches!(ins, HIRInstruction::TypeParam(_)))
+        .and_then(|(ins, sig)| match ins {
+            HIRInstruction::SelfParam(receiver) => Some((*receiver, sig)),
+            _ => None,
+        })
+}
+
+impl<'m> Monomorphizer<'m> {
+    fn collect_generics(&mut self) {
+        let instructions = &self.module.instructions;
+        //The interface or the type whose methods are being declared, if any
+        let mut interface: Option<&String> = None;
+        let mut impl_name: Option<String> = None;
+        let mut depth = 0;
+        for (idx, ins) in instructions.iter().enumerate() {
+            let (name, closing) = match ins {
+                HIRInstruction::Interface(name) => {
+                    interface = Some(name);
+                    continue;
+                }
+                HIRInstruction::EndInterface => {
+                    interface = None;
+                    continue;
+                }
+                HIRInstruction::Impl(_) => {
+                    impl_name = match &self.module.signatures[idx] {
+                        TypeSignature::Struct(s) => Some(s.name.clone()),
+                        sig => Some(sig.to_string()),
+                    };
+                    continue;
+                }
+                HIRInstruction::EndImpl => {
+                    impl_name = None;
+                    continue;
+                }
+                HIRInstruction::EndFn => {
+                    depth -= 1;
+                    continue;
+                }
+                HIRInstruction::Fn(name) => (name, HIRInstruction::EndFn),
+                HIRInstruction::Struct(name) => (name, HIRInstruction::EndStruct),
+                _ => continue,
+            };
+            let end = Self::matching_end(instructions, idx, ins, &closing);
+            if closing == HIRInstruction::EndFn {
+                depth += 1;
+                if let (1, Some(interface), Some(end)) = (depth, interface, end) {
+                    if receiver_of(self.module, idx).is_some() {
+                        self.defaults.entry(interface.clone()).or_default().push((idx, end));
+                    }
+                    continue;
+                }
+            }
+            let params = instructions[idx + 1..]
+                .iter()
+                .take_while(|ins| matches!(ins, HIRInstruction::TypeParam(_)))
+                .map(|ins| match ins {
+                    HIRInstruction::TypeParam(p) => p.clone(),
+                    _ => unreachable!(),
+                })
+                .collect::<Vec<String>>();
+            if params.is_empty() {
+                continue;
+            }
+            let end = match end {
+                Some(end) => end,
+                None => continue,
+            };
+            let receiver = receiver_of(self.module, idx).map(|(_, sig)| sig.clone());
+            let global = match (&receiver, &impl_name) {
+                (Some(_), _) => None,
+                (None, Some(impl_name)) if depth == 1 => Some(format!("{}.{}", impl_name, name)),
+                (None, _) => Some(name.clone()),
+            };
+            let decl = GenericDecl {
+                start: idx,
+                end,
+                params,
+                sig: self.module.signatures[idx].clone(),
+                global,
+                receiver,
+            };
+            if closing == HIRInstruction::EndFn {
+                self.functions.push(decl);
+            } else {
+                self.structs.insert(name.clone(), decl);
+            }
+        }
+    }
+
+    fn matching_end(
+        instructions: &[HIRInstruction],
+        start: usize,
+        opening: &HIRInstruction,
+        closing: &HIRInstruction,
+    ) -> Option<usize> {
+        let mut depth = 0;
+        for (idx, ins) in instructions.iter().enumerate().skip(start) {
+            if std::mem::discriminant(ins) == std::mem::discriminant(opening) {
+                depth += 1;
+            } else if ins == closing {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(idx);
+                }
+            }
+        }
+        None
+    }
+
+    fn generic_decl_end(&self, idx: usize) -> Option<usize> {
+        match self.module.instructions.get(idx)? {
+            HIRInstruction::Fn(_) => self.functions.iter().find(|decl| decl.start == idx),
+            HIRInstruction::Struct(name) => self.structs.get(name).filter(|decl| decl.start == idx),
+            _ => None,
+        }
+        .map(|decl| decl.end)
+    }
+
+    ///The index of the `EndFn` of the default method declared at `idx`, if there is one.
+    fn default_method_end(&self, idx: usize) -> Option<usize> {
+        self.defaults
+            .values()
+            .flatten()
+            .find(|(start, _)| *start == idx)
+            .map(|(_, end)| *end)
+    }
+
+    ///Copy the default methods that the impl block from `start` to `end` doesn't define into `out`,
+    ///specialized for the type it implements the interface for.
+    fn default_methods(&mut self, start: usize, end: usize, out: &mut Module) {
+        let module = self.module;
+        let interface = match &module.instructions[start] {
+            HIRInstruction::Impl(Some(interface)) => interface,
+            _ => return,
+        };
+        let mut defined = HashSet::new();
+        let mut depth = 0;
+        for ins in module.instructions[start + 1..end].iter() {
+            match ins {
+                HIRInstruction::Fn(name) if depth == 0 => {
+                    defined.insert(name);
+                    depth += 1;
+                }
+                HIRInstruction::Fn(_) => depth += 1,
+                HIRInstruction::EndFn => depth -= 1,
+                _ => {}
+            }
+        }
+        let substitutions = HashMap::from([("Self".to_string(), module.signatures[start].clone())]);
+        for (method_start, method_end) in self.defaults.get(interface).cloned().unwrap_or_default() {
+            if let HIRInstruction::Fn(name) = &module.instructions[method_start] {
+                if defined.contains(name) {
+                    continue;
+                }
+            }
+            for idx in method_start..=method_end {
+                let ir = self.rewrite(idx, &substitutions);
+                out.push_ir(ir);
+            }
+        }
+    }
+
+    ///Copy the instruction at `idx`, substituting type params and pointing any reference to a
+    ///generic declaration at its specialized copy.
+    fn rewrite(&mut self, idx: usize, substitutions: &HashMap<String, TypeSignature>) -> HIR {
+        let sig = self.module.signatures[idx].substitute(substitutions);
+        let ins = match &self.module.instructions[idx] {
+            HIRInstruction::GlobalRef(name) => match self.function_instance(name, &sig) {
+                Some(mangled) => HIRInstruction::GlobalRef(mangled),
+                None => HIRInstruction::GlobalRef(name.clone()),
+            },
+            HIRInstruction::MethodCall(name, argc) => {
+                let receiver = self.module.signatures[idx + 1].substitute(substitutions);
+                match self.method_instance(name, &receiver, &sig) {
+                    Some(mangled) => HIRInstruction::MethodCall(mangled, *argc),
+                    None => HIRInstruction::MethodCall(name.clone(), *argc),
+                }
+            }
+            ins => ins.clone(),
+        };
+        let sig = self.rewrite_signature(&sig);
+        //The constructor of a generic struct is named after the specialized copy it returns
+        let ins = match (ins, &sig) {
+            (HIRInstruction::GlobalRef(name), TypeSignature::Function(f)) if self.structs.contains_key(&name) => {
+                match &*f.return_type_signature {
+                    TypeSignature::Struct(s) => HIRInstruction::GlobalRef(s.name.clone()),
+                    _ => HIRInstruction::GlobalRef(name),
+                }
+            }
+            (ins, _) => ins,
+        };
+        HIR {
+            pos: self.module.positions[idx],
+            sig,
+            ins,
+            symbol: self.module.symbols.get(idx).copied().flatten(),
+        }
+    }
+
+    ///Queue the instantiation of the generic function referred to as `name` used with the concrete signature `sig`.
+    fn function_instance(&mut self, name: &str, sig: &TypeSignature) -> Option<String> {
+        if sig.is_generic() {
+            return None;
+        }
+        let decl = self
+            .functions
+            .iter()
+            .position(|decl| decl.global.as_deref() == Some(name))?;
+        let mut substitutions = HashMap::new();
+        if !self.functions[decl].sig.unify(sig, &mut substitutions) {
+            return None;
+        }
+        Some(self.function_copy(decl, substitutions))
+    }
+
+    ///Queue the instantiation of the generic method or extension function `name` called on a receiver of type
+    ///`receiver` and returning `ret`, if one applies to the receiver.
+    fn method_instance(&mut self, name: &str, receiver: &TypeSignature, ret: &TypeSignature) -> Option<String> {
+        if receiver.is_generic() {
+            return None;
+        }
+        let module = self.module;
+        let (decl, mut substitutions) = self.functions.iter().enumerate().find_map(|(idx, decl)| {
+            let mut substitutions = HashMap::new();
+            match (&module.instructions[decl.start], &decl.receiver) {
+                (HIRInstruction::Fn(declared), Some(declared_receiver))
+                    if declared == name && declared_receiver.unify(receiver, &mut substitutions) =>
+                {
+                    Some((idx, substitutions))
+                }
+                _ => None,
+            }
+        })?;
+        //The receiver doesn't always tell every type param apart, unlike the type the call returns
+        if let TypeSignature::Function(f) = &self.functions[decl].sig {
+            if !ret.is_generic() {
+                f.return_type_signature.unify(ret, &mut substitutions);
+            }
+        }
+        Some(self.function_copy(decl, substitutions))
+    }
+
+    ///Queue the copy of the generic function at `decl` of `functions` specialized with `substitutions`,
+    ///unless it already is, returning the name of the copy.
+    fn function_copy(&mut self, decl: usize, substitutions: HashMap<String, TypeSignature>) -> String {
+        let mangled = self.copy_name(&self.functions[decl], &substitutions);
+        if self.seen.insert((self.functions[decl].start, mangled.clone())) {
+            self.queue
+                .push_back(Instantiation::Function(decl, substitutions));
+        }
+        mangled
+    }
+
+    ///The name of the copy of `decl` specialized with `substitutions`.
+    ///A method keeps its own name, which its receiver tells apart from the methods of other types.
+    fn copy_name(&self, decl: &GenericDecl, substitutions: &HashMap<String, TypeSignature>) -> String {
+        let args = decl
+            .params
+            .iter()
+            .map(|p| substitutions.get(p).cloned().unwrap_or(TypeSignature::None))
+            .collect::<Vec<TypeSignature>>();
+        match (&decl.global, &self.module.instructions[decl.start]) {
+            (Some(global), _) => mangle(global, &args),
+            (None, HIRInstruction::Fn(name) | HIRInstruction::Struct(name)) => mangle(name, &args),
+            (None, _) => unreachable!(),
+        }
+    }
+
+    ///Replace concrete uses of generic structs in `sig` with their specialized copies.
+    fn rewrite_signature(&mut self, sig: &TypeSignature) -> TypeSignature {
+        match sig {
+            TypeSignature::Struct(s) if !s.type_arguments.is_empty() && !sig.is_generic() => {
+                let args = s
+                    .type_arguments
+                    .iter()
+                    .map(|t| self.rewrite_signature(t))
+                    .collect::<Vec<TypeSignature>>();
+                let decl = match self.structs.get(&s.name) {
+                    Some(decl) => decl,
+                    None => return sig.clone(),
+                };
+                let mangled = mangle(&s.name, &args);
+                if self.seen.insert((decl.start, mangled.clone())) {
+                    let substitutions = decl
+                        .params
+                        .iter()
+                        .cloned()
+                        .zip(args)
+                        .collect::<HashMap<String, TypeSignature>>();
+                    self.queue
+                        .push_back(Instantiation::Struct(s.name.clone(), substitutions));
+                }
+                TypeSignature::Struct(StructSignature {
+                    name: mangled,
+                    type_arguments: vec![],
+                })
+            }
+            TypeSignature::Function(f) => {
+                let mut f = f.clone();
+                f.parameters = f
+                    .parameters
+                    .iter()
+                    .map(|p| Parameter {
+                        sig: self.rewrite_signature(&p.sig),
+                        ..p.clone()
+                    })
+                    .collect();
+                f.return_type_signature = Box::new(self.rewrite_signature(&f.return_type_signature));
+                TypeSignature::Function(f)
+            }
+            TypeSignature::Array(element, len) => {
+                TypeSignature::Array(Box::new(self.rewrite_signature(element)), *len)
+            }
+            TypeSignature::List(element) => TypeSignature::List(Box::new(self.rewrite_signature(element))),
+            TypeSignature::Option(element) => TypeSignature::Option(Box::new(self.rewrite_signature(element))),
+            TypeSignature::Range(element) => TypeSignature::Range(Box::new(self.rewrite_signature(element))),
+            TypeSignature::Result(value, error) => TypeSignature::Result(
+                Box::new(self.rewrite_signature(value)),
+                Box::new(self.rewrite_signature(error)),
+            ),
+            _ => sig.clone(),
+        }
+    }
+
+    fn instantiate(&mut self, inst: Instantiation, out: &mut Module) {
+        let (decl, substitutions, is_fn) = match inst {
+            Instantiation::Function(decl, subs) => (&self.functions[decl], subs, true),
+            Instantiation::Struct(name, subs) => (&self.structs[&name], subs, false),
+        };
+        let (start, end) = (decl.start, decl.end);
+        let mangled = self.copy_name(decl, &substitutions);
+
+        for idx in start..=end {
+            if let HIRInstruction::TypeParam(_) = self.module.instructions[idx] {
+                continue;
+            }
+            let mut ir = self.rewrite(idx, &substitutions);
+            if idx == start {
+                ir.ins = if is_fn {
+                    HIRInstruction::Fn(mangled.clone())
+                } else {
+                    HIRInstruction::Struct(mangled.clone())
+                };
+            }
+            out.push_ir(ir);
+        }
+    }
+}
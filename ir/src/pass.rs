@@ -0,0 +1,216 @@
+//Passes report the errors they find as notices themselves, so a pass failing has nothing more to say than that it failed
+#![allow(clippy::result_unit_err)]
+
+use crate::{
+    hir::{self, VerifyError},
+    mir, text,
+};
+use std::collections::HashSet;
+
+///A pass reading a HIR module without changing it, such as an analysis.
+///Passes report the errors they find themselves, and fail once they have.
+pub trait HirVisitor {
+    ///Visit the whole module, by default each of its instructions in order.
+    fn visit_module(&mut self, module: &hir::Module) -> Result<(), ()> {
+        (0..module.instructions.len()).try_for_each(|idx| self.visit_instruction(module, idx))
+    }
+
+    ///Visit the instruction at `idx`. The rest of the module is there to look at its operands or where it is nested.
+    fn visit_instruction(&mut self, _module: &hir::Module, _idx: usize) -> Result<(), ()> {
+        Ok(())
+    }
+}
+
+///A pass changing a HIR module in place, such as the type checker filling in the signatures of its instructions.
+pub trait HirMutVisitor {
+    ///Visit the whole module, by default each of its instructions in order.
+    ///Passes adding or removing instructions do so here.
+    fn visit_module_mut(&mut self, module: &mut hir::Module) -> Result<(), ()> {
+        (0..module.instructions.len()).try_for_each(|idx| self.visit_instruction_mut(module, idx))
+    }
+
+    ///Visit the instruction at `idx`, which along with its signature can be replaced but not removed.
+    fn visit_instruction_mut(&mut self, _module: &mut hir::Module, _idx: usize) -> Result<(), ()> {
+        Ok(())
+    }
+}
+
+///A pass over a MIR module, which it may change in place.
+pub trait MirPass {
+    fn run(&mut self, module: &mut mir::Module) -> Result<(), ()>;
+}
+
+///Turns the HIR module the HIR passes come out with into the MIR module the MIR passes run over.
+pub trait Lowering {
+    fn lower(&mut self, module: &hir::Module) -> Result<mir::Module, ()>;
+}
+
+enum HirPass {
+    Analysis(Box<dyn HirVisitor>),
+    Transform(Box<dyn HirMutVisitor>),
+}
+
+#[derive(Debug)]
+pub enum PassError {
+    ///The pass with this name failed, after reporting why
+    Failed(String),
+    ///The pass with this name produced malformed HIR, which is a bug of the pass
+    Malformed(String, Vec<VerifyError>),
+    ///The lowering or MIR pass with this name produced this malformed MIR, which is a bug of the pass
+    MalformedMir(String, Box<mir::Module>, Vec<VerifyError>),
+}
+
+///Runs named passes over a module in the order they were added, printing the module before or after the passes asked for
+///in the text syntax of its IR, which can be read back. The HIR passes run first, then the lowering to MIR if there is one,
+///then the MIR passes.
+///In debug builds, the HIR coming out of every pass changing it is verified, and so is the MIR coming out of the lowering
+///and of every MIR pass.
+pub struct PassManager {
+    hir_passes: Vec<(String, HirPass)>,
+    lowering: Option<(String, Box<dyn Lowering>)>,
+    mir_passes: Vec<(String, Box<dyn MirPass>)>,
+    dump_before: HashSet<String>,
+    dump_after: HashSet<String>,
+    verify: bool,
+}
+
+impl Default for PassManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PassManager {
+    pub fn new() -> Self {
+        PassManager {
+            hir_passes: Vec::new(),
+            lowering: None,
+            mir_passes: Vec::new(),
+            dump_before: HashSet::new(),
+            dump_after: HashSet::new(),
+            verify: cfg!(debug_assertions),
+        }
+    }
+
+    pub fn add_analysis(&mut self, name: &str, pass: Box<dyn HirVisitor>) {
+        self.hir_passes.push((name.to_string(), HirPass::Analysis(pass)));
+    }
+
+    pub fn add_transform(&mut self, name: &str, pass: Box<dyn HirMutVisitor>) {
+        self.hir_passes.push((name.to_string(), HirPass::Transform(pass)));
+    }
+
+    ///Lower the module to MIR with `pass` once the HIR passes have run, replacing any lowering set before.
+    pub fn set_lowering(&mut self, name: &str, pass: Box<dyn Lowering>) {
+        self.lowering = Some((name.to_string(), pass));
+    }
+
+    pub fn add_mir_pass(&mut self, name: &str, pass: Box<dyn MirPass>) {
+        self.mir_passes.push((name.to_string(), pass));
+    }
+
+    ///The names of the passes, including the lowering, in the order they run.
+    pub fn names(&self) -> Vec<&str> {
+        self.hir_passes
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .chain(self.lowering.iter().map(|(name, _)| name.as_str()))
+            .chain(self.mir_passes.iter().map(|(name, _)| name.as_str()))
+            .collect()
+    }
+
+    ///Print the module before the pass `name` runs. Fails if there is no such pass.
+    pub fn dump_before(&mut self, name: &str) -> Result<(), String> {
+        self.check_name(name)?;
+        self.dump_before.insert(name.to_string());
+        Ok(())
+    }
+
+    ///Print the module after the pass `name` has run. Fails if there is no such pass.
+    pub fn dump_after(&mut self, name: &str) -> Result<(), String> {
+        self.check_name(name)?;
+        self.dump_after.insert(name.to_string());
+        Ok(())
+    }
+
+    fn check_name(&self, name: &str) -> Result<(), String> {
+        let names = self.names();
+        if names.contains(&name) {
+            Ok(())
+        } else {
+            Err(format!("There is no pass named {}. The passes are: {}", name, names.join(", ")))
+        }
+    }
+
+    ///Run the HIR passes over `module`, stopping at the first that fails.
+    ///The passes the module has been through already, as a module read back from disk may have, are skipped.
+    pub fn run_hir(&mut self, module: &mut hir::Module) -> Result<(), PassError> {
+        for (name, pass) in self.hir_passes.iter_mut() {
+            if module.passes.contains(name) {
+                continue;
+            }
+            if self.dump_before.contains(name) {
+                print!("//HIR of {} before {}\n{}", module.name, name, text::print_hir(module));
+            }
+            let ret = match pass {
+                HirPass::Analysis(pass) => pass.visit_module(module),
+                HirPass::Transform(pass) => pass.visit_module_mut(module),
+            };
+            ret.map_err(|_| PassError::Failed(name.clone()))?;
+            module.passes.push(name.clone());
+            if self.verify {
+                if let HirPass::Transform(_) = pass {
+                    hir::verify(module).map_err(|errors| PassError::Malformed(name.clone(), errors))?;
+                }
+            }
+            if self.dump_after.contains(name) {
+                print!("//HIR of {} after {}\n{}", module.name, name, text::print_hir(module));
+            }
+        }
+        Ok(())
+    }
+
+    ///Run the HIR passes over `module`, then lower it and run the MIR passes over the result.
+    ///Without a lowering, only the HIR passes run and there is no MIR module.
+    pub fn run(&mut self, module: &mut hir::Module) -> Result<Option<mir::Module>, PassError> {
+        self.run_hir(module)?;
+        let (name, lowering) = match self.lowering.as_mut() {
+            Some(lowering) => lowering,
+            None => return Ok(None),
+        };
+        if self.dump_before.contains(name) {
+            print!("//HIR of {} before {}\n{}", module.name, name, text::print_hir(module));
+        }
+        let mut lowered = lowering.lower(module).map_err(|_| PassError::Failed(name.clone()))?;
+        if self.verify {
+            verify_mir(name, &lowered)?;
+        }
+        if self.dump_after.contains(name) {
+            print!("//MIR of {} after {}\n{}", lowered.name, name, text::print_mir(&lowered));
+        }
+        self.run_mir(&mut lowered)?;
+        Ok(Some(lowered))
+    }
+
+    ///Run the MIR passes over `module`, stopping at the first that fails.
+    pub fn run_mir(&mut self, module: &mut mir::Module) -> Result<(), PassError> {
+        for (name, pass) in self.mir_passes.iter_mut() {
+            if self.dump_before.contains(name) {
+                print!("//MIR of {} before {}\n{}", module.name, name, text::print_mir(module));
+            }
+            pass.run(module).map_err(|_| PassError::Failed(name.clone()))?;
+            if self.verify {
+                verify_mir(name, module)?;
+            }
+            if self.dump_after.contains(name) {
+                print!("//MIR of {} after {}\n{}", module.name, name, text::print_mir(module));
+            }
+        }
+        Ok(())
+    }
+}
+
+///Verify `module`, as it came out of the pass `name`.
+fn verify_mir(name: &str, module: &mir::Module) -> Result<(), PassError> {
+    mir::verify(module).map_err(|errors| PassError::MalformedMir(name.to_string(), Box::new(module.clone()), errors))
+}
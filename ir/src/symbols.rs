@@ -0,0 +1,163 @@
+use core::pos::BiPos;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+///A symbol of a module, numbered in the order name resolution declares them.
+///Unlike the index of an instruction, it stays the same when passes add or remove instructions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct SymbolId(pub usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ScopeId(pub usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SymbolKind {
+    ///The module itself, or one it imports
+    Module,
+    ///A function, including native functions, methods and the methods of interfaces
+    Function,
+    ///A property declared at the top level
+    Global,
+    Const,
+    Param,
+    ///A local variable, including the variable of a for loop
+    Local,
+    ///A struct, newtype, type alias or interface
+    Type,
+    TypeParam,
+    Field,
+    ///A name the language provides, such as `Some`
+    Builtin,
+}
+
+impl SymbolKind {
+    ///Whether the symbol lives in a function, and is referred to by `LocalRef` rather than `GlobalRef`.
+    pub fn is_local(self) -> bool {
+        matches!(self, SymbolKind::Param | SymbolKind::Local)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    ///Where the symbol is declared, which is nowhere for builtins and the names of imported modules
+    pub pos: BiPos,
+    ///The scope the symbol is declared in
+    pub scope: ScopeId,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ScopeKind {
+    ///The names the language provides, around everything else
+    Builtins,
+    ///The names exported by the imported modules
+    Imports,
+    ///The top level of the module
+    Module,
+    ///The params and body of a function, which can't see the locals of any function around it
+    Function,
+    ///The members of a struct, interface or impl block
+    Item,
+    Block,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Scope {
+    pub kind: ScopeKind,
+    pub parent: Option<ScopeId>,
+    ///The symbols that can be looked up by name in the scope, in declaration order
+    pub symbols: Vec<SymbolId>,
+}
+
+///The symbols of a module and the nested scopes they are declared in.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SymbolTable {
+    pub symbols: Vec<Symbol>,
+    pub scopes: Vec<Scope>,
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn symbol(&self, id: SymbolId) -> &Symbol {
+        &self.symbols[id.0]
+    }
+
+    pub fn scope(&self, id: ScopeId) -> &Scope {
+        &self.scopes[id.0]
+    }
+
+    pub fn push_scope(&mut self, kind: ScopeKind, parent: Option<ScopeId>) -> ScopeId {
+        self.scopes.push(Scope {
+            kind,
+            parent,
+            symbols: Vec::new(),
+        });
+        ScopeId(self.scopes.len() - 1)
+    }
+
+    ///Add a symbol that can't be looked up by name, such as the module itself.
+    pub fn add(&mut self, symbol: Symbol) -> SymbolId {
+        self.symbols.push(symbol);
+        SymbolId(self.symbols.len() - 1)
+    }
+
+    ///Declare `symbol` in its scope, unless the scope already declares its name, in which case that symbol is returned instead.
+    pub fn declare(&mut self, symbol: Symbol) -> Result<SymbolId, SymbolId> {
+        if let Some(declared) = self.lookup_in(symbol.scope, &symbol.name) {
+            return Err(declared);
+        }
+        let scope = symbol.scope;
+        let id = self.add(symbol);
+        self.scopes[scope.0].symbols.push(id);
+        Ok(id)
+    }
+
+    ///The symbol `name` declared in `scope` itself.
+    pub fn lookup_in(&self, scope: ScopeId, name: &str) -> Option<SymbolId> {
+        self.scope(scope)
+            .symbols
+            .iter()
+            .copied()
+            .find(|id| self.symbol(*id).name == name)
+    }
+
+    ///`scope` followed by each scope around it, innermost first.
+    pub fn enclosing(&self, scope: ScopeId) -> impl Iterator<Item = ScopeId> + '_ {
+        std::iter::successors(Some(scope), move |scope| self.scope(*scope).parent)
+    }
+
+    ///The symbol `name` as seen from `scope`: the one declared by the innermost scope around it that has a symbol of that name
+    ///for which `visible` holds.
+    pub fn lookup(&self, scope: ScopeId, name: &str, visible: impl Fn(&Symbol) -> bool) -> Option<SymbolId> {
+        self.enclosing(scope)
+            .filter_map(|scope| self.lookup_in(scope, name))
+            .find(|id| visible(self.symbol(*id)))
+    }
+}
+
+impl Display for SymbolId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "#{}", self.0)
+    }
+}
+
+impl Display for SymbolKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            SymbolKind::Module => write!(f, "module"),
+            SymbolKind::Function => write!(f, "function"),
+            SymbolKind::Global => write!(f, "property"),
+            SymbolKind::Const => write!(f, "constant"),
+            SymbolKind::Param => write!(f, "param"),
+            SymbolKind::Local => write!(f, "local variable"),
+            SymbolKind::Type => write!(f, "type"),
+            SymbolKind::TypeParam => write!(f, "type param"),
+            SymbolKind::Field => write!(f, "field"),
+            SymbolKind::Builtin => write!(f, "builtin"),
+        }
+    }
+}
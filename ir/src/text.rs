@@ -0,0 +1,1121 @@
+use crate::{
+    hir::{self, AnnotationArg, BinaryOp, HIRInstruction, ReceiverKind, UnaryOp},
+    mir::{self, MIRInstruction},
+    symbols::SymbolId,
+    type_signature::{
+        CallingConvention, FunctionSignature, GenericParam, Parameter, PrimitiveType, StructSignature, TypeSignature,
+    },
+};
+use core::pos::{BiPos, Position};
+use std::fmt::{Display, Formatter, Result as FmtResult, Write};
+
+//The text syntax of HIR and MIR, which unlike their `Display` is plain and can be read back.
+//A module starts with a `hir` or `mir` header line giving its name, followed by one instruction per line:
+//
+//      hir main
+//      Module main
+//          Fn main : fun() -> Int @ 1:1 1:4
+//              Return value : Int
+//              Binary Add : Int
+//                  Integer 1 : Int
+//                  LocalRef x : Int
+//          EndFn
+//      EndModule
+//
+//An instruction is written as the name of its variant followed by its operands, then its signature after
+//a `:` unless it has none, then its position after a `@` unless it has none. Flags are written as a word when set,
+//such as `mut` after the name of a mutable `LocalVar`. Names that aren't plain identifiers are quoted.
+//Once names are resolved, a HIR instruction declaring or referring to a symbol ends its operands with the id of
+//the symbol, as in `LocalRef x #3`. The symbol table itself isn't written, since name resolution builds it again.
+//The header of a HIR module lists the passes that have run over it, as in `hir main after resolve typeck`,
+//and compiling the module read back resumes after them.
+//Indentation is ignored when reading, and so is anything after `//`.
+//
+//Types are written as in Beagle, except that generic params are marked with a quote as in `'T: Show`
+//and that function types give the names of their params, as in `fun<'T>(x: 'T, y: Int = default) -> Unit`.
+//`none`, `untyped` and `unknown` stand for the lack of a signature, one yet to be inferred and an unknown primitive.
+
+///An error in a module written in the text syntax of HIR or MIR.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextError {
+    pub msg: String,
+    ///The line of the error, counted from 1
+    pub line: usize,
+}
+
+impl Display for TextError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "line {}: {}", self.line, self.msg)
+    }
+}
+
+type TextResult<T> = Result<T, TextError>;
+
+///The names used for types of their own, which a struct named the same has to be quoted as.
+const TYPE_WORDS: &[&str] = &[
+    "none", "untyped", "unknown", "Int", "Float", "Bool", "String", "Unit", "dyn", "fun", "extern", "List", "Option",
+    "Range", "Result",
+];
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_alphabetic() || c == '_') && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+///`name` as written in the text syntax: as is if it is a plain identifier, quoted otherwise.
+fn name(name: &str) -> String {
+    if is_identifier(name) {
+        name.to_string()
+    } else {
+        format!("{:?}", name)
+    }
+}
+
+///The text of the signature `sig`.
+pub fn type_to_text(sig: &TypeSignature) -> String {
+    match sig {
+        TypeSignature::None => "none".to_string(),
+        TypeSignature::Untyped => "untyped".to_string(),
+        TypeSignature::Primitive(PrimitiveType::None) => "unknown".to_string(),
+        TypeSignature::Primitive(PrimitiveType::Integer) => "Int".to_string(),
+        TypeSignature::Primitive(PrimitiveType::Float) => "Float".to_string(),
+        TypeSignature::Primitive(PrimitiveType::Bool) => "Bool".to_string(),
+        TypeSignature::Primitive(PrimitiveType::String) => "String".to_string(),
+        TypeSignature::Primitive(PrimitiveType::Unit) => "Unit".to_string(),
+        TypeSignature::Struct(s) => {
+            let struct_name = if TYPE_WORDS.contains(&s.name.as_str()) {
+                format!("{:?}", s.name)
+            } else {
+                name(&s.name)
+            };
+            if s.type_arguments.is_empty() {
+                struct_name
+            } else {
+                format!("{}<{}>", struct_name, types_to_text(&s.type_arguments))
+            }
+        }
+        TypeSignature::Param(param) => generic_param(param),
+        TypeSignature::Dyn(interface) => format!("dyn {}", name(interface)),
+        TypeSignature::Array(element, len) => format!("[{}; {}]", type_to_text(element), len),
+        TypeSignature::List(element) => format!("List<{}>", type_to_text(element)),
+        TypeSignature::Option(element) => format!("Option<{}>", type_to_text(element)),
+        TypeSignature::Range(element) => format!("Range<{}>", type_to_text(element)),
+        TypeSignature::Result(value, error) => format!("Result<{}, {}>", type_to_text(value), type_to_text(error)),
+        TypeSignature::Function(function) => {
+            let mut text = String::new();
+            if function.calling_convention == CallingConvention::C {
+                text.push_str("extern ");
+            }
+            text.push_str("fun");
+            if !function.type_parameters.is_empty() {
+                let params = function.type_parameters.iter().map(generic_param).collect::<Vec<String>>();
+                let _ = write!(text, "<{}>", params.join(", "));
+            }
+            let params = function
+                .parameters
+                .iter()
+                .enumerate()
+                .map(|(i, param)| {
+                    let vararg = if function.variadic && i == function.parameters.len() - 1 { "vararg " } else { "" };
+                    let default = if param.default { " = default" } else { "" };
+                    format!("{}{}: {}{}", vararg, name(&param.name), type_to_text(&param.sig), default)
+                })
+                .collect::<Vec<String>>();
+            let _ = write!(text, "({}) -> {}", params.join(", "), type_to_text(&function.return_type_signature));
+            text
+        }
+    }
+}
+
+fn types_to_text(sigs: &[TypeSignature]) -> String {
+    sigs.iter().map(type_to_text).collect::<Vec<String>>().join(", ")
+}
+
+fn generic_param(param: &GenericParam) -> String {
+    let bounds = param.bounds.iter().map(|bound| name(bound)).collect::<Vec<String>>();
+    if bounds.is_empty() {
+        format!("'{}", name(&param.name))
+    } else {
+        format!("'{}: {}", name(&param.name), bounds.join(" + "))
+    }
+}
+
+fn receiver(kind: ReceiverKind) -> &'static str {
+    match kind {
+        ReceiverKind::Value => "self",
+        ReceiverKind::Ref => "&self",
+        ReceiverKind::MutRef => "&mut self",
+    }
+}
+
+fn flag(set: bool, word: &str) -> Option<String> {
+    if set {
+        Some(word.to_string())
+    } else {
+        None
+    }
+}
+
+///The variant name and operands of a HIR instruction.
+fn hir_operands(ins: &HIRInstruction) -> (&'static str, Vec<String>) {
+    use HIRInstruction::*;
+    let named = |op, n: &std::string::String| (op, vec![name(n)]);
+    match ins {
+        Module(n) => named("Module", n),
+        EndModule => ("EndModule", vec![]),
+        Visibility(visibility) => ("Visibility", vec![visibility.to_string()]),
+        Annotation(n, args) => {
+            let mut operands = vec![name(n)];
+            operands.extend(args.iter().map(|arg| match arg {
+                AnnotationArg::String(s) => format!("{:?}", s),
+                AnnotationArg::Integer(int) => int.to_string(),
+                AnnotationArg::Float(float) => format!("{:?}", float),
+                AnnotationArg::Name(n) => n.clone(),
+            }));
+            ("Annotation", operands)
+        }
+        Fn(n) => named("Fn", n),
+        EndFn => ("EndFn", vec![]),
+        NativeFn(n) => named("NativeFn", n),
+        FnParam(n) => named("FnParam", n),
+        DefaultArg => ("DefaultArg", vec![]),
+        TypeParam(n) => named("TypeParam", n),
+        FnType(n) => named("FnType", n),
+        Property(n, mutable) => ("Property", std::iter::once(name(n)).chain(flag(*mutable, "mut")).collect()),
+        Const(n) => named("Const", n),
+        LocalVar(n, mutable) => ("LocalVar", std::iter::once(name(n)).chain(flag(*mutable, "mut")).collect()),
+        Struct(n) => named("Struct", n),
+        StructField(n) => named("StructField", n),
+        EndStruct => ("EndStruct", vec![]),
+        TypeAlias(n) => named("TypeAlias", n),
+        Newtype(n) => named("Newtype", n),
+        Interface(n) => named("Interface", n),
+        InterfaceMethod(n, kind) => ("InterfaceMethod", vec![name(n), receiver(*kind).to_string()]),
+        EndInterface => ("EndInterface", vec![]),
+        Impl(interface) => ("Impl", interface.iter().map(|n| name(n)).collect()),
+        EndImpl => ("EndImpl", vec![]),
+        SelfParam(kind) => ("SelfParam", vec![receiver(*kind).to_string()]),
+        Import(n) => named("Import", n),
+        LocalRef(n) => named("LocalRef", n),
+        GlobalRef(n) => named("GlobalRef", n),
+        Call(argc) => ("Call", vec![argc.to_string()]),
+        NamedArg(n) => named("NamedArg", n),
+        Field(n) => named("Field", n),
+        MethodCall(n, argc) => ("MethodCall", vec![name(n), argc.to_string()]),
+        Array(len) => ("Array", vec![len.to_string()]),
+        Index => ("Index", vec![]),
+        Slice(start, end) => ("Slice", flag(*start, "start").into_iter().chain(flag(*end, "end")).collect()),
+        Try => ("Try", vec![]),
+        Binary(op) => ("Binary", vec![format!("{:?}", op)]),
+        Unary(op) => ("Unary", vec![format!("{:?}", op)]),
+        Range(inclusive) => ("Range", flag(*inclusive, "inclusive").into_iter().collect()),
+        For(n) => named("For", n),
+        EndFor => ("EndFor", vec![]),
+        If(has_else) => ("If", flag(*has_else, "else").into_iter().collect()),
+        Else => ("Else", vec![]),
+        EndIf => ("EndIf", vec![]),
+        While => ("While", vec![]),
+        EndWhile => ("EndWhile", vec![]),
+        Loop => ("Loop", vec![]),
+        EndLoop => ("EndLoop", vec![]),
+        Break => ("Break", vec![]),
+        Continue => ("Continue", vec![]),
+        Assign => ("Assign", vec![]),
+        Return(value) => ("Return", flag(*value, "value").into_iter().collect()),
+        Block => ("Block", vec![]),
+        EndBlock => ("EndBlock", vec![]),
+        Integer(int) => ("Integer", vec![int.to_string()]),
+        Float(float) => ("Float", vec![format!("{:?}", float)]),
+        Bool(b) => ("Bool", vec![b.to_string()]),
+        String(s) => ("String", vec![format!("{:?}", s)]),
+        Halt => ("Halt", vec![]),
+    }
+}
+
+///The variant name and operands of a MIR instruction.
+fn mir_operands(ins: &MIRInstruction) -> (&'static str, Vec<String>) {
+    use MIRInstruction::*;
+    let named = |op, n: &std::string::String| (op, vec![name(n)]);
+    match ins {
+        Module(n) => named("Module", n),
+        EndModule => ("EndModule", vec![]),
+        ObjInit(n, mutable) => ("ObjInit", std::iter::once(name(n)).chain(flag(*mutable, "mut")).collect()),
+        Drop(n) => named("Drop", n),
+        Fun(n) => named("Fun", n),
+        EndFun => ("EndFun", vec![]),
+        NativeFun(n) => named("NativeFun", n),
+        FunParam(n) => named("FunParam", n),
+        DefaultArg => ("DefaultArg", vec![]),
+        Integer(int) => ("Integer", vec![int.to_string()]),
+        Float(float) => ("Float", vec![format!("{:?}", float)]),
+        String(s) => ("String", vec![format!("{:?}", s)]),
+        Bool(b) => ("Bool", vec![b.to_string()]),
+        Unit => ("Unit", vec![]),
+        Ref(n) => named("Ref", n),
+        Move(n) => named("Move", n),
+        Copy(n) => named("Copy", n),
+        HeapAlloc(size) => ("HeapAlloc", vec![size.to_string()]),
+        StackAlloc(size) => ("StackAlloc", vec![size.to_string()]),
+        Lateinit(size) => ("Lateinit", vec![size.to_string()]),
+        ObjMut(n) => named("ObjMut", n),
+        FieldMut(n) => named("FieldMut", n),
+        Global(n) => named("Global", n),
+        Call(argc) => ("Call", vec![argc.to_string()]),
+        NamedArg(n) => named("NamedArg", n),
+        Field(n) => named("Field", n),
+        Binary(op) => ("Binary", vec![format!("{:?}", op)]),
+        Unary(op) => ("Unary", vec![format!("{:?}", op)]),
+        VTable(interface, ty) => ("VTable", vec![name(interface), name(ty)]),
+        VTableEntry(n) => named("VTableEntry", n),
+        EndVTable => ("EndVTable", vec![]),
+        MakeDyn(n) => named("MakeDyn", n),
+        DynCall(slot, argc) => ("DynCall", vec![slot.to_string(), argc.to_string()]),
+        ArrayInit(len) => ("ArrayInit", vec![len.to_string()]),
+        ListInit(len) => ("ListInit", vec![len.to_string()]),
+        ArrayToList => ("ArrayToList", vec![]),
+        BoundsCheck => ("BoundsCheck", vec![]),
+        Index => ("Index", vec![]),
+        Slice => ("Slice", vec![]),
+        Len => ("Len", vec![]),
+        ListPush => ("ListPush", vec![]),
+        Loop => ("Loop", vec![]),
+        EndLoop => ("EndLoop", vec![]),
+        Break => ("Break", vec![]),
+        Continue => ("Continue", vec![]),
+        RangeInit(inclusive) => ("RangeInit", flag(*inclusive, "inclusive").into_iter().collect()),
+        BreakIfNone(n) => named("BreakIfNone", n),
+        Unwrap(n) => named("Unwrap", n),
+        IsErr(n) => named("IsErr", n),
+        UnwrapErr(n) => named("UnwrapErr", n),
+        If(has_else) => ("If", flag(*has_else, "else").into_iter().collect()),
+        Else => ("Else", vec![]),
+        EndIf => ("EndIf", vec![]),
+        Return(value) => ("Return", flag(*value, "value").into_iter().collect()),
+        Halt => ("Halt", vec![]),
+    }
+}
+
+///Write the line of an instruction at `depth`, given its variant name and operands.
+fn write_line(text: &mut String, depth: usize, (op, operands): (&str, Vec<String>), sig: &TypeSignature, pos: &BiPos) {
+    text.push_str(&"    ".repeat(depth));
+    text.push_str(op);
+    for operand in operands {
+        text.push(' ');
+        text.push_str(&operand);
+    }
+    if *sig != TypeSignature::None {
+        let _ = write!(text, " : {}", type_to_text(sig));
+    }
+    if pos.start != Position::default() || pos.end != Position::default() {
+        let _ = write!(text, " @ {}:{} {}:{}", pos.start.0, pos.start.1, pos.end.0, pos.end.1);
+    }
+    text.push('\n');
+}
+
+///The text of the HIR module `module`.
+pub fn print_hir(module: &hir::Module) -> String {
+    let mut text = format!("hir {}", name(&module.name));
+    if !module.passes.is_empty() {
+        let _ = write!(text, " after {}", module.passes.join(" "));
+    }
+    text.push('\n');
+    let mut depth: usize = 0;
+    let lines = module.instructions.iter().zip(module.signatures.iter()).zip(module.positions.iter());
+    for (((ins, sig), pos), symbol) in lines.zip(module.symbols.iter()) {
+        let opens = matches!(
+            ins,
+            HIRInstruction::Module(_)
+                | HIRInstruction::Fn(_)
+                | HIRInstruction::Struct(_)
+                | HIRInstruction::Interface(_)
+                | HIRInstruction::Impl(_)
+                | HIRInstruction::For(_)
+                | HIRInstruction::If(_)
+                | HIRInstruction::While
+                | HIRInstruction::Loop
+                | HIRInstruction::Block
+        );
+        let closes = matches!(
+            ins,
+            HIRInstruction::EndModule
+                | HIRInstruction::EndFn
+                | HIRInstruction::EndStruct
+                | HIRInstruction::EndInterface
+                | HIRInstruction::EndImpl
+                | HIRInstruction::EndFor
+                | HIRInstruction::EndIf
+                | HIRInstruction::EndWhile
+                | HIRInstruction::EndLoop
+                | HIRInstruction::EndBlock
+        );
+        if closes {
+            depth = depth.saturating_sub(1);
+        }
+        //An `Else` lines up with its `If`
+        let line_depth = if *ins == HIRInstruction::Else { depth.saturating_sub(1) } else { depth };
+        let (op, mut operands) = hir_operands(ins);
+        operands.extend(symbol.map(|symbol| symbol.to_string()));
+        write_line(&mut text, line_depth, (op, operands), sig, pos);
+        if opens {
+            depth += 1;
+        }
+    }
+    text
+}
+
+///The text of the MIR module `module`.
+pub fn print_mir(module: &mir::Module) -> String {
+    let mut text = format!("mir {}\n", name(&module.name));
+    let mut depth: usize = 0;
+    let lines = module.instructions.iter().zip(module.signatures.iter()).zip(module.positions.iter());
+    for ((ins, sig), pos) in lines {
+        if matches!(
+            ins,
+            MIRInstruction::EndModule
+                | MIRInstruction::EndFun
+                | MIRInstruction::EndLoop
+                | MIRInstruction::EndVTable
+                | MIRInstruction::EndIf
+        ) {
+            depth = depth.saturating_sub(1);
+        }
+        //An `Else` lines up with its `If`
+        let line_depth = if matches!(ins, MIRInstruction::Else) { depth.saturating_sub(1) } else { depth };
+        write_line(&mut text, line_depth, mir_operands(ins), sig, pos);
+        if matches!(
+            ins,
+            MIRInstruction::Module(_)
+                | MIRInstruction::Fun(_)
+                | MIRInstruction::Loop
+                | MIRInstruction::VTable(..)
+                | MIRInstruction::If(_)
+        ) {
+            depth += 1;
+        }
+    }
+    text
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    ///An identifier, keyword or number
+    Word(String),
+    ///A quoted string, with its escapes resolved
+    Quoted(String),
+    Arrow,
+    Punct(char),
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Token::Word(word) => write!(f, "{}", word),
+            Token::Quoted(s) => write!(f, "{:?}", s),
+            Token::Arrow => write!(f, "->"),
+            Token::Punct(c) => write!(f, "{}", c),
+        }
+    }
+}
+
+///Split a line into tokens, leaving out any comment.
+fn tokenize(line: &str, number: usize) -> TextResult<Vec<Token>> {
+    let error = |msg: String| TextError { msg, line: number };
+    let mut tokens = Vec::new();
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '/' if chars.peek() == Some(&'/') => break,
+            '-' if chars.peek() == Some(&'>') => {
+                chars.next();
+                tokens.push(Token::Arrow);
+            }
+            '"' => {
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => s.push('\n'),
+                            Some('r') => s.push('\r'),
+                            Some('t') => s.push('\t'),
+                            Some('0') => s.push('\0'),
+                            Some(c @ ('\\' | '"' | '\'')) => s.push(c),
+                            Some('u') => {
+                                let code = chars
+                                    .by_ref()
+                                    .skip_while(|c| *c == '{')
+                                    .take_while(|c| *c != '}')
+                                    .collect::<std::string::String>();
+                                match u32::from_str_radix(&code, 16).ok().and_then(std::char::from_u32) {
+                                    Some(c) => s.push(c),
+                                    None => return Err(error(format!("Invalid unicode escape \\u{{{}}}", code))),
+                                }
+                            }
+                            Some(c) => return Err(error(format!("Invalid escape \\{}", c))),
+                            None => return Err(error("Unterminated string".to_string())),
+                        },
+                        Some(c) => s.push(c),
+                        None => return Err(error("Unterminated string".to_string())),
+                    }
+                }
+                tokens.push(Token::Quoted(s));
+            }
+            c if c.is_alphanumeric() || c == '_' || (c == '-' && chars.peek().is_some_and(|c| c.is_ascii_digit())) => {
+                let mut word = c.to_string();
+                let numeric = c == '-' || c.is_ascii_digit();
+                while let Some(&next) = chars.peek() {
+                    //The exponent of a float can have a sign, as in `1e-7`
+                    let sign = numeric && (next == '-' || next == '+') && word.ends_with('e');
+                    if next.is_alphanumeric() || next == '_' || (numeric && next == '.') || sign {
+                        word.push(next);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(Token::Word(word));
+            }
+            ':' | '<' | '>' | ',' | '(' | ')' | '[' | ']' | ';' | '=' | '+' | '@' | '\'' | '&' | '#' => tokens.push(Token::Punct(c)),
+            c => return Err(error(format!("Unexpected character {:?}", c))),
+        }
+    }
+    Ok(tokens)
+}
+
+///Reads the tokens of one line.
+struct Cursor {
+    tokens: Vec<Token>,
+    idx: usize,
+    line: usize,
+}
+
+impl Cursor {
+    fn error<T>(&self, msg: String) -> TextResult<T> {
+        Err(TextError { msg, line: self.line })
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.idx)
+    }
+
+    fn next(&mut self) -> TextResult<Token> {
+        match self.tokens.get(self.idx) {
+            Some(token) => {
+                self.idx += 1;
+                Ok(token.clone())
+            }
+            None => self.error("Unexpected end of the line".to_string()),
+        }
+    }
+
+    ///Skip the punctuation `c` if it comes next, returning whether it did.
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(&Token::Punct(c)) {
+            self.idx += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> TextResult<()> {
+        match self.next()? {
+            Token::Punct(found) if found == c => Ok(()),
+            found => self.error(format!("Expected '{}' but found {}", c, found)),
+        }
+    }
+
+    ///Skip the word `word` if it comes next, returning whether it did. This reads the flags of instructions.
+    fn flag(&mut self, word: &str) -> bool {
+        if self.peek() == Some(&Token::Word(word.to_string())) {
+            self.idx += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn word(&mut self) -> TextResult<String> {
+        match self.next()? {
+            Token::Word(word) => Ok(word),
+            found => self.error(format!("Expected a word but found {}", found)),
+        }
+    }
+
+    ///A name, either a plain identifier or quoted.
+    fn name(&mut self) -> TextResult<String> {
+        match self.next()? {
+            Token::Word(word) if is_identifier(&word) => Ok(word),
+            Token::Quoted(s) => Ok(s),
+            found => self.error(format!("Expected a name but found {}", found)),
+        }
+    }
+
+    fn quoted(&mut self) -> TextResult<String> {
+        match self.next()? {
+            Token::Quoted(s) => Ok(s),
+            found => self.error(format!("Expected a quoted string but found {}", found)),
+        }
+    }
+
+    fn number<T: std::str::FromStr>(&mut self) -> TextResult<T> {
+        let word = self.word()?;
+        match word.parse() {
+            Ok(number) => Ok(number),
+            Err(_) => self.error(format!("Expected a number but found {}", word)),
+        }
+    }
+
+    fn bool(&mut self) -> TextResult<bool> {
+        match self.word()?.as_str() {
+            "true" => Ok(true),
+            "false" => Ok(false),
+            found => self.error(format!("Expected true or false but found {}", found)),
+        }
+    }
+
+    fn receiver(&mut self) -> TextResult<ReceiverKind> {
+        if self.eat('&') {
+            let kind = if self.flag("mut") { ReceiverKind::MutRef } else { ReceiverKind::Ref };
+            self.flag("self").then_some(kind).map_or_else(|| self.error("Expected self".to_string()), Ok)
+        } else if self.flag("self") {
+            Ok(ReceiverKind::Value)
+        } else {
+            self.error("Expected self, &self or &mut self".to_string())
+        }
+    }
+
+    fn type_(&mut self) -> TextResult<TypeSignature> {
+        let word = match self.next()? {
+            Token::Word(word) => word,
+            Token::Quoted(s) => return self.struct_type(s),
+            Token::Punct('\'') => return Ok(TypeSignature::Param(self.generic_param()?)),
+            Token::Punct('[') => {
+                let element = self.type_()?;
+                self.expect(';')?;
+                let len = self.number()?;
+                self.expect(']')?;
+                return Ok(TypeSignature::Array(Box::new(element), len));
+            }
+            found => return self.error(format!("Expected a type but found {}", found)),
+        };
+        let sig = match word.as_str() {
+            "none" => TypeSignature::None,
+            "untyped" => TypeSignature::Untyped,
+            "unknown" => TypeSignature::Primitive(PrimitiveType::None),
+            "Int" => TypeSignature::Primitive(PrimitiveType::Integer),
+            "Float" => TypeSignature::Primitive(PrimitiveType::Float),
+            "Bool" => TypeSignature::Primitive(PrimitiveType::Bool),
+            "String" => TypeSignature::Primitive(PrimitiveType::String),
+            "Unit" => TypeSignature::Primitive(PrimitiveType::Unit),
+            "dyn" => TypeSignature::Dyn(self.name()?),
+            "List" | "Option" | "Range" => {
+                self.expect('<')?;
+                let element = Box::new(self.type_()?);
+                self.expect('>')?;
+                match word.as_str() {
+                    "List" => TypeSignature::List(element),
+                    "Option" => TypeSignature::Option(element),
+                    _ => TypeSignature::Range(element),
+                }
+            }
+            "Result" => {
+                self.expect('<')?;
+                let value = self.type_()?;
+                self.expect(',')?;
+                let error = self.type_()?;
+                self.expect('>')?;
+                TypeSignature::Result(Box::new(value), Box::new(error))
+            }
+            "extern" => {
+                if !self.flag("fun") {
+                    return self.error("Expected fun after extern".to_string());
+                }
+                self.function_type(CallingConvention::C)?
+            }
+            "fun" => self.function_type(CallingConvention::Beagle)?,
+            _ if is_identifier(&word) => return self.struct_type(word),
+            _ => return self.error(format!("Expected a type but found {}", word)),
+        };
+        Ok(sig)
+    }
+
+    fn struct_type(&mut self, name: String) -> TextResult<TypeSignature> {
+        let mut type_arguments = Vec::new();
+        if self.eat('<') {
+            loop {
+                type_arguments.push(self.type_()?);
+                if !self.eat(',') {
+                    break;
+                }
+            }
+            self.expect('>')?;
+        }
+        Ok(TypeSignature::Struct(StructSignature { name, type_arguments }))
+    }
+
+    ///A generic param along with its bounds, after its quote.
+    fn generic_param(&mut self) -> TextResult<GenericParam> {
+        let name = self.name()?;
+        let mut bounds = Vec::new();
+        if self.eat(':') {
+            loop {
+                bounds.push(self.name()?);
+                if !self.eat('+') {
+                    break;
+                }
+            }
+        }
+        Ok(GenericParam { name, bounds })
+    }
+
+    ///A function type, after its `fun`.
+    fn function_type(&mut self, calling_convention: CallingConvention) -> TextResult<TypeSignature> {
+        let mut type_parameters = Vec::new();
+        if self.eat('<') {
+            loop {
+                self.expect('\'')?;
+                type_parameters.push(self.generic_param()?);
+                if !self.eat(',') {
+                    break;
+                }
+            }
+            self.expect('>')?;
+        }
+        self.expect('(')?;
+        let mut parameters = Vec::new();
+        let mut variadic = false;
+        if !self.eat(')') {
+            loop {
+                if variadic {
+                    return self.error("Only the last param can be a vararg".to_string());
+                }
+                variadic = self.flag("vararg");
+                let name = self.name()?;
+                self.expect(':')?;
+                let sig = self.type_()?;
+                let default = self.eat('=');
+                if default && !self.flag("default") {
+                    return self.error("Expected default after '='".to_string());
+                }
+                parameters.push(Parameter { name, sig, default });
+                if !self.eat(',') {
+                    break;
+                }
+            }
+            self.expect(')')?;
+        }
+        match self.next()? {
+            Token::Arrow => {}
+            found => return self.error(format!("Expected -> but found {}", found)),
+        }
+        Ok(TypeSignature::Function(FunctionSignature {
+            type_parameters,
+            parameters,
+            variadic,
+            return_type_signature: Box::new(self.type_()?),
+            calling_convention,
+        }))
+    }
+
+    ///The signature and position after the operands of an instruction, which end the line.
+    fn rest(&mut self) -> TextResult<(TypeSignature, BiPos)> {
+        let sig = if self.eat(':') { self.type_()? } else { TypeSignature::None };
+        let mut pos = BiPos::default();
+        if self.eat('@') {
+            pos.start = self.position()?;
+            pos.end = self.position()?;
+        }
+        match self.peek() {
+            Some(found) => self.error(format!("Unexpected {} at the end of the instruction", found)),
+            None => Ok((sig, pos)),
+        }
+    }
+
+    fn position(&mut self) -> TextResult<Position> {
+        let line = self.number()?;
+        self.expect(':')?;
+        Ok(Position(line, self.number()?))
+    }
+
+    fn hir_instruction(&mut self, op: &str) -> TextResult<HIRInstruction> {
+        use HIRInstruction::*;
+        let ins = match op {
+            "Module" => Module(self.name()?),
+            "EndModule" => EndModule,
+            "Visibility" => Visibility(match self.word()?.as_str() {
+                "public" => hir::Visibility::Public,
+                "internal" => hir::Visibility::Internal,
+                "private" => hir::Visibility::Private,
+                found => return self.error(format!("Expected public, internal or private but found {}", found)),
+            }),
+            "Annotation" => {
+                let name = self.name()?;
+                let mut args = Vec::new();
+                while let Some(token) = self.peek().cloned() {
+                    let arg = match token {
+                        Token::Quoted(s) => AnnotationArg::String(s),
+                        Token::Word(word) if word.starts_with(|c: char| c == '-' || c.is_ascii_digit()) => {
+                            match (word.parse(), word.parse()) {
+                                (Ok(int), _) => AnnotationArg::Integer(int),
+                                (_, Ok(float)) => AnnotationArg::Float(float),
+                                _ => return self.error(format!("Expected a number but found {}", word)),
+                            }
+                        }
+                        Token::Word(word) if is_identifier(&word) => AnnotationArg::Name(word),
+                        _ => break,
+                    };
+                    self.idx += 1;
+                    args.push(arg);
+                }
+                Annotation(name, args)
+            }
+            "Fn" => Fn(self.name()?),
+            "EndFn" => EndFn,
+            "NativeFn" => NativeFn(self.name()?),
+            "FnParam" => FnParam(self.name()?),
+            "DefaultArg" => DefaultArg,
+            "TypeParam" => TypeParam(self.name()?),
+            "FnType" => FnType(self.name()?),
+            "Property" => Property(self.name()?, self.flag("mut")),
+            "Const" => Const(self.name()?),
+            "LocalVar" => LocalVar(self.name()?, self.flag("mut")),
+            "Struct" => Struct(self.name()?),
+            "StructField" => StructField(self.name()?),
+            "EndStruct" => EndStruct,
+            "TypeAlias" => TypeAlias(self.name()?),
+            "Newtype" => Newtype(self.name()?),
+            "Interface" => Interface(self.name()?),
+            "InterfaceMethod" => InterfaceMethod(self.name()?, self.receiver()?),
+            "EndInterface" => EndInterface,
+            "Impl" => match self.peek() {
+                Some(Token::Word(_)) | Some(Token::Quoted(_)) => Impl(Some(self.name()?)),
+                _ => Impl(None),
+            },
+            "EndImpl" => EndImpl,
+            "SelfParam" => SelfParam(self.receiver()?),
+            "Import" => Import(self.name()?),
+            "LocalRef" => LocalRef(self.name()?),
+            "GlobalRef" => GlobalRef(self.name()?),
+            "Call" => Call(self.number()?),
+            "NamedArg" => NamedArg(self.name()?),
+            "Field" => Field(self.name()?),
+            "MethodCall" => MethodCall(self.name()?, self.number()?),
+            "Array" => Array(self.number()?),
+            "Index" => Index,
+            "Slice" => Slice(self.flag("start"), self.flag("end")),
+            "Try" => Try,
+            "Binary" => Binary(self.binary_op()?),
+            "Unary" => Unary(self.unary_op()?),
+            "Range" => Range(self.flag("inclusive")),
+            "For" => For(self.name()?),
+            "EndFor" => EndFor,
+            "If" => If(self.flag("else")),
+            "Else" => Else,
+            "EndIf" => EndIf,
+            "While" => While,
+            "EndWhile" => EndWhile,
+            "Loop" => Loop,
+            "EndLoop" => EndLoop,
+            "Break" => Break,
+            "Continue" => Continue,
+            "Assign" => Assign,
+            "Return" => Return(self.flag("value")),
+            "Block" => Block,
+            "EndBlock" => EndBlock,
+            "Integer" => Integer(self.number()?),
+            "Float" => Float(self.number()?),
+            "Bool" => Bool(self.bool()?),
+            "String" => String(self.quoted()?),
+            "Halt" => Halt,
+            _ => return self.error(format!("Unknown HIR instruction {}", op)),
+        };
+        Ok(ins)
+    }
+
+    fn binary_op(&mut self) -> TextResult<BinaryOp> {
+        Ok(match self.word()?.as_str() {
+            "Add" => BinaryOp::Add,
+            "Sub" => BinaryOp::Sub,
+            "Mul" => BinaryOp::Mul,
+            "Div" => BinaryOp::Div,
+            "Rem" => BinaryOp::Rem,
+            "Eq" => BinaryOp::Eq,
+            "Ne" => BinaryOp::Ne,
+            "Lt" => BinaryOp::Lt,
+            "Le" => BinaryOp::Le,
+            "Gt" => BinaryOp::Gt,
+            "Ge" => BinaryOp::Ge,
+            "And" => BinaryOp::And,
+            "Or" => BinaryOp::Or,
+            found => return self.error(format!("Unknown binary operator {}", found)),
+        })
+    }
+
+    fn unary_op(&mut self) -> TextResult<UnaryOp> {
+        Ok(match self.word()?.as_str() {
+            "Neg" => UnaryOp::Neg,
+            "Not" => UnaryOp::Not,
+            found => return self.error(format!("Unknown unary operator {}", found)),
+        })
+    }
+
+    fn mir_instruction(&mut self, op: &str) -> TextResult<MIRInstruction> {
+        use MIRInstruction::*;
+        let ins = match op {
+            "Module" => Module(self.name()?),
+            "EndModule" => EndModule,
+            "ObjInit" => ObjInit(self.name()?, self.flag("mut")),
+            "Drop" => Drop(self.name()?),
+            "Fun" => Fun(self.name()?),
+            "EndFun" => EndFun,
+            "NativeFun" => NativeFun(self.name()?),
+            "FunParam" => FunParam(self.name()?),
+            "DefaultArg" => DefaultArg,
+            "Integer" => Integer(self.number()?),
+            "Float" => Float(self.number()?),
+            "String" => String(self.quoted()?),
+            "Bool" => Bool(self.bool()?),
+            "Unit" => Unit,
+            "Ref" => Ref(self.name()?),
+            "Move" => Move(self.name()?),
+            "Copy" => Copy(self.name()?),
+            "HeapAlloc" => HeapAlloc(self.number()?),
+            "StackAlloc" => StackAlloc(self.number()?),
+            "Lateinit" => Lateinit(self.number()?),
+            "ObjMut" => ObjMut(self.name()?),
+            "FieldMut" => FieldMut(self.name()?),
+            "Global" => Global(self.name()?),
+            "Call" => Call(self.number()?),
+            "NamedArg" => NamedArg(self.name()?),
+            "Field" => Field(self.name()?),
+            "Binary" => Binary(self.binary_op()?),
+            "Unary" => Unary(self.unary_op()?),
+            "VTable" => VTable(self.name()?, self.name()?),
+            "VTableEntry" => VTableEntry(self.name()?),
+            "EndVTable" => EndVTable,
+            "MakeDyn" => MakeDyn(self.name()?),
+            "DynCall" => DynCall(self.number()?, self.number()?),
+            "ArrayInit" => ArrayInit(self.number()?),
+            "ListInit" => ListInit(self.number()?),
+            "ArrayToList" => ArrayToList,
+            "BoundsCheck" => BoundsCheck,
+            "Index" => Index,
+            "Slice" => Slice,
+            "Len" => Len,
+            "ListPush" => ListPush,
+            "Loop" => Loop,
+            "EndLoop" => EndLoop,
+            "Break" => Break,
+            "Continue" => Continue,
+            "RangeInit" => RangeInit(self.flag("inclusive")),
+            "BreakIfNone" => BreakIfNone(self.name()?),
+            "Unwrap" => Unwrap(self.name()?),
+            "IsErr" => IsErr(self.name()?),
+            "UnwrapErr" => UnwrapErr(self.name()?),
+            "If" => If(self.flag("else")),
+            "Else" => Else,
+            "EndIf" => EndIf,
+            "Return" => Return(self.flag("value")),
+            "Halt" => Halt,
+            _ => return self.error(format!("Unknown MIR instruction {}", op)),
+        };
+        Ok(ins)
+    }
+}
+
+///An instruction read from a line, along with its signature, its position and the number of the line.
+type Line<I> = (I, TypeSignature, BiPos, usize);
+
+///Read a module of the kind `kind` from `text`: its name and the passes its header lists, then each of its lines.
+fn parse_lines<I>(
+    text: &str,
+    kind: &str,
+    instruction: impl Fn(&mut Cursor, &str) -> TextResult<I>,
+) -> TextResult<(String, Vec<String>, Vec<Line<I>>)> {
+    let mut name = None;
+    let mut passes = Vec::new();
+    let mut lines = Vec::new();
+    for (idx, line) in text.lines().enumerate() {
+        let tokens = tokenize(line, idx + 1)?;
+        if tokens.is_empty() {
+            continue;
+        }
+        let mut cursor = Cursor { tokens, idx: 0, line: idx + 1 };
+        let op = cursor.word()?;
+        if name.is_none() {
+            if op != kind {
+                return cursor.error(format!("Expected a {} header naming the module but found {}", kind, op));
+            }
+            name = Some(cursor.name()?);
+            if cursor.flag("after") {
+                while let Some(Token::Word(_)) = cursor.peek() {
+                    passes.push(cursor.word()?);
+                }
+            }
+            cursor.rest()?;
+            continue;
+        }
+        let ins = instruction(&mut cursor, &op)?;
+        let (sig, pos) = cursor.rest()?;
+        lines.push((ins, sig, pos, idx + 1));
+    }
+    match name {
+        Some(name) => Ok((name, passes, lines)),
+        None => Err(TextError {
+            msg: format!("Expected a {} header naming the module", kind),
+            line: 1,
+        }),
+    }
+}
+
+///Read a HIR module from its text, as printed by `print_hir`.
+pub fn parse_hir(text: &str) -> TextResult<hir::Module> {
+    read_hir(text, false)
+}
+
+///Read a HIR module from its text like `parse_hir`, but with each instruction positioned at its own line of the text
+///rather than where it was in the source it was lowered from. This is how the text is compiled, so that errors
+///about it point into it.
+pub fn parse_hir_at_lines(text: &str) -> TextResult<hir::Module> {
+    read_hir(text, true)
+}
+
+fn read_hir(text: &str, at_lines: bool) -> TextResult<hir::Module> {
+    let (name, passes, lines) = parse_lines(text, "hir", |cursor, op| {
+        let ins = cursor.hir_instruction(op)?;
+        let symbol = if cursor.eat('#') { Some(SymbolId(cursor.number()?)) } else { None };
+        Ok((ins, symbol))
+    })?;
+    let mut module = hir::Module::new(name);
+    module.passes = passes;
+    let text_lines = text.lines().collect::<Vec<&str>>();
+    for ((ins, symbol), sig, mut pos, line) in lines {
+        if at_lines {
+            let text = text_lines[line - 1];
+            let indent = text.len() - text.trim_start().len();
+            pos = BiPos { start: Position(line, indent + 1), end: Position(line, text.trim_end().len()) };
+        }
+        module.push(pos, sig, ins);
+        *module.symbols.last_mut().unwrap() = symbol;
+    }
+    Ok(module)
+}
+
+///Read a MIR module from its text, as printed by `print_mir`.
+pub fn parse_mir(text: &str) -> TextResult<mir::Module> {
+    let (name, _, lines) = parse_lines(text, "mir", |cursor, op| cursor.mir_instruction(op))?;
+    let mut module = mir::Module::new(name);
+    for (ins, sig, pos, _) in lines {
+        module.push(pos, sig, ins);
+    }
+    Ok(module)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HIR: &str = r#"hir test after resolve typeck
+Module test #7
+    Struct Point #4 : Point @ 1:2 1:7
+        StructField x #8 : Int @ 1:17 1:17
+    EndStruct @ 1:2 1:7
+    Fn f #5 : fun(a: Int, b: Int = default) -> Int @ 5:2 5:4
+        FnParam a #11 : Int @ 5:8 5:8
+        FnParam b #12 : Int @ 5:16 5:16
+        DefaultArg @ 5:25 5:25
+        Integer 2 : Int @ 5:25 5:25
+        Binary Add : Int @ 5:35 5:39
+        LocalRef a #11 : Int @ 5:35 5:35
+        LocalRef b #12 : Int @ 5:39 5:39
+    EndFn @ 5:2 5:4
+    Fn main #6 : fun() -> Int @ 6:2 6:4
+        LocalVar p mut #13 : Point @ 7:14 7:14
+        Call 1 : Point @ 7:18 7:25
+        GlobalRef Point #4 : fun(x: Int) -> Point @ 7:18 7:22
+        Integer 1 : Int @ 7:24 7:24
+        Assign : Unit @ 8:6 8:22
+        Field x : Int @ 8:6 8:8
+        LocalRef p #13 : Point @ 8:6 8:6
+        Call 2 : Int @ 8:12 8:22
+        GlobalRef f #5 : fun(a: Int, b: Int = default) -> Int @ 8:12 8:12
+        Integer 1 : Int @ 8:14 8:14
+        NamedArg b : Int @ 8:17 8:21
+        Integer 3 : Int @ 8:21 8:21
+        LocalVar s #14 : String @ 9:10 9:10
+        String "a \"quoted\"\n" : String @ 9:14 9:27
+        Field x : Int @ 10:6 10:8
+        LocalRef p #13 : Point @ 10:6 10:6
+    EndFn @ 6:2 6:4
+EndModule
+"#;
+
+    const MIR: &str = r#"mir test
+Module test
+    Fun "Point.len" : fun(self: Point) -> Int @ 3:6 3:8
+        FunParam self : Point @ 3:14 3:14
+        StackAlloc 4 @ 3:28 3:33
+        ObjInit "ret.0" : Int @ 3:28 3:33
+        Field x : Int @ 3:28 3:33
+        Ref self : Point @ 3:28 3:31
+        Drop self : Point @ 3:28 3:33
+        Return value : Int @ 3:28 3:33
+        Move "ret.0" : Int @ 3:28 3:33
+    EndFun @ 3:6 3:8
+    Fun main : fun() -> Int @ 6:2 6:4
+        StackAlloc 4 @ 7:14 7:14
+        ObjInit p mut : Point @ 7:14 7:14
+        Call 1 : Point @ 7:18 7:25
+        Global Point : fun(x: Int) -> Point @ 7:18 7:22
+        Integer 1 : Int @ 7:24 7:24
+        FieldMut x : Unit @ 8:6 8:22
+        Ref p : Point @ 8:6 8:6
+        Integer 3 : Int @ 8:21 8:21
+        Return value : Int @ 9:9 9:15
+        Call 1 : Int @ 9:9 9:15
+        Global "Point.len" : fun(self: Point) -> Int @ 9:9 9:15
+        Move p : Point @ 9:9 9:9
+    EndFun @ 6:2 6:4
+EndModule
+"#;
+
+    #[test]
+    fn hir_round_trips() {
+        let module = parse_hir(HIR).unwrap();
+        assert_eq!(module.name, "test");
+        assert_eq!(module.passes, vec!["resolve", "typeck"]);
+        assert_eq!(module.instructions[18], hir::HIRInstruction::Assign);
+        assert_eq!(module.symbols[1], Some(SymbolId(4)));
+        assert_eq!(print_hir(&module), HIR);
+    }
+
+    #[test]
+    fn hir_can_be_positioned_at_its_own_lines() {
+        let module = parse_hir_at_lines(HIR).unwrap();
+        //The `Assign` is on line 20 of the text, indented by 8 spaces
+        let at = |module: &hir::Module, idx: usize| (module.positions[idx].start, module.positions[idx].end);
+        assert_eq!(at(&module, 18), (Position(20, 9), Position(20, 32)));
+        assert_eq!(at(&module, 0), (Position(2, 1), Position(2, 14)));
+        assert_eq!(at(&parse_hir(HIR).unwrap(), 18), (Position(8, 6), Position(8, 22)));
+    }
+
+    #[test]
+    fn mir_round_trips() {
+        let module = parse_mir(MIR).unwrap();
+        assert!(matches!(&module.instructions[17], mir::MIRInstruction::FieldMut(field) if field == "x"));
+        assert_eq!(print_mir(&module), MIR);
+    }
+
+    #[test]
+    fn reports_the_line_of_errors() {
+        let text = HIR.replacen("Integer 2", "Integr 2", 1);
+        assert_eq!(parse_hir(&text).unwrap_err().line, 10);
+        let text = HIR.replacen("\"a \\\"quoted\\\"\\n\"", "\"unterminated", 1);
+        let error = parse_hir(&text).unwrap_err();
+        assert_eq!((error.msg.as_str(), error.line), ("Unterminated string", 29));
+        let error = parse_mir("Module test\nEndModule\n").unwrap_err();
+        assert_eq!((error.msg.as_str(), error.line), ("Expected a mir header naming the module but found Module", 1));
+    }
+}
@@ -1,11 +1,29 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum TypeSignature {
     None,
     Untyped,
     Primitive(PrimitiveType),
     Function(FunctionSignature),
+    ///A named, user defined type such as a struct, optionally applied to type arguments.
+    Struct(StructSignature),
+    ///A generic type parameter that has not been substituted yet.
+    Param(GenericParam),
+    ///A trait object: a value of some type implementing the named interface, dispatched through a vtable.
+    Dyn(String),
+    ///A fixed-size array of `len` (second) elements of the first type, written `[Int; 4]`.
+    Array(Box<TypeSignature>, usize),
+    ///A growable list of elements, written `List<Int>`.
+    List(Box<TypeSignature>),
+    ///An optional value, written `Option<Int>`: either `Some` of a value or `None`.
+    Option(Box<TypeSignature>),
+    ///A range of values produced by `a..b` or `a..=b`, written `Range<Int>`.
+    Range(Box<TypeSignature>),
+    ///The result of something that can fail, written `Result<Int, String>`: either `Ok` of a value
+    ///of the first type or `Err` of an error of the second.
+    Result(Box<TypeSignature>, Box<TypeSignature>),
 }
 
 impl TypeSignature {
@@ -23,6 +41,131 @@ impl TypeSignature {
     pub fn is_bool(&self) -> bool {
         matches!(self, Self::Primitive(PrimitiveType::Bool))
     }
+
+    ///Whether values of this type can be copied bit for bit, leaving the original usable.
+    ///Strings and lists own a heap buffer, and structs, trait objects and generic values may hold one,
+    ///so values of those types are moved rather than copied.
+    pub fn is_trivially_copyable(&self) -> bool {
+        match self {
+            Self::Primitive(PrimitiveType::String) | Self::Struct(_) | Self::Param(_) | Self::Dyn(_) | Self::List(_) => false,
+            Self::Array(element, _) | Self::Option(element) | Self::Range(element) => element.is_trivially_copyable(),
+            Self::Result(value, error) => value.is_trivially_copyable() && error.is_trivially_copyable(),
+            Self::Primitive(_) | Self::None | Self::Untyped | Self::Function(_) => true,
+        }
+    }
+
+    ///Whether this signature still mentions a generic type parameter somewhere.
+    pub fn is_generic(&self) -> bool {
+        match self {
+            Self::Param(_) => true,
+            Self::Struct(s) => s.type_arguments.iter().any(|t| t.is_generic()),
+            Self::Array(element, _)
+            | Self::List(element)
+            | Self::Option(element)
+            | Self::Range(element) => element.is_generic(),
+            Self::Result(value, error) => value.is_generic() || error.is_generic(),
+            Self::Function(f) => {
+                !f.type_parameters.is_empty()
+                    || f.parameters.iter().any(|p| p.sig.is_generic())
+                    || f.return_type_signature.is_generic()
+            }
+            _ => false,
+        }
+    }
+
+    ///Replace every type parameter found in `substitutions` with its concrete type.
+    pub fn substitute(&self, substitutions: &HashMap<String, TypeSignature>) -> TypeSignature {
+        match self {
+            Self::Param(p) => match substitutions.get(&p.name) {
+                Some(t) => t.clone(),
+                None => self.clone(),
+            },
+            Self::Struct(s) => Self::Struct(StructSignature {
+                name: s.name.clone(),
+                type_arguments: s
+                    .type_arguments
+                    .iter()
+                    .map(|t| t.substitute(substitutions))
+                    .collect(),
+            }),
+            Self::Function(f) => Self::Function(FunctionSignature {
+                type_parameters: f
+                    .type_parameters
+                    .iter()
+                    .filter(|p| !substitutions.contains_key(&p.name))
+                    .cloned()
+                    .collect(),
+                parameters: f
+                    .parameters
+                    .iter()
+                    .map(|p| Parameter {
+                        sig: p.sig.substitute(substitutions),
+                        ..p.clone()
+                    })
+                    .collect(),
+                variadic: f.variadic,
+                return_type_signature: Box::new(f.return_type_signature.substitute(substitutions)),
+                calling_convention: f.calling_convention,
+            }),
+            Self::Array(element, len) => Self::Array(Box::new(element.substitute(substitutions)), *len),
+            Self::List(element) => Self::List(Box::new(element.substitute(substitutions))),
+            Self::Option(element) => Self::Option(Box::new(element.substitute(substitutions))),
+            Self::Range(element) => Self::Range(Box::new(element.substitute(substitutions))),
+            Self::Result(value, error) => Self::Result(
+                Box::new(value.substitute(substitutions)),
+                Box::new(error.substitute(substitutions)),
+            ),
+            _ => self.clone(),
+        }
+    }
+
+    ///Match `self`, which may contain type parameters, against the concrete signature `other`,
+    ///recording what each type parameter must be in `substitutions`.
+    ///Returns false if the two signatures have a different shape or a parameter would be bound twice to different types.
+    pub fn unify(
+        &self,
+        other: &TypeSignature,
+        substitutions: &mut HashMap<String, TypeSignature>,
+    ) -> bool {
+        match (self, other) {
+            (Self::Param(p), _) => match substitutions.get(&p.name) {
+                Some(bound) => bound == other,
+                None => {
+                    substitutions.insert(p.name.clone(), other.clone());
+                    true
+                }
+            },
+            (Self::Struct(a), Self::Struct(b)) => {
+                a.name == b.name
+                    && a.type_arguments.len() == b.type_arguments.len()
+                    && a
+                        .type_arguments
+                        .iter()
+                        .zip(b.type_arguments.iter())
+                        .all(|(a, b)| a.unify(b, substitutions))
+            }
+            (Self::Function(a), Self::Function(b)) => {
+                a.variadic == b.variadic
+                    && a.parameters.len() == b.parameters.len()
+                    && a
+                        .parameters
+                        .iter()
+                        .zip(b.parameters.iter())
+                        .all(|(a, b)| a.sig.unify(&b.sig, substitutions))
+                    && a
+                        .return_type_signature
+                        .unify(&b.return_type_signature, substitutions)
+            }
+            (Self::Array(a, a_len), Self::Array(b, b_len)) => a_len == b_len && a.unify(b, substitutions),
+            (Self::List(a), Self::List(b))
+            | (Self::Option(a), Self::Option(b))
+            | (Self::Range(a), Self::Range(b)) => a.unify(b, substitutions),
+            (Self::Result(a_value, a_error), Self::Result(b_value, b_error)) => {
+                a_value.unify(b_value, substitutions) && a_error.unify(b_error, substitutions)
+            }
+            (a, b) => a == b,
+        }
+    }
 }
 
 impl Display for TypeSignature {
@@ -31,13 +174,20 @@ impl Display for TypeSignature {
             Self::None => write!(f, "None"),
             Self::Untyped => write!(f, "Untyped"),
             Self::Primitive(p) => write!(f, "{}", p),
-            // Self::Struct(s) => write!(f, "{}", s),
+            Self::Struct(s) => write!(f, "{}", s),
             Self::Function(func) => write!(f, "{}", func),
+            Self::Param(p) => write!(f, "{}", p.name),
+            Self::Dyn(i) => write!(f, "dyn {}", i),
+            Self::Array(element, len) => write!(f, "[{}; {}]", element, len),
+            Self::List(element) => write!(f, "List<{}>", element),
+            Self::Option(element) => write!(f, "Option<{}>", element),
+            Self::Range(element) => write!(f, "Range<{}>", element),
+            Self::Result(value, error) => write!(f, "Result<{}, {}>", value, error),
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum PrimitiveType {
     None,
     Integer,
@@ -60,10 +210,64 @@ impl PrimitiveType {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct FunctionSignature{
-    pub parameters: Vec<TypeSignature>,
+    ///The generic type parameters declared by the function, e.g. `fun <T> first(...)`
+    pub type_parameters: Vec<GenericParam>,
+    pub parameters: Vec<Parameter>,
+    ///Whether the last parameter is a `vararg`, which takes any number of arguments of its type
+    pub variadic: bool,
     pub return_type_signature: Box<TypeSignature>,
+    pub calling_convention: CallingConvention,
+}
+
+///A parameter of a function, which calls can pass arguments to by position or by name.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Parameter{
+    pub name: String,
+    pub sig: TypeSignature,
+    ///Whether the parameter has a default value, which is used when a call leaves it out
+    pub default: bool,
+}
+
+impl Parameter{
+    pub fn new(name: &str, sig: TypeSignature) -> Self{
+        Parameter{
+            name: name.to_string(),
+            sig,
+            default: false,
+        }
+    }
+}
+
+///The names and default values of parameters aren't part of the type of a function,
+///so parameters are the same when their types are.
+impl PartialEq for Parameter{
+    fn eq(&self, other: &Self) -> bool{
+        self.sig == other.sig
+    }
+}
+
+///How a function is called at the machine level.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum CallingConvention{
+    ///A function written in Beagle
+    Beagle,
+    ///A `native fun`, called with the C calling convention of the target and resolved by the linker
+    C,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StructSignature{
+    pub name: String,
+    pub type_arguments: Vec<TypeSignature>,
+}
+
+///A generic type parameter along with the names of the bounds it must satisfy.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GenericParam{
+    pub name: String,
+    pub bounds: Vec<String>,
 }
 
 use std::fmt::{
@@ -87,14 +291,56 @@ impl Display for PrimitiveType {
 
 impl Display for FunctionSignature {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        if self.calling_convention == CallingConvention::C{
+            write!(f, "extern \"C\" ")?;
+        }
+        if !self.type_parameters.is_empty(){
+            write!(f, "<")?;
+            for (i, param) in self.type_parameters.iter().enumerate() {
+                if i != 0{
+                    write!(f, ", ")?;
+                }
+                write!(f, "{}", param)?;
+            }
+            write!(f, ">")?;
+        }
         write!(f, "(")?;
         for (i, parameter) in self.parameters.iter().enumerate() {
-            if i >= self.parameters.len() - 1{
-                write!(f, "{}", parameter)?;
-            }else{
-                write!(f, "{}, ", parameter)?;
+            if i != 0{
+                write!(f, ", ")?;
+            }
+            if self.variadic && i == self.parameters.len() - 1{
+                write!(f, "vararg ")?;
             }
+            write!(f, "{}", parameter.sig)?;
         }
         write!(f, ") -> {}", self.return_type_signature)
     }
 }
+
+impl Display for StructSignature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}", self.name)?;
+        if !self.type_arguments.is_empty(){
+            write!(f, "<")?;
+            for (i, arg) in self.type_arguments.iter().enumerate() {
+                if i != 0{
+                    write!(f, ", ")?;
+                }
+                write!(f, "{}", arg)?;
+            }
+            write!(f, ">")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for GenericParam {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}", self.name)?;
+        if !self.bounds.is_empty(){
+            write!(f, ": {}", self.bounds.join(" + "))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,648 @@
+use crate::{
+    hir::{HIRInstruction, Module},
+    mir::{self, MIRInstruction},
+    type_signature::TypeSignature,
+};
+use std::collections::HashMap;
+
+///A way in which a HIR or MIR stream is malformed, found by `verify` or `verify_mir`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VerifyError {
+    pub msg: String,
+    ///The index of the offending instruction
+    pub idx: usize,
+}
+
+///What the instructions being verified are nested in, which decides what they can be.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Scope {
+    Module,
+    ///The body of a function, made of statements
+    Function,
+    Interface,
+    Impl,
+}
+
+///Check that `module` is well-formed: every instruction opening a construct has its matching end,
+///every operation is followed by as many operands as it takes, and each instruction only comes where it can.
+///A module whose only instruction is a `Halt`, or that has none, is one a pass gave up on and is well-formed.
+///Errors are reported in the order of the instructions they are about.
+pub fn verify(module: &Module) -> Result<(), Vec<VerifyError>> {
+    let mut errors = Vec::new();
+    let len = module.instructions.len();
+    if module.signatures.len() != len || module.positions.len() != len || module.symbols.len() != len {
+        errors.push(VerifyError {
+            msg: format!(
+                "The module has {} instructions but {} signatures, {} positions and {} symbols",
+                len,
+                module.signatures.len(),
+                module.positions.len(),
+                module.symbols.len()
+            ),
+            idx: len
+                .min(module.signatures.len())
+                .min(module.positions.len())
+                .min(module.symbols.len()),
+        });
+    }
+    //A `Halt` can only come last, so any other one is reported where it is found
+    let end = match module.instructions.last() {
+        Some(HIRInstruction::Halt) => len - 1,
+        _ => len,
+    };
+    let mut verifier = Verifier {
+        instructions: &module.instructions[..end],
+        errors,
+        loops: 0,
+    };
+    verifier.module();
+    if verifier.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(verifier.errors)
+    }
+}
+
+///Check that every `Call` of the MIR `module` passes as many arguments as its callee takes: one for each of its params
+///but those with a default, which can be left out. The arguments given for a vararg are passed as one list.
+///The callee of a `Global` is the function the module declares under that name if there is one.
+pub fn verify_mir(module: &mir::Module) -> Result<(), Vec<VerifyError>> {
+    let declared: HashMap<&str, &TypeSignature> = module
+        .instructions
+        .iter()
+        .zip(module.signatures.iter())
+        .filter_map(|(ins, sig)| match ins {
+            MIRInstruction::Fun(name) | MIRInstruction::NativeFun(name) => Some((name.as_str(), sig)),
+            _ => None,
+        })
+        .collect();
+    let mut errors = Vec::new();
+    for (idx, ins) in module.instructions.iter().enumerate() {
+        let argc = match ins {
+            MIRInstruction::Call(argc) => *argc,
+            _ => continue,
+        };
+        let (callee, sig) = match (module.instructions.get(idx + 1), module.signatures.get(idx + 1)) {
+            (Some(MIRInstruction::Global(name)), Some(sig)) => {
+                (name.clone(), declared.get(name.as_str()).copied().unwrap_or(sig))
+            }
+            (Some(callee), Some(sig)) => (format!("{:?}", callee), sig),
+            _ => {
+                errors.push(VerifyError { msg: format!("The call at {} has no callee", idx), idx });
+                continue;
+            }
+        };
+        let f = match sig {
+            TypeSignature::Function(f) => f,
+            sig => {
+                let msg =
+                    format!("The callee {} of the call at {} is of type {}, which isn't a function", callee, idx, sig);
+                errors.push(VerifyError { msg, idx: idx + 1 });
+                continue;
+            }
+        };
+        let params = f.parameters.len();
+        let required = f.parameters.iter().filter(|param| !param.default).count();
+        if argc < required || argc > params {
+            let takes = if required == params { params.to_string() } else { format!("{} to {}", required, params) };
+            let msg = format!("The call at {} passes {} arguments to {}, which takes {}", idx, argc, callee, takes);
+            errors.push(VerifyError { msg, idx });
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+struct Verifier<'a> {
+    instructions: &'a [HIRInstruction],
+    errors: Vec<VerifyError>,
+    ///The number of loops enclosing the statement being verified, within its function
+    loops: usize,
+}
+
+impl<'a> Verifier<'a> {
+    fn error(&mut self, msg: String, idx: usize) {
+        self.errors.push(VerifyError { msg, idx });
+    }
+
+    fn module(&mut self) {
+        let first = match self.instructions.first() {
+            Some(first) => first,
+            None => return,
+        };
+        if !matches!(first, HIRInstruction::Module(_)) {
+            self.error(format!("The module must start with a Module, but starts with {:?}", first), 0);
+            return;
+        }
+        let end = self.block(1, 0, HIRInstruction::EndModule, Scope::Module);
+        if let Some(ins) = self.instructions.get(end) {
+            self.error(format!("{:?} at {} comes after the end of the module", ins, end), end);
+        }
+    }
+
+    ///Verify the contents of the construct opened at `opener`, from `idx` up to the first of `ends`.
+    ///Returns the index of the end that was found, or of where the contents stopped if none was.
+    fn until(&mut self, mut idx: usize, opener: usize, ends: &[HIRInstruction], scope: Scope) -> usize {
+        loop {
+            match self.instructions.get(idx) {
+                Some(ins) if ends.contains(ins) => return idx,
+                //Leave an end that doesn't match to an enclosing construct, which it may belong to
+                Some(ins) if is_end(ins) && scope != Scope::Module => {
+                    let msg = format!(
+                        "{:?} at {} is closed by {:?} at {} instead of {:?}",
+                        self.instructions[opener],
+                        opener,
+                        ins,
+                        idx,
+                        ends[ends.len() - 1]
+                    );
+                    self.error(msg, opener);
+                    return idx;
+                }
+                Some(_) if scope == Scope::Function => idx = self.statement(idx),
+                Some(_) => idx = self.item(idx, scope),
+                None => {
+                    let msg = format!("{:?} at {} has no matching {:?}", self.instructions[opener], opener, ends[ends.len() - 1]);
+                    self.error(msg, opener);
+                    return idx;
+                }
+            }
+        }
+    }
+
+    ///Verify the contents of the construct opened at `opener` from `idx`, returning the index after its `end`.
+    fn block(&mut self, idx: usize, opener: usize, end: HIRInstruction, scope: Scope) -> usize {
+        let idx = self.until(idx, opener, std::slice::from_ref(&end), scope);
+        self.skip(idx, &end)
+    }
+
+    ///Verify a loop body from `idx`, in which `Break` and `Continue` can be used.
+    fn loop_body(&mut self, idx: usize, opener: usize, end: HIRInstruction) -> usize {
+        self.loops += 1;
+        let idx = self.block(idx, opener, end, Scope::Function);
+        self.loops -= 1;
+        idx
+    }
+
+    ///The index after `idx` if it holds `end`, which `until` found.
+    ///Any other end is left to the construct it may belong to.
+    fn skip(&self, idx: usize, end: &HIRInstruction) -> usize {
+        match self.instructions.get(idx) {
+            Some(ins) if ins == end => idx + 1,
+            _ => idx,
+        }
+    }
+
+    ///Verify the declaration at `idx` along with the annotations and visibility marker before it,
+    ///returning the index after it.
+    fn item(&mut self, idx: usize, scope: Scope) -> usize {
+        let mut decl = idx;
+        while let Some(HIRInstruction::Annotation(..)) = self.instructions.get(decl) {
+            decl += 1;
+        }
+        if let Some(HIRInstruction::Visibility(_)) = self.instructions.get(decl) {
+            decl += 1;
+        }
+        let ins = match self.instructions.get(decl) {
+            Some(ins) if is_declaration(ins) => ins,
+            _ if decl != idx => {
+                let marker = &self.instructions[decl - 1];
+                self.error(format!("{:?} at {} must be followed by a declaration", marker, decl - 1), decl - 1);
+                return decl;
+            }
+            _ => return self.misplaced(idx, scope),
+        };
+        match ins {
+            HIRInstruction::Property(..) | HIRInstruction::Const(_) => {
+                let mut next = self.expr(decl + 1);
+                //In a function, an expression after the initializer is a statement of its own
+                if scope != Scope::Function && self.instructions.get(next).is_some_and(is_expression) {
+                    self.error(format!("{:?} at {} must be followed by exactly one expression", ins, decl), decl);
+                    while self.instructions.get(next).is_some_and(is_expression) {
+                        next = self.expr(next);
+                    }
+                }
+                if scope != Scope::Module && matches!(ins, HIRInstruction::Const(_)) {
+                    self.error(format!("{:?} at {} can only be declared at the top level", ins, decl), decl);
+                }
+                next
+            }
+            HIRInstruction::TypeAlias(_) | HIRInstruction::Import(_) if scope != Scope::Module => {
+                self.error(format!("{:?} at {} can only be declared at the top level", ins, decl), decl);
+                decl + 1
+            }
+            HIRInstruction::InterfaceMethod(..) if scope != Scope::Interface => {
+                self.error(format!("{:?} at {} is not inside an Interface", ins, decl), decl);
+                decl + 1
+            }
+            HIRInstruction::InterfaceMethod(..) => decl + 1,
+            HIRInstruction::Fn(_) => self.function(decl),
+            HIRInstruction::Struct(_) => self.struct_(decl),
+            HIRInstruction::Interface(_) => self.block(decl + 1, decl, HIRInstruction::EndInterface, Scope::Interface),
+            HIRInstruction::Impl(_) => self.block(decl + 1, decl, HIRInstruction::EndImpl, Scope::Impl),
+            _ if scope == Scope::Interface || scope == Scope::Impl => {
+                self.error(format!("{:?} at {} cannot be declared in an interface or impl block", ins, decl), decl);
+                decl + 1
+            }
+            _ => decl + 1,
+        }
+    }
+
+    ///Verify the function at `idx`: its type params, receiver, params with their default values, then its body.
+    fn function(&mut self, idx: usize) -> usize {
+        let mut next = idx + 1;
+        while let Some(HIRInstruction::TypeParam(_)) = self.instructions.get(next) {
+            next += 1;
+        }
+        if let Some(HIRInstruction::SelfParam(_)) = self.instructions.get(next) {
+            next += 1;
+        }
+        while let Some(HIRInstruction::FnParam(_)) = self.instructions.get(next) {
+            next += 1;
+            if let Some(HIRInstruction::DefaultArg) = self.instructions.get(next) {
+                next = self.expr(next + 1);
+            }
+        }
+        if let Some(HIRInstruction::FnType(_)) = self.instructions.get(next) {
+            next += 1;
+        }
+        //Loops don't carry over into the functions declared in them
+        let outer_loops = std::mem::take(&mut self.loops);
+        let next = self.block(next, idx, HIRInstruction::EndFn, Scope::Function);
+        self.loops = outer_loops;
+        next
+    }
+
+    ///Verify the struct at `idx`: its type params, then its fields with their visibility markers.
+    fn struct_(&mut self, idx: usize) -> usize {
+        let mut next = idx + 1;
+        while let Some(HIRInstruction::TypeParam(_)) = self.instructions.get(next) {
+            next += 1;
+        }
+        loop {
+            match self.instructions.get(next) {
+                Some(HIRInstruction::EndStruct) => return next + 1,
+                Some(HIRInstruction::StructField(_)) => next += 1,
+                Some(HIRInstruction::Visibility(_)) if matches!(self.instructions.get(next + 1), Some(HIRInstruction::StructField(_))) => {
+                    next += 2
+                }
+                Some(ins) if is_end(ins) => {
+                    let msg = format!("{:?} at {} is closed by {:?} at {} instead of EndStruct", self.instructions[idx], idx, ins, next);
+                    self.error(msg, idx);
+                    return next;
+                }
+                Some(ins) => {
+                    let msg = format!("{:?} at {} can only hold fields, but holds {:?} at {}", self.instructions[idx], idx, ins, next);
+                    self.error(msg, next);
+                    next += 1;
+                }
+                None => {
+                    let msg = format!("{:?} at {} has no matching EndStruct", self.instructions[idx], idx);
+                    self.error(msg, idx);
+                    return next;
+                }
+            }
+        }
+    }
+
+    ///Verify the statement at `idx`, returning the index after it.
+    fn statement(&mut self, idx: usize) -> usize {
+        let ins = &self.instructions[idx];
+        match ins {
+            HIRInstruction::LocalVar(..) => self.expr(idx + 1),
+            HIRInstruction::For(_) => {
+                let body = self.expr(idx + 1);
+                self.loop_body(body, idx, HIRInstruction::EndFor)
+            }
+            HIRInstruction::While => {
+                let body = self.expr(idx + 1);
+                self.loop_body(body, idx, HIRInstruction::EndWhile)
+            }
+            HIRInstruction::Loop => self.loop_body(idx + 1, idx, HIRInstruction::EndLoop),
+            HIRInstruction::If(has_else) => {
+                let then = self.expr(idx + 1);
+                let next = self.until(then, idx, &[HIRInstruction::Else, HIRInstruction::EndIf], Scope::Function);
+                match self.instructions.get(next) {
+                    Some(HIRInstruction::Else) => {
+                        if !has_else {
+                            self.error(format!("{:?} at {} has an Else at {} but isn't flagged as having one", ins, idx, next), idx);
+                        }
+                        self.block(next + 1, idx, HIRInstruction::EndIf, Scope::Function)
+                    }
+                    Some(HIRInstruction::EndIf) if *has_else => {
+                        self.error(format!("{:?} at {} is flagged as having an Else but has none", ins, idx), idx);
+                        next + 1
+                    }
+                    _ => self.skip(next, &HIRInstruction::EndIf),
+                }
+            }
+            HIRInstruction::Break | HIRInstruction::Continue if self.loops == 0 => {
+                self.error(format!("{:?} at {} is not inside a For, While or Loop", ins, idx), idx);
+                idx + 1
+            }
+            HIRInstruction::Return(true) => self.expr(idx + 1),
+            HIRInstruction::Assign => {
+                let value = self.target(idx + 1);
+                self.expr(value)
+            }
+            HIRInstruction::Block => self.block(idx + 1, idx, HIRInstruction::EndBlock, Scope::Function),
+            HIRInstruction::Break | HIRInstruction::Continue | HIRInstruction::Return(false) => idx + 1,
+            ins if is_expression(ins) => self.expr(idx),
+            _ => self.item(idx, Scope::Function),
+        }
+    }
+
+    ///Report the instruction at `idx`, which can't come where it is, and return the index to go on from.
+    fn misplaced(&mut self, idx: usize, scope: Scope) -> usize {
+        let ins = &self.instructions[idx];
+        let msg = match ins {
+            HIRInstruction::TypeParam(_) => format!("{:?} at {} can only come right after the Fn or Struct it belongs to", ins, idx),
+            HIRInstruction::SelfParam(_) | HIRInstruction::FnParam(_) | HIRInstruction::FnType(_) => {
+                format!("{:?} at {} can only come right after the Fn it belongs to and the params before it", ins, idx)
+            }
+            HIRInstruction::DefaultArg => format!("{:?} at {} can only come right after a FnParam", ins, idx),
+            HIRInstruction::StructField(_) => format!("{:?} at {} is not inside a Struct", ins, idx),
+            HIRInstruction::NamedArg(_) => format!("{:?} at {} is not an argument of a call", ins, idx),
+            HIRInstruction::Module(_) => format!("{:?} at {} is nested in another module", ins, idx),
+            HIRInstruction::Halt => format!("{:?} at {} is not the last instruction", ins, idx),
+            ins if is_end(ins) => format!("{:?} at {} does not close anything", ins, idx),
+            ins if is_expression(ins) => {
+                let msg = format!("{:?} at {} is an expression outside of a function", ins, idx);
+                self.error(msg, idx);
+                return self.expr(idx);
+            }
+            _ if scope != Scope::Function => format!("{:?} at {} is a statement outside of a function", ins, idx),
+            _ => format!("{:?} at {} cannot be a statement", ins, idx),
+        };
+        self.error(msg, idx);
+        idx + 1
+    }
+
+    ///Verify the expression starting at `idx` along with its operands, returning the index after it.
+    fn expr(&mut self, idx: usize) -> usize {
+        let ins = match self.instructions.get(idx) {
+            Some(ins) => ins,
+            None => {
+                self.error("Expected an expression, but the module ends".to_string(), idx);
+                return idx;
+            }
+        };
+        match ins {
+            HIRInstruction::Integer(_)
+            | HIRInstruction::Float(_)
+            | HIRInstruction::Bool(_)
+            | HIRInstruction::String(_)
+            | HIRInstruction::LocalRef(_)
+            | HIRInstruction::GlobalRef(_) => idx + 1,
+            HIRInstruction::Call(argc) | HIRInstruction::MethodCall(_, argc) => {
+                let callee = self.expr(idx + 1);
+                self.args(callee, *argc)
+            }
+            HIRInstruction::Field(_) | HIRInstruction::Unary(_) | HIRInstruction::Try => self.expr(idx + 1),
+            HIRInstruction::Index | HIRInstruction::Binary(_) | HIRInstruction::Range(_) => {
+                let rhs = self.expr(idx + 1);
+                self.expr(rhs)
+            }
+            HIRInstruction::Array(len) => (0..*len).fold(idx + 1, |next, _| self.expr(next)),
+            HIRInstruction::Slice(start, end) => {
+                let operands = 1 + *start as usize + *end as usize;
+                (0..operands).fold(idx + 1, |next, _| self.expr(next))
+            }
+            HIRInstruction::NamedArg(_) => {
+                self.error(format!("{:?} at {} is not an argument of a call", ins, idx), idx);
+                self.expr(idx + 1)
+            }
+            //The instruction is left to the enclosing construct, which it may end
+            _ => {
+                self.error(format!("Expected an expression, but found {:?} at {}", ins, idx), idx);
+                idx
+            }
+        }
+    }
+
+    ///Verify the target of an assignment starting at `idx`, a local, property or field of one,
+    ///returning the index after it.
+    fn target(&mut self, idx: usize) -> usize {
+        match self.instructions.get(idx) {
+            Some(HIRInstruction::LocalRef(_)) | Some(HIRInstruction::GlobalRef(_)) => idx + 1,
+            Some(HIRInstruction::Field(_)) => self.target(idx + 1),
+            Some(ins) if is_expression(ins) => {
+                self.error(format!("{:?} at {} cannot be assigned to", ins, idx), idx);
+                self.expr(idx)
+            }
+            _ => self.expr(idx),
+        }
+    }
+
+    ///Verify the `argc` arguments of a call starting at `idx`, returning the index after them.
+    ///Which of them are named is up to the type checker to check.
+    fn args(&mut self, idx: usize, argc: usize) -> usize {
+        (0..argc).fold(idx, |next, _| match self.instructions.get(next) {
+            Some(HIRInstruction::NamedArg(_)) => self.expr(next + 1),
+            _ => self.expr(next),
+        })
+    }
+}
+
+///Whether `ins` closes a construct, or the first half of an `If`.
+fn is_end(ins: &HIRInstruction) -> bool {
+    matches!(
+        ins,
+        HIRInstruction::EndModule
+            | HIRInstruction::EndFn
+            | HIRInstruction::EndStruct
+            | HIRInstruction::EndInterface
+            | HIRInstruction::EndImpl
+            | HIRInstruction::EndFor
+            | HIRInstruction::Else
+            | HIRInstruction::EndIf
+            | HIRInstruction::EndWhile
+            | HIRInstruction::EndLoop
+            | HIRInstruction::EndBlock
+    )
+}
+
+///Whether `ins` declares something, and can be preceded by annotations and a visibility marker.
+fn is_declaration(ins: &HIRInstruction) -> bool {
+    matches!(
+        ins,
+        HIRInstruction::Property(..)
+            | HIRInstruction::Const(_)
+            | HIRInstruction::Fn(_)
+            | HIRInstruction::NativeFn(_)
+            | HIRInstruction::Struct(_)
+            | HIRInstruction::TypeAlias(_)
+            | HIRInstruction::Newtype(_)
+            | HIRInstruction::Interface(_)
+            | HIRInstruction::InterfaceMethod(..)
+            | HIRInstruction::Impl(_)
+            | HIRInstruction::Import(_)
+    )
+}
+
+///Whether `ins` starts an expression. A `NamedArg` only starts an argument of a call.
+fn is_expression(ins: &HIRInstruction) -> bool {
+    matches!(
+        ins,
+        HIRInstruction::Integer(_)
+            | HIRInstruction::Float(_)
+            | HIRInstruction::Bool(_)
+            | HIRInstruction::String(_)
+            | HIRInstruction::LocalRef(_)
+            | HIRInstruction::GlobalRef(_)
+            | HIRInstruction::Call(_)
+            | HIRInstruction::MethodCall(..)
+            | HIRInstruction::Field(_)
+            | HIRInstruction::Array(_)
+            | HIRInstruction::Index
+            | HIRInstruction::Slice(..)
+            | HIRInstruction::Try
+            | HIRInstruction::Binary(_)
+            | HIRInstruction::Unary(_)
+            | HIRInstruction::Range(_)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hir::BinaryOp,
+        type_signature::{CallingConvention, FunctionSignature, Parameter, PrimitiveType},
+    };
+    use core::pos::BiPos;
+
+    fn hir(instructions: Vec<HIRInstruction>) -> Module {
+        let mut module = Module::new("test".to_string());
+        for ins in instructions {
+            module.push(BiPos::default(), TypeSignature::None, ins);
+        }
+        module
+    }
+
+    ///The messages of the errors found in `module`, which must have some.
+    fn errors(module: &Module) -> Vec<String> {
+        verify(module).unwrap_err().into_iter().map(|error| error.msg).collect()
+    }
+
+    fn main(body: Vec<HIRInstruction>) -> Module {
+        let mut instructions = vec![HIRInstruction::Module("test".to_string()), HIRInstruction::Fn("main".to_string())];
+        instructions.extend(body);
+        instructions.extend(vec![HIRInstruction::EndFn, HIRInstruction::EndModule]);
+        hir(instructions)
+    }
+
+    #[test]
+    fn accepts_well_formed_modules() {
+        let module = main(vec![
+            HIRInstruction::LocalVar("x".to_string(), true),
+            HIRInstruction::Integer(1),
+            HIRInstruction::Assign,
+            HIRInstruction::LocalRef("x".to_string()),
+            HIRInstruction::Binary(BinaryOp::Add),
+            HIRInstruction::LocalRef("x".to_string()),
+            HIRInstruction::Integer(1),
+            HIRInstruction::Loop,
+            HIRInstruction::Break,
+            HIRInstruction::EndLoop,
+        ]);
+        assert_eq!(verify(&module), Ok(()));
+        assert_eq!(verify(&hir(vec![HIRInstruction::Halt])), Ok(()));
+    }
+
+    #[test]
+    fn rejects_unclosed_functions() {
+        let module = hir(vec![HIRInstruction::Module("test".to_string()), HIRInstruction::Fn("main".to_string())]);
+        let expected = vec![r#"Fn("main") at 1 has no matching EndFn"#, r#"Module("test") at 0 has no matching EndModule"#];
+        assert_eq!(errors(&module), expected);
+    }
+
+    #[test]
+    fn rejects_missing_operands() {
+        let module = main(vec![HIRInstruction::Call(1), HIRInstruction::GlobalRef("f".to_string())]);
+        assert_eq!(errors(&module), vec!["Expected an expression, but found EndFn at 4"]);
+    }
+
+    #[test]
+    fn rejects_breaks_outside_of_loops() {
+        let module = main(vec![HIRInstruction::Break]);
+        assert_eq!(errors(&module), vec!["Break at 2 is not inside a For, While or Loop"]);
+    }
+
+    #[test]
+    fn rejects_assignments_to_values() {
+        let module = main(vec![HIRInstruction::Assign, HIRInstruction::Integer(1), HIRInstruction::Integer(2)]);
+        assert_eq!(errors(&module), vec!["Integer(1) at 3 cannot be assigned to"]);
+    }
+
+    #[test]
+    fn rejects_mismatched_signatures() {
+        let mut module = main(Vec::new());
+        module.signatures.pop();
+        assert_eq!(errors(&module), vec!["The module has 4 instructions but 3 signatures, 4 positions and 4 symbols"]);
+    }
+
+    fn function(params: Vec<Parameter>) -> TypeSignature {
+        TypeSignature::Function(FunctionSignature {
+            type_parameters: Vec::new(),
+            parameters: params,
+            variadic: false,
+            return_type_signature: Box::new(TypeSignature::Primitive(PrimitiveType::Integer)),
+            calling_convention: CallingConvention::Beagle,
+        })
+    }
+
+    fn mir(instructions: Vec<(MIRInstruction, TypeSignature)>) -> mir::Module {
+        let mut module = mir::Module::new("test".to_string());
+        for (ins, sig) in instructions {
+            module.instructions.push(ins);
+            module.signatures.push(sig);
+            module.positions.push(BiPos::default());
+        }
+        module
+    }
+
+    ///A MIR module declaring `f` with the signature `sig`, calling it from `main` with `argc` arguments.
+    fn call(sig: TypeSignature, argc: usize) -> mir::Module {
+        let int = TypeSignature::Primitive(PrimitiveType::Integer);
+        let mut instructions = vec![
+            (MIRInstruction::Module("test".to_string()), TypeSignature::None),
+            (MIRInstruction::Fun("f".to_string()), sig),
+            (MIRInstruction::EndFun, TypeSignature::None),
+            (MIRInstruction::Fun("main".to_string()), function(Vec::new())),
+            (MIRInstruction::Call(argc), int.clone()),
+            (MIRInstruction::Global("f".to_string()), function(Vec::new())),
+        ];
+        instructions.extend((0..argc).map(|arg| (MIRInstruction::Integer(arg as i32), int.clone())));
+        instructions.push((MIRInstruction::EndFun, TypeSignature::None));
+        instructions.push((MIRInstruction::EndModule, TypeSignature::None));
+        mir(instructions)
+    }
+
+    #[test]
+    fn checks_the_arity_of_calls_against_the_callee() {
+        let int = TypeSignature::Primitive(PrimitiveType::Integer);
+        let mut default = Parameter::new("b", int.clone());
+        default.default = true;
+        let sig = function(vec![Parameter::new("a", int), default]);
+        assert_eq!(mir::verify(&call(sig.clone(), 1)), Ok(()));
+        assert_eq!(mir::verify(&call(sig.clone(), 2)), Ok(()));
+        let error = mir::verify(&call(sig.clone(), 0)).unwrap_err();
+        assert_eq!(error[0].msg, "The call at 4 passes 0 arguments to f, which takes 1 to 2");
+        assert_eq!(error[0].idx, 4);
+        let error = mir::verify(&call(sig, 3)).unwrap_err();
+        assert_eq!(error[0].msg, "The call at 4 passes 3 arguments to f, which takes 1 to 2");
+    }
+
+    #[test]
+    fn rejects_calls_of_values_that_are_not_functions() {
+        let int = TypeSignature::Primitive(PrimitiveType::Integer);
+        let module = mir(vec![(MIRInstruction::Call(0), int.clone()), (MIRInstruction::Integer(1), int)]);
+        let error = mir::verify(&module).unwrap_err();
+        assert_eq!(error[0].msg, "The callee Integer(1) of the call at 0 is of type Int, which isn't a function");
+        assert_eq!(error[0].idx, 1);
+    }
+}
@@ -0,0 +1,444 @@
+use crate::{
+    methods::{range_next, with_receiver},
+    ModuleLowering,
+};
+use ir::{
+    consteval::ConstValue,
+    hir::{BinaryOp, HIRInstruction, ReceiverKind, UnaryOp},
+    mir::{MIRInstruction, MIR},
+    type_signature::{CallingConvention, FunctionSignature, Parameter, PrimitiveType, TypeSignature},
+};
+
+///How an operand is passed to the instruction using it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Operand {
+    ///Its value
+    Value,
+    ///A `Ref` of the object it is, which is a temporary holding its value unless it is a local or property
+    Place,
+    ///A `Ref` of the object it is if it is a local or property, and its value otherwise
+    PlaceOrValue,
+}
+
+///Whether `code` computes a value without side effects, so that evaluating it later gives the same value.
+fn is_pure(code: &[MIR]) -> bool {
+    let value = match code.first().map(|ir| &ir.ins) {
+        Some(MIRInstruction::NamedArg(_)) => &code[1..],
+        _ => code,
+    };
+    value.len() == 1
+        && matches!(
+            value[0].ins,
+            MIRInstruction::Integer(_)
+                | MIRInstruction::Float(_)
+                | MIRInstruction::Bool(_)
+                | MIRInstruction::String(_)
+                | MIRInstruction::Unit
+                | MIRInstruction::Copy(_)
+                | MIRInstruction::Ref(_)
+                | MIRInstruction::Global(_)
+        )
+}
+
+impl ModuleLowering<'_> {
+    ///Lower the expression at `idx`, returning the instructions computing its value along with the index after it.
+    ///Whatever has to run before the statement containing the expression is added to the prelude.
+    pub(crate) fn expr(&mut self, idx: usize) -> (Vec<MIR>, usize) {
+        let hir = self.hir;
+        let ins = match &hir.instructions[idx] {
+            HIRInstruction::Integer(i) => MIRInstruction::Integer(*i),
+            HIRInstruction::Float(f) => MIRInstruction::Float(*f),
+            HIRInstruction::Bool(b) => MIRInstruction::Bool(*b),
+            HIRInstruction::String(s) => MIRInstruction::String(s.clone()),
+            HIRInstruction::LocalRef(name) => MIRInstruction::Copy(self.local(idx, name)),
+            HIRInstruction::GlobalRef(name) => self.global(name),
+            HIRInstruction::Call(argc) => return self.call(idx, *argc),
+            HIRInstruction::MethodCall(name, argc) => return self.method_call(idx, name, *argc),
+            HIRInstruction::NamedArg(name) => {
+                return self.prefixed(idx, MIRInstruction::NamedArg(name.clone()), &[Operand::Value])
+            }
+            HIRInstruction::Field(name) => {
+                return self.prefixed(idx, MIRInstruction::Field(name.clone()), &[Operand::PlaceOrValue])
+            }
+            HIRInstruction::Array(len) => {
+                return self.prefixed(idx, MIRInstruction::ArrayInit(*len), &vec![Operand::Value; *len])
+            }
+            HIRInstruction::Index => return self.index(idx),
+            HIRInstruction::Slice(start, end) => return self.slice(idx, *start, *end),
+            HIRInstruction::Try => return self.try_(idx),
+            HIRInstruction::Binary(op) => return self.binary(idx, *op),
+            HIRInstruction::Unary(op) => return self.prefixed(idx, MIRInstruction::Unary(*op), &[Operand::Value]),
+            HIRInstruction::Range(inclusive) => {
+                return self.prefixed(idx, MIRInstruction::RangeInit(*inclusive), &[Operand::Value; 2])
+            }
+            //The type checker reports anything else in place of an expression
+            _ => MIRInstruction::Halt,
+        };
+        (vec![self.mir(idx, ins)], idx + 1)
+    }
+
+    ///Lower the expression at `idx` to a `Ref` of the object holding its value.
+    pub(crate) fn place(&mut self, idx: usize) -> (Vec<MIR>, usize) {
+        self.operand(idx, Operand::Place)
+    }
+
+    ///Lower the instruction at `idx` to `ins` followed by its operands, passed as `modes` says.
+    fn prefixed(&mut self, idx: usize, ins: MIRInstruction, modes: &[Operand]) -> (Vec<MIR>, usize) {
+        let (operands, next) = self.operands(idx + 1, modes);
+        let mut code = vec![self.mir(idx, ins)];
+        code.extend(operands.into_iter().flatten());
+        (code, next)
+    }
+
+    ///The value of the global `name`, which is the value itself for a constant.
+    fn global(&mut self, name: &str) -> MIRInstruction {
+        if self.properties.contains(name) {
+            return MIRInstruction::Ref(name.to_string());
+        }
+        if self.constant_names.contains(name) {
+            match self.constants.constant(name) {
+                Ok(ConstValue::Integer(i)) => return MIRInstruction::Integer(i),
+                Ok(ConstValue::Float(f)) => return MIRInstruction::Float(f),
+                Ok(ConstValue::Bool(b)) => return MIRInstruction::Bool(b),
+                Ok(ConstValue::String(s)) => return MIRInstruction::String(s),
+                //The type checker reports constants that can't be evaluated
+                Err(_) => return MIRInstruction::Halt,
+            }
+        }
+        MIRInstruction::Global(name.to_string())
+    }
+
+    ///Lower the operand at `idx` as `mode` says, returning it along with the index after it.
+    fn operand(&mut self, idx: usize, mode: Operand) -> (Vec<MIR>, usize) {
+        if mode != Operand::Value {
+            let object = match &self.hir.instructions[idx] {
+                HIRInstruction::LocalRef(name) => Some(self.local(idx, name)),
+                HIRInstruction::GlobalRef(name) if self.properties.contains(name) => Some(name.clone()),
+                _ => None,
+            };
+            if let Some(object) = object {
+                return (vec![self.mir(idx, MIRInstruction::Ref(object))], idx + 1);
+            }
+        }
+        let (value, next) = self.expr(idx);
+        if mode != Operand::Place {
+            return (value, next);
+        }
+        let sig = self.hir.signatures[idx].clone();
+        let name = self.spill(idx, &sig, value);
+        (vec![self.typed(idx, sig, MIRInstruction::Ref(name))], next)
+    }
+
+    ///Lower the operands starting at `idx`, passed as `modes` says, returning them along with the index after the last.
+    ///An operand whose lowering adds to the prelude runs before the statement, so the operands before it that
+    ///can have side effects are moved into the prelude too, keeping the order they are evaluated in.
+    pub(crate) fn operands(&mut self, mut idx: usize, modes: &[Operand]) -> (Vec<Vec<MIR>>, usize) {
+        let mut operands: Vec<Vec<MIR>> = Vec::with_capacity(modes.len());
+        for mode in modes {
+            let start = self.prelude.len();
+            let (operand, next) = self.operand(idx, *mode);
+            if self.prelude.len() > start {
+                let prelude = self.prelude.split_off(start);
+                for earlier in operands.iter_mut().filter(|earlier| !is_pure(earlier)) {
+                    *earlier = self.hoisted(std::mem::take(earlier));
+                }
+                self.prelude.extend(prelude);
+            }
+            operands.push(operand);
+            idx = next;
+        }
+        (operands, idx)
+    }
+
+    ///Initialize a temporary with `value`, the value of the expression at `idx` of type `sig`, in the prelude.
+    fn spill(&mut self, idx: usize, sig: &TypeSignature, value: Vec<MIR>) -> String {
+        let name = self.temp("tmp", sig);
+        let code = self.binding(idx, &name, false, sig, value);
+        self.prelude.extend(code);
+        name
+    }
+
+    ///Compute the operand `code` in the prelude, leaving a `Copy` of its value in its place.
+    fn hoisted(&mut self, mut code: Vec<MIR>) -> Vec<MIR> {
+        let value = match code.first().map(|ir| &ir.ins) {
+            Some(MIRInstruction::NamedArg(_)) => code.split_off(1),
+            _ => std::mem::take(&mut code),
+        };
+        let (pos, sig) = (value[0].pos, value[0].sig.clone());
+        let name = self.temp("tmp", &sig);
+        let binding = self.binding_at(pos, &name, false, &sig, value);
+        self.prelude.extend(binding);
+        code.push(MIR { pos, sig, ins: MIRInstruction::Copy(name) });
+        code
+    }
+
+    ///`code` as a pure expression, computing it into a temporary first if it isn't one.
+    fn pure(&mut self, code: Vec<MIR>) -> Vec<MIR> {
+        if is_pure(&code) {
+            code
+        } else {
+            self.hoisted(code)
+        }
+    }
+
+    ///`value` converted to the type `expected` where the type checker accepted a different one:
+    ///a concrete type passed as a trait object, or an array passed as a list.
+    ///A result or option leaving some of its type untyped, such as `Ok(1)`, takes the rest of it from `expected`.
+    pub(crate) fn coerce(&self, expected: &TypeSignature, mut value: Vec<MIR>) -> Vec<MIR> {
+        let found = &value[0].sig;
+        let ins = match (expected, found) {
+            (TypeSignature::Result(..), TypeSignature::Result(..))
+            | (TypeSignature::Option(_), TypeSignature::Option(_))
+                if expected != found =>
+            {
+                value[0].sig = expected.clone();
+                //The constructor called returns the whole type too
+                if let [MIR { ins: MIRInstruction::Call(_), .. }, MIR { ins: MIRInstruction::Global(_), sig: TypeSignature::Function(f), .. }, ..] =
+                    value.as_mut_slice()
+                {
+                    *f.return_type_signature = expected.clone();
+                }
+                return value;
+            }
+            (TypeSignature::Dyn(_), TypeSignature::Dyn(_)) => return value,
+            (TypeSignature::Dyn(interface), found)
+                if found != &TypeSignature::None && found != &TypeSignature::Untyped =>
+            {
+                MIRInstruction::MakeDyn(interface.clone())
+            }
+            (TypeSignature::List(_), TypeSignature::Array(..)) => MIRInstruction::ArrayToList,
+            _ => return value,
+        };
+        let mut code = vec![MIR { pos: value[0].pos, sig: expected.clone(), ins }];
+        code.extend(value);
+        code
+    }
+
+    ///The arguments `args` of the call at `idx` of a function with the signature `sig`, converted to the types of
+    ///its params. The arguments given for a vararg are gathered into the list the function takes.
+    fn arguments(&self, idx: usize, sig: &TypeSignature, args: Vec<Vec<MIR>>) -> Vec<Vec<MIR>> {
+        let f = match sig {
+            TypeSignature::Function(f) => f,
+            _ => return args,
+        };
+        let mut arguments = Vec::with_capacity(args.len());
+        let mut varargs = Vec::new();
+        let vararg = match f.parameters.len().checked_sub(1) {
+            Some(vararg) if f.variadic => Some(vararg),
+            _ => None,
+        };
+        for (position, mut arg) in args.into_iter().enumerate() {
+            match arg[0].ins.clone() {
+                MIRInstruction::NamedArg(name) => {
+                    let value = arg.split_off(1);
+                    let value = match f.parameters.iter().find(|param| param.name == name) {
+                        Some(param) => {
+                            arg[0].sig = param.sig.clone();
+                            self.coerce(&param.sig, value)
+                        }
+                        None => value,
+                    };
+                    arg.extend(value);
+                    arguments.push(arg);
+                }
+                _ => match f.parameters.get(position.min(vararg.unwrap_or(position))) {
+                    Some(param) if vararg.is_some_and(|vararg| position >= vararg) => {
+                        varargs.push(self.coerce(&param.sig, arg))
+                    }
+                    Some(param) => arguments.push(self.coerce(&param.sig, arg)),
+                    None => arguments.push(arg),
+                },
+            }
+        }
+        if let Some(vararg) = vararg {
+            let element = f.parameters[vararg].sig.clone();
+            let list = TypeSignature::List(Box::new(element));
+            let mut code = vec![self.typed(idx, list, MIRInstruction::ListInit(varargs.len()))];
+            code.extend(varargs.into_iter().flatten());
+            //Named arguments come after every positional one, which the list stands for the last of
+            let named = arguments.iter().position(|arg| matches!(arg[0].ins, MIRInstruction::NamedArg(_)));
+            arguments.insert(named.unwrap_or(arguments.len()), code);
+        }
+        arguments
+    }
+
+    fn call(&mut self, idx: usize, argc: usize) -> (Vec<MIR>, usize) {
+        let (mut operands, next) = self.operands(idx + 1, &vec![Operand::Value; argc + 1]);
+        let callee = operands.remove(0);
+        let args = self.arguments(idx, &self.hir.signatures[idx + 1], operands);
+        let mut code = vec![self.mir(idx, MIRInstruction::Call(args.len()))];
+        code.extend(callee);
+        code.extend(args.into_iter().flatten());
+        (code, next)
+    }
+
+    ///Lower the call of the method `name` with `argc` arguments at `idx`.
+    ///The builtin methods of collections become the instructions doing the same.
+    fn method_call(&mut self, idx: usize, name: &str, argc: usize) -> (Vec<MIR>, usize) {
+        let receiver = self.hir.signatures[idx + 1].clone();
+        let mut modes = vec![Operand::Value; argc + 1];
+        match (&receiver, name) {
+            (TypeSignature::Dyn(interface), _) => {
+                let slot = self.methods.slot(interface, name).unwrap_or(0);
+                let sig = self.methods.interface_method(interface, name).map(|method| method.sig.clone());
+                let (mut operands, next) = self.operands(idx + 1, &modes);
+                let receiver = operands.remove(0);
+                let args = self.arguments(idx, &sig.unwrap_or(TypeSignature::None), operands);
+                let mut code = vec![self.mir(idx, MIRInstruction::DynCall(slot, args.len()))];
+                code.extend(receiver);
+                code.extend(args.into_iter().flatten());
+                (code, next)
+            }
+            (TypeSignature::Array(..), "len") | (TypeSignature::List(_), "len") => {
+                self.prefixed(idx, MIRInstruction::Len, &[Operand::PlaceOrValue])
+            }
+            (TypeSignature::List(element), "push") => {
+                let (mut operands, next) = self.operands(idx + 1, &[Operand::Place, Operand::Value]);
+                let value = operands.pop().unwrap();
+                let mut code = vec![self.mir(idx, MIRInstruction::ListPush)];
+                code.extend(operands.pop().unwrap());
+                code.extend(self.coerce(element, value));
+                (code, next)
+            }
+            _ => {
+                //The function takes the receiver as its first param, before the params of the method
+                let (function, sig, callee_sig, kind) = match (&receiver, self.methods.lookup(&receiver, name)) {
+                    (TypeSignature::Range(_), _) => {
+                        ("Range.next".to_string(), TypeSignature::None, range_next(&receiver), ReceiverKind::MutRef)
+                    }
+                    (_, Some(method)) => {
+                        let callee_sig = with_receiver(&method.sig, &receiver);
+                        (method.function, method.sig, callee_sig, method.receiver)
+                    }
+                    (_, None) => (
+                        format!("{}.{}", receiver, name),
+                        TypeSignature::None,
+                        TypeSignature::None,
+                        ReceiverKind::Value,
+                    ),
+                };
+                if kind != ReceiverKind::Value {
+                    modes[0] = Operand::Place;
+                }
+                let (mut operands, next) = self.operands(idx + 1, &modes);
+                let callee = self.typed(idx, callee_sig, MIRInstruction::Global(function));
+                //The receiver isn't one of the params of the method, which the arguments after it are given to
+                let receiver = operands.remove(0);
+                let args = self.arguments(idx, &sig, operands);
+                let mut code = vec![self.mir(idx, MIRInstruction::Call(args.len() + 1)), callee];
+                code.extend(receiver);
+                code.extend(args.into_iter().flatten());
+                (code, next)
+            }
+        }
+    }
+
+    ///Lower the `?` at `idx`. The result is kept in a temporary, and when it is an `Err` the prelude returns its error
+    ///from the function, dropping the objects declared so far. The value of the `?` is then the one held by the `Ok`.
+    fn try_(&mut self, idx: usize) -> (Vec<MIR>, usize) {
+        let (value, next) = self.expr(idx + 1);
+        let result = self.hir.signatures[idx + 1].clone();
+        let error = match &result {
+            TypeSignature::Result(_, error) => (**error).clone(),
+            _ => TypeSignature::Untyped,
+        };
+        //The value of the temporary is moved out on both paths, so it is never dropped
+        let name = self.unique("try", true);
+        let mut code = self.binding(idx, &name, false, &result, value);
+        code.push(self.typed(idx, TypeSignature::None, MIRInstruction::If(false)));
+        let bool = TypeSignature::Primitive(PrimitiveType::Bool);
+        code.push(self.typed(idx, bool, MIRInstruction::IsErr(name.clone())));
+        let return_type = self.functions.last().unwrap().return_type.clone();
+        let err = TypeSignature::Function(FunctionSignature {
+            type_parameters: Vec::new(),
+            parameters: vec![Parameter::new("value", error.clone())],
+            variadic: false,
+            return_type_signature: Box::new(return_type.clone()),
+            calling_convention: CallingConvention::Beagle,
+        });
+        let returned = vec![
+            self.typed(idx, return_type, MIRInstruction::Call(1)),
+            self.typed(idx, err, MIRInstruction::Global("Err".to_string())),
+            self.typed(idx, error, MIRInstruction::UnwrapErr(name.clone())),
+        ];
+        code.extend(self.returning(idx, returned));
+        code.push(self.typed(idx, TypeSignature::None, MIRInstruction::EndIf));
+        self.prelude.extend(code);
+        (vec![self.mir(idx, MIRInstruction::Unwrap(name))], next)
+    }
+
+    ///Lower the indexing at `idx`, whose bounds are checked in the prelude.
+    fn index(&mut self, idx: usize) -> (Vec<MIR>, usize) {
+        let (mut operands, next) = self.operands(idx + 1, &[Operand::Place, Operand::Value]);
+        let index = operands.pop().unwrap();
+        let index = self.pure(index);
+        let collection = operands.pop().unwrap();
+        self.prelude.push(self.typed(idx, TypeSignature::None, MIRInstruction::BoundsCheck));
+        self.prelude.extend(index.iter().cloned());
+        self.prelude.extend(collection.iter().cloned());
+        let mut code = vec![self.mir(idx, MIRInstruction::Index)];
+        code.extend(collection);
+        code.extend(index);
+        (code, next)
+    }
+
+    ///Lower the slice at `idx`, whose bounds default to the start and the end of the collection.
+    fn slice(&mut self, idx: usize, start: bool, end: bool) -> (Vec<MIR>, usize) {
+        let mut modes = vec![Operand::Place];
+        modes.extend((0..start as usize + end as usize).map(|_| Operand::Value));
+        let (operands, next) = self.operands(idx + 1, &modes);
+        let mut operands = operands.into_iter();
+        let collection = operands.next().unwrap();
+        let int = TypeSignature::Primitive(PrimitiveType::Integer);
+        let start = match start {
+            true => operands.next().unwrap(),
+            false => vec![self.typed(idx, int.clone(), MIRInstruction::Integer(0))],
+        };
+        let end = match end {
+            true => operands.next().unwrap(),
+            false => {
+                let mut len = vec![self.typed(idx, int, MIRInstruction::Len)];
+                len.extend(collection.iter().cloned());
+                len
+            }
+        };
+        let mut code = vec![self.mir(idx, MIRInstruction::Slice)];
+        code.extend(collection);
+        code.extend(start);
+        code.extend(end);
+        (code, next)
+    }
+
+    ///Lower the binary operation at `idx`.
+    ///The right operand of `&&` and `||` is only evaluated when the left one doesn't decide the result,
+    ///so a prelude it needs runs in an `If` on the left operand, which is kept in a temporary along with the result.
+    fn binary(&mut self, idx: usize, op: BinaryOp) -> (Vec<MIR>, usize) {
+        if op != BinaryOp::And && op != BinaryOp::Or {
+            return self.prefixed(idx, MIRInstruction::Binary(op), &[Operand::Value; 2]);
+        }
+        let (left, right_start) = self.expr(idx + 1);
+        let start = self.prelude.len();
+        let (right, next) = self.expr(right_start);
+        if self.prelude.len() == start {
+            let mut code = vec![self.mir(idx, MIRInstruction::Binary(op))];
+            code.extend(left);
+            code.extend(right);
+            return (code, next);
+        }
+        let right_prelude = self.prelude.split_off(start);
+        let bool = TypeSignature::Primitive(PrimitiveType::Bool);
+        let result = self.temp("cond", &bool);
+        let mut code = self.binding(idx, &result, true, &bool, left);
+        code.push(self.typed(idx, TypeSignature::None, MIRInstruction::If(false)));
+        if op == BinaryOp::Or {
+            code.push(self.typed(idx, bool.clone(), MIRInstruction::Unary(UnaryOp::Not)));
+        }
+        code.push(self.typed(idx, bool.clone(), MIRInstruction::Copy(result.clone())));
+        code.extend(right_prelude);
+        code.push(self.typed(idx, bool.clone(), MIRInstruction::ObjMut(result.clone())));
+        code.extend(right);
+        code.push(self.typed(idx, TypeSignature::None, MIRInstruction::EndIf));
+        self.prelude.extend(code);
+        (vec![self.typed(idx, bool, MIRInstruction::Copy(result))], next)
+    }
+}
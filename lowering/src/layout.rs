@@ -0,0 +1,87 @@
+use ir::{
+    hir::{HIRInstruction, Module},
+    interface::ModuleInterface,
+    type_signature::{PrimitiveType, TypeSignature},
+};
+use std::collections::HashMap;
+
+///The size of a pointer, which is what references and functions take.
+const POINTER: usize = 8;
+
+///The fields of the structs a module can use, to know how much space their values take.
+pub(crate) struct Layouts {
+    ///The type params and the types of the fields of each struct, in declaration order
+    structs: HashMap<String, (Vec<String>, Vec<TypeSignature>)>,
+}
+
+impl Layouts {
+    ///The layouts of the structs and newtypes declared by `module` and exported by the modules it imports.
+    pub(crate) fn of(module: &Module, imports: &HashMap<String, ModuleInterface>) -> Self {
+        let mut structs = HashMap::new();
+        for layout in imports.values().flat_map(|interface| interface.structs.iter()) {
+            let params = layout.params.iter().map(|param| param.name.clone()).collect();
+            let fields = layout.fields.iter().map(|field| field.sig.clone()).collect();
+            structs.insert(layout.name.clone(), (params, fields));
+        }
+        let instructions = &module.instructions;
+        for (idx, ins) in instructions.iter().enumerate() {
+            match ins {
+                HIRInstruction::Struct(name) => {
+                    let mut params = Vec::new();
+                    let mut fields = Vec::new();
+                    for (ins, sig) in instructions[idx + 1..].iter().zip(module.signatures[idx + 1..].iter()) {
+                        match ins {
+                            HIRInstruction::TypeParam(param) => params.push(param.clone()),
+                            HIRInstruction::StructField(_) => fields.push(sig.clone()),
+                            HIRInstruction::Visibility(_) | HIRInstruction::Annotation(..) => {}
+                            _ => break,
+                        }
+                    }
+                    structs.insert(name.clone(), (params, fields));
+                }
+                HIRInstruction::Newtype(name) => {
+                    structs.insert(name.clone(), (Vec::new(), vec![module.signatures[idx].clone()]));
+                }
+                _ => {}
+            }
+        }
+        Layouts { structs }
+    }
+
+    ///The number of bytes a value of type `sig` takes, with its fields laid out one after the other without padding.
+    ///Strings and lists own a heap buffer, so only the pointer to it and its length, and for a list its capacity, are counted.
+    pub(crate) fn size_of(&self, sig: &TypeSignature) -> usize {
+        self.size_in(sig, &mut Vec::new())
+    }
+
+    ///The size of `sig` found inside the structs in `outer`.
+    ///A struct containing itself would take infinite space, so such a field counts as a pointer.
+    ///Generic functions and structs are monomorphized before they are lowered, so no type param is left to size.
+    fn size_in(&self, sig: &TypeSignature, outer: &mut Vec<String>) -> usize {
+        match sig {
+            TypeSignature::Primitive(PrimitiveType::Integer) | TypeSignature::Primitive(PrimitiveType::Float) => 4,
+            TypeSignature::Primitive(PrimitiveType::Bool) => 1,
+            TypeSignature::Primitive(PrimitiveType::String) => 2 * POINTER,
+            TypeSignature::Primitive(_) | TypeSignature::None | TypeSignature::Untyped => 0,
+            TypeSignature::Function(_) => POINTER,
+            TypeSignature::Param(param) => panic!("The type param {} is left after monomorphization", param.name),
+            TypeSignature::Dyn(_) => 2 * POINTER,
+            TypeSignature::Array(element, len) => len * self.size_in(element, outer),
+            TypeSignature::List(_) => 3 * POINTER,
+            TypeSignature::Option(element) => 1 + self.size_in(element, outer),
+            TypeSignature::Range(element) => 2 * self.size_in(element, outer),
+            TypeSignature::Result(value, error) => 1 + self.size_in(value, outer).max(self.size_in(error, outer)),
+            TypeSignature::Struct(s) => {
+                let (params, fields) = match self.structs.get(&s.name) {
+                    Some(layout) if !outer.contains(&s.name) => layout,
+                    _ => return POINTER,
+                };
+                let substitutions = params.iter().cloned().zip(s.type_arguments.iter().cloned()).collect();
+                outer.push(s.name.clone());
+                let size = fields.iter().map(|field| self.size_in(&field.substitute(&substitutions), outer)).sum();
+                outer.pop();
+                size
+            }
+        }
+    }
+}
@@ -0,0 +1,728 @@
+use core::pos::BiPos;
+use ir::{
+    consteval::ConstEvaluator,
+    hir::{self, expression_end, BinaryOp, HIRInstruction, UnaryOp},
+    interface::ModuleInterface,
+    mir::{self, MIRInstruction, MIR},
+    mono,
+    pass::Lowering,
+    symbols::SymbolId,
+    type_signature::{PrimitiveType, TypeSignature},
+};
+use std::collections::{HashMap, HashSet};
+
+mod expressions;
+mod layout;
+mod methods;
+mod ownership;
+use expressions::Operand;
+use layout::Layouts;
+use methods::{range_next, receiver_of, type_key, with_receiver, Methods};
+pub use ownership::Ownership;
+
+///Lowers typechecked HIR into MIR, once its generic functions and structs are monomorphized.
+///Locals and properties become an allocation of their size followed by an `ObjInit` of their value, and every param,
+///local and temporary is dropped at the end of the scope declaring it, or before a `Break`, `Continue` or `Return`
+///leaving that scope. A return, including the one of a `?` on an `Err`, only drops the objects declared before it.
+///Loops become `Loop`s left through `Break`, and method calls become calls of the function implementing the method,
+///or `DynCall`s through the vtable of a trait object.
+pub struct Lowerer {
+    ///The interfaces of the modules the module may import, by name
+    imports: HashMap<String, ModuleInterface>,
+}
+
+impl Lowerer {
+    pub fn new(imports: HashMap<String, ModuleInterface>) -> Self {
+        Lowerer { imports }
+    }
+}
+
+impl Lowering for Lowerer {
+    fn lower(&mut self, module: &hir::Module) -> Result<mir::Module, ()> {
+        let module = mono::monomorphize(module);
+        Ok(ModuleLowering::new(&module, &self.imports).lower())
+    }
+}
+
+///A lexical scope of a function being lowered, such as a block or the body of a loop.
+#[derive(Default)]
+struct Scope {
+    ///The objects declared in the scope in declaration order, which are dropped when it is left
+    owned: Vec<(String, TypeSignature)>,
+    ///Whether the scope is the body of a loop, which is left at the end of every iteration and by `Break` and `Continue`
+    is_loop: bool,
+}
+
+///The params, locals and temporaries of a function being lowered.
+struct Function {
+    ///The MIR name of each param and local declared so far, by its symbol
+    names: HashMap<SymbolId, String>,
+    ///Every name in use in the function, which its params, locals and temporaries can't be given again
+    used: HashSet<String>,
+    ///The scopes the statement being lowered is in, innermost last. The first one is the body of the function.
+    scopes: Vec<Scope>,
+    return_type: TypeSignature,
+    ///Whether the body of the function has returned, so that nothing is left to drop at its end
+    returned: bool,
+}
+
+impl Function {
+    ///A function returning `return_type`, in a module with the top level properties `properties`.
+    fn new(properties: &HashSet<String>, return_type: TypeSignature) -> Self {
+        Function {
+            names: HashMap::new(),
+            used: properties.clone(),
+            scopes: vec![Scope::default()],
+            return_type,
+            returned: false,
+        }
+    }
+}
+
+///The lowering of a single module.
+struct ModuleLowering<'a> {
+    hir: &'a hir::Module,
+    layouts: Layouts,
+    methods: Methods,
+    constants: ConstEvaluator<'a>,
+    ///The constants the module declares or imports, whose uses are replaced by their value
+    constant_names: HashSet<String>,
+    ///The top level properties of the module, which are referred to through a `Ref`
+    properties: HashSet<String>,
+    ///The functions being lowered, innermost last. The first one stands for the top level of the module,
+    ///whose objects live as long as the program and are never dropped.
+    functions: Vec<Function>,
+    ///The instructions the statement being lowered needs to run before it, such as the bounds checks of its indexing
+    prelude: Vec<MIR>,
+    ///The type whose impl block is being lowered, along with the interface the block implements
+    impl_block: Option<(String, Option<String>)>,
+    module: mir::Module,
+}
+
+impl<'a> ModuleLowering<'a> {
+    fn new(hir: &'a hir::Module, imports: &HashMap<String, ModuleInterface>) -> Self {
+        let mut constants = ConstEvaluator::new(&hir.instructions);
+        let mut constant_names = HashSet::new();
+        for constant in imports.values().flat_map(|interface| interface.constants.iter()) {
+            constants.import(&constant.name, constant.value.clone());
+            constant_names.insert(constant.name.clone());
+        }
+        let mut properties = HashSet::new();
+        let mut depth = 0;
+        for ins in hir.instructions.iter() {
+            match ins {
+                HIRInstruction::Const(name) if depth == 0 => {
+                    constant_names.insert(name.clone());
+                }
+                HIRInstruction::Property(name, _) if depth == 0 => {
+                    properties.insert(name.clone());
+                }
+                HIRInstruction::Fn(_) => depth += 1,
+                HIRInstruction::EndFn => depth -= 1,
+                _ => {}
+            }
+        }
+        ModuleLowering {
+            hir,
+            layouts: Layouts::of(hir, imports),
+            methods: Methods::of(hir),
+            constants,
+            constant_names,
+            functions: vec![Function::new(&properties, TypeSignature::None)],
+            properties,
+            prelude: Vec::new(),
+            impl_block: None,
+            module: mir::Module::new(hir.name.clone()),
+        }
+    }
+
+    fn lower(mut self) -> mir::Module {
+        let mut idx = 0;
+        while idx < self.hir.instructions.len() {
+            idx = self.statement(idx);
+        }
+        self.module
+    }
+
+    ///The instruction `ins` standing for the HIR instruction at `idx`, with its position and signature.
+    fn mir(&self, idx: usize, ins: MIRInstruction) -> MIR {
+        self.typed(idx, self.hir.signatures[idx].clone(), ins)
+    }
+
+    ///The instruction `ins` with the signature `sig`, added by the lowering of the HIR instruction at `idx`.
+    fn typed(&self, idx: usize, sig: TypeSignature, ins: MIRInstruction) -> MIR {
+        MIR { pos: self.hir.positions[idx], sig, ins }
+    }
+
+    ///Add `code` to the module, after the prelude of the statement it belongs to.
+    fn emit(&mut self, code: Vec<MIR>) {
+        for ir in std::mem::take(&mut self.prelude).into_iter().chain(code) {
+            self.module.push_ir(ir);
+        }
+    }
+
+    fn function(&mut self) -> &mut Function {
+        self.functions.last_mut().unwrap()
+    }
+
+    ///A name for an object of the current function that isn't in use yet, which is `name` itself if it is free.
+    ///Otherwise it is `name` followed by a number, like temporaries. No identifier has a dot, so these never clash with one.
+    fn unique(&mut self, name: &str, numbered: bool) -> String {
+        let function = self.function();
+        let mut unique = name.to_string();
+        let mut n = 0;
+        while numbered || function.used.contains(&unique) {
+            unique = format!("{}.{}", name, n);
+            n += 1;
+            if !function.used.contains(&unique) {
+                break;
+            }
+        }
+        function.used.insert(unique.clone());
+        unique
+    }
+
+    ///Declare the param or local `name` of type `sig`, declared at `idx`, giving it a name unique in its function.
+    fn declare(&mut self, idx: usize, name: &str, sig: &TypeSignature) -> String {
+        let unique = self.unique(name, false);
+        let symbol = self.hir.symbols.get(idx).copied().flatten();
+        let function = self.function();
+        if let Some(symbol) = symbol {
+            function.names.insert(symbol, unique.clone());
+        }
+        function.scopes.last_mut().unwrap().owned.push((unique.clone(), sig.clone()));
+        unique
+    }
+
+    ///Declare a temporary of type `sig` holding an intermediate value, such as the index of a loop over an array.
+    fn temp(&mut self, prefix: &str, sig: &TypeSignature) -> String {
+        let name = self.unique(prefix, true);
+        self.function().scopes.last_mut().unwrap().owned.push((name.clone(), sig.clone()));
+        name
+    }
+
+    fn open_scope(&mut self, is_loop: bool) {
+        self.function().scopes.push(Scope { owned: Vec::new(), is_loop });
+    }
+
+    ///Leave the innermost scope at the HIR instruction `idx` closing it, returning the drops of its objects.
+    fn close_scope(&mut self, idx: usize) -> Vec<MIR> {
+        let depth = self.functions.last().unwrap().scopes.len() - 1;
+        let drops = self.drops_from(idx, depth);
+        self.function().scopes.pop();
+        drops
+    }
+
+    ///The drops of the objects of the scopes from the one at `depth` to the innermost, for the HIR instruction at `idx`
+    ///leaving them. Objects are dropped in the reverse order of their declaration.
+    fn drops_from(&self, idx: usize, depth: usize) -> Vec<MIR> {
+        self.functions.last().unwrap().scopes[depth..]
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.owned.iter().rev())
+            .map(|(name, sig)| self.typed(idx, sig.clone(), MIRInstruction::Drop(name.clone())))
+            .collect()
+    }
+
+    ///The drops of the objects of the scopes that the `Break` or `Continue` at `idx` leaves, up to the body of its loop.
+    fn loop_exit_drops(&self, idx: usize) -> Vec<MIR> {
+        match self.functions.last().unwrap().scopes.iter().rposition(|scope| scope.is_loop) {
+            Some(depth) => self.drops_from(idx, depth),
+            None => Vec::new(),
+        }
+    }
+
+    ///The MIR name of the local `name` referred to at `idx`.
+    fn local(&self, idx: usize, name: &str) -> String {
+        self.hir
+            .symbols
+            .get(idx)
+            .copied()
+            .flatten()
+            .and_then(|symbol| self.functions.last().unwrap().names.get(&symbol))
+            .cloned()
+            .unwrap_or_else(|| name.to_string())
+    }
+
+    ///The allocation and initialization of the object `name` of type `sig` to `value`, for the HIR instruction at `idx`.
+    ///Objects at the top level of the module live on the heap, and those of functions on the stack.
+    fn binding(&self, idx: usize, name: &str, mutable: bool, sig: &TypeSignature, value: Vec<MIR>) -> Vec<MIR> {
+        self.binding_at(self.hir.positions[idx], name, mutable, sig, value)
+    }
+
+    ///The allocation and initialization of the object `name` at `pos`, like `binding`.
+    fn binding_at(&self, pos: BiPos, name: &str, mutable: bool, sig: &TypeSignature, value: Vec<MIR>) -> Vec<MIR> {
+        let size = self.layouts.size_of(sig);
+        let alloc =
+            if self.functions.len() == 1 { MIRInstruction::HeapAlloc(size) } else { MIRInstruction::StackAlloc(size) };
+        let mut code = vec![
+            MIR { pos, sig: TypeSignature::None, ins: alloc },
+            MIR { pos, sig: sig.clone(), ins: MIRInstruction::ObjInit(name.to_string(), mutable) },
+        ];
+        code.extend(value);
+        code
+    }
+
+    ///Lower the statement or declaration at `idx`, returning the index after it.
+    fn statement(&mut self, idx: usize) -> usize {
+        let hir = self.hir;
+        let ins = match &hir.instructions[idx] {
+            HIRInstruction::Module(name) => MIRInstruction::Module(name.clone()),
+            HIRInstruction::EndModule => MIRInstruction::EndModule,
+            HIRInstruction::Fn(name) => {
+                let name = self.methods.function_name(idx).unwrap_or(name).to_string();
+                let return_type = match &hir.signatures[idx] {
+                    TypeSignature::Function(f) => (*f.return_type_signature).clone(),
+                    _ => TypeSignature::Primitive(PrimitiveType::Unit),
+                };
+                self.functions.push(Function::new(&self.properties, return_type));
+                //A method takes its receiver before its params, as its calls pass it
+                let sig = match receiver_of(hir, idx) {
+                    Some((_, receiver)) => with_receiver(&hir.signatures[idx], receiver),
+                    None => hir.signatures[idx].clone(),
+                };
+                self.emit(vec![self.typed(idx, sig, MIRInstruction::Fun(name))]);
+                return idx + 1;
+            }
+            HIRInstruction::EndFn => {
+                let function = self.functions.last().unwrap();
+                let drops = if function.returned { Vec::new() } else { self.drops_from(idx, 0) };
+                self.functions.pop();
+                self.emit(drops);
+                MIRInstruction::EndFun
+            }
+            HIRInstruction::NativeFn(name) => MIRInstruction::NativeFun(name.clone()),
+            HIRInstruction::FnParam(name) => MIRInstruction::FunParam(self.declare(idx, name, &hir.signatures[idx])),
+            HIRInstruction::SelfParam(_) => MIRInstruction::FunParam(self.declare(idx, "self", &hir.signatures[idx])),
+            HIRInstruction::DefaultArg => {
+                let (value, next) = self.expr(idx + 1);
+                let value = self.coerce(&hir.signatures[idx - 1], value);
+                let mut code = vec![self.mir(idx, MIRInstruction::DefaultArg)];
+                code.extend(value);
+                self.emit(code);
+                return next;
+            }
+            HIRInstruction::LocalVar(name, mutable) | HIRInstruction::Property(name, mutable) => {
+                let (value, next) = self.expr(idx + 1);
+                let sig = &hir.signatures[idx];
+                let value = self.coerce(sig, value);
+                //Top level properties keep their name, which every function can refer to them by
+                let name = if self.functions.len() == 1 { name.clone() } else { self.declare(idx, name, sig) };
+                let code = self.binding(idx, &name, *mutable, sig, value);
+                self.emit(code);
+                return next;
+            }
+            HIRInstruction::Const(_) => return expression_end(&hir.instructions, idx + 1),
+            HIRInstruction::Impl(interface) => {
+                self.impl_block = type_key(&hir.signatures[idx]).map(|key| (key, interface.clone()));
+                return idx + 1;
+            }
+            HIRInstruction::EndImpl => {
+                if let Some((key, Some(interface))) = self.impl_block.take() {
+                    let mut code = vec![self.mir(idx, MIRInstruction::VTable(interface.clone(), key.clone()))];
+                    for function in self.methods.vtable(&interface, &key) {
+                        code.push(self.typed(idx, TypeSignature::None, MIRInstruction::VTableEntry(function)));
+                    }
+                    code.push(self.typed(idx, TypeSignature::None, MIRInstruction::EndVTable));
+                    self.emit(code);
+                }
+                return idx + 1;
+            }
+            HIRInstruction::For(name) => return self.for_loop(idx, name),
+            //The objects of the body of a loop are dropped at the end of every iteration
+            HIRInstruction::EndFor | HIRInstruction::EndWhile | HIRInstruction::EndLoop => {
+                let drops = self.close_scope(idx);
+                self.emit(drops);
+                MIRInstruction::EndLoop
+            }
+            HIRInstruction::If(has_else) => {
+                let (condition, next) = self.expr(idx + 1);
+                let mut code = vec![self.mir(idx, MIRInstruction::If(*has_else))];
+                code.extend(condition);
+                self.emit(code);
+                self.open_scope(false);
+                return next;
+            }
+            HIRInstruction::Else => {
+                let drops = self.close_scope(idx);
+                self.emit(drops);
+                self.open_scope(false);
+                MIRInstruction::Else
+            }
+            HIRInstruction::EndIf => {
+                let drops = self.close_scope(idx);
+                self.emit(drops);
+                MIRInstruction::EndIf
+            }
+            //The condition is checked at the start of every iteration, which is where its prelude runs too
+            HIRInstruction::While => {
+                self.emit(vec![self.mir(idx, MIRInstruction::Loop)]);
+                self.open_scope(true);
+                let (condition, next) = self.expr(idx + 1);
+                let bool = TypeSignature::Primitive(PrimitiveType::Bool);
+                let mut code = vec![
+                    self.typed(idx, TypeSignature::None, MIRInstruction::If(false)),
+                    self.typed(idx, bool, MIRInstruction::Unary(UnaryOp::Not)),
+                ];
+                code.extend(condition);
+                code.extend(self.loop_exit_drops(idx));
+                code.push(self.typed(idx, TypeSignature::None, MIRInstruction::Break));
+                code.push(self.typed(idx, TypeSignature::None, MIRInstruction::EndIf));
+                self.emit(code);
+                return next;
+            }
+            HIRInstruction::Loop => {
+                self.open_scope(true);
+                MIRInstruction::Loop
+            }
+            HIRInstruction::Break | HIRInstruction::Continue => {
+                let drops = self.loop_exit_drops(idx);
+                self.emit(drops);
+                if matches!(hir.instructions[idx], HIRInstruction::Break) {
+                    MIRInstruction::Break
+                } else {
+                    MIRInstruction::Continue
+                }
+            }
+            HIRInstruction::Return(false) => {
+                let drops = self.drops_from(idx, 0);
+                self.emit(drops);
+                self.end_body();
+                MIRInstruction::Return(false)
+            }
+            HIRInstruction::Return(true) => {
+                let (value, next) = self.expr(idx + 1);
+                self.emit_return(idx, value);
+                return next;
+            }
+            HIRInstruction::Assign => return self.assign(idx),
+            HIRInstruction::Halt => MIRInstruction::Halt,
+            //Types and interfaces only exist in signatures
+            HIRInstruction::Visibility(_)
+            | HIRInstruction::Annotation(..)
+            | HIRInstruction::TypeParam(_)
+            | HIRInstruction::FnType(_)
+            | HIRInstruction::Struct(_)
+            | HIRInstruction::StructField(_)
+            | HIRInstruction::EndStruct
+            | HIRInstruction::TypeAlias(_)
+            | HIRInstruction::Newtype(_)
+            | HIRInstruction::Interface(_)
+            | HIRInstruction::InterfaceMethod(..)
+            | HIRInstruction::EndInterface
+            | HIRInstruction::Import(_) => return idx + 1,
+            HIRInstruction::Block => {
+                self.open_scope(false);
+                return idx + 1;
+            }
+            HIRInstruction::EndBlock => {
+                let drops = self.close_scope(idx);
+                self.emit(drops);
+                return idx + 1;
+            }
+            _ => return self.expression_statement(idx),
+        };
+        self.emit(vec![self.mir(idx, ins)]);
+        idx + 1
+    }
+
+    ///Return `value`, the value of the expression at `idx + 1`, from the current function.
+    fn emit_return(&mut self, idx: usize, value: Vec<MIR>) {
+        let code = self.returning(idx, value);
+        self.emit(code);
+        self.end_body();
+    }
+
+    ///The code returning `value` from the current function for the HIR instruction at `idx`.
+    ///Every object declared so far is dropped before the return, once the value that may use them is computed.
+    pub(crate) fn returning(&mut self, idx: usize, value: Vec<MIR>) -> Vec<MIR> {
+        let return_type = self.function().return_type.clone();
+        let mut value = self.coerce(&return_type, value);
+        let drops = self.drops_from(idx, 0);
+        let mut code = Vec::new();
+        if !drops.is_empty() {
+            //The returned value is moved out of its temporary, which is never dropped
+            let name = self.unique("ret", true);
+            code = self.binding(idx, &name, false, &return_type, value);
+            code.extend(drops);
+            value = vec![self.typed(idx, return_type.clone(), MIRInstruction::Copy(name))];
+        }
+        code.push(self.typed(idx, return_type, MIRInstruction::Return(true)));
+        code.extend(value);
+        code
+    }
+
+    ///Mark the body of the current function as having returned if the return just lowered is directly in it,
+    ///so that its objects aren't dropped again at its end.
+    fn end_body(&mut self) {
+        let function = self.function();
+        if function.scopes.len() == 1 {
+            function.returned = true;
+        }
+    }
+
+    ///Lower the assignment at `idx`, returning the index after it.
+    ///The new value is computed before the old one is dropped, since it may be computed from it.
+    fn assign(&mut self, idx: usize) -> usize {
+        let hir = self.hir;
+        let target = idx + 1;
+        let sig = hir.signatures[target].clone();
+        let (value, next) = self.expr(expression_end(&hir.instructions, target));
+        let value = self.coerce(&sig, value);
+        let code = match &hir.instructions[target] {
+            HIRInstruction::Field(name) => {
+                let mut code = vec![self.mir(idx, MIRInstruction::FieldMut(name.clone()))];
+                code.extend(self.assigned(target + 1));
+                code.extend(value);
+                code
+            }
+            HIRInstruction::LocalRef(name) | HIRInstruction::GlobalRef(name) => {
+                //Top level properties keep their name
+                let object = match &hir.instructions[target] {
+                    HIRInstruction::LocalRef(_) => self.local(target, name),
+                    _ => name.clone(),
+                };
+                //The new value is moved out of its temporary, which is never dropped
+                let name = self.unique("tmp", true);
+                let mut code = self.binding(idx, &name, false, &sig, value);
+                code.push(self.typed(idx, sig.clone(), MIRInstruction::Drop(object.clone())));
+                code.push(self.typed(idx, sig.clone(), MIRInstruction::ObjMut(object)));
+                code.push(self.typed(idx, sig, MIRInstruction::Copy(name)));
+                code
+            }
+            //The type checker only accepts locals, properties and their fields as targets
+            _ => vec![self.mir(idx, MIRInstruction::Halt)],
+        };
+        self.emit(code);
+        next
+    }
+
+    ///The object assigned to by the target at `idx`: a `Ref` of a local or property, or a `Field` of such an object.
+    fn assigned(&self, idx: usize) -> Vec<MIR> {
+        match &self.hir.instructions[idx] {
+            HIRInstruction::Field(name) => {
+                let mut code = vec![self.mir(idx, MIRInstruction::Field(name.clone()))];
+                code.extend(self.assigned(idx + 1));
+                code
+            }
+            HIRInstruction::LocalRef(name) => vec![self.mir(idx, MIRInstruction::Ref(self.local(idx, name)))],
+            HIRInstruction::GlobalRef(name) => vec![self.mir(idx, MIRInstruction::Ref(name.clone()))],
+            //The type checker only accepts locals, properties and their fields as targets
+            _ => vec![self.mir(idx, MIRInstruction::Halt)],
+        }
+    }
+
+    ///Lower the expression at `idx`, whose value is unused unless it is the last statement of a function returning
+    ///something, which returns its value.
+    fn expression_statement(&mut self, idx: usize) -> usize {
+        let (value, next) = self.expr(idx);
+        let returned =
+            !matches!(self.function().return_type, TypeSignature::Primitive(PrimitiveType::Unit) | TypeSignature::None);
+        if returned && self.hir.instructions.get(next) == Some(&HIRInstruction::EndFn) {
+            self.emit_return(idx, value);
+        } else {
+            self.emit(value);
+        }
+        next
+    }
+
+    ///Lower the for loop at `idx` binding each value to the local `name`, up to the start of its body.
+    ///The loop variable and the value it comes from belong to the body, and the rest of the loop to its enclosing scope.
+    ///Ranges written in the loop are iterated by advancing a counter from their start, and arrays and lists through
+    ///a hidden index. Anything else is an iterator whose `next` method is called until it returns `None`.
+    fn for_loop(&mut self, idx: usize, name: &str) -> usize {
+        let hir = self.hir;
+        let iterable = idx + 1;
+        let element = hir.signatures[idx].clone();
+        let int = TypeSignature::Primitive(PrimitiveType::Integer);
+        let bool = TypeSignature::Primitive(PrimitiveType::Bool);
+        let copy = |lowering: &Self, name: &str, sig: &TypeSignature| {
+            lowering.typed(idx, sig.clone(), MIRInstruction::Copy(name.to_string()))
+        };
+        //Leave the loop unless the condition in `code`, which follows the `Not`, holds
+        let break_unless = |lowering: &Self, condition: Vec<MIR>| {
+            let mut code = vec![
+                lowering.typed(idx, TypeSignature::None, MIRInstruction::If(false)),
+                lowering.typed(idx, bool.clone(), MIRInstruction::Unary(UnaryOp::Not)),
+            ];
+            code.extend(condition);
+            code.push(lowering.typed(idx, TypeSignature::None, MIRInstruction::Break));
+            code.push(lowering.typed(idx, TypeSignature::None, MIRInstruction::EndIf));
+            code
+        };
+        let increment = |lowering: &Self, counter: &str| {
+            vec![
+                lowering.typed(idx, int.clone(), MIRInstruction::ObjMut(counter.to_string())),
+                lowering.typed(idx, int.clone(), MIRInstruction::Binary(BinaryOp::Add)),
+                copy(lowering, counter, &int),
+                lowering.typed(idx, int.clone(), MIRInstruction::Integer(1)),
+            ]
+        };
+        let loop_start = self.typed(idx, TypeSignature::None, MIRInstruction::Loop);
+        match (&hir.instructions[iterable], &hir.signatures[iterable]) {
+            (HIRInstruction::Range(inclusive), _) => {
+                let (mut bounds, next) = self.operands(iterable + 1, &[Operand::Value; 2]);
+                let end = bounds.pop().unwrap();
+                let start = bounds.pop().unwrap();
+                let counter = self.temp("index", &int);
+                let mut code = self.binding(idx, &counter, true, &int, start);
+                let end_name = self.temp("end", &int);
+                code.extend(self.binding(idx, &end_name, false, &int, end));
+                code.push(loop_start);
+                self.open_scope(true);
+                let op = if *inclusive { BinaryOp::Le } else { BinaryOp::Lt };
+                let condition = vec![
+                    self.typed(idx, bool.clone(), MIRInstruction::Binary(op)),
+                    copy(self, &counter, &int),
+                    copy(self, &end_name, &int),
+                ];
+                code.extend(break_unless(self, condition));
+                let local = self.declare(idx, name, &element);
+                code.extend(self.binding(idx, &local, false, &element, vec![copy(self, &counter, &int)]));
+                code.extend(increment(self, &counter));
+                self.emit(code);
+                next
+            }
+            (_, TypeSignature::Array(..)) | (_, TypeSignature::List(_)) => {
+                let (collection, next) = self.place(iterable);
+                let index = self.temp("index", &int);
+                let zero = self.typed(idx, int.clone(), MIRInstruction::Integer(0));
+                let mut code = self.binding(idx, &index, true, &int, vec![zero]);
+                code.push(loop_start);
+                self.open_scope(true);
+                let mut condition = vec![
+                    self.typed(idx, bool.clone(), MIRInstruction::Binary(BinaryOp::Lt)),
+                    copy(self, &index, &int),
+                    self.typed(idx, int.clone(), MIRInstruction::Len),
+                ];
+                condition.extend(collection.iter().cloned());
+                code.extend(break_unless(self, condition));
+                code.push(self.typed(idx, TypeSignature::None, MIRInstruction::BoundsCheck));
+                code.push(copy(self, &index, &int));
+                code.extend(collection.iter().cloned());
+                let mut value = vec![self.typed(idx, element.clone(), MIRInstruction::Index)];
+                value.extend(collection);
+                value.push(copy(self, &index, &int));
+                let local = self.declare(idx, name, &element);
+                code.extend(self.binding(idx, &local, false, &element, value));
+                code.extend(increment(self, &index));
+                self.emit(code);
+                next
+            }
+            (_, iterable_sig) => {
+                let (value, next) = self.expr(iterable);
+                let iterator = self.temp("iter", iterable_sig);
+                let mut code = self.binding(idx, &iterator, true, iterable_sig, value);
+                code.push(loop_start);
+                self.open_scope(true);
+                let (function, sig) = match (iterable_sig, self.methods.lookup(iterable_sig, "next")) {
+                    (TypeSignature::Range(_), _) => ("Range.next".to_string(), range_next(iterable_sig)),
+                    (_, Some(method)) => (method.function, with_receiver(&method.sig, iterable_sig)),
+                    (_, None) => (format!("{}.next", iterable_sig), TypeSignature::None),
+                };
+                let option = TypeSignature::Option(Box::new(element.clone()));
+                let call = vec![
+                    self.typed(idx, option.clone(), MIRInstruction::Call(1)),
+                    self.typed(idx, sig, MIRInstruction::Global(function)),
+                    self.typed(idx, iterable_sig.clone(), MIRInstruction::Ref(iterator)),
+                ];
+                let next_value = self.temp("next", &option);
+                code.extend(self.binding(idx, &next_value, false, &option, call));
+                code.push(self.typed(idx, TypeSignature::None, MIRInstruction::BreakIfNone(next_value.clone())));
+                let unwrap = self.typed(idx, element.clone(), MIRInstruction::Unwrap(next_value));
+                let local = self.declare(idx, name, &element);
+                code.extend(self.binding(idx, &local, false, &element, vec![unwrap]));
+                self.emit(code);
+                next
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ir::text;
+
+    ///Lower the HIR `text`, keeping only the instructions of the MIR that drop objects or leave a construct.
+    fn exits(text: &str) -> Vec<String> {
+        let module = Lowerer::new(HashMap::new()).lower(&text::parse_hir(text).unwrap()).unwrap();
+        module
+            .instructions
+            .iter()
+            .filter_map(|ins| match ins {
+                MIRInstruction::Drop(name) => Some(format!("Drop {}", name)),
+                MIRInstruction::Return(_) => Some("Return".to_string()),
+                MIRInstruction::Break => Some("Break".to_string()),
+                MIRInstruction::EndIf => Some("EndIf".to_string()),
+                MIRInstruction::EndLoop => Some("EndLoop".to_string()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn drops_what_is_declared_before_leaving_a_scope() {
+        let exits = exits(
+            r#"hir test
+Module test #5
+    Fn f #4 : fun(a: String, c: Bool) -> Int @ 1:2 1:4
+        FnParam a #6 : String @ 1:8 1:8
+        FnParam c #7 : Bool @ 1:19 1:19
+        LocalVar b #8 : String @ 2:10 2:10
+        String "b" : String @ 2:15 2:16
+        If @ 3:6 3:7
+            LocalRef c #7 : Bool @ 3:9 3:9
+            Return value : Int @ 3:13 3:18
+            Integer 1 : Int @ 3:20 3:20
+        EndIf @ 3:6 3:7
+        LocalVar d #9 : String @ 4:10 4:10
+        String "d" : String @ 4:15 4:16
+        While @ 5:6 5:10
+            LocalRef c #7 : Bool @ 5:12 5:12
+            LocalVar e #10 : String @ 6:14 6:14
+            String "e" : String @ 6:19 6:20
+            Break @ 7:10 7:14
+        EndWhile @ 5:6 5:10
+        Integer 2 : Int @ 9:6 9:6
+    EndFn @ 1:2 1:4
+EndModule
+"#,
+        );
+        let expected = [
+            //The early return doesn't drop `d`, which isn't declared yet
+            vec!["Drop b", "Drop c", "Drop a", "Return", "EndIf"],
+            //The condition of the loop breaks out of it before `e` is declared
+            vec!["Break", "EndIf"],
+            //The body drops `e` before the `break` and at the end of every iteration
+            vec!["Drop e", "Break", "Drop e", "EndLoop"],
+            vec!["Drop d", "Drop b", "Drop c", "Drop a", "Return"],
+        ]
+        .concat();
+        assert_eq!(exits, expected);
+    }
+
+    #[test]
+    fn drops_what_is_declared_before_a_try_returns() {
+        let exits = exits(
+            r#"hir test
+Module test #6
+    Fn g #5 : fun() -> Result<Int, String> @ 2:2 2:4
+        LocalVar s #8 : String @ 3:10 3:10
+        String "s" : String @ 3:15 3:16
+        LocalVar v #9 : Int @ 4:10 4:10
+        Try : Int @ 4:14 4:22
+        Call 1 : Result<Int, String> @ 4:14 4:21
+        GlobalRef parse #4 : fun(x: Int) -> Result<Int, String> @ 4:14 4:18
+        Integer 1 : Int @ 4:20 4:20
+        Call 1 : Result<Int, untyped> @ 5:6 5:10
+        GlobalRef Ok #2 : fun(value: Int) -> Result<Int, untyped> @ 5:6 5:7
+        LocalRef v #9 : Int @ 5:9 5:9
+    EndFn @ 2:2 2:4
+EndModule
+"#,
+        );
+        //The result being tried is moved out on both paths, so it is never dropped
+        assert_eq!(exits, vec!["Drop s", "Return", "EndIf", "Drop v", "Drop s", "Return"]);
+    }
+}
@@ -0,0 +1,242 @@
+use ir::{
+    hir::{HIRInstruction, Module, ReceiverKind},
+    type_signature::{CallingConvention, FunctionSignature, Parameter, TypeSignature},
+};
+use std::collections::HashMap;
+
+///The function a method call is lowered to, along with how it takes its receiver.
+#[derive(Debug, Clone)]
+pub(crate) struct Method {
+    pub name: String,
+    ///The name of the function in MIR, such as `Point.norm`, `Point.Shape.area` for the method of an interface
+    ///or `ext Point.norm` for an extension function
+    pub function: String,
+    pub sig: TypeSignature,
+    pub receiver: ReceiverKind,
+}
+
+///The methods of the types and interfaces of a module, which method calls are resolved against.
+///Methods aren't exported, so every method a module can call is declared in it.
+#[derive(Default)]
+pub(crate) struct Methods {
+    ///The MIR name of each function that is a method, by the index of its `Fn`
+    functions: HashMap<usize, String>,
+    ///The methods defined in the impl blocks of each type, by the type, the interface the impl block defining the
+    ///method implements if any, and the name of the method
+    defined: HashMap<(String, Option<String>, String), Method>,
+    ///The extension functions of each type, by the type and the name of the function
+    extensions: HashMap<(String, String), Method>,
+    ///The methods of each interface in declaration order
+    interfaces: HashMap<String, Vec<Method>>,
+    ///The interfaces each type implements
+    implementations: HashMap<String, Vec<String>>,
+}
+
+///The name the methods of a type are known under, if it can have any.
+pub(crate) fn type_key(sig: &TypeSignature) -> Option<String> {
+    match sig {
+        TypeSignature::Struct(s) => Some(s.name.clone()),
+        TypeSignature::Primitive(p) => Some(p.to_string()),
+        //Extension functions of collections are declared for a concrete type, such as `List<Int>`
+        TypeSignature::Array(..)
+        | TypeSignature::List(_)
+        | TypeSignature::Option(_)
+        | TypeSignature::Range(_)
+        | TypeSignature::Result(..)
+            if !sig.is_generic() =>
+        {
+            Some(sig.to_string())
+        }
+        _ => None,
+    }
+}
+
+///The signature `sig` of a method as the one of the function it is lowered to, which takes the receiver of type
+///`receiver` as its first param.
+pub(crate) fn with_receiver(sig: &TypeSignature, receiver: &TypeSignature) -> TypeSignature {
+    let mut f = match sig {
+        TypeSignature::Function(f) => f.clone(),
+        _ => return sig.clone(),
+    };
+    f.parameters.insert(0, Parameter::new("self", receiver.clone()));
+    TypeSignature::Function(f)
+}
+
+///The signature of `Range.next` of the runtime, which advances the range of type `range` it is given.
+pub(crate) fn range_next(range: &TypeSignature) -> TypeSignature {
+    let element = match range {
+        TypeSignature::Range(element) => (**element).clone(),
+        _ => TypeSignature::Untyped,
+    };
+    TypeSignature::Function(FunctionSignature {
+        type_parameters: Vec::new(),
+        parameters: vec![Parameter::new("self", range.clone())],
+        variadic: false,
+        return_type_signature: Box::new(TypeSignature::Option(Box::new(element))),
+        calling_convention: CallingConvention::Beagle,
+    })
+}
+
+///The receiver of the function declared at `idx`, if it is a method.
+pub(crate) fn receiver_of(module: &Module, idx: usize) -> Option<(ReceiverKind, &TypeSignature)> {
+    module.instructions[idx + 1..]
+        .iter()
+        .zip(module.signatures[idx + 1..].iter())
+        .find(|(ins, _)| !matches!(ins, HIRInstruction::TypeParam(_)))
+        .and_then(|(ins, sig)| match ins {
+            HIRInstruction::SelfParam(receiver) => Some((*receiver, sig)),
+            _ => None,
+        })
+}
+
+impl Methods {
+    pub(crate) fn of(module: &Module) -> Self {
+        let mut methods = Methods::default();
+        //The interface or the type whose methods are being declared, if any
+        let mut interface: Option<String> = None;
+        let mut impl_block: Option<(String, Option<String>)> = None;
+        let mut depth = 0;
+        for (idx, ins) in module.instructions.iter().enumerate() {
+            match ins {
+                HIRInstruction::Interface(name) => {
+                    interface = Some(name.clone());
+                    methods.interfaces.entry(name.clone()).or_default();
+                }
+                HIRInstruction::EndInterface => interface = None,
+                HIRInstruction::Impl(implemented) => {
+                    impl_block = type_key(&module.signatures[idx]).map(|key| (key, implemented.clone()));
+                    if let Some((key, Some(implemented))) = &impl_block {
+                        methods.implementations.entry(key.clone()).or_default().push(implemented.clone());
+                    }
+                }
+                HIRInstruction::EndImpl => impl_block = None,
+                HIRInstruction::InterfaceMethod(name, receiver) => {
+                    if let Some(interface) = &interface {
+                        let method = Method {
+                            name: name.clone(),
+                            function: format!("{}.{}", interface, name),
+                            sig: module.signatures[idx].clone(),
+                            receiver: *receiver,
+                        };
+                        methods.interfaces.get_mut(interface).unwrap().push(method);
+                    }
+                }
+                HIRInstruction::Fn(name) => {
+                    if depth == 0 {
+                        methods.declare(module, idx, name, interface.as_deref(), impl_block.as_ref());
+                    }
+                    depth += 1;
+                }
+                HIRInstruction::EndFn => depth -= 1,
+                _ => {}
+            }
+        }
+        methods
+    }
+
+    ///Declare the function `name` at `idx`, which isn't nested in another function, if it is a method
+    ///of `interface` or of the type of `impl_block` along with the interface it implements, or an extension function.
+    fn declare(
+        &mut self,
+        module: &Module,
+        idx: usize,
+        name: &str,
+        interface: Option<&str>,
+        impl_block: Option<&(String, Option<String>)>,
+    ) {
+        let receiver = receiver_of(module, idx);
+        let (owner, implemented) = match (interface, impl_block) {
+            (Some(interface), _) => (interface.to_string(), None),
+            (None, Some((key, implemented))) => (key.clone(), implemented.clone()),
+            //A function with a receiver outside of impl blocks is an extension function, which a method of the same
+            //name shadows. It is named apart from the method, which has no space in its name.
+            (None, None) => {
+                let (receiver, key) = match receiver.and_then(|(receiver, sig)| Some((receiver, type_key(sig)?))) {
+                    Some(extended) => extended,
+                    None => return,
+                };
+                let function = format!("ext {}.{}", key, name);
+                self.functions.insert(idx, function.clone());
+                let method = Method { name: name.to_string(), function, sig: module.signatures[idx].clone(), receiver };
+                self.extensions.insert((key, name.to_string()), method);
+                return;
+            }
+        };
+        let receiver = match receiver {
+            Some((receiver, _)) => receiver,
+            //An associated function is called through its name, which the type checker has already rewritten the call to
+            None => {
+                self.functions.insert(idx, format!("{}.{}", owner, name));
+                return;
+            }
+        };
+        //The methods a type implements an interface with are named after the interface too, since two interfaces
+        //can declare methods of the same name
+        let function = match &implemented {
+            Some(implemented) => format!("{}.{}.{}", owner, implemented, name),
+            None => format!("{}.{}", owner, name),
+        };
+        self.functions.insert(idx, function.clone());
+        let method = Method { name: name.to_string(), function, sig: module.signatures[idx].clone(), receiver };
+        match interface {
+            //A default method is declared by the interface like the methods without one
+            Some(interface) => self.interfaces.get_mut(interface).unwrap().push(method),
+            None => {
+                self.defined.insert((owner, implemented, name.to_string()), method);
+            }
+        }
+    }
+
+    ///The MIR name of the function declared at `idx`, if it is a method, a default method or an extension function.
+    pub(crate) fn function_name(&self, idx: usize) -> Option<&str> {
+        self.functions.get(&idx).map(String::as_str)
+    }
+
+    ///The method `name` of the interface `interface`.
+    pub(crate) fn interface_method(&self, interface: &str, name: &str) -> Option<&Method> {
+        self.interfaces.get(interface)?.iter().find(|method| method.name == name)
+    }
+
+    ///The method `name` called on a receiver of type `receiver`: the one its type defines outside of the impl blocks
+    ///of interfaces, or else the one of the first interface it implements that declares it, or else an extension function.
+    ///A method of a generic param is the one of the interface bounding it that declares it.
+    pub(crate) fn lookup(&self, receiver: &TypeSignature, name: &str) -> Option<Method> {
+        if let TypeSignature::Param(param) = receiver {
+            return param.bounds.iter().find_map(|bound| self.interface_method(bound, name)).cloned();
+        }
+        let key = type_key(receiver)?;
+        if let Some(method) = self.defined.get(&(key.clone(), None, name.to_string())) {
+            return Some(method.clone());
+        }
+        self.implementations
+            .get(&key)
+            .into_iter()
+            .flatten()
+            .find(|interface| self.interface_method(interface, name).is_some())
+            .and_then(|interface| self.implementation(&key, interface, name))
+            .or_else(|| self.extensions.get(&(key, name.to_string())).cloned())
+    }
+
+    ///The function implementing the method `name` of `interface` for the type `key`, which is the default one of
+    ///the interface when the type doesn't define it.
+    fn implementation(&self, key: &str, interface: &str, name: &str) -> Option<Method> {
+        let defined = self.defined.get(&(key.to_string(), Some(interface.to_string()), name.to_string()));
+        defined.or_else(|| self.interface_method(interface, name)).cloned()
+    }
+
+    ///The slot of the method `name` in the vtables of `interface`, which is its position in the interface.
+    pub(crate) fn slot(&self, interface: &str, name: &str) -> Option<usize> {
+        self.interfaces.get(interface)?.iter().position(|method| method.name == name)
+    }
+
+    ///The functions implementing each method of `interface` for the type `key`, in vtable order.
+    pub(crate) fn vtable(&self, interface: &str, key: &str) -> Vec<String> {
+        self.interfaces
+            .get(interface)
+            .into_iter()
+            .flatten()
+            .filter_map(|method| self.implementation(key, interface, &method.name))
+            .map(|method| method.function)
+            .collect()
+    }
+}
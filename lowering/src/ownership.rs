@@ -0,0 +1,587 @@
+use ir::{
+    hir::BinaryOp,
+    mir::{self, expression_end, MIRInstruction},
+    pass::MirPass,
+    type_signature::TypeSignature,
+};
+use notices::{Notice, NoticeLevel};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::mpsc::Sender;
+
+///Chooses whether each use of a local copies or moves it, from where the local is still used afterwards.
+///The last use of a local on every path is a `Move` and the others are `Copy`s. Values of trivially copyable types
+///can be copied any number of times, but the others are move-only, so a use of one that isn't its last is reported
+///as moving it before it is used again. The `Drop` of an object that is moved on every path to it is removed.
+pub struct Ownership {
+    notice_tx: Sender<Option<Notice>>,
+}
+
+impl Ownership {
+    pub fn new(notice_tx: Sender<Option<Notice>>) -> Self {
+        Ownership { notice_tx }
+    }
+
+    fn notice(&self, module: &mir::Module, msg: String, level: NoticeLevel, idx: usize, help: Option<String>) {
+        let notice = Notice {
+            from: "Ownership".to_string(),
+            msg,
+            pos: module.positions[idx],
+            file: module.name.clone(),
+            level,
+            help,
+        };
+        self.notice_tx.send(Some(notice)).expect("Failed to send a notice through the notice channel.");
+    }
+}
+
+impl MirPass for Ownership {
+    fn run(&mut self, module: &mut mir::Module) -> Result<(), ()> {
+        let mut failed = false;
+        let mut uses = Vec::new();
+        let mut drops = HashSet::new();
+        for start in 0..module.instructions.len() {
+            if !matches!(module.instructions[start], MIRInstruction::Fun(_)) {
+                continue;
+            }
+            let function = Graph::of(&module.instructions, start);
+            let objects = function.objects(module);
+            let live = function.liveness();
+            let mut moves = HashSet::new();
+            for (node, idx) in function.nodes.iter().enumerate().filter_map(|(node, n)| match n.effect {
+                Effect::Use(idx) => Some((node, idx)),
+                _ => None,
+            }) {
+                let name = match &module.instructions[idx] {
+                    MIRInstruction::Copy(name) | MIRInstruction::Move(name) => name,
+                    _ => continue,
+                };
+                let sig = match objects.get(name.as_str()) {
+                    Some(sig) => sig,
+                    None => continue,
+                };
+                if !live[node].contains(name.as_str()) {
+                    moves.insert(idx);
+                    uses.push((idx, MIRInstruction::Move(name.clone())));
+                    continue;
+                }
+                if sig.is_trivially_copyable() {
+                    uses.push((idx, MIRInstruction::Copy(name.clone())));
+                    continue;
+                }
+                //A move-only value is moved by every use taking it, so it is gone by the time it is used again
+                failed = true;
+                let shown = name.split('.').next().unwrap();
+                let help = Some(format!("Values of type {} are moved rather than copied", sig));
+                match function.next_use(node, name) {
+                    Some(later) if later == idx => {
+                        let msg = format!("{} is moved by an earlier iteration of the loop it is used in", shown);
+                        self.notice(module, msg, NoticeLevel::Error, idx, help);
+                    }
+                    later => {
+                        let later = later.unwrap_or(idx);
+                        self.notice(
+                            module,
+                            format!("{} is used after it is moved", shown),
+                            NoticeLevel::Error,
+                            later,
+                            help,
+                        );
+                        self.notice(module, format!("{} is moved here", shown), NoticeLevel::Notice, idx, None);
+                    }
+                }
+            }
+            drops.extend(function.moved_drops(&moves));
+        }
+        if failed {
+            return Err(());
+        }
+        for (idx, ins) in uses {
+            module.instructions[idx] = ins;
+        }
+        remove(&mut module.instructions, &drops);
+        remove(&mut module.signatures, &drops);
+        remove(&mut module.positions, &drops);
+        Ok(())
+    }
+}
+
+///Remove the items at the indices in `removed` from `items`.
+fn remove<T>(items: &mut Vec<T>, removed: &HashSet<usize>) {
+    let mut idx = 0;
+    items.retain(|_| {
+        idx += 1;
+        !removed.contains(&(idx - 1))
+    });
+}
+
+///What a node of the control flow graph of a function does, by the index of the MIR instruction doing it.
+#[derive(Debug, Clone, Copy)]
+enum Effect {
+    None,
+    ///Uses an object, by copying or moving it, referring to it or reading the value of an optional or result
+    Use(usize),
+    ///Gives an object its value, once the value is computed
+    Def(usize),
+    Drop(usize),
+}
+
+#[derive(Debug)]
+struct Node {
+    effect: Effect,
+    successors: Vec<usize>,
+}
+
+///The control flow graph of a function, where every use, definition and drop of one of its objects is a node.
+///The first node is the entry of the function and the last one its exit.
+struct Graph<'a> {
+    instructions: &'a [MIRInstruction],
+    nodes: Vec<Node>,
+    ///The nodes flowing into the next node added, which are none after a jump
+    open: Vec<usize>,
+    ///The head of each enclosing loop, along with the nodes leaving it
+    loops: Vec<(usize, Vec<usize>)>,
+    ///The nodes returning from the function, which flow into its exit
+    returns: Vec<usize>,
+}
+
+///The name of the object the MIR instruction `ins` uses, defines or drops, if it does.
+fn object(ins: &MIRInstruction) -> Option<&str> {
+    match ins {
+        MIRInstruction::Copy(name)
+        | MIRInstruction::Move(name)
+        | MIRInstruction::Ref(name)
+        | MIRInstruction::Unwrap(name)
+        | MIRInstruction::IsErr(name)
+        | MIRInstruction::UnwrapErr(name)
+        | MIRInstruction::BreakIfNone(name)
+        | MIRInstruction::ObjInit(name, _)
+        | MIRInstruction::ObjMut(name)
+        | MIRInstruction::FunParam(name)
+        | MIRInstruction::Drop(name) => Some(name),
+        _ => None,
+    }
+}
+
+///The index of the `EndFun` closing the function starting at `idx`.
+fn function_end(instructions: &[MIRInstruction], idx: usize) -> usize {
+    let mut depth = 0;
+    for (end, ins) in instructions.iter().enumerate().skip(idx) {
+        match ins {
+            MIRInstruction::Fun(_) => depth += 1,
+            MIRInstruction::EndFun if depth == 1 => return end,
+            MIRInstruction::EndFun => depth -= 1,
+            _ => {}
+        }
+    }
+    instructions.len()
+}
+
+impl<'a> Graph<'a> {
+    ///The control flow graph of the function starting at `start`, leaving out the functions nested in it.
+    fn of(instructions: &'a [MIRInstruction], start: usize) -> Self {
+        let mut graph =
+            Graph { instructions, nodes: Vec::new(), open: Vec::new(), loops: Vec::new(), returns: Vec::new() };
+        graph.add(Effect::None);
+        graph.block(start + 1);
+        graph.open.append(&mut graph.returns);
+        graph.add(Effect::None);
+        graph
+    }
+
+    fn add(&mut self, effect: Effect) -> usize {
+        let node = self.nodes.len();
+        self.nodes.push(Node { effect, successors: Vec::new() });
+        self.jump(node);
+        self.open = vec![node];
+        node
+    }
+
+    ///Make the open nodes flow into `node`, leaving none open.
+    fn jump(&mut self, node: usize) {
+        for open in std::mem::take(&mut self.open) {
+            self.nodes[open].successors.push(node);
+        }
+    }
+
+    ///Add the expression at `idx`, returning the index after it.
+    ///The operands of an instruction are evaluated in order before it, so its uses come in the order they are written.
+    fn expr(&mut self, idx: usize) -> usize {
+        match &self.instructions[idx] {
+            MIRInstruction::Copy(_)
+            | MIRInstruction::Move(_)
+            | MIRInstruction::Ref(_)
+            | MIRInstruction::Unwrap(_)
+            | MIRInstruction::IsErr(_)
+            | MIRInstruction::UnwrapErr(_) => {
+                self.add(Effect::Use(idx));
+                idx + 1
+            }
+            //The right operand is skipped when the left one decides the result
+            MIRInstruction::Binary(BinaryOp::And) | MIRInstruction::Binary(BinaryOp::Or) => {
+                let right = self.expr(idx + 1);
+                let skipped = self.open.clone();
+                let next = self.expr(right);
+                self.open.extend(skipped);
+                next
+            }
+            _ => {
+                let end = expression_end(self.instructions, idx);
+                let mut next = idx + 1;
+                while next < end {
+                    next = self.expr(next);
+                }
+                end
+            }
+        }
+    }
+
+    ///Add the statements from `idx` up to the `Else`, `EndIf`, `EndLoop` or `EndFun` closing them, returning its index.
+    fn block(&mut self, mut idx: usize) -> usize {
+        while idx < self.instructions.len() {
+            idx = match &self.instructions[idx] {
+                MIRInstruction::Else | MIRInstruction::EndIf | MIRInstruction::EndLoop | MIRInstruction::EndFun => {
+                    return idx
+                }
+                //Nested functions are analysed on their own
+                MIRInstruction::Fun(_) => function_end(self.instructions, idx) + 1,
+                MIRInstruction::FunParam(_) => {
+                    self.add(Effect::Def(idx));
+                    idx + 1
+                }
+                //The default value is only computed for the calls leaving the param out
+                MIRInstruction::DefaultArg => {
+                    let skipped = self.open.clone();
+                    let next = self.expr(idx + 1);
+                    self.open.extend(skipped);
+                    next
+                }
+                MIRInstruction::ObjInit(..) | MIRInstruction::ObjMut(_) => {
+                    let next = self.expr(idx + 1);
+                    self.add(Effect::Def(idx));
+                    next
+                }
+                MIRInstruction::Drop(_) => {
+                    self.add(Effect::Drop(idx));
+                    idx + 1
+                }
+                MIRInstruction::If(_) => {
+                    let next = self.expr(idx + 1);
+                    let condition = self.open.clone();
+                    let mut end = self.block(next);
+                    if let Some(MIRInstruction::Else) = self.instructions.get(end) {
+                        let then = std::mem::replace(&mut self.open, condition);
+                        end = self.block(end + 1);
+                        self.open.extend(then);
+                    } else {
+                        self.open.extend(condition);
+                    }
+                    end + 1
+                }
+                MIRInstruction::Loop => {
+                    let head = self.add(Effect::None);
+                    self.loops.push((head, Vec::new()));
+                    let end = self.block(idx + 1);
+                    self.jump(head);
+                    self.open = self.loops.pop().unwrap().1;
+                    end + 1
+                }
+                MIRInstruction::Break => {
+                    let open = std::mem::take(&mut self.open);
+                    if let Some((_, breaks)) = self.loops.last_mut() {
+                        breaks.extend(open);
+                    }
+                    idx + 1
+                }
+                MIRInstruction::Continue => {
+                    match self.loops.last() {
+                        Some(&(head, _)) => self.jump(head),
+                        None => self.open.clear(),
+                    }
+                    idx + 1
+                }
+                MIRInstruction::BreakIfNone(_) => {
+                    let node = self.add(Effect::Use(idx));
+                    if let Some((_, breaks)) = self.loops.last_mut() {
+                        breaks.push(node);
+                    }
+                    idx + 1
+                }
+                MIRInstruction::Return(value) => {
+                    let next = if *value { self.expr(idx + 1) } else { idx + 1 };
+                    self.returns.append(&mut self.open);
+                    next
+                }
+                MIRInstruction::Halt => {
+                    self.open.clear();
+                    idx + 1
+                }
+                _ => self.expr(idx),
+            };
+        }
+        idx
+    }
+
+    ///The name of the object the node `node` is about, if any.
+    fn object_of(&self, node: usize) -> Option<&'a str> {
+        match self.nodes[node].effect {
+            Effect::Use(idx) | Effect::Def(idx) | Effect::Drop(idx) => object(&self.instructions[idx]),
+            Effect::None => None,
+        }
+    }
+
+    ///The params and locals of the function, along with their types.
+    fn objects(&self, module: &'a mir::Module) -> HashMap<&'a str, &'a TypeSignature> {
+        self.nodes
+            .iter()
+            .filter_map(|node| match node.effect {
+                Effect::Def(idx) => Some((object(&module.instructions[idx])?, &module.signatures[idx])),
+                _ => None,
+            })
+            .collect()
+    }
+
+    ///The objects each node leaves live, which are used again later on some path without being given a new value first.
+    ///Drops aren't uses, or the objects dropped would never be used for the last time.
+    fn liveness(&self) -> Vec<HashSet<&'a str>> {
+        let mut live_out = vec![HashSet::new(); self.nodes.len()];
+        let mut live_in: Vec<HashSet<&str>> = vec![HashSet::new(); self.nodes.len()];
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for node in (0..self.nodes.len()).rev() {
+                let out: HashSet<&str> = self.nodes[node]
+                    .successors
+                    .iter()
+                    .flat_map(|successor| live_in[*successor].iter().copied())
+                    .collect();
+                let mut live = out.clone();
+                match (self.nodes[node].effect, self.object_of(node)) {
+                    (Effect::Use(_), Some(name)) => {
+                        live.insert(name);
+                    }
+                    (Effect::Def(_), Some(name)) => {
+                        live.remove(name);
+                    }
+                    _ => {}
+                }
+                live_out[node] = out;
+                if live != live_in[node] {
+                    live_in[node] = live;
+                    changed = true;
+                }
+            }
+        }
+        live_out
+    }
+
+    ///The index of the MIR instruction using the object `name` next after the node `node`, without it being given
+    ///a new value in between.
+    fn next_use(&self, node: usize, name: &str) -> Option<usize> {
+        let mut seen = HashSet::new();
+        let mut queue: VecDeque<usize> = self.nodes[node].successors.iter().copied().collect();
+        while let Some(next) = queue.pop_front() {
+            if !seen.insert(next) {
+                continue;
+            }
+            match (self.nodes[next].effect, self.object_of(next)) {
+                (Effect::Use(idx), Some(object)) if object == name => return Some(idx),
+                (Effect::Def(_), Some(object)) if object == name => continue,
+                _ => {}
+            }
+            queue.extend(self.nodes[next].successors.iter().copied());
+        }
+        None
+    }
+
+    ///The indices of the `Drop`s of objects that are moved on every path to them, which have nothing left to drop.
+    ///`moves` holds the indices of the uses moving an object.
+    fn moved_drops(&self, moves: &HashSet<usize>) -> Vec<usize> {
+        let mut predecessors = vec![Vec::new(); self.nodes.len()];
+        for (node, n) in self.nodes.iter().enumerate() {
+            for successor in n.successors.iter() {
+                predecessors[*successor].push(node);
+            }
+        }
+        //The objects moved on every path out of each node, or none for the nodes not reached yet
+        let mut moved_out: Vec<Option<HashSet<&str>>> = vec![None; self.nodes.len()];
+        let mut moved_in: Vec<Option<HashSet<&str>>> = vec![None; self.nodes.len()];
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for node in 0..self.nodes.len() {
+                let mut reached = predecessors[node].iter().filter_map(|predecessor| moved_out[*predecessor].as_ref());
+                let moved = match (node, reached.next()) {
+                    (0, _) => HashSet::new(),
+                    (_, None) => continue,
+                    (_, Some(first)) => {
+                        reached.fold(first.clone(), |moved, other| moved.intersection(other).copied().collect())
+                    }
+                };
+                let mut out = moved.clone();
+                match (self.nodes[node].effect, self.object_of(node)) {
+                    (Effect::Use(idx), Some(name)) if moves.contains(&idx) => {
+                        out.insert(name);
+                    }
+                    (Effect::Def(_), Some(name)) => {
+                        out.remove(name);
+                    }
+                    _ => {}
+                }
+                moved_in[node] = Some(moved);
+                if moved_out[node].as_ref() != Some(&out) {
+                    moved_out[node] = Some(out);
+                    changed = true;
+                }
+            }
+        }
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter_map(|(node, n)| match (n.effect, self.object_of(node), &moved_in[node]) {
+                (Effect::Drop(idx), Some(name), Some(moved)) if moved.contains(name) => Some(idx),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::pos::Position;
+    use ir::text;
+    use std::sync::mpsc::channel;
+
+    ///Run the pass over the MIR `text`, returning whether it succeeded, the module it leaves and its notices.
+    fn run(text: &str) -> (Result<(), ()>, mir::Module, Vec<Notice>) {
+        let mut module = text::parse_mir(text).unwrap();
+        let (notice_tx, notice_rx) = channel();
+        let ret = Ownership::new(notice_tx).run(&mut module);
+        (ret, module, notice_rx.try_iter().flatten().collect())
+    }
+
+    ///The instructions of `module` as they are printed, without their signatures and positions.
+    fn instructions(module: &mir::Module) -> Vec<String> {
+        let text = text::print_mir(module);
+        text.lines()
+            .skip(1)
+            .map(|line| line.split(" :").next().unwrap().split(" @").next().unwrap().trim().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn moves_the_last_use_of_each_local() {
+        let (ret, module, notices) = run(r#"mir test
+Module test
+    Fun main : fun() -> Int @ 2:2 2:4
+        StackAlloc 16 @ 3:10 3:10
+        ObjInit s : String @ 3:10 3:10
+        String "a" : String @ 3:15 3:16
+        StackAlloc 4 @ 4:10 4:10
+        ObjInit n : Int @ 4:10 4:10
+        Call 1 : Int @ 4:14 4:20
+        Global take : fun(s: String) -> Int @ 4:14 4:17
+        Copy s : String @ 4:19 4:19
+        StackAlloc 4 @ 5:6 5:10
+        ObjInit "ret.0" : Int @ 5:6 5:10
+        Binary Add : Int @ 5:6 5:10
+        Copy n : Int @ 5:6 5:6
+        Copy n : Int @ 5:10 5:10
+        Drop n : Int @ 5:6 5:10
+        Drop s : String @ 5:6 5:10
+        Return value : Int @ 5:6 5:10
+        Copy "ret.0" : Int @ 5:6 5:10
+    EndFun @ 2:2 2:4
+EndModule
+"#);
+        assert_eq!(ret, Ok(()));
+        assert!(notices.is_empty());
+        let expected = vec![
+            "Module test",
+            "Fun main",
+            "StackAlloc 16",
+            "ObjInit s",
+            "String \"a\"",
+            "StackAlloc 4",
+            "ObjInit n",
+            "Call 1",
+            "Global take",
+            "Move s",
+            "StackAlloc 4",
+            "ObjInit \"ret.0\"",
+            "Binary Add",
+            "Copy n",
+            "Move n",
+            "Return value",
+            "Move \"ret.0\"",
+            "EndFun",
+            "EndModule",
+        ];
+        assert_eq!(instructions(&module), expected);
+    }
+
+    #[test]
+    fn reports_uses_after_a_move() {
+        let (ret, _, notices) = run(r#"mir test
+Module test
+    Fun main : fun() -> Int @ 2:2 2:4
+        StackAlloc 16 @ 3:10 3:10
+        ObjInit s : String @ 3:10 3:10
+        String "a" : String @ 3:15 3:16
+        StackAlloc 4 @ 4:10 4:10
+        ObjInit n : Int @ 4:10 4:10
+        Call 1 : Int @ 4:14 4:20
+        Global take : fun(s: String) -> Int @ 4:14 4:17
+        Copy s : String @ 4:19 4:19
+        StackAlloc 4 @ 5:6 5:12
+        ObjInit "ret.0" : Int @ 5:6 5:12
+        Call 1 : Int @ 5:6 5:12
+        Global take : fun(s: String) -> Int @ 5:6 5:9
+        Copy s : String @ 5:11 5:11
+        Drop n : Int @ 5:6 5:12
+        Drop s : String @ 5:6 5:12
+        Return value : Int @ 5:6 5:12
+        Copy "ret.0" : Int @ 5:6 5:12
+    EndFun @ 2:2 2:4
+EndModule
+"#);
+        assert_eq!(ret, Err(()));
+        let notices = notices.iter().map(|n| (n.msg.as_str(), n.pos.start)).collect::<Vec<_>>();
+        assert_eq!(
+            notices,
+            vec![("s is used after it is moved", Position(5, 11)), ("s is moved here", Position(4, 19))]
+        );
+    }
+
+    #[test]
+    fn reports_moves_in_earlier_iterations_of_a_loop() {
+        let (ret, _, notices) = run(r#"mir test
+Module test
+    Fun main : fun() -> Int @ 2:2 2:4
+        StackAlloc 16 @ 3:10 3:10
+        ObjInit s : String @ 3:10 3:10
+        String "a" : String @ 3:15 3:16
+        Loop @ 4:6 4:10
+            If @ 4:6 4:10
+                Unary Not : Bool @ 4:6 4:10
+                Bool true : Bool @ 4:12 4:15
+                Break @ 4:6 4:10
+            EndIf @ 4:6 4:10
+            Call 1 : Int @ 4:19 4:25
+            Global take : fun(s: String) -> Int @ 4:19 4:22
+            Copy s : String @ 4:24 4:24
+        EndLoop @ 4:6 4:10
+        StackAlloc 4 @ 5:6 5:6
+        ObjInit "ret.0" : Int @ 5:6 5:6
+        Integer 0 : Int @ 5:6 5:6
+        Drop s : String @ 5:6 5:6
+        Return value : Int @ 5:6 5:6
+        Copy "ret.0" : Int @ 5:6 5:6
+    EndFun @ 2:2 2:4
+EndModule
+"#);
+        assert_eq!(ret, Err(()));
+        assert_eq!(notices[0].msg, "s is moved by an earlier iteration of the loop it is used in");
+        assert_eq!(notices[0].pos.start, Position(4, 24));
+    }
+}
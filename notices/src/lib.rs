@@ -19,6 +19,8 @@ pub struct Notice {
     pub pos: BiPos,
     pub file: String,
     pub level: NoticeLevel,
+    ///A note on how to fix what the notice reports, such as a name that was probably meant
+    pub help: Option<String>,
 }
 
 impl Notice {
@@ -65,7 +67,54 @@ impl Notice {
                 }
             }
         }
+
+        if let Some(help) = self.help {
+            println!("\t{}help{}: {}\n", ansi::Fg::Cyan, ansi::Fg::Reset, help);
+        }
+    }
+}
+
+///The number of characters to insert, delete, substitute or swap with their neighbour to turn `a` into `b`.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    //The distances from the prefixes of `a` to the prefixes of `b`, one row per prefix of `a`
+    let mut rows = vec![(0..=b.len()).collect::<Vec<usize>>()];
+    for i in 1..=a.len() {
+        let mut row = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            row[j] = (rows[i - 1][j] + 1)
+                .min(row[j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = row[j].min(rows[i - 2][j - 2] + 1);
+            }
+        }
+        rows.push(row);
     }
+    rows[a.len()][b.len()]
+}
+
+///The candidate closest to `name`, the misspelling of a name, if any is close enough to be what was meant.
+///Ties go to the candidate that comes first alphabetically,
+///so that the suggestion doesn't depend on the order of `candidates`.
+pub fn closest_name<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    let len = name.chars().count();
+    let max = (len / 3).max(1);
+    //A candidate that would have to be rewritten entirely, such as `x` for `y`, isn't a misspelling of it
+    candidates
+        .into_iter()
+        .filter(|candidate| *candidate != name)
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, candidate)| {
+            *distance <= max && *distance < len.max(candidate.chars().count())
+        })
+        .min()
+        .map(|(_, candidate)| candidate)
 }
 
 pub const TAB_WIDTH: usize = 5;
@@ -101,7 +150,7 @@ impl SourceOrigin for BiPos {
             String::from("~").repeat(self.start.1 as usize - 1 + tab_count * TAB_WIDTH),
             "^",
             if (self.start.1 as usize) < error_line.len() {
-                String::from("~").repeat(error_line.len() - self.end.1 as usize)
+                String::from("~").repeat(error_line.len().saturating_sub(self.end.1 as usize))
             } else {
                 String::new()
             }
@@ -109,3 +158,39 @@ impl SourceOrigin for BiPos {
         Some((start_line as usize, lines, squiggly_line))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_edits_and_swaps() {
+        assert_eq!(edit_distance("count", "count"), 0);
+        assert_eq!(edit_distance("cont", "count"), 1);
+        assert_eq!(edit_distance("conut", "count"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn suggests_close_enough_names() {
+        assert_eq!(closest_name("cont", vec!["total", "count"]), Some("count"));
+        assert_eq!(closest_name("whle", vec!["while", "if"]), Some("while"));
+        //Only one edit is allowed for every three characters of the name
+        assert_eq!(closest_name("lenght", vec!["length"]), Some("length"));
+        assert_eq!(closest_name("cnt", vec!["count"]), None);
+    }
+
+    #[test]
+    fn does_not_suggest_rewriting_a_name_entirely() {
+        assert_eq!(closest_name("x", vec!["y"]), None);
+        assert_eq!(closest_name("x", vec!["x"]), None);
+        assert_eq!(closest_name("x", vec!["xs"]), Some("xs"));
+    }
+
+    #[test]
+    fn breaks_ties_alphabetically() {
+        assert_eq!(closest_name("bat", vec!["cat", "bar"]), Some("bar"));
+        assert_eq!(closest_name("bat", vec!["bar", "cat"]), Some("bar"));
+    }
+}
@@ -1,13 +1,141 @@
 // #![feature(async_closure)]
 
 use frontend::Driver;
+use ir::encoding::{self, EncodingError, Format, IrModule};
+use ir::text;
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::thread;
 
+const USAGE: &str = "Usage: beaglec [file] [--emit-hir <path>] [--emit-mir <path>] [--dump-before <pass>] [--dump-after <pass>]
+       beaglec --read <path>
+
+A path ending in .hir or .mir is written or read in the text syntax of HIR or MIR, as printed by --dump-before and --dump-after.
+A path ending in .json is written or read as JSON, and any other path in the compact binary format.
+A .hir file can also be compiled in place of a Beagle source file, resuming after the passes its header lists,
+with errors pointing at its own lines.
+The interface of the compiled file is written next to it in a .bmi file, which modules importing it are checked against.
+--dump-before and --dump-after print each compiled module before or after the named pass, such as resolve, typeck, lower or ownership, and can be repeated.
+Options can also be given as --option=value.";
+
+///What the driver was asked to do on the command line.
+enum Command {
+    ///Compile the file along with the modules it imports whose interface is stale,
+    ///also writing its HIR and MIR to the given paths if there are any.
+    Compile {
+        source: PathBuf,
+        emit_hir: Option<PathBuf>,
+        emit_mir: Option<PathBuf>,
+        driver: Driver,
+    },
+    ///Print a HIR or MIR module or a module interface previously written to disk.
+    Read(PathBuf),
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let mut source = None;
+    let mut emit_hir = None;
+    let mut emit_mir = None;
+    let mut read = None;
+    let mut driver = Driver::default();
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            if source.replace(PathBuf::from(arg)).is_some() {
+                return Err("Only one source file can be compiled at a time".to_string());
+            }
+            continue;
+        }
+        let (option, value) = match arg.split_once('=') {
+            Some((option, value)) => (option.to_string(), Some(value.to_string())),
+            None => (arg, None),
+        };
+        if !["--emit-hir", "--emit-mir", "--read", "--dump-before", "--dump-after"].contains(&option.as_str()) {
+            return Err(format!("Unknown option {}", option));
+        }
+        let value = match value.or_else(|| args.next()) {
+            Some(value) => value,
+            None => return Err(format!("Expected a value after {}", option)),
+        };
+        match option.as_str() {
+            "--emit-hir" => emit_hir = Some(PathBuf::from(value)),
+            "--emit-mir" => emit_mir = Some(PathBuf::from(value)),
+            "--read" => read = Some(PathBuf::from(value)),
+            "--dump-before" => driver.dump_before.push(value),
+            _ => driver.dump_after.push(value),
+        }
+    }
+    match (read, source, emit_hir, emit_mir) {
+        (Some(path), None, None, None) if driver.dump_before.is_empty() && driver.dump_after.is_empty() => Ok(Command::Read(path)),
+        (Some(_), ..) => Err("--read cannot be combined with compiling a file or the options for it".to_string()),
+        (None, source, emit_hir, emit_mir) => Ok(Command::Compile {
+            source: source.unwrap_or_else(|| PathBuf::from("test.txt")),
+            emit_hir,
+            emit_mir,
+            driver,
+        }),
+    }
+}
+
+///Write `module` to `path`: in the text syntax printed by `print` if the path has the extension `text`,
+///and otherwise encoded as JSON or in the binary format.
+fn emit<M: IrModule>(module: &M, path: &Path, text: &str, print: fn(&M) -> String) -> Result<(), String> {
+    if path.extension().is_some_and(|extension| extension == text) {
+        std::fs::write(path, print(module)).map_err(|e| format!("Could not write {}: {}", path.display(), e))
+    } else {
+        encoding::write(module, path).map_err(|e| e.to_string())
+    }
+}
+
+///Print the module at `path`, whichever representation it is in.
+fn read(path: &Path) -> Result<(), String> {
+    let text_of = |path: &Path| std::fs::read_to_string(path).map_err(|e| format!("Could not read {}: {}", path.display(), e));
+    let error_in = |e: text::TextError| format!("{}, {}", path.display(), e);
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("hir") => println!("{}", text::parse_hir(&text_of(path)?).map_err(error_in)?),
+        Some("mir") => println!("{}", text::parse_mir(&text_of(path)?).map_err(error_in)?),
+        _ => decode(path).map_err(|e| e.to_string())?,
+    }
+    Ok(())
+}
+
+///Print the module at `path`, which is in an encoding of HIR or MIR or a module interface.
+fn decode(path: &Path) -> Result<(), EncodingError> {
+    let bytes = std::fs::read(path)?;
+    let format = Format::of_path(path);
+    match encoding::kind_of(&bytes, format)?.as_str() {
+        "mir" => println!("{}", encoding::decode::<ir::mir::Module>(&bytes, format)?),
+        "bmi" => println!("{}", encoding::decode::<ir::interface::ModuleInterface>(&bytes, format)?),
+        _ => println!("{}", encoding::decode::<ir::hir::Module>(&bytes, format)?),
+    }
+    Ok(())
+}
+
 fn main() -> std::io::Result<()> {
-    let driver = Driver;
-    let tir = futures::executor::block_on(driver.begin_parsing(Path::new("test.txt")));
-    println!("{}", tir);
+    let command = match parse_args(std::env::args().skip(1)) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+    let result = match command {
+        Command::Read(path) => read(&path),
+        Command::Compile { source, emit_hir, emit_mir, driver } => {
+            driver.compile(&source).and_then(|(tir, mir)| {
+                println!("{}", tir);
+                if let Some(path) = emit_hir {
+                    emit(&tir, &path, "hir", text::print_hir)?;
+                }
+                if let Some(path) = emit_mir {
+                    emit(&mir, &path, "mir", text::print_mir)?;
+                }
+                Ok(())
+            })
+        }
+    };
+    if let Err(e) = result {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
     Ok(())
 }
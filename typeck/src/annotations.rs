@@ -0,0 +1,125 @@
+use crate::TypeckVM;
+use ir::{
+    hir::{AnnotationArg, HIRInstruction},
+    type_signature::TypeSignature,
+};
+use notices::NoticeLevel;
+
+///The kind of declaration found at an instruction, for diagnostics.
+fn describe(ins: &HIRInstruction) -> &'static str{
+    match ins{
+        HIRInstruction::Fn(_) => "a function",
+        HIRInstruction::NativeFn(_) => "a native function",
+        HIRInstruction::Property(_, _) => "a property",
+        HIRInstruction::Const(_) => "a constant",
+        HIRInstruction::Struct(_) => "a struct",
+        HIRInstruction::TypeAlias(_) => "a type alias",
+        HIRInstruction::Newtype(_) => "a newtype",
+        HIRInstruction::Interface(_) => "an interface",
+        HIRInstruction::Impl(_) => "an impl block",
+        _ => "this",
+    }
+}
+
+impl TypeckVM{
+    pub(crate) fn warning(&mut self, msg: String, idx: usize){
+        let pos = self.ir_stack[idx].pos;
+        let _ = self.emit_notice(msg, NoticeLevel::Warning, pos);
+    }
+
+    ///Check that every annotation is known, takes the right arguments and is applied to a declaration it makes sense for.
+    ///Unknown annotations are only warned about, so other tools can define their own.
+    pub(crate) fn check_annotations(&mut self) -> Result<(), ()>{
+        let mut depth = 0;
+        for idx in 0 .. self.ir_stack.len(){
+            let (name, args) = match &self.ir_stack[idx].ins{
+                HIRInstruction::Fn(_) | HIRInstruction::Struct(_) | HIRInstruction::Interface(_) | HIRInstruction::Impl(_) => {
+                    depth += 1;
+                    continue
+                },
+                HIRInstruction::EndFn | HIRInstruction::EndStruct | HIRInstruction::EndInterface | HIRInstruction::EndImpl => {
+                    depth -= 1;
+                    continue
+                },
+                HIRInstruction::Annotation(name, args) => (name.clone(), args.clone()),
+                _ => continue,
+            };
+            let target = self.ir_stack[idx + 1 ..]
+                .iter()
+                .position(|ir| !matches!(ir.ins, HIRInstruction::Annotation(_, _) | HIRInstruction::Visibility(_)))
+                .map(|offset| idx + 1 + offset)
+                .unwrap();
+            let target_ins = self.ir_stack[target].ins.clone();
+            let (applies, args_ok, expected_args) = match name.as_str(){
+                "inline" => (matches!(target_ins, HIRInstruction::Fn(_)), args.is_empty(), "no arguments"),
+                "test" => {
+                    let no_params = match &self.ir_stack[target].sig{
+                        TypeSignature::Function(f) => f.parameters.is_empty(),
+                        _ => false,
+                    };
+                    (matches!(target_ins, HIRInstruction::Fn(_)) && no_params, args.is_empty(), "no arguments")
+                },
+                "deprecated" => (
+                    matches!(
+                        target_ins,
+                        HIRInstruction::Fn(_)
+                            | HIRInstruction::NativeFn(_)
+                            | HIRInstruction::Property(_, _)
+                            | HIRInstruction::Const(_)
+                            | HIRInstruction::Struct(_)
+                            | HIRInstruction::Interface(_)
+                    ),
+                    matches!(args.as_slice(), [] | [AnnotationArg::String(_)]),
+                    "an optional message"
+                ),
+                "extern" => (
+                    matches!(target_ins, HIRInstruction::NativeFn(_)),
+                    matches!(args.as_slice(), [AnnotationArg::String(_)]),
+                    "the name of the C function"
+                ),
+                _ => {
+                    self.warning(format!("Unknown annotation @{}", name), idx);
+                    continue
+                },
+            };
+            if !args_ok{
+                return self.error(format!("@{} expects {}", name, expected_args), idx)
+            }
+            if !applies{
+                let target = if name == "test" && matches!(target_ins, HIRInstruction::Fn(_)){
+                    "a function with parameters"
+                }else{
+                    describe(&target_ins)
+                };
+                return self.error(format!("@{} cannot be applied to {}", name, target), idx)
+            }
+            if name == "deprecated" && depth == 0{
+                let declared = match &target_ins{
+                    HIRInstruction::Fn(name)
+                    | HIRInstruction::NativeFn(name)
+                    | HIRInstruction::Property(name, _)
+                    | HIRInstruction::Const(name)
+                    | HIRInstruction::Struct(name)
+                    | HIRInstruction::Interface(name) => name.clone(),
+                    _ => unreachable!(),
+                };
+                let message = match args.first(){
+                    Some(AnnotationArg::String(message)) => Some(message.clone()),
+                    _ => None,
+                };
+                self.deprecated.insert(declared, message);
+            }
+        }
+        Ok(())
+    }
+
+    ///Warn about a use of the deprecated top level declaration `name` at `idx`.
+    pub(crate) fn check_deprecated_use(&mut self, name: &str, idx: usize){
+        let message = match self.deprecated.get(name){
+            Some(Some(message)) => format!("{} is deprecated: {}", name, message),
+            Some(None) => format!("{} is deprecated", name),
+            None => return,
+        };
+        self.warning(message, idx);
+    }
+}
@@ -0,0 +1,102 @@
+use crate::{interfaces::MethodDecl, TypeckVM};
+use ir::{
+    hir::{HIRInstruction, ReceiverKind, Visibility},
+    type_signature::{CallingConvention, FunctionSignature, Parameter, PrimitiveType, TypeSignature},
+};
+
+///The methods every array and list has, such as `len`, `push` for lists, and `next` for ranges.
+pub(crate) fn builtin_method(receiver: &TypeSignature, name: &str) -> Option<MethodDecl>{
+    let (receiver_kind, parameters, return_type) = match (receiver, name){
+        (TypeSignature::Array(..), "len") | (TypeSignature::List(_), "len") => {
+            (ReceiverKind::Ref, vec![], TypeSignature::Primitive(PrimitiveType::Integer))
+        },
+        (TypeSignature::List(element), "push") => {
+            (ReceiverKind::MutRef, vec![Parameter::new("value", (**element).clone())], TypeSignature::Primitive(PrimitiveType::Unit))
+        },
+        (TypeSignature::Range(element), "next") => {
+            (ReceiverKind::MutRef, vec![], TypeSignature::Option(element.clone()))
+        },
+        _ => return None,
+    };
+    Some(MethodDecl{
+        name: name.to_string(),
+        sig: FunctionSignature{
+            type_parameters: Vec::new(),
+            parameters,
+            variadic: false,
+            return_type_signature: Box::new(return_type),
+            calling_convention: CallingConvention::Beagle,
+        },
+        receiver: Some(receiver_kind),
+        default: true,
+        visibility: Visibility::Public,
+        decl: None,
+    })
+}
+
+///The type of the elements of `sig`, if it is a collection.
+pub(crate) fn element_type(sig: &TypeSignature) -> Option<TypeSignature>{
+    match sig{
+        TypeSignature::Array(element, _) | TypeSignature::List(element) => Some((**element).clone()),
+        _ => None,
+    }
+}
+
+impl TypeckVM{
+    ///Check an array literal with `len` elements, all of which must have the same type.
+    ///An empty literal has no element type of its own and takes the one it is used as.
+    pub(crate) fn check_array(&mut self, idx: usize, len: usize) -> Result<(TypeSignature, usize), ()>{
+        let mut element = TypeSignature::Untyped;
+        let mut next = idx + 1;
+        for i in 0 .. len{
+            let (found, element_next) = self.check_expr(next)?;
+            if i == 0{
+                element = found;
+            }else{
+                self.cmp_types(&element, &found, next)?;
+            }
+            next = element_next;
+        }
+        let sig = TypeSignature::Array(Box::new(element), len);
+        self.ir_stack[idx].sig = sig.clone();
+        Ok((sig, next))
+    }
+
+    ///Check that the expression at `idx` is an integer that can be used as an index.
+    fn check_index_expr(&mut self, idx: usize) -> Result<usize, ()>{
+        let (found, next) = self.check_expr(idx)?;
+        self.cmp_types(&TypeSignature::Primitive(PrimitiveType::Integer), &found, idx)?;
+        Ok(next)
+    }
+
+    pub(crate) fn check_index(&mut self, idx: usize) -> Result<(TypeSignature, usize), ()>{
+        let (collection, next) = self.check_expr(idx + 1)?;
+        let element = match element_type(&collection){
+            Some(element) => element,
+            None => return self.error(format!("Cannot index into a value of type {}", collection), idx + 1),
+        };
+        if let (TypeSignature::Array(_, len), HIRInstruction::Integer(index)) = (&collection, &self.ir_stack[next].ins){
+            if *index < 0 || *index as usize >= *len{
+                return self.error(format!("Index {} is out of bounds for an array of length {}", index, len), next)
+            }
+        }
+        let next = self.check_index_expr(next)?;
+        self.ir_stack[idx].sig = element.clone();
+        Ok((element, next))
+    }
+
+    ///Check a slice of a collection, which is a new list of the same elements.
+    pub(crate) fn check_slice(&mut self, idx: usize, start: bool, end: bool) -> Result<(TypeSignature, usize), ()>{
+        let (collection, mut next) = self.check_expr(idx + 1)?;
+        let element = match element_type(&collection){
+            Some(element) => element,
+            None => return self.error(format!("Cannot slice a value of type {}", collection), idx + 1),
+        };
+        for _ in 0 .. start as usize + end as usize{
+            next = self.check_index_expr(next)?;
+        }
+        let sig = TypeSignature::List(Box::new(element));
+        self.ir_stack[idx].sig = sig.clone();
+        Ok((sig, next))
+    }
+}
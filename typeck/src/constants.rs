@@ -0,0 +1,54 @@
+use crate::TypeckVM;
+use ir::{
+    consteval::ConstEvaluator,
+    hir::{expression_end, HIRInstruction},
+    type_signature::{PrimitiveType, TypeSignature},
+};
+use notices::NoticeLevel;
+
+impl TypeckVM{
+    ///Check a constant, which must have a type its value can be computed in at compile time.
+    pub(crate) fn check_const(&mut self, idx: usize, name: &str) -> Result<usize, ()>{
+        let next = self.check_binding(idx)?;
+        let sig = self.ir_stack[idx].sig.clone();
+        let computable = matches!(
+            sig,
+            TypeSignature::Primitive(PrimitiveType::Integer)
+            | TypeSignature::Primitive(PrimitiveType::Float)
+            | TypeSignature::Primitive(PrimitiveType::Bool)
+            | TypeSignature::Primitive(PrimitiveType::String)
+        );
+        if !computable{
+            return self.error(format!("Constant {} must have type Int, Float, Bool or String, but it has type {}", name, sig), idx)
+        }
+        self.globals.insert(name.to_string(), sig);
+        Ok(next)
+    }
+
+    ///Evaluate every constant of the module, which fails on the first one whose value can't be computed.
+    ///An error inside a function called by the initializer also points out the constant being evaluated.
+    pub(crate) fn evaluate_constants(&mut self) -> Result<(), ()>{
+        let instructions = self.ir_stack.iter().map(|ir| ir.ins.clone()).collect::<Vec<HIRInstruction>>();
+        let mut evaluator = ConstEvaluator::new(&instructions);
+        for constant in self.imports.values().flat_map(|interface| interface.constants.iter()){
+            evaluator.import(&constant.name, constant.value.clone());
+        }
+        for (idx, ins) in instructions.iter().enumerate(){
+            let name = match ins{
+                HIRInstruction::Const(name) => name,
+                _ => continue,
+            };
+            let error = match evaluator.constant(name){
+                Ok(_) => continue,
+                Err(error) => error,
+            };
+            let ret = self.error(error.msg, error.idx);
+            if !(idx ..= expression_end(&instructions, idx + 1)).contains(&error.idx){
+                let pos = self.ir_stack[idx].pos;
+                let _ = self.emit_notice(format!("While evaluating the constant {}", name), NoticeLevel::Notice, pos);
+            }
+            return ret
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,53 @@
+use crate::TypeckVM;
+use ir::{
+    hir::HIRInstruction,
+    type_signature::{PrimitiveType, TypeSignature},
+};
+
+impl TypeckVM{
+    ///Check the condition of an `if` or `while` starting at `idx`, which must be a bool.
+    fn check_condition(&mut self, idx: usize) -> Result<usize, ()>{
+        let (cond, next) = self.check_expr(idx)?;
+        if cond != TypeSignature::Primitive(PrimitiveType::Bool){
+            return self.error(format!("Condition must be a Bool, but this has type {}", cond), idx)
+        }
+        Ok(next)
+    }
+
+    ///Check the statements of a nested block starting at `idx`, up to the `closing` instruction.
+    pub(crate) fn check_block(&mut self, idx: usize, closing: HIRInstruction) -> Result<usize, ()>{
+        self.check_statements(idx, closing)
+    }
+
+    ///Check a conditional, along with its else branch if it has one.
+    pub(crate) fn check_if(&mut self, idx: usize, has_else: bool) -> Result<usize, ()>{
+        let next = self.check_condition(idx + 1)?;
+        if !has_else{
+            return self.check_block(next, HIRInstruction::EndIf)
+        }
+        let next = self.check_block(next, HIRInstruction::Else)?;
+        self.check_block(next, HIRInstruction::EndIf)
+    }
+
+    pub(crate) fn check_while(&mut self, idx: usize) -> Result<usize, ()>{
+        let next = self.check_condition(idx + 1)?;
+        self.check_block(next, HIRInstruction::EndWhile)
+    }
+
+    ///Check a `return`, whose value must have the return type of the enclosing function.
+    ///A `return` without a value returns `Unit`.
+    pub(crate) fn check_return(&mut self, idx: usize, has_value: bool) -> Result<usize, ()>{
+        let (found, next) = if has_value{
+            self.check_expr(idx + 1)?
+        }else{
+            (TypeSignature::Primitive(PrimitiveType::Unit), idx + 1)
+        };
+        let expected = match self.return_type.clone(){
+            Some(expected) => expected,
+            None => return self.error("'return' can only be used inside a function".to_string(), idx),
+        };
+        self.cmp_types(&expected, &found, if has_value{ idx + 1 }else{ idx })?;
+        self.ir_stack[idx].sig = found;
+        Ok(next)
+    }
+}
@@ -0,0 +1,141 @@
+use crate::{interfaces::MethodDecl, TypeckVM};
+use ir::{
+    hir::{HIRInstruction, ReceiverKind, HIR},
+    type_signature::TypeSignature,
+};
+use notices::NoticeLevel;
+use std::collections::HashMap;
+
+///A function declared at the top level on an existing type, such as `fun String.shout()`,
+///which can be called like a method on any value of that type.
+#[derive(Debug, Clone)]
+pub(crate) struct ExtensionDecl{
+    ///The type the function extends, which may mention the type params of the function
+    pub receiver: TypeSignature,
+    pub method: MethodDecl,
+}
+
+///The type extended by the top level function declared at `idx`, if it is an extension function.
+pub(crate) fn extension_receiver(ir_stack: &[HIR], idx: usize) -> Option<TypeSignature>{
+    ir_stack[idx + 1 ..]
+        .iter()
+        .find(|ir| !matches!(ir.ins, HIRInstruction::TypeParam(_)))
+        .and_then(|ir| match ir.ins{
+            HIRInstruction::SelfParam(_) => Some(ir.sig.clone()),
+            _ => None,
+        })
+}
+
+impl TypeckVM{
+    ///Record the extension functions declared at the top level of the module.
+    pub(crate) fn declare_extensions(&mut self){
+        let mut depth = 0;
+        for idx in 0 .. self.ir_stack.len(){
+            let ir = &self.ir_stack[idx];
+            match &ir.ins{
+                HIRInstruction::Fn(name) => {
+                    if let (0, TypeSignature::Function(sig), Some(receiver)) = (depth, &ir.sig, extension_receiver(&self.ir_stack, idx)){
+                        let method = MethodDecl{
+                            name: name.clone(),
+                            sig: sig.clone(),
+                            receiver: Some(ReceiverKind::Value),
+                            default: true,
+                            visibility: self.visibility(idx),
+                            decl: Some(idx),
+                        };
+                        self.extensions.push(ExtensionDecl{ receiver, method });
+                    }
+                    depth += 1;
+                },
+                HIRInstruction::Interface(_) | HIRInstruction::Impl(_) => depth += 1,
+                HIRInstruction::EndFn | HIRInstruction::EndInterface | HIRInstruction::EndImpl => depth -= 1,
+                _ => {}
+            }
+        }
+    }
+
+    ///The extension function declared at `idx`, if the function there is one.
+    pub(crate) fn extension(&self, idx: usize) -> Option<&ExtensionDecl>{
+        self.extensions.iter().find(|extension| extension.method.decl == Some(idx))
+    }
+
+    pub(crate) fn is_extension(&self, idx: usize) -> bool{
+        self.extension(idx).is_some()
+    }
+
+    ///Check an extension function, which must not be declared twice for the same type.
+    ///An extension with the name of a method its type already has can never be called, so it gets a warning.
+    pub(crate) fn check_extension(&mut self, idx: usize) -> Result<usize, ()>{
+        let extension = self.extension(idx).cloned().unwrap();
+        let name = &extension.method.name;
+        let first = self.extensions
+            .iter()
+            .find(|other| other.method.name == *name && other.receiver == extension.receiver)
+            .and_then(|other| other.method.decl)
+            .unwrap();
+        if first != idx{
+            return self.declaration_error(
+                format!("Extension function {} is declared more than once for type {}", name, extension.receiver),
+                idx,
+                first,
+                format!("{} is first declared here", name)
+            )
+        }
+        if !extension.receiver.is_generic() && !self.members(&extension.receiver, name).is_empty(){
+            self.warning(
+                format!("Extension function {} is shadowed by the method {} that {} already has, so it is never called", name, name, extension.receiver),
+                idx
+            );
+        }
+        self.check_function(idx)
+    }
+
+    ///Find the extension function `name` that applies to a receiver of type `receiver`, for the method call at `idx`.
+    ///Every extension whose type matches the receiver is a candidate, and more than one candidate is an error.
+    pub(crate) fn resolve_extension(&mut self, receiver: &TypeSignature, name: &str, idx: usize) -> Result<Option<MethodDecl>, ()>{
+        let candidates = self.extensions
+            .iter()
+            .filter(|extension| extension.method.name == name)
+            .filter_map(|extension| {
+                let mut substitutions = HashMap::new();
+                if !extension.receiver.unify(receiver, &mut substitutions){
+                    return None
+                }
+                let bounded = extension.method.sig.type_parameters.iter().all(|param| {
+                    substitutions
+                        .get(&param.name)
+                        .is_none_or(|argument| param.bounds.iter().all(|bound| self.implements(argument, bound)))
+                });
+                if !bounded{
+                    return None
+                }
+                Some((extension.clone(), substitutions))
+            })
+            .collect::<Vec<(ExtensionDecl, HashMap<String, TypeSignature>)>>();
+        match candidates.as_slice(){
+            [] => Ok(None),
+            [(extension, substitutions)] => match TypeSignature::Function(extension.method.sig.clone()).substitute(substitutions){
+                TypeSignature::Function(sig) => Ok(Some(MethodDecl{ sig, ..extension.method.clone() })),
+                _ => unreachable!(),
+            },
+            [(first, _), (second, _), ..] => {
+                let ret = self.error(
+                    format!(
+                        "Ambiguous call of extension function {} on type {}: it is declared for both {} and {}",
+                        name, receiver, first.receiver, second.receiver
+                    ),
+                    idx
+                );
+                for (extension, _) in candidates.iter(){
+                    let pos = self.ir_stack[extension.method.decl.unwrap()].pos;
+                    let _ = self.emit_notice(
+                        format!("{} is declared for type {} here", name, extension.receiver),
+                        NoticeLevel::Notice,
+                        pos
+                    );
+                }
+                ret
+            },
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use crate::{FieldDecl, StructDecl, TypeckVM};
+use ir::hir::{HIRInstruction, Visibility};
+
+impl TypeckVM{
+    ///Declare what the module imported at `idx` exports, as found in its interface.
+    ///Its structs are declared at the import, which is where errors about them point,
+    ///while notes about their fields point at the fields in the source of the module.
+    pub(crate) fn declare_import(&mut self, idx: usize, name: &str){
+        let interface = match self.imports.get(name){
+            Some(interface) => interface,
+            None => return,
+        };
+        for function in interface.functions.iter(){
+            self.globals.insert(function.name.clone(), function.sig.clone());
+        }
+        for constant in interface.constants.iter(){
+            self.globals.insert(constant.name.clone(), constant.sig.clone());
+        }
+        for layout in interface.structs.iter(){
+            let fields = layout.fields
+                .iter()
+                .map(|field| FieldDecl{
+                    name: field.name.clone(),
+                    sig: field.sig.clone(),
+                    //Fields that aren't public can't be used outside of their own module
+                    visibility: if field.visibility == Visibility::Public{ Visibility::Public }else{ Visibility::Private },
+                    decl: idx,
+                    origin: Some((interface.name.clone(), field.pos)),
+                })
+                .collect();
+            let decl = StructDecl{ params: layout.params.clone(), fields, visibility: Visibility::Public, decl: idx };
+            self.structs.insert(layout.name.clone(), decl);
+        }
+    }
+
+    ///The index of the top level function, property, constant, struct or newtype `name` of the module, if there is one.
+    fn top_level_declaration(&self, name: &str) -> Option<usize>{
+        let mut depth = 0;
+        for (idx, ir) in self.ir_stack.iter().enumerate(){
+            match &ir.ins{
+                HIRInstruction::Fn(declared) if depth == 0 && declared == name => return Some(idx),
+                HIRInstruction::NativeFn(declared)
+                | HIRInstruction::Property(declared, _)
+                | HIRInstruction::Const(declared)
+                | HIRInstruction::Struct(declared)
+                | HIRInstruction::Newtype(declared) if depth == 0 && declared == name => return Some(idx),
+                HIRInstruction::Fn(_) | HIRInstruction::Interface(_) | HIRInstruction::Impl(_) => depth += 1,
+                HIRInstruction::EndFn | HIRInstruction::EndInterface | HIRInstruction::EndImpl => depth -= 1,
+                _ => {}
+            }
+        }
+        None
+    }
+
+    ///Check that the module imported at `idx` was found, and that this module doesn't declare anything it exports.
+    pub(crate) fn check_import(&mut self, idx: usize, name: &str) -> Result<usize, ()>{
+        let interface = match self.imports.get(name){
+            Some(interface) => interface,
+            None => return self.error(format!("Could not find the interface of the imported module {}", name), idx),
+        };
+        let exported = interface.functions
+            .iter()
+            .map(|function| &function.name)
+            .chain(interface.constants.iter().map(|constant| &constant.name))
+            .chain(interface.structs.iter().map(|layout| &layout.name))
+            .cloned()
+            .collect::<Vec<String>>();
+        for export in exported{
+            if let Some(decl) = self.top_level_declaration(&export){
+                return self.declaration_error(
+                    format!("{} is declared both in this module and in the imported module {}", export, name),
+                    idx,
+                    decl,
+                    format!("{} is declared here", export)
+                )
+            }
+        }
+        Ok(idx + 1)
+    }
+}
@@ -0,0 +1,401 @@
+use crate::{collections::builtin_method, TypeckVM};
+use ir::{
+    hir::{HIRInstruction, ReceiverKind, Visibility, HIR},
+    type_signature::{FunctionSignature, TypeSignature},
+};
+use std::collections::HashMap;
+
+///A method declared by an interface or defined in an impl block.
+#[derive(Debug, Clone)]
+pub(crate) struct MethodDecl{
+    pub name: String,
+    pub sig: FunctionSignature,
+    ///How the method takes `self`, or None for an associated function
+    pub receiver: Option<ReceiverKind>,
+    ///Whether the method has a body, which for an interface method is its default implementation
+    pub default: bool,
+    pub visibility: Visibility,
+    ///The index of the instruction declaring the method, if it is declared in the module
+    pub decl: Option<usize>,
+}
+
+///The name the implementations of a type are recorded under, if the type can implement interfaces.
+pub(crate) fn type_key(sig: &TypeSignature) -> Option<String>{
+    match sig{
+        TypeSignature::Struct(s) => Some(s.name.clone()),
+        TypeSignature::Primitive(p) => Some(p.to_string()),
+        _ => None,
+    }
+}
+
+///The receiver of the function declared at `idx`, if it has one.
+fn receiver_of(ir_stack: &[HIR], idx: usize) -> Option<ReceiverKind>{
+    ir_stack[idx + 1 ..]
+        .iter()
+        .find(|ir| !matches!(ir.ins, HIRInstruction::TypeParam(_)))
+        .and_then(|ir| match ir.ins{
+            HIRInstruction::SelfParam(receiver) => Some(receiver),
+            _ => None,
+        })
+}
+
+fn describe_receiver(receiver: Option<ReceiverKind>) -> String{
+    match receiver{
+        Some(receiver) => format!("'{}'", receiver),
+        None => "no receiver".to_string(),
+    }
+}
+
+fn self_substitution(self_type: &TypeSignature) -> HashMap<String, TypeSignature>{
+    let mut substitutions = HashMap::new();
+    substitutions.insert("Self".to_string(), self_type.clone());
+    substitutions
+}
+
+impl TypeckVM{
+    ///Record the methods of every interface, which types implement which interfaces,
+    ///and the methods and associated functions defined in impl blocks of each type.
+    pub(crate) fn declare_interfaces(&mut self){
+        let mut interface: Option<String> = None;
+        let mut impl_key: Option<String> = None;
+        let mut depth = 0;
+        for idx in 0 .. self.ir_stack.len(){
+            let ir = &self.ir_stack[idx];
+            match &ir.ins{
+                HIRInstruction::Interface(name) => {
+                    interface = Some(name.clone());
+                    self.interfaces.insert(name.clone(), Vec::new());
+                },
+                HIRInstruction::EndInterface => interface = None,
+                HIRInstruction::Impl(Some(name)) => {
+                    if let Some(key) = type_key(&ir.sig){
+                        self.implementations.entry(key).or_default().push(name.clone());
+                    }
+                },
+                HIRInstruction::Impl(None) => impl_key = type_key(&ir.sig),
+                HIRInstruction::EndImpl => impl_key = None,
+                HIRInstruction::InterfaceMethod(name, receiver) => {
+                    if let (Some(interface), TypeSignature::Function(sig)) = (&interface, &ir.sig){
+                        self.interfaces.get_mut(interface).unwrap().push(MethodDecl{
+                            name: name.clone(),
+                            sig: sig.clone(),
+                            receiver: Some(*receiver),
+                            default: false,
+                            visibility: Visibility::default(),
+                            decl: Some(idx),
+                        });
+                    }
+                },
+                HIRInstruction::Fn(name) => {
+                    if let (0, TypeSignature::Function(sig)) = (depth, &ir.sig){
+                        let method = MethodDecl{
+                            name: name.clone(),
+                            sig: sig.clone(),
+                            receiver: receiver_of(&self.ir_stack, idx),
+                            default: true,
+                            visibility: self.visibility(idx),
+                            decl: Some(idx),
+                        };
+                        if let Some(interface) = &interface{
+                            self.interfaces.get_mut(interface).unwrap().push(method);
+                        }else if let Some(key) = &impl_key{
+                            if method.receiver.is_none(){
+                                self.globals.insert(format!("{}.{}", key, name), ir.sig.clone());
+                            }
+                            self.methods.entry(key.clone()).or_default().push(method);
+                        }
+                    }
+                    depth += 1;
+                },
+                HIRInstruction::EndFn => depth -= 1,
+                _ => {}
+            }
+        }
+    }
+
+    ///Whether values of type `sig` can be used where interface `interface` is expected.
+    pub(crate) fn implements(&self, sig: &TypeSignature, interface: &str) -> bool{
+        match sig{
+            TypeSignature::Dyn(i) => i == interface,
+            TypeSignature::Param(p) => p.bounds.iter().any(|b| b == interface),
+            _ => type_key(sig)
+                .and_then(|key| self.implementations.get(&key))
+                .is_some_and(|interfaces| interfaces.iter().any(|i| i == interface)),
+        }
+    }
+
+    pub(crate) fn check_interface(&mut self, idx: usize) -> Result<usize, ()>{
+        let mut names = Vec::<String>::new();
+        let mut idx = idx + 1;
+        loop{
+            idx = match self.ir_stack[idx].ins.clone(){
+                HIRInstruction::EndInterface => return Ok(idx + 1),
+                HIRInstruction::InterfaceMethod(name, _) | HIRInstruction::Fn(name) => {
+                    if names.contains(&name){
+                        return self.error(format!("Method {} is declared more than once.", name), idx)
+                    }
+                    names.push(name);
+                    if let HIRInstruction::Fn(_) = self.ir_stack[idx].ins{
+                        self.check_function(idx)?
+                    }else{
+                        let sig = self.ir_stack[idx].sig.clone();
+                        self.check_type(&sig, idx)?;
+                        idx + 1
+                    }
+                },
+                _ => idx + 1,
+            };
+        }
+    }
+
+    pub(crate) fn check_impl(&mut self, idx: usize) -> Result<usize, ()>{
+        let self_type = self.ir_stack[idx].sig.clone();
+        self.check_type(&self_type, idx)?;
+        let key = match type_key(&self_type){
+            Some(key) => key,
+            None => return self.error(format!("Cannot implement methods for type {}", self_type), idx),
+        };
+        let outer_impl = self.current_impl.replace(key.clone());
+        let ret = match self.ir_stack[idx].ins.clone(){
+            HIRInstruction::Impl(Some(interface)) => self.check_interface_impl(idx, &interface, &key, &self_type),
+            _ => self.check_inherent_impl(idx, &key),
+        };
+        self.current_impl = outer_impl;
+        ret
+    }
+
+    ///Check the methods and associated functions a type defines for itself.
+    fn check_inherent_impl(&mut self, idx: usize, key: &str) -> Result<usize, ()>{
+        let mut idx = idx + 1;
+        loop{
+            idx = match self.ir_stack[idx].ins.clone(){
+                HIRInstruction::EndImpl => return Ok(idx + 1),
+                HIRInstruction::Fn(name) => {
+                    if self.methods[key].iter().filter(|m| m.name == name).count() > 1{
+                        return self.error(format!("Method {} is defined more than once for {}", name, key), idx)
+                    }
+                    self.check_function(idx)?
+                },
+                _ => idx + 1,
+            };
+        }
+    }
+
+    ///Check that an implementation provides exactly the methods its interface asks for, with matching signatures.
+    fn check_interface_impl(&mut self, idx: usize, interface: &str, key: &str, self_type: &TypeSignature) -> Result<usize, ()>{
+        let impl_idx = idx;
+        let methods = match self.interfaces.get(interface){
+            Some(methods) => methods.clone(),
+            None => return self.error(format!("Unknown interface {}", interface), idx),
+        };
+        if self.implementations[key].iter().filter(|i| *i == interface).count() > 1{
+            return self.error(format!("Interface {} is implemented more than once for {}", interface, self_type), idx)
+        }
+
+        let substitutions = self_substitution(self_type);
+        let mut provided = Vec::<String>::new();
+        let mut idx = idx + 1;
+        loop{
+            idx = match self.ir_stack[idx].ins.clone(){
+                HIRInstruction::EndImpl => break,
+                HIRInstruction::Visibility(_) => return self.error(
+                    format!("Methods implementing interface {} have the visibility of the interface and cannot be given their own.", interface),
+                    idx
+                ),
+                HIRInstruction::Fn(name) => {
+                    let (expected, receiver) = match methods.iter().find(|m| m.name == name){
+                        Some(m) => (TypeSignature::Function(m.sig.clone()).substitute(&substitutions), m.receiver),
+                        None => return self.error(format!("Method {} is not a member of interface {}", name, interface), idx),
+                    };
+                    if self.ir_stack[idx].sig != expected{
+                        return self.error(
+                            format!("Method {} has the signature {} but interface {} requires {}", name, self.ir_stack[idx].sig, interface, expected),
+                            idx
+                        )
+                    }
+                    let found = receiver_of(&self.ir_stack, idx);
+                    if found != receiver{
+                        return self.error(
+                            format!("Method {} takes {} but interface {} requires {}", name, describe_receiver(found), interface, describe_receiver(receiver)),
+                            idx
+                        )
+                    }
+                    if provided.contains(&name){
+                        return self.error(format!("Method {} is implemented more than once.", name), idx)
+                    }
+                    provided.push(name);
+                    self.check_function(idx)?
+                },
+                _ => idx + 1,
+            };
+        }
+        for method in methods.iter(){
+            if !method.default && !provided.contains(&method.name){
+                return self.error(
+                    format!("Missing method {} required by interface {} in its implementation for {}", method.name, interface, self_type),
+                    impl_idx
+                )
+            }
+        }
+        Ok(idx + 1)
+    }
+
+    ///The methods named `name` that values of type `receiver` have, along with the interface providing each one.
+    ///Methods a type defines for itself take precedence over the methods of the interfaces it implements.
+    pub(crate) fn members(&self, receiver: &TypeSignature, name: &str) -> Vec<(String, MethodDecl)>{
+        if let Some(method) = builtin_method(receiver, name){
+            return vec![(String::new(), method)]
+        }
+        let inherent = type_key(receiver)
+            .and_then(|key| self.methods.get(&key))
+            .and_then(|methods| methods.iter().find(|m| m.name == name))
+            .cloned();
+        if let Some(method) = inherent{
+            return vec![(String::new(), method)]
+        }
+        let interfaces = match receiver{
+            TypeSignature::Dyn(interface) => vec![interface.clone()],
+            TypeSignature::Param(param) => param.bounds.clone(),
+            _ => type_key(receiver)
+                .and_then(|key| self.implementations.get(&key))
+                .cloned()
+                .unwrap_or_default(),
+        };
+        interfaces
+            .iter()
+            .filter_map(|interface| {
+                self.interfaces
+                    .get(interface)
+                    .and_then(|methods| methods.iter().find(|m| m.name == name))
+                    .map(|m| (interface.clone(), m.clone()))
+            })
+            .collect()
+    }
+
+    ///Find the method `name` called on a receiver of type `receiver` by the call at `idx`.
+    ///The methods of the type come first, and extension functions are only looked at when it has none by that name.
+    pub(crate) fn resolve_method(&mut self, receiver: &TypeSignature, name: &str, idx: usize) -> Result<MethodDecl, ()>{
+        let found = self.members(receiver, name);
+        match found.as_slice(){
+            [] => match self.resolve_extension(receiver, name, idx)?{
+                Some(method) => Ok(method),
+                None => self.error(format!("No method {} found for type {}", name, receiver), idx),
+            },
+            [(_, method)] if method.receiver.is_none() => self.error(
+                format!("{} is an associated function of {} and must be called as {}.{}(...)", name, receiver, receiver, name),
+                idx
+            ),
+            [(_, method)] => match TypeSignature::Function(method.sig.clone()).substitute(&self_substitution(receiver)){
+                TypeSignature::Function(sig) => Ok(MethodDecl{ sig, ..method.clone() }),
+                _ => unreachable!(),
+            },
+            [(first, _), (second, _), ..] => self.error(
+                format!("Ambiguous call of method {}: it is provided by both {} and {}", name, first, second),
+                idx
+            ),
+        }
+    }
+
+    ///Whether the expression at `idx` is a place, which names an object rather than computing a new one.
+    pub(crate) fn is_place(&self, idx: usize) -> bool{
+        match &self.ir_stack[idx].ins{
+            HIRInstruction::LocalRef(_) | HIRInstruction::GlobalRef(_) => true,
+            HIRInstruction::Field(_) => self.is_place(idx + 1),
+            _ => false,
+        }
+    }
+
+    ///Whether the place computed by the expression at `idx` may be mutated.
+    pub(crate) fn is_mutable(&self, idx: usize) -> bool{
+        match &self.ir_stack[idx].ins{
+            HIRInstruction::LocalRef(_) => self.local(idx).1,
+            HIRInstruction::GlobalRef(name) => self.mutable_globals.contains(name),
+            HIRInstruction::Field(_) => self.is_mutable(idx + 1),
+            _ => true,
+        }
+    }
+
+    pub(crate) fn check_method_call(&mut self, idx: usize, name: &str, argc: usize) -> Result<(TypeSignature, usize), ()>{
+        if let HIRInstruction::GlobalRef(type_name) = self.ir_stack[idx + 1].ins.clone(){
+            if !self.globals.contains_key(&type_name){
+                return self.check_associated_call(idx, &type_name, name, argc)
+            }
+        }
+        let (receiver, next) = self.check_expr(idx + 1)?;
+        let method = self.resolve_method(&receiver, name, idx)?;
+        if let (Some(owner), Some(decl)) = (type_key(&receiver), method.decl.filter(|decl| !self.is_extension(*decl))){
+            self.check_private_access(&owner, &format!("Method {}", name), method.visibility, idx, decl, None)?;
+        }
+        if method.receiver == Some(ReceiverKind::MutRef) && !self.is_mutable(idx + 1){
+            return self.error(
+                format!("Cannot call method {}, which takes '&mut self', on an immutable value", name),
+                idx + 1
+            )
+        }
+        let (instantiated, next) = self.check_args(idx, method.sig, next, argc)?;
+        let ret = (*instantiated.return_type_signature).clone();
+        self.ir_stack[idx].sig = ret.clone();
+        Ok((ret, next))
+    }
+
+    ///Check a call such as `Point.new(1, 2)` of an associated function through the name of its type.
+    ///The call is rewritten into a plain call of the function, which is known as `Point.new`.
+    fn check_associated_call(&mut self, idx: usize, type_name: &str, name: &str, argc: usize) -> Result<(TypeSignature, usize), ()>{
+        let function = format!("{}.{}", type_name, name);
+        if !self.globals.contains_key(&function){
+            return if self.structs.contains_key(type_name) || self.methods.contains_key(type_name){
+                self.error(format!("No associated function {} found for type {}", name, type_name), idx)
+            }else{
+                self.error(format!("Unknown name {}", type_name), idx + 1)
+            }
+        }
+        let method = self.methods[type_name].iter().find(|m| m.name == name).cloned();
+        if let Some(MethodDecl{ visibility, decl: Some(decl), .. }) = method{
+            self.check_private_access(type_name, &format!("Function {}", name), visibility, idx, decl, None)?;
+        }
+        self.ir_stack[idx].ins = HIRInstruction::Call(argc);
+        self.ir_stack[idx + 1].ins = HIRInstruction::GlobalRef(function);
+        self.check_call(idx, argc)
+    }
+
+    ///Check an access of field `name`, whose struct is computed by the expression following `idx`.
+    pub(crate) fn check_field(&mut self, idx: usize, name: &str) -> Result<(TypeSignature, usize), ()>{
+        let (receiver, next) = self.check_expr(idx + 1)?;
+        let field = match &receiver{
+            TypeSignature::Struct(s) => self.structs
+                .get(&s.name)
+                .and_then(|decl| decl.field(name, &s.type_arguments))
+                .map(|(field, sig)| (s.name.clone(), field.visibility, field.decl, field.origin.clone(), sig)),
+            _ => None,
+        };
+        match field{
+            Some((owner, visibility, decl, origin, sig)) => {
+                self.check_private_access(&owner, &format!("Field {}", name), visibility, idx, decl, origin)?;
+                self.ir_stack[idx].sig = sig.clone();
+                Ok((sig, next))
+            },
+            None => {
+                //Only the fields that could be used here are suggested
+                let fields = match &receiver{
+                    TypeSignature::Struct(s) => self.structs
+                        .get(&s.name)
+                        .map(|decl| {
+                            decl.fields
+                                .iter()
+                                .filter(|field| field.visibility != Visibility::Private || self.current_impl.as_deref() == Some(s.name.as_str()))
+                                .map(|field| field.name.clone())
+                                .collect()
+                        })
+                        .unwrap_or_default(),
+                    _ => Vec::new(),
+                };
+                self.error_with_suggestion(
+                    format!("Type {} has no field {}", receiver, name),
+                    idx,
+                    name,
+                    fields.iter().map(String::as_str)
+                )
+            },
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use crate::{collections::element_type, TypeckVM};
+use ir::{
+    hir::{HIRInstruction, ReceiverKind},
+    type_signature::{CallingConvention, FunctionSignature, GenericParam, Parameter, PrimitiveType, TypeSignature},
+};
+
+///The constructors of optionals, `Some(value)` and `None`.
+pub(crate) fn builtin_globals() -> Vec<(String, TypeSignature)>{
+    let param = GenericParam{
+        name: "T".to_string(),
+        bounds: Vec::new(),
+    };
+    let some = TypeSignature::Function(FunctionSignature{
+        type_parameters: vec![param.clone()],
+        parameters: vec![Parameter::new("value", TypeSignature::Param(param.clone()))],
+        variadic: false,
+        return_type_signature: Box::new(TypeSignature::Option(Box::new(TypeSignature::Param(param)))),
+        calling_convention: CallingConvention::Beagle,
+    });
+    vec![
+        ("Some".to_string(), some),
+        ("None".to_string(), TypeSignature::Option(Box::new(TypeSignature::Untyped))),
+    ]
+}
+
+///Whether `sig` is the type of `None`, `Ok`, `Err` or of an empty array, which take their element type from where they are used.
+pub(crate) fn is_placeholder(sig: &TypeSignature) -> bool{
+    match sig{
+        TypeSignature::Array(element, 0) | TypeSignature::Option(element) => **element == TypeSignature::Untyped,
+        TypeSignature::Result(value, error) => **value == TypeSignature::Untyped || **error == TypeSignature::Untyped,
+        _ => false,
+    }
+}
+
+impl TypeckVM{
+    pub(crate) fn check_range(&mut self, idx: usize) -> Result<(TypeSignature, usize), ()>{
+        let int = TypeSignature::Primitive(PrimitiveType::Integer);
+        let mut next = idx + 1;
+        for _ in 0 .. 2{
+            let (found, bound_next) = self.check_expr(next)?;
+            self.cmp_types(&int, &found, next)?;
+            next = bound_next;
+        }
+        let sig = TypeSignature::Range(Box::new(int));
+        self.ir_stack[idx].sig = sig.clone();
+        Ok((sig, next))
+    }
+
+    ///The type of the values produced by iterating over a value of type `iterable`.
+    ///Arrays and lists produce their elements, and anything else must have a method `next(&mut self): Option<T>`.
+    fn iterated_type(&mut self, iterable: &TypeSignature, idx: usize) -> Result<TypeSignature, ()>{
+        if let Some(element) = element_type(iterable){
+            return Ok(element)
+        }
+        let method = self.resolve_method(iterable, "next", idx)?;
+        match (method.receiver, method.sig.parameters.len(), *method.sig.return_type_signature){
+            (Some(ReceiverKind::MutRef), 0, TypeSignature::Option(element)) => Ok(*element),
+            _ => self.error(
+                format!("Type {} cannot be iterated: its method next must have the signature next(&mut self): Option<T>", iterable),
+                idx
+            ),
+        }
+    }
+
+    ///Check a for loop, whose values are bound to the local it declares for the duration of its body.
+    pub(crate) fn check_for(&mut self, idx: usize) -> Result<usize, ()>{
+        let (iterable, next) = self.check_expr(idx + 1)?;
+        let element = self.iterated_type(&iterable, idx + 1)?;
+        self.ir_stack[idx].sig = element.clone();
+        self.declare_local(idx, element, false);
+        self.check_statements(next, HIRInstruction::EndFor)
+    }
+}
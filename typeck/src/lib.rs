@@ -2,37 +2,144 @@ use ir::{
     type_signature::{
         TypeSignature,
         PrimitiveType,
+        FunctionSignature,
+        Parameter,
+        CallingConvention,
+        GenericParam,
+        StructSignature,
     },
     hir::{
         HIR,
-        HIRInstruction
+        HIRInstruction,
+        Module,
+        ReceiverKind,
+        Visibility,
     },
+    interface::ModuleInterface,
+    pass::HirMutVisitor,
+    symbols::SymbolId,
 };
 use notices::*;
-use std::sync::mpsc::{
-    Sender, Receiver
-};
+use std::sync::mpsc::Sender;
+use std::collections::{HashMap, HashSet};
 
 use core::pos::BiPos;
 
+mod annotations;
+mod collections;
+mod constants;
+mod control_flow;
+mod extensions;
+mod imports;
+mod interfaces;
+mod iteration;
+mod operators;
+mod resolve;
+mod results;
+mod visibility;
+use extensions::ExtensionDecl;
+use interfaces::MethodDecl;
+pub use resolve::Resolver;
+
+///The types the language provides, which are named without being declared.
+const BUILTIN_TYPES: [&str; 9] = ["Int", "Float", "Bool", "String", "Unit", "List", "Option", "Range", "Result"];
+
+///A declared struct type.
+struct StructDecl{
+    params: Vec<GenericParam>,
+    fields: Vec<FieldDecl>,
+    visibility: Visibility,
+    ///The index of the `Struct` instruction
+    decl: usize,
+}
+
+struct FieldDecl{
+    name: String,
+    sig: TypeSignature,
+    visibility: Visibility,
+    ///The index of the `StructField` instruction, or of the import for a field of an imported struct
+    decl: usize,
+    ///For a field of an imported struct, the module declaring it and where in its source
+    origin: Option<(String, BiPos)>,
+}
+
+impl StructDecl{
+    ///The field `name` along with its type in an instance of the struct with the given type arguments.
+    fn field(&self, name: &str, type_arguments: &[TypeSignature]) -> Option<(&FieldDecl, TypeSignature)>{
+        let field = self.fields.iter().find(|field| field.name == name)?;
+        let substitutions = self.params
+            .iter()
+            .map(|param| param.name.clone())
+            .zip(type_arguments.iter().cloned())
+            .collect();
+        Some((field, field.sig.substitute(&substitutions)))
+    }
+
+    ///The signature of the function constructing the struct `name` from its fields, in declaration order.
+    fn constructor(&self, name: &str) -> FunctionSignature{
+        FunctionSignature{
+            type_parameters: self.params.clone(),
+            parameters: self.fields.iter().map(|field| Parameter::new(&field.name, field.sig.clone())).collect(),
+            variadic: false,
+            return_type_signature: Box::new(TypeSignature::Struct(StructSignature{
+                name: name.to_string(),
+                type_arguments: self.params.iter().cloned().map(TypeSignature::Param).collect(),
+            })),
+            calling_convention: CallingConvention::Beagle,
+        }
+    }
+}
+
 pub struct TypeckVM{
     module_name: String,
     ir_stack: Vec<HIR>,
-    ir_rx: Receiver<Option<HIR>>,
     notice_tx: Sender<Option<Notice>>,
-    typeck_tx: Sender<Option<HIR>>,
+    ///Signatures of everything declared at the top level of the module
+    globals: HashMap<String, TypeSignature>,
+    ///The names of the top level properties declared with var
+    mutable_globals: HashSet<String>,
+    ///The declared struct types
+    structs: HashMap<String, StructDecl>,
+    ///The type of each param and local checked so far, and whether it is mutable, by the symbol names resolve to
+    locals: HashMap<SymbolId, (TypeSignature, bool)>,
+    ///The methods of each declared interface, in declaration order
+    interfaces: HashMap<String, Vec<MethodDecl>>,
+    ///The interfaces implemented by each type
+    implementations: HashMap<String, Vec<String>>,
+    ///The methods and associated functions each type defines in its own impl blocks
+    methods: HashMap<String, Vec<MethodDecl>>,
+    ///The extension functions declared in the module, in declaration order
+    extensions: Vec<ExtensionDecl>,
+    ///The type whose impl block is being checked, which may use the private fields and methods of the type
+    current_impl: Option<String>,
+    ///The top level declarations annotated with `@deprecated`, along with their deprecation message
+    deprecated: HashMap<String, Option<String>>,
+    ///The declared return type of the function being checked
+    return_type: Option<TypeSignature>,
+    ///The interfaces of the modules the module may import, by name
+    imports: HashMap<String, ModuleInterface>,
 }
 
 impl TypeckVM{
     fn emit_notice(&mut self, msg: String, level: NoticeLevel, pos: BiPos) -> Result<(),()>{
+        self.emit_notice_with_help(msg, level, pos, None)
+    }
+
+    fn emit_notice_with_help(&mut self, msg: String, level: NoticeLevel, pos: BiPos, help: Option<String>) -> Result<(),()>{
+        self.emit_notice_in(self.module_name.clone(), msg, level, pos, help)
+    }
+
+    ///Emit a notice at `pos` in the source of the module `file`.
+    fn emit_notice_in(&mut self, file: String, msg: String, level: NoticeLevel, pos: BiPos, help: Option<String>) -> Result<(),()>{
         if level == NoticeLevel::Error{
             if self.notice_tx.send(
                 Some(notices::Notice{
                     from: "Type checker came back with an error.".to_string(),
                     msg: msg.clone(),
-                    file: self.module_name.clone(),
+                    file: file.clone(),
                     level,
-                    pos
+                    pos,
+                    help: None,
                 })
             ).is_err(){
                 return Err(())
@@ -43,9 +150,10 @@ impl TypeckVM{
             Some(notices::Notice{
                 from: "Type checker".to_string(),
                 msg,
-                file: self.module_name.clone(),
+                file,
                 level,
-                pos
+                pos,
+                help,
             })
         ).is_err(){
             return Err(())
@@ -53,179 +161,571 @@ impl TypeckVM{
         Ok(())
     }
 
-    fn cmp_types(&mut self) -> Result<(), ()>{
-        Ok(())
+    ///Report an error at the instruction at `idx` and fail.
+    fn error<T>(&mut self, msg: String, idx: usize) -> Result<T, ()>{
+        let pos = self.ir_stack[idx].pos;
+        let _ = self.emit_notice(msg, NoticeLevel::Error, pos);
+        Err(())
     }
 
-    fn check(&mut self) -> Result<(),()>{
-        loop{
-            let ir = if let Ok(Some(ir)) = self.ir_rx.recv(){
-                ir
-            }else{
+    ///Report an error about the misspelled `name` at the instruction at `idx` and fail,
+    ///suggesting the candidate closest to it.
+    fn error_with_suggestion<'a, T>(&mut self, msg: String, idx: usize, name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Result<T, ()>{
+        let pos = self.ir_stack[idx].pos;
+        let help = notices::closest_name(name, candidates).map(|candidate| format!("Did you mean `{}`?", candidate));
+        let _ = self.emit_notice_with_help(msg, NoticeLevel::Error, pos, help);
+        Err(())
+    }
+
+    fn cmp_types(&mut self, expected: &TypeSignature, found: &TypeSignature, idx: usize) -> Result<(), ()>{
+        if expected == found{
+            return Ok(())
+        }
+        if let TypeSignature::Dyn(interface) = expected{
+            if self.implements(found, interface){
                 return Ok(())
-            };
-            let ir_clone = ir.clone();
-            let ins = ir.ins;
-            if ins == HIRInstruction::Halt{
-                self.typeck_tx.send(Some(ir_clone)).unwrap();
-                break
             }
-            let sig = ir.sig.clone();
-            match &sig{
-                TypeSignature::Primitive(p) => {
-                    match ins{
-                        HIRInstruction::FnParam(_) => self.ir_stack.push(ir_clone),
-                        _ => {
-                            let next_ir = if let Ok(Some(ir)) = self.ir_rx.recv(){
-                                ir
-                            }else{
-                                return Ok(())
-                            };
-                            match p{
-                                PrimitiveType::Integer => {
-                                    match ins{
-                                        HIRInstruction::Integer(_) => self.ir_stack.push(HIR{
-                                            pos: ir.pos.clone(),
-                                            sig,
-                                            ins
-                                        }),
-                                        _ => {
-                                            if self.emit_notice(
-                                                format!("Expected an expression of type Integer but instead got {:?}", next_ir.sig),
-                                                NoticeLevel::Error,
-                                                ir.pos
-                                            ).is_err(){
-                                                return Err(())
-                                            }
-                                            return Err(())
-        
-                                        }
-                                    };
-                                },
-                                PrimitiveType::Float => {
-                                    match ins{
-                                        HIRInstruction::Float(_) => self.ir_stack.push(HIR{
-                                            pos: ir.pos.clone(),
-                                            sig,
-                                            ins
-                                        }),
-                                        _ => {
-                                            if self.emit_notice(
-                                                format!("Expected an expression of type Float but instead got {:?}", next_ir.sig),
-                                                NoticeLevel::Error,
-                                                ir.pos
-                                            ).is_err(){
-                                                return Err(())
-                                            }
-                                            return Err(())
-        
-                                        }
-                                    };
-                                },
-                                PrimitiveType::String => {
-                                    match ins{
-                                        HIRInstruction::String(_) => self.ir_stack.push(HIR{
-                                            pos: ir.pos.clone(),
-                                            sig,
-                                            ins
-                                        }),
-                                        _ => {
-                                            if self.emit_notice(
-                                                format!("Expected an expression of type String but instead got {:?}", next_ir.sig),
-                                                NoticeLevel::Error,
-                                                ir.pos
-                                            ).is_err(){
-                                                return Err(())
-                                            }
-                                            return Err(())
-                                        }
-                                    };
-                                }
-                                _ => {
-                                    if self.emit_notice(
-                                        format!("Unexpected type: {:?}", next_ir.sig),
-                                        NoticeLevel::Error,
-                                        ir.pos
-                                    ).is_err(){
-                                        return Err(())
-                                    }
-                                    return Err(())
-        
-                                }
-                            }
-                        }
+        }
+        //An array can be used as a list of the same elements, an empty array as any array or list,
+        //`None` as any optional, and `Ok` or `Err` as any result with the same value or error type
+        match (expected, found){
+            (TypeSignature::Result(value, error), TypeSignature::Result(found_value, found_error))
+                if (value == found_value || **found_value == TypeSignature::Untyped)
+                    && (error == found_error || **found_error == TypeSignature::Untyped) => return Ok(()),
+            (TypeSignature::List(element), TypeSignature::Array(found_element, _))
+            | (TypeSignature::Array(element, 0), TypeSignature::Array(found_element, 0))
+            | (TypeSignature::Option(element), TypeSignature::Option(found_element))
+                if element == found_element || **found_element == TypeSignature::Untyped => return Ok(()),
+            _ => {}
+        }
+        self.error(format!("Expected an expression of type {} but instead got {}", expected, found), idx)
+    }
+
+    ///Record the signatures of every top level function, typed property, struct and interface
+    ///so they can be referenced before their declaration.
+    fn declare(&mut self){
+        self.globals.extend(iteration::builtin_globals());
+        self.globals.extend(results::builtin_globals());
+        let mut depth = 0;
+        for idx in 0 .. self.ir_stack.len(){
+            let ir = &self.ir_stack[idx];
+            match &ir.ins{
+                HIRInstruction::Fn(name) => {
+                    if depth == 0 && extensions::extension_receiver(&self.ir_stack, idx).is_none(){
+                        self.globals.insert(name.clone(), ir.sig.clone());
                     }
+                    depth += 1;
                 },
-                TypeSignature::Untyped => {
-                    let next_ir = if let Ok(Some(ir)) = self.ir_rx.recv(){
-                        ir
-                    }else{
-                        return Ok(())
+                HIRInstruction::NativeFn(name) => {
+                    self.globals.insert(name.clone(), ir.sig.clone());
+                },
+                HIRInstruction::Import(name) => {
+                    let name = name.clone();
+                    self.declare_import(idx, &name);
+                },
+                HIRInstruction::Interface(_) | HIRInstruction::Impl(_) => depth += 1,
+                HIRInstruction::EndFn | HIRInstruction::EndInterface | HIRInstruction::EndImpl => depth -= 1,
+                HIRInstruction::Const(name) if depth == 0 && ir.sig != TypeSignature::Untyped => {
+                    self.globals.insert(name.clone(), ir.sig.clone());
+                },
+                HIRInstruction::Property(name, mutable) if depth == 0 => {
+                    if ir.sig != TypeSignature::Untyped{
+                        self.globals.insert(name.clone(), ir.sig.clone());
+                    }
+                    if *mutable{
+                        self.mutable_globals.insert(name.clone());
+                    }
+                },
+                HIRInstruction::Struct(name) => {
+                    let params = match &ir.sig{
+                        TypeSignature::Struct(s) => s.type_arguments
+                            .iter()
+                            .filter_map(|arg| match arg{
+                                TypeSignature::Param(param) => Some(param.clone()),
+                                _ => None,
+                            })
+                            .collect(),
+                        _ => Vec::new(),
                     };
-                    match &next_ir.ins{
-                        HIRInstruction::Integer(_) => {
-                            self.ir_stack.push(HIR{
-                                pos: ir.pos,
-                                sig: TypeSignature::Primitive(PrimitiveType::Integer),
-                                ins
-                            });
-                        },
-                        HIRInstruction::Float(_) => {
-                            self.ir_stack.push(HIR{
-                                pos: ir.pos,
-                                sig: TypeSignature::Primitive(PrimitiveType::String),
-                                ins
-                            });
-                        },
-                        HIRInstruction::String(_) => {
-                            self.ir_stack.push(HIR{
-                                pos: ir.pos,
-                                sig: TypeSignature::Primitive(PrimitiveType::String),
-                                ins
-                            });
-                        }
-                        HIRInstruction::Bool(_) => {
-                            self.ir_stack.push(HIR{
-                                pos: ir.pos,
-                                sig: TypeSignature::Primitive(PrimitiveType::Bool),
-                                ins
-                            });
-                        }
-                        _ => {
-                            self.ir_stack.push(HIR{
-                                pos: ir.pos,
-                                sig: TypeSignature::Primitive(PrimitiveType::Unit),
-                                ins
-                            });
-                        }
+                    let fields = self.ir_stack[idx + 1 ..]
+                        .iter()
+                        .take_while(|ir| ir.ins != HIRInstruction::EndStruct)
+                        .enumerate()
+                        .filter_map(|(i, ir)| match &ir.ins{
+                            HIRInstruction::StructField(field) => Some(FieldDecl{
+                                name: field.clone(),
+                                sig: ir.sig.clone(),
+                                visibility: self.visibility(idx + 1 + i),
+                                decl: idx + 1 + i,
+                                origin: None,
+                            }),
+                            _ => None,
+                        })
+                        .collect();
+                    let visibility = self.visibility(idx);
+                    self.structs.insert(name.clone(), StructDecl{ params, fields, visibility, decl: idx });
+                },
+                //A newtype is a struct with the single field `value`, so it is a type of its own
+                HIRInstruction::Newtype(name) => {
+                    let visibility = self.visibility(idx);
+                    let value = FieldDecl{ name: "value".to_string(), sig: ir.sig.clone(), visibility, decl: idx, origin: None };
+                    self.structs.insert(name.clone(), StructDecl{ params: Vec::new(), fields: vec![value], visibility, decl: idx });
+                },
+                _ => {}
+            }
+        }
+        self.declare_interfaces();
+        self.declare_extensions();
+    }
+
+    ///Check that every struct mentioned in `sig` exists and is given the right number of type arguments.
+    fn check_type(&mut self, sig: &TypeSignature, idx: usize) -> Result<(), ()>{
+        match sig{
+            TypeSignature::Struct(s) => {
+                match self.structs.get(&s.name).map(|decl| decl.params.len()){
+                    None => {
+                        let types = self.structs
+                            .keys()
+                            .chain(self.interfaces.keys())
+                            .cloned()
+                            .collect::<Vec<String>>();
+                        let candidates = BUILTIN_TYPES.iter().copied().chain(types.iter().map(String::as_str));
+                        return self.error_with_suggestion(format!("Unknown type {}", s.name), idx, &s.name, candidates)
+                    },
+                    Some(params) if params != s.type_arguments.len() => {
+                        return self.error(
+                            format!("Type {} expects {} type arguments but was given {}", s.name, params, s.type_arguments.len()),
+                            idx
+                        )
                     }
-                    self.ir_stack.push(next_ir);
+                    _ => {}
+                }
+                for arg in s.type_arguments.iter(){
+                    self.check_type(arg, idx)?;
+                }
+                Ok(())
+            },
+            TypeSignature::Function(f) => {
+                for param in f.parameters.iter(){
+                    self.check_type(&param.sig, idx)?;
+                }
+                self.check_type(&f.return_type_signature, idx)
+            },
+            TypeSignature::Primitive(PrimitiveType::None) => self.error("Unknown type".to_string(), idx),
+            TypeSignature::Dyn(interface) if !self.interfaces.contains_key(interface) => {
+                self.error(format!("Unknown interface {}", interface), idx)
+            },
+            TypeSignature::Param(param) => {
+                for bound in param.bounds.iter(){
+                    if !self.interfaces.contains_key(bound){
+                        return self.error(format!("Unknown interface {} in the bounds of {}", bound, param.name), idx)
+                    }
+                }
+                Ok(())
+            },
+            _ => Ok(())
+        }
+    }
+
+    fn check(&mut self) -> Result<(),()>{
+        self.declare();
+        self.check_annotations()?;
+        let mut idx = 0;
+        while idx < self.ir_stack.len(){
+            idx = match self.ir_stack[idx].ins.clone(){
+                HIRInstruction::Fn(_) if self.is_extension(idx) => self.check_extension(idx)?,
+                HIRInstruction::Fn(_) => self.check_function(idx)?,
+                HIRInstruction::NativeFn(name) => self.check_native_function(idx, &name)?,
+                HIRInstruction::Struct(_) => self.check_struct(idx)?,
+                HIRInstruction::Interface(_) => self.check_interface(idx)?,
+                HIRInstruction::Impl(_) => self.check_impl(idx)?,
+                HIRInstruction::TypeAlias(name) => self.check_alias(idx, &name)?,
+                HIRInstruction::Const(name) => self.check_const(idx, &name)?,
+                HIRInstruction::Import(name) => self.check_import(idx, &name)?,
+                HIRInstruction::Newtype(_) => {
+                    let sig = self.ir_stack[idx].sig.clone();
+                    self.check_type(&sig, idx)?;
+                    idx + 1
+                },
+                HIRInstruction::Property(name, _) => {
+                    let next = self.check_binding(idx)?;
+                    let sig = self.ir_stack[idx].sig.clone();
+                    self.globals.insert(name, sig);
+                    next
                 },
-                _ => self.ir_stack.push(ir_clone)
+                _ => idx + 1,
+            };
+        }
+        self.evaluate_constants()?;
+        self.check_exports()
+    }
+
+    ///Record the type of the param or local declared at `idx`, which the uses of its symbol have.
+    pub(crate) fn declare_local(&mut self, idx: usize, sig: TypeSignature, mutable: bool){
+        if let Some(symbol) = self.ir_stack[idx].symbol{
+            self.locals.insert(symbol, (sig, mutable));
+        }
+    }
+
+    ///The type of the local referred to at `idx`, and whether it is mutable.
+    ///Names are resolved before they are checked, so every local is known by then.
+    pub(crate) fn local(&self, idx: usize) -> &(TypeSignature, bool){
+        self.ir_stack[idx]
+            .symbol
+            .and_then(|symbol| self.locals.get(&symbol))
+            .expect("A local is used before the declaration its name resolves to")
+    }
+
+    ///Check a property or local variable along with the expression that initializes it.
+    fn check_binding(&mut self, idx: usize) -> Result<usize, ()>{
+        let declared = self.ir_stack[idx].sig.clone();
+        let (found, next) = self.check_expr(idx + 1)?;
+        if declared == TypeSignature::Untyped{
+            if found.is_generic() || iteration::is_placeholder(&found){
+                return self.error("Could not infer the type of this declaration.".to_string(), idx)
             }
+            self.ir_stack[idx].sig = found;
+        }else{
+            self.check_type(&declared, idx)?;
+            self.cmp_types(&declared, &found, idx + 1)?;
         }
-        self.emit_notice("Halting".to_string(), NoticeLevel::Halt, BiPos::default()).expect("Failed to send a notice from the type checker.");
-        Ok(())
+        Ok(next)
     }
 
-    pub async fn start_checking(module_name: String, ir_rx: Receiver<Option<HIR>>, notice_tx: Sender<Option<Notice>>, typeck_tx: Sender<Option<HIR>>) -> Result<(), ()>{
-        let mut typeck = Self{
-            module_name,
-            ir_stack: Vec::new(),
-            ir_rx,
-            notice_tx,
-            typeck_tx
+    ///Check an assignment, whose target must be a mutable local or property, or a field of one.
+    fn check_assign(&mut self, idx: usize) -> Result<usize, ()>{
+        let (expected, value) = self.check_expr(idx + 1)?;
+        if !self.is_place(idx + 1){
+            return self.error("Only a local, a property or a field of one can be assigned to".to_string(), idx + 1)
+        }
+        if !self.is_mutable(idx + 1){
+            return self.error("Cannot assign to an immutable value".to_string(), idx + 1)
+        }
+        let (found, next) = self.check_expr(value)?;
+        self.cmp_types(&expected, &found, value)?;
+        Ok(next)
+    }
+
+    fn check_struct(&mut self, idx: usize) -> Result<usize, ()>{
+        let mut idx = idx + 1;
+        loop{
+            match &self.ir_stack[idx].ins{
+                HIRInstruction::EndStruct => return Ok(idx + 1),
+                HIRInstruction::StructField(_) => {
+                    let sig = self.ir_stack[idx].sig.clone();
+                    self.check_type(&sig, idx)?;
+                },
+                _ => {}
+            }
+            idx += 1;
+        }
+    }
+
+    ///Check the type a type alias stands for, and that no struct or newtype has the name of the alias.
+    fn check_alias(&mut self, idx: usize, name: &str) -> Result<usize, ()>{
+        if let Some(decl) = self.structs.get(name).map(|decl| decl.decl){
+            return self.declaration_error(
+                format!("Type alias {} has the name of a type that is already declared", name),
+                idx,
+                decl,
+                format!("{} is declared here", name)
+            )
+        }
+        let sig = self.ir_stack[idx].sig.clone();
+        self.check_type(&sig, idx)?;
+        Ok(idx + 1)
+    }
+
+    ///Check that a native function only passes values that have a C representation.
+    fn check_native_function(&mut self, idx: usize, name: &str) -> Result<usize, ()>{
+        let func = match &self.ir_stack[idx].sig{
+            TypeSignature::Function(func) => func.clone(),
+            _ => return self.error(format!("Expected native function {} to have a function signature.", name), idx),
+        };
+        for sig in func.parameters.iter().map(|param| &param.sig).chain(std::iter::once(&*func.return_type_signature)){
+            match sig{
+                TypeSignature::Primitive(_) => self.check_type(sig, idx)?,
+                _ => return self.error(format!("Native function {} cannot pass a value of type {} to C", name, sig), idx),
+            }
+        }
+        Ok(idx + 1)
+    }
+
+    fn check_function(&mut self, idx: usize) -> Result<usize, ()>{
+        let sig = self.ir_stack[idx].sig.clone();
+        self.check_type(&sig, idx)?;
+        let return_type = match &sig{
+            TypeSignature::Function(f) => Some((*f.return_type_signature).clone()),
+            _ => None,
         };
+        let outer_return_type = std::mem::replace(&mut self.return_type, return_type);
+        let ret = self.check_statements(idx + 1, HIRInstruction::EndFn);
+        self.return_type = outer_return_type;
+        ret
+    }
 
-        if typeck.check().is_err(){
-            return Ok(())
+    ///Check the statements of a function or block starting at `idx`, up to the `closing` instruction.
+    ///Returns the index after the closing instruction.
+    fn check_statements(&mut self, idx: usize, closing: HIRInstruction) -> Result<usize, ()>{
+        let mut idx = idx;
+        loop{
+            if idx >= self.ir_stack.len(){
+                return self.error("Block is never closed.".to_string(), idx - 1)
+            }
+            if self.ir_stack[idx].ins == closing{
+                return Ok(idx + 1)
+            }
+            idx = match self.ir_stack[idx].ins.clone(){
+                HIRInstruction::TypeParam(_) => {
+                    let sig = self.ir_stack[idx].sig.clone();
+                    self.check_type(&sig, idx)?;
+                    idx + 1
+                },
+                HIRInstruction::FnParam(_) => {
+                    let sig = self.ir_stack[idx].sig.clone();
+                    self.declare_local(idx, sig, false);
+                    idx + 1
+                },
+                //The default value of a param follows it
+                HIRInstruction::DefaultArg => {
+                    let expected = self.ir_stack[idx - 1].sig.clone();
+                    let (found, next) = self.check_expr(idx + 1)?;
+                    self.cmp_types(&expected, &found, idx + 1)?;
+                    next
+                },
+                HIRInstruction::SelfParam(receiver) => {
+                    let sig = self.ir_stack[idx].sig.clone();
+                    self.check_type(&sig, idx)?;
+                    self.declare_local(idx, sig, receiver == ReceiverKind::MutRef);
+                    idx + 1
+                },
+                HIRInstruction::LocalVar(_, mutable) | HIRInstruction::Property(_, mutable) => {
+                    let next = self.check_binding(idx)?;
+                    let sig = self.ir_stack[idx].sig.clone();
+                    self.declare_local(idx, sig, mutable);
+                    next
+                },
+                HIRInstruction::Fn(_) => self.check_function(idx)?,
+                HIRInstruction::Struct(_) => self.check_struct(idx)?,
+                HIRInstruction::Newtype(_) => {
+                    let sig = self.ir_stack[idx].sig.clone();
+                    self.check_type(&sig, idx)?;
+                    idx + 1
+                },
+                HIRInstruction::Interface(_) => self.check_interface(idx)?,
+                HIRInstruction::Impl(_) => self.check_impl(idx)?,
+                HIRInstruction::For(_) => self.check_for(idx)?,
+                HIRInstruction::If(has_else) => self.check_if(idx, has_else)?,
+                HIRInstruction::While => self.check_while(idx)?,
+                HIRInstruction::Loop => self.check_block(idx + 1, HIRInstruction::EndLoop)?,
+                HIRInstruction::Block => self.check_block(idx + 1, HIRInstruction::EndBlock)?,
+                HIRInstruction::Break | HIRInstruction::Continue => idx + 1,
+                HIRInstruction::Return(has_value) => self.check_return(idx, has_value)?,
+                HIRInstruction::Assign => self.check_assign(idx)?,
+                _ => self.check_expr(idx)?.1,
+            };
         }
+    }
 
-        for ir in typeck.ir_stack{
-            typeck.typeck_tx.send(Some(ir)).unwrap();
+    ///Check the expression starting at `idx`, filling in the signatures of its instructions.
+    ///Returns the type of the expression and the index of the instruction after it.
+    fn check_expr(&mut self, idx: usize) -> Result<(TypeSignature, usize), ()>{
+        if idx >= self.ir_stack.len(){
+            return self.error("Expected an expression.".to_string(), idx - 1)
         }
-        
-        
-        Ok(())
+        let sig = match self.ir_stack[idx].ins.clone(){
+            HIRInstruction::Integer(_) => TypeSignature::Primitive(PrimitiveType::Integer),
+            HIRInstruction::Float(_) => TypeSignature::Primitive(PrimitiveType::Float),
+            HIRInstruction::Bool(_) => TypeSignature::Primitive(PrimitiveType::Bool),
+            HIRInstruction::String(_) => TypeSignature::Primitive(PrimitiveType::String),
+            HIRInstruction::LocalRef(_) => self.local(idx).0.clone(),
+            HIRInstruction::GlobalRef(name) => {
+                self.check_deprecated_use(&name, idx);
+                match (self.globals.get(&name), self.structs.get(&name)){
+                    (Some(TypeSignature::Untyped) | None, Some(decl)) => TypeSignature::Function(decl.constructor(&name)),
+                    (Some(TypeSignature::Untyped) | None, None) => return self.error(format!("Unknown name {}", name), idx),
+                    (Some(sig), _) => sig.clone(),
+                }
+            },
+            HIRInstruction::Field(name) => return self.check_field(idx, &name),
+            HIRInstruction::Array(len) => return self.check_array(idx, len),
+            HIRInstruction::Index => return self.check_index(idx),
+            HIRInstruction::Slice(start, end) => return self.check_slice(idx, start, end),
+            HIRInstruction::Range(_) => return self.check_range(idx),
+            HIRInstruction::Try => return self.check_try(idx),
+            HIRInstruction::Binary(op) => return self.check_binary(idx, op),
+            HIRInstruction::Unary(op) => return self.check_unary(idx, op),
+            HIRInstruction::Call(argc) => return self.check_call(idx, argc),
+            HIRInstruction::MethodCall(name, argc) => return self.check_method_call(idx, &name, argc),
+            ins => return self.error(format!("Expected an expression but instead got {:?}", ins), idx),
+        };
+        self.ir_stack[idx].sig = sig.clone();
+        Ok((sig, idx + 1))
+    }
+
+    fn check_call(&mut self, idx: usize, argc: usize) -> Result<(TypeSignature, usize), ()>{
+        let (callee, next) = self.check_expr(idx + 1)?;
+        let func = match callee{
+            TypeSignature::Function(f) => f,
+            sig => return self.error(format!("Cannot call a value of type {}", sig), idx + 1),
+        };
+        let (instantiated, next) = self.check_args(idx, func, next, argc)?;
+        let ret = (*instantiated.return_type_signature).clone();
+        self.ir_stack[idx + 1].sig = TypeSignature::Function(instantiated);
+        self.ir_stack[idx].sig = ret.clone();
+        Ok((ret, next))
+    }
+
+    ///Check the `argc` arguments starting at `next` against the params of `func`, the function called by the call at `idx`.
+    ///Positional arguments go to the params in order, with a vararg param taking every one left over, and named arguments
+    ///go to the param with their name. Params that are left out must have a default value.
+    ///Returns the signature of `func` with any type arguments inferred, and the index after the last argument.
+    fn check_args(&mut self, idx: usize, func: FunctionSignature, next: usize, argc: usize) -> Result<(FunctionSignature, usize), ()>{
+        let vararg = if func.variadic{ func.parameters.len().checked_sub(1) }else{ None };
+        let mut next = next;
+        //The param each argument is given to, along with the index and type of the argument
+        let mut args = Vec::<(usize, usize, TypeSignature)>::new();
+        let mut positional = 0;
+        for _ in 0 .. argc{
+            let param = match self.ir_stack[next].ins.clone(){
+                HIRInstruction::NamedArg(name) => {
+                    let param = self.named_param(&func, &name, next)?;
+                    if args.iter().any(|(given, _, _)| *given == param){
+                        return self.error(format!("Parameter {} is given more than once in this call", name), next)
+                    }
+                    next += 1;
+                    param
+                },
+                _ if positional < args.len() => {
+                    return self.error("Positional arguments must come before the named arguments of a call".to_string(), next)
+                },
+                _ => {
+                    positional += 1;
+                    vararg.map_or(positional - 1, |vararg| vararg.min(positional - 1))
+                },
+            };
+            let (arg, arg_next) = self.check_expr(next)?;
+            args.push((param, next, arg));
+            next = arg_next;
+        }
+        if vararg.is_none() && positional > func.parameters.len(){
+            return self.error(
+                format!("Expected {} arguments but instead got {}", func.parameters.len(), argc),
+                idx
+            )
+        }
+        let missing = func.parameters
+            .iter()
+            .enumerate()
+            .find(|(i, param)| !param.default && Some(*i) != vararg && !args.iter().any(|(given, _, _)| given == i));
+        if let Some((_, param)) = missing{
+            return self.error(format!("Missing an argument for parameter {}, which has no default value", param.name), idx)
+        }
+        let instantiated = if func.type_parameters.is_empty(){
+            func
+        }else{
+            self.infer_type_arguments(&func, &args, idx)?
+        };
+        for (param, arg_idx, arg) in args.iter(){
+            self.cmp_types(&instantiated.parameters[*param].sig, arg, *arg_idx)?;
+        }
+        Ok((instantiated, next))
+    }
+
+    ///The index of the param called `name` that the named argument at `idx` is given to.
+    fn named_param(&mut self, func: &FunctionSignature, name: &str, idx: usize) -> Result<usize, ()>{
+        match func.parameters.iter().position(|param| param.name == name){
+            Some(param) if func.variadic && param + 1 == func.parameters.len() => {
+                self.error(format!("The vararg parameter {} cannot be given by name", name), idx)
+            },
+            Some(param) => Ok(param),
+            None => self.error(format!("The called function has no parameter named {}", name), idx),
+        }
+    }
+
+    ///Work out the type arguments of a call to the generic function `func` from the types of the arguments it is given,
+    ///and return the signature of the function with those type arguments substituted in.
+    ///An array given for a list param is matched against it as a list of the same elements, as `cmp_types` accepts it.
+    fn infer_type_arguments(&mut self, func: &FunctionSignature, args: &[(usize, usize, TypeSignature)], idx: usize) -> Result<FunctionSignature, ()>{
+        let mut substitutions = HashMap::new();
+        for (param, arg_idx, arg) in args.iter(){
+            let param = &func.parameters[*param].sig;
+            let unified = match (param, arg){
+                (TypeSignature::List(element), TypeSignature::Array(found, _)) => element.unify(found, &mut substitutions),
+                _ => param.unify(arg, &mut substitutions),
+            };
+            if !unified{
+                let expected = param.substitute(&substitutions);
+                return self.error(
+                    format!("Expected an argument of type {} but instead got {}", expected, arg),
+                    *arg_idx
+                )
+            }
+        }
+        for param in func.type_parameters.iter(){
+            let argument = match substitutions.get(&param.name){
+                Some(argument) => argument.clone(),
+                None => return self.error(
+                    format!("Could not infer the type parameter {} of this call.", param.name),
+                    idx
+                ),
+            };
+            for bound in param.bounds.iter(){
+                if !self.implements(&argument, bound){
+                    return self.error(
+                        format!("Type {} does not implement interface {} required by the type parameter {}", argument, bound, param.name),
+                        idx
+                    )
+                }
+            }
+        }
+        match TypeSignature::Function(func.clone()).substitute(&substitutions){
+            TypeSignature::Function(f) => Ok(f),
+            _ => unreachable!(),
+        }
+    }
+
+    ///A type checker for a module importing the modules whose interfaces are in `imports`.
+    pub fn new(notice_tx: Sender<Option<Notice>>, imports: HashMap<String, ModuleInterface>) -> Self{
+        Self{
+            module_name: String::new(),
+            ir_stack: Vec::new(),
+            notice_tx,
+            globals: HashMap::new(),
+            mutable_globals: HashSet::new(),
+            structs: HashMap::new(),
+            locals: HashMap::new(),
+            interfaces: HashMap::new(),
+            implementations: HashMap::new(),
+            methods: HashMap::new(),
+            extensions: Vec::new(),
+            current_impl: None,
+            deprecated: HashMap::new(),
+            return_type: None,
+            imports,
+        }
+    }
+}
+
+impl HirMutVisitor for TypeckVM{
+    ///Check the module, giving each of its instructions the type it was inferred to have.
+    fn visit_module_mut(&mut self, module: &mut Module) -> Result<(), ()>{
+        self.module_name = module.name.clone();
+        let instructions = std::mem::take(&mut module.instructions);
+        let signatures = std::mem::take(&mut module.signatures);
+        let positions = std::mem::take(&mut module.positions);
+        let symbols = std::mem::take(&mut module.symbols);
+        self.ir_stack = instructions
+            .into_iter()
+            .zip(signatures)
+            .zip(positions)
+            .zip(symbols.into_iter().chain(std::iter::repeat(None)))
+            .map(|(((ins, sig), pos), symbol)| HIR{ pos, sig, ins, symbol })
+            .collect();
+        let ret = self.check();
+        for ir in std::mem::take(&mut self.ir_stack){
+            module.push_ir(ir);
+        }
+        ret
     }
-}
\ No newline at end of file
+}
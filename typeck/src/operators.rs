@@ -0,0 +1,59 @@
+use crate::TypeckVM;
+use ir::{
+    hir::{BinaryOp, UnaryOp},
+    type_signature::{PrimitiveType, TypeSignature},
+};
+
+///The type of `lhs op rhs`, if the operator applies to operands of those types.
+///Arithmetic takes two ints or two floats, `+` also joins two strings, and comparisons take two ints, floats
+///or strings. Bools can only be compared for equality, and combined with `&&` and `||`.
+fn binary_type(op: BinaryOp, lhs: &TypeSignature, rhs: &TypeSignature) -> Option<TypeSignature>{
+    let primitive = match (lhs, rhs){
+        (TypeSignature::Primitive(lhs), TypeSignature::Primitive(rhs)) if lhs == rhs => lhs.clone(),
+        _ => return None,
+    };
+    let applies = match op{
+        BinaryOp::Add => matches!(primitive, PrimitiveType::Integer | PrimitiveType::Float | PrimitiveType::String),
+        BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem => matches!(primitive, PrimitiveType::Integer | PrimitiveType::Float),
+        BinaryOp::Eq | BinaryOp::Ne => matches!(primitive, PrimitiveType::Integer | PrimitiveType::Float | PrimitiveType::String | PrimitiveType::Bool),
+        BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => matches!(primitive, PrimitiveType::Integer | PrimitiveType::Float | PrimitiveType::String),
+        BinaryOp::And | BinaryOp::Or => primitive == PrimitiveType::Bool,
+    };
+    if !applies{
+        return None
+    }
+    if op.is_comparison(){
+        Some(TypeSignature::Primitive(PrimitiveType::Bool))
+    }else{
+        Some(TypeSignature::Primitive(primitive))
+    }
+}
+
+impl TypeckVM{
+    pub(crate) fn check_binary(&mut self, idx: usize, op: BinaryOp) -> Result<(TypeSignature, usize), ()>{
+        let (lhs, next) = self.check_expr(idx + 1)?;
+        let (rhs, next) = self.check_expr(next)?;
+        let sig = match binary_type(op, &lhs, &rhs){
+            Some(sig) => sig,
+            None => return self.error(format!("Operator {} cannot be applied to {} and {}", op, lhs, rhs), idx),
+        };
+        self.ir_stack[idx].sig = sig.clone();
+        Ok((sig, next))
+    }
+
+    ///Check a negation, which takes an int or a float, or a logical not, which takes a bool.
+    pub(crate) fn check_unary(&mut self, idx: usize, op: UnaryOp) -> Result<(TypeSignature, usize), ()>{
+        let (operand, next) = self.check_expr(idx + 1)?;
+        let applies = matches!(
+            (op, &operand),
+            (UnaryOp::Neg, TypeSignature::Primitive(PrimitiveType::Integer))
+            | (UnaryOp::Neg, TypeSignature::Primitive(PrimitiveType::Float))
+            | (UnaryOp::Not, TypeSignature::Primitive(PrimitiveType::Bool))
+        );
+        if !applies{
+            return self.error(format!("Operator {} cannot be applied to {}", op, operand), idx)
+        }
+        self.ir_stack[idx].sig = operand.clone();
+        Ok((operand, next))
+    }
+}
@@ -0,0 +1,496 @@
+use ir::{
+    hir::{expression_end, HIRInstruction, Module},
+    interface::{HiddenDecl, ModuleInterface},
+    pass::HirMutVisitor,
+    symbols::{ScopeId, ScopeKind, Symbol, SymbolId, SymbolKind, SymbolTable},
+};
+use notices::{Notice, NoticeLevel};
+use core::pos::BiPos;
+use std::collections::HashMap;
+use std::sync::mpsc::Sender;
+
+use crate::{iteration, results};
+
+///The keywords that can start a statement or expression, which a name that is undefined may be a misspelling of.
+const KEYWORDS: [&str; 10] = ["let", "if", "while", "for", "loop", "break", "continue", "return", "true", "false"];
+
+///Resolves the names of a module, building its symbol table.
+///Each declaration gets a symbol in the scope it is declared in, and each `LocalRef` and `GlobalRef` the symbol
+///it refers to. Names that are undefined or declared twice in the same scope are errors, and locals shadowing
+///the locals of an enclosing block get a warning.
+pub struct Resolver{
+    notice_tx: Sender<Option<Notice>>,
+    ///The interfaces of the modules the module may import, by name
+    imports: HashMap<String, ModuleInterface>,
+    module_name: String,
+    positions: Vec<BiPos>,
+    table: SymbolTable,
+    symbols: Vec<Option<SymbolId>>,
+    ///The scopes around the instruction being resolved, innermost last
+    scopes: Vec<ScopeId>,
+    ///The locals declared at the index in the first field, which is where the expression initializing them ends,
+    ///along with the index of their declaration and the scope they are declared in
+    pending: Vec<(usize, usize, String, ScopeId)>,
+    ///The top level items of the imported modules that they don't export, along with the module declaring each
+    hidden: HashMap<String, (String, HiddenDecl)>,
+    failed: bool,
+}
+
+///Whether the function declared at `idx` is an extension function, which has a receiver without being in an impl block.
+fn is_extension(instructions: &[HIRInstruction], idx: usize) -> bool{
+    instructions[idx + 1 ..]
+        .iter()
+        .find(|ins| !matches!(ins, HIRInstruction::TypeParam(_)))
+        .is_some_and(|ins| matches!(ins, HIRInstruction::SelfParam(_)))
+}
+
+impl Resolver{
+    pub fn new(notice_tx: Sender<Option<Notice>>, imports: HashMap<String, ModuleInterface>) -> Self{
+        Self{
+            notice_tx,
+            imports,
+            module_name: String::new(),
+            positions: Vec::new(),
+            table: SymbolTable::new(),
+            symbols: Vec::new(),
+            scopes: Vec::new(),
+            pending: Vec::new(),
+            hidden: HashMap::new(),
+            failed: false,
+        }
+    }
+
+    fn notice(&mut self, msg: String, level: NoticeLevel, pos: BiPos){
+        self.notice_with_help(msg, level, pos, None)
+    }
+
+    fn notice_with_help(&mut self, msg: String, level: NoticeLevel, pos: BiPos, help: Option<String>){
+        self.notice_in(self.module_name.clone(), msg, level, pos, help)
+    }
+
+    ///Report `msg` at `pos` in the source of the module `file`.
+    fn notice_in(&mut self, file: String, msg: String, level: NoticeLevel, pos: BiPos, help: Option<String>){
+        if level == NoticeLevel::Error{
+            self.failed = true;
+        }
+        let notice = Notice{
+            from: "Name resolution".to_string(),
+            msg,
+            pos,
+            file,
+            level,
+            help,
+        };
+        self.notice_tx.send(Some(notice)).expect("Failed to send a notice through the notice channel.");
+    }
+
+    fn scope(&self) -> ScopeId{
+        *self.scopes.last().unwrap()
+    }
+
+    fn push_scope(&mut self, kind: ScopeKind){
+        let scope = self.table.push_scope(kind, Some(self.scope()));
+        self.scopes.push(scope);
+    }
+
+    ///Report `msg` about the instruction at `idx` along with `note` about the symbol `other`, which is where the problem comes from.
+    fn report(&mut self, level: NoticeLevel, msg: String, idx: usize, other: SymbolId, note: String){
+        self.notice(msg, level, self.positions[idx]);
+        let pos = self.table.symbol(other).pos;
+        if pos.start != BiPos::default().start{
+            self.notice(note, NoticeLevel::Notice, pos);
+        }
+    }
+
+    ///Declare the symbol `name` of the declaration at `idx` in the current scope.
+    fn declare(&mut self, idx: usize, name: &str, kind: SymbolKind){
+        self.declare_in(self.scope(), idx, name, kind)
+    }
+
+    ///Declare the symbol `name` of the declaration at `idx` in `scope`.
+    ///Locals shadowing a local of an enclosing block of their function get a warning.
+    fn declare_in(&mut self, scope: ScopeId, idx: usize, name: &str, kind: SymbolKind){
+        if kind.is_local(){
+            let outer = self.table
+                .scope(scope)
+                .parent
+                .and_then(|parent| self.local(parent, name));
+            if let Some(outer) = outer{
+                let outer_kind = self.table.symbol(outer).kind;
+                self.report(
+                    NoticeLevel::Warning,
+                    format!("The {} {} shadows the {} {} of an enclosing block", kind, name, outer_kind, name),
+                    idx,
+                    outer,
+                    format!("The shadowed {} is declared here", name)
+                );
+            }
+        }
+        let symbol = Symbol{ name: name.to_string(), kind, pos: self.positions[idx], scope };
+        match self.table.declare(symbol){
+            Ok(id) => self.symbols[idx] = Some(id),
+            Err(first) => {
+                self.report(
+                    NoticeLevel::Error,
+                    format!("{} is declared more than once in the same scope", name),
+                    idx,
+                    first,
+                    format!("{} is first declared here", name)
+                );
+                let symbol = Symbol{ name: name.to_string(), kind, pos: self.positions[idx], scope };
+                self.symbols[idx] = Some(self.table.add(symbol));
+            },
+        }
+    }
+
+    ///Give the declaration at `idx` a symbol that can't be looked up by name.
+    fn add(&mut self, idx: usize, name: &str, kind: SymbolKind){
+        let symbol = Symbol{ name: name.to_string(), kind, pos: self.positions[idx], scope: self.scope() };
+        self.symbols[idx] = Some(self.table.add(symbol));
+    }
+
+    ///The param or local `name` seen from `scope`, which can only be declared by the function around it.
+    fn local(&self, scope: ScopeId, name: &str) -> Option<SymbolId>{
+        let mut in_function = true;
+        self.table
+            .enclosing(scope)
+            .take_while(|scope| {
+                let more = in_function;
+                in_function = self.table.scope(*scope).kind != ScopeKind::Function;
+                more
+            })
+            .filter_map(|scope| self.table.lookup_in(scope, name))
+            .find(|id| self.table.symbol(*id).kind.is_local())
+    }
+
+    ///Report that `name`, used at `idx`, is undefined, suggesting the visible name or keyword closest to it.
+    ///Only the params and locals of the current function are candidates when `locals_only` holds.
+    fn undefined(&mut self, msg: String, idx: usize, name: &str, locals_only: bool){
+        let mut in_function = true;
+        let mut candidates = Vec::new();
+        for scope in self.table.enclosing(self.scope()){
+            let scope = self.table.scope(scope);
+            for symbol in scope.symbols.iter().map(|id| self.table.symbol(*id)){
+                let visible = if symbol.kind.is_local(){
+                    in_function
+                }else{
+                    !locals_only && scope.kind != ScopeKind::Item
+                };
+                if visible{
+                    candidates.push(symbol.name.as_str());
+                }
+            }
+            in_function = in_function && scope.kind != ScopeKind::Function;
+        }
+        let help = match notices::closest_name(name, candidates){
+            Some(candidate) => Some(format!("Did you mean `{}`?", candidate)),
+            None if !locals_only => notices::closest_name(name, KEYWORDS.iter().copied())
+                .map(|keyword| format!("Did you mean the keyword `{}`?", keyword)),
+            None => None,
+        };
+        self.notice_with_help(msg, NoticeLevel::Error, self.positions[idx], help);
+    }
+
+    ///Report that `name`, used at `idx`, is declared by an imported module that doesn't export it, if it is.
+    fn hidden(&mut self, idx: usize, name: &str) -> bool{
+        let (module, decl) = match self.hidden.get(name){
+            Some((module, decl)) => (module.clone(), decl.clone()),
+            None => return false,
+        };
+        let msg = format!("{} is {} in module {} and can only be used from within it", name, decl.visibility, module);
+        self.notice(msg, NoticeLevel::Error, self.positions[idx]);
+        let note = format!("{} is declared {} here", name, decl.visibility);
+        self.notice_in(module, note, NoticeLevel::Notice, decl.pos, None);
+        true
+    }
+
+    ///Declare the names the language provides, and those exported by the imported modules.
+    fn declare_builtins_and_imports(&mut self, module: &Module){
+        let builtins = self.table.push_scope(ScopeKind::Builtins, None);
+        for (name, _) in iteration::builtin_globals().into_iter().chain(results::builtin_globals()){
+            let _ = self.table.declare(Symbol{ name, kind: SymbolKind::Builtin, pos: BiPos::default(), scope: builtins });
+        }
+        let imports = self.table.push_scope(ScopeKind::Imports, Some(builtins));
+        self.scopes = vec![imports];
+        for (idx, ins) in module.instructions.iter().enumerate(){
+            let name = match ins{
+                HIRInstruction::Import(name) => name,
+                _ => continue,
+            };
+            self.add(idx, name, SymbolKind::Module);
+            //A missing interface is reported by the type checker
+            let interface = match self.imports.get(name){
+                Some(interface) => interface,
+                None => continue,
+            };
+            let exports = interface.functions
+                .iter()
+                .map(|function| (function.name.clone(), SymbolKind::Function))
+                .chain(interface.constants.iter().map(|constant| (constant.name.clone(), SymbolKind::Const)))
+                .chain(interface.structs.iter().map(|layout| (layout.name.clone(), SymbolKind::Type)))
+                .collect::<Vec<(String, SymbolKind)>>();
+            for decl in interface.hidden.iter(){
+                self.hidden.insert(decl.name.clone(), (interface.name.clone(), decl.clone()));
+            }
+            for (export, kind) in exports{
+                let symbol = Symbol{ name: export.clone(), kind, pos: self.positions[idx], scope: imports };
+                if let Err(first) = self.table.declare(symbol){
+                    self.report(
+                        NoticeLevel::Error,
+                        format!("{} is exported by more than one of the imported modules", export),
+                        idx,
+                        first,
+                        format!("{} is also imported here", export)
+                    );
+                }
+            }
+        }
+    }
+
+    ///Declare everything at the top level of the module, which can be used before its declaration.
+    fn declare_top_level(&mut self, module: &Module){
+        let mut depth = 0;
+        for (idx, ins) in module.instructions.iter().enumerate(){
+            match ins{
+                HIRInstruction::Fn(name) => {
+                    if depth == 0 && !is_extension(&module.instructions, idx){
+                        self.declare(idx, name, SymbolKind::Function);
+                    }
+                    depth += 1;
+                },
+                HIRInstruction::NativeFn(name) if depth == 0 => self.declare(idx, name, SymbolKind::Function),
+                HIRInstruction::Property(name, _) if depth == 0 => self.declare(idx, name, SymbolKind::Global),
+                HIRInstruction::Const(name) if depth == 0 => self.declare(idx, name, SymbolKind::Const),
+                HIRInstruction::Struct(name)
+                | HIRInstruction::Newtype(name)
+                | HIRInstruction::TypeAlias(name) if depth == 0 => self.declare(idx, name, SymbolKind::Type),
+                HIRInstruction::Interface(name) => {
+                    if depth == 0{
+                        self.declare(idx, name, SymbolKind::Type);
+                    }
+                    depth += 1;
+                },
+                HIRInstruction::Impl(_) => depth += 1,
+                HIRInstruction::EndFn | HIRInstruction::EndInterface | HIRInstruction::EndImpl => depth -= 1,
+                _ => {}
+            }
+        }
+    }
+
+    ///Declare the locals whose initializing expression ends at `idx`.
+    fn declare_pending(&mut self, idx: usize){
+        while let Some(pos) = self.pending.iter().position(|(end, _, _, _)| *end == idx){
+            let (_, decl, name, scope) = self.pending.remove(pos);
+            self.declare_in(scope, decl, &name, SymbolKind::Local);
+        }
+    }
+
+    fn resolve(&mut self, module: &Module){
+        let instructions = &module.instructions;
+        for (idx, ins) in instructions.iter().enumerate(){
+            self.declare_pending(idx);
+            let declared = self.symbols[idx].is_some();
+            match ins{
+                HIRInstruction::Module(name) => self.add(idx, name, SymbolKind::Module),
+                HIRInstruction::Fn(name) => {
+                    if is_extension(instructions, idx) && self.table.scope(self.scope()).kind == ScopeKind::Module{
+                        //Extension functions are only found through the method calls on their receiver
+                        self.add(idx, name, SymbolKind::Function);
+                    }else if !declared{
+                        self.declare(idx, name, SymbolKind::Function);
+                    }
+                    self.push_scope(ScopeKind::Function);
+                },
+                HIRInstruction::NativeFn(name) | HIRInstruction::InterfaceMethod(name, _) if !declared => {
+                    self.declare(idx, name, SymbolKind::Function)
+                },
+                HIRInstruction::Struct(name) | HIRInstruction::Interface(name) => {
+                    if !declared{
+                        self.declare(idx, name, SymbolKind::Type);
+                    }
+                    self.push_scope(ScopeKind::Item);
+                },
+                HIRInstruction::Newtype(name) | HIRInstruction::TypeAlias(name) if !declared => {
+                    self.declare(idx, name, SymbolKind::Type)
+                },
+                HIRInstruction::Impl(_) => self.push_scope(ScopeKind::Item),
+                HIRInstruction::StructField(name) => self.declare(idx, name, SymbolKind::Field),
+                HIRInstruction::TypeParam(name) => self.declare(idx, name, SymbolKind::TypeParam),
+                HIRInstruction::FnParam(name) => self.declare(idx, name, SymbolKind::Param),
+                HIRInstruction::SelfParam(_) => self.declare(idx, "self", SymbolKind::Param),
+                //A local can't be used by the expression initializing it
+                HIRInstruction::LocalVar(name, _) | HIRInstruction::Property(name, _) if !declared => {
+                    self.pending.push((expression_end(instructions, idx + 1), idx, name.clone(), self.scope()))
+                },
+                //The variable of a for loop gets a scope of its own, around the one of the body
+                HIRInstruction::For(name) => {
+                    self.push_scope(ScopeKind::Block);
+                    self.pending.push((expression_end(instructions, idx + 1), idx, name.clone(), self.scope()));
+                    self.push_scope(ScopeKind::Block);
+                },
+                HIRInstruction::If(_) | HIRInstruction::While | HIRInstruction::Loop | HIRInstruction::Block => {
+                    self.push_scope(ScopeKind::Block)
+                },
+                HIRInstruction::Else => {
+                    self.scopes.pop();
+                    self.push_scope(ScopeKind::Block);
+                },
+                HIRInstruction::EndFor => {
+                    self.scopes.truncate(self.scopes.len() - 2);
+                },
+                HIRInstruction::EndFn
+                | HIRInstruction::EndStruct
+                | HIRInstruction::EndInterface
+                | HIRInstruction::EndImpl
+                | HIRInstruction::EndIf
+                | HIRInstruction::EndWhile
+                | HIRInstruction::EndLoop
+                | HIRInstruction::EndBlock => {
+                    self.scopes.pop();
+                },
+                HIRInstruction::LocalRef(name) => match self.local(self.scope(), name){
+                    Some(id) => self.symbols[idx] = Some(id),
+                    None => self.undefined(format!("Undefined local variable {}", name), idx, name, true),
+                },
+                HIRInstruction::GlobalRef(name) => {
+                    //The members of a struct, interface or impl block are only reached through a value or type
+                    let found = self.table.lookup(self.scope(), name, |symbol| {
+                        !symbol.kind.is_local() && self.table.scope(symbol.scope).kind != ScopeKind::Item
+                    });
+                    match found{
+                        Some(id) => self.symbols[idx] = Some(id),
+                        None if self.hidden(idx, name) => {},
+                        None => self.undefined(format!("Undefined name {}", name), idx, name, false),
+                    }
+                },
+                _ => {}
+            }
+        }
+    }
+}
+
+impl HirMutVisitor for Resolver{
+    ///Resolve the names of the module, replacing its symbols and symbol table.
+    fn visit_module_mut(&mut self, module: &mut Module) -> Result<(), ()>{
+        self.module_name = module.name.clone();
+        self.positions = module.positions.clone();
+        self.table = SymbolTable::new();
+        self.symbols = vec![None; module.instructions.len()];
+        self.pending.clear();
+        self.hidden.clear();
+        self.failed = false;
+        self.declare_builtins_and_imports(module);
+        self.push_scope(ScopeKind::Module);
+        self.declare_top_level(module);
+        self.resolve(module);
+        module.symbols = std::mem::take(&mut self.symbols);
+        module.symbol_table = std::mem::take(&mut self.table);
+        if self.failed{
+            Err(())
+        }else{
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use ir::text;
+    use std::sync::mpsc::channel;
+
+    ///Resolve the HIR `text`, returning whether that succeeded, the module and the notices reported.
+    fn resolve(text: &str) -> (Result<(), ()>, Module, Vec<Notice>){
+        let mut module = text::parse_hir(text).unwrap();
+        let (notice_tx, notice_rx) = channel();
+        let ret = Resolver::new(notice_tx, HashMap::new()).visit_module_mut(&mut module);
+        (ret, module, notice_rx.try_iter().flatten().collect())
+    }
+
+    #[test]
+    fn resolves_names_to_their_declarations(){
+        let (ret, module, notices) = resolve(r#"hir test
+Module test
+    Fn f : fun(count: Int) -> Int @ 1:2 1:4
+        FnParam count : Int @ 1:8 1:12
+        LocalVar total : untyped @ 2:10 2:14
+        LocalRef count : untyped @ 2:18 2:22
+        Call 1 : untyped @ 3:6 3:13
+        GlobalRef f : untyped @ 3:6 3:6
+        LocalRef total : untyped @ 3:8 3:12
+    EndFn @ 1:2 1:4
+EndModule
+"#);
+        assert_eq!(ret, Ok(()));
+        assert!(notices.is_empty());
+        let symbols = &module.symbols;
+        assert_eq!(symbols[4], symbols[2]);
+        assert_eq!(symbols[6], symbols[1]);
+        assert_eq!(symbols[7], symbols[3]);
+        let total = module.symbol_table.symbol(symbols[7].unwrap());
+        assert_eq!(total.name, "total");
+        assert!(total.kind.is_local());
+    }
+
+    #[test]
+    fn reports_names_declared_twice(){
+        let (ret, _, notices) = resolve(r#"hir test
+Module test
+    Fn f : fun(count: Int) -> Int @ 1:2 1:4
+        FnParam count : Int @ 1:8 1:12
+        LocalVar total : untyped @ 2:10 2:14
+        LocalRef count : untyped @ 2:18 2:22
+        LocalVar total : untyped @ 3:10 3:14
+        Integer 2 : Int @ 3:18 3:18
+        LocalRef total : untyped @ 4:6 4:10
+    EndFn @ 1:2 1:4
+EndModule
+"#);
+        assert_eq!(ret, Err(()));
+        let notices = notices.iter().map(|n| (n.msg.as_str(), n.pos.start.0)).collect::<Vec<_>>();
+        assert_eq!(notices, vec![("total is declared more than once in the same scope", 3), ("total is first declared here", 2)]);
+    }
+
+    #[test]
+    fn warns_about_locals_shadowing_those_of_an_enclosing_block(){
+        let (ret, module, notices) = resolve(r#"hir test
+Module test
+    Fn f : fun() -> Int @ 1:2 1:4
+        LocalVar total : untyped @ 2:10 2:14
+        Integer 1 : Int @ 2:18 2:18
+        Block @ 3:6 3:6
+            LocalVar total : untyped @ 4:14 4:18
+            Integer 2 : Int @ 4:22 4:22
+        EndBlock @ 5:6 5:6
+        LocalRef total : untyped @ 6:6 6:10
+    EndFn @ 1:2 1:4
+EndModule
+"#);
+        assert_eq!(ret, Ok(()));
+        assert_eq!(notices[0].msg, "The local variable total shadows the local variable total of an enclosing block");
+        assert_eq!(notices[0].level, NoticeLevel::Warning);
+        assert_eq!(notices[0].pos.start.0, 4);
+        //The local of the block is out of scope once it ends
+        assert_eq!(module.symbols[8], module.symbols[2]);
+    }
+
+    #[test]
+    fn suggests_the_closest_name_or_keyword(){
+        let (ret, _, notices) = resolve(r#"hir test
+Module test
+    Fn f : fun(count: Int) -> Int @ 1:2 1:4
+        FnParam count : Int @ 1:8 1:12
+        LocalRef cont : untyped @ 2:6 2:9
+        GlobalRef whle : untyped @ 3:6 3:9
+    EndFn @ 1:2 1:4
+EndModule
+"#);
+        assert_eq!(ret, Err(()));
+        let notices = notices.iter().map(|n| (n.msg.as_str(), n.help.as_deref())).collect::<Vec<_>>();
+        let expected = vec![
+            ("Undefined local variable cont", Some("Did you mean `count`?")),
+            ("Undefined name whle", Some("Did you mean the keyword `while`?")),
+        ];
+        assert_eq!(notices, expected);
+    }
+}
@@ -0,0 +1,54 @@
+use crate::TypeckVM;
+use ir::type_signature::{CallingConvention, FunctionSignature, GenericParam, Parameter, TypeSignature};
+
+///The constructors of results, `Ok(value)` and `Err(error)`.
+///Each one leaves the other type of the result untyped, to be taken from where the result is used.
+pub(crate) fn builtin_globals() -> Vec<(String, TypeSignature)>{
+    let constructor = |param: &str, result: fn(Box<TypeSignature>) -> TypeSignature| {
+        let param = GenericParam{
+            name: param.to_string(),
+            bounds: Vec::new(),
+        };
+        TypeSignature::Function(FunctionSignature{
+            type_parameters: vec![param.clone()],
+            parameters: vec![Parameter::new("value", TypeSignature::Param(param.clone()))],
+            variadic: false,
+            return_type_signature: Box::new(result(Box::new(TypeSignature::Param(param)))),
+            calling_convention: CallingConvention::Beagle,
+        })
+    };
+    vec![
+        ("Ok".to_string(), constructor("T", |value| TypeSignature::Result(value, Box::new(TypeSignature::Untyped)))),
+        ("Err".to_string(), constructor("E", |error| TypeSignature::Result(Box::new(TypeSignature::Untyped), error))),
+    ]
+}
+
+impl TypeckVM{
+    ///Check `result?`, which gives the value of an `Ok` result and returns an `Err` one from the enclosing function.
+    ///The enclosing function has to return a result with the same error type.
+    pub(crate) fn check_try(&mut self, idx: usize) -> Result<(TypeSignature, usize), ()>{
+        let (found, next) = self.check_expr(idx + 1)?;
+        let (value, error) = match found{
+            TypeSignature::Result(value, error) => (*value, *error),
+            sig => return self.error(format!("The ? operator can only be applied to a Result, but this has type {}", sig), idx + 1),
+        };
+        match self.return_type.clone(){
+            Some(TypeSignature::Result(_, expected)) if *expected == error || error == TypeSignature::Untyped => {},
+            Some(TypeSignature::Result(_, expected)) => {
+                return self.error(
+                    format!("The ? operator would return an error of type {} from a function whose errors are of type {}", error, expected),
+                    idx
+                )
+            },
+            Some(sig) => {
+                return self.error(
+                    format!("The ? operator can only be used in a function that returns a Result, but this one returns {}", sig),
+                    idx
+                )
+            },
+            None => return self.error("The ? operator can only be used inside a function".to_string(), idx),
+        }
+        self.ir_stack[idx].sig = value.clone();
+        Ok((value, next))
+    }
+}
@@ -0,0 +1,131 @@
+use crate::TypeckVM;
+use ir::{
+    hir::{HIRInstruction, Visibility},
+    type_signature::TypeSignature,
+};
+use notices::NoticeLevel;
+use core::pos::BiPos;
+
+impl TypeckVM{
+    ///The visibility the marker before the declaration at `idx` gives it.
+    pub(crate) fn visibility(&self, idx: usize) -> Visibility{
+        match idx.checked_sub(1).map(|prev| &self.ir_stack[prev].ins){
+            Some(HIRInstruction::Visibility(visibility)) => *visibility,
+            _ => Visibility::default(),
+        }
+    }
+
+    ///Report an error at `idx` about the declaration at `decl`, along with a note pointing at the declaration.
+    pub(crate) fn declaration_error<T>(&mut self, msg: String, idx: usize, decl: usize, note: String) -> Result<T, ()>{
+        let pos = self.ir_stack[decl].pos;
+        let ret = self.error(msg, idx);
+        let _ = self.emit_notice(note, NoticeLevel::Notice, pos);
+        ret
+    }
+
+    ///Report an error at `idx` about a declaration of the imported module `module`,
+    ///along with a note pointing at the declaration in the source of that module.
+    fn imported_declaration_error<T>(&mut self, msg: String, idx: usize, module: String, pos: BiPos, note: String) -> Result<T, ()>{
+        let ret = self.error(msg, idx);
+        let _ = self.emit_notice_in(module, note, NoticeLevel::Notice, pos, None);
+        ret
+    }
+
+    ///Check that a private member `name` of `owner`, declared at `decl` or in the imported module `origin`,
+    ///is only used from the impl blocks of `owner`.
+    pub(crate) fn check_private_access(
+        &mut self,
+        owner: &str,
+        name: &str,
+        visibility: Visibility,
+        idx: usize,
+        decl: usize,
+        origin: Option<(String, BiPos)>
+    ) -> Result<(), ()>{
+        if visibility != Visibility::Private || self.current_impl.as_deref() == Some(owner){
+            return Ok(())
+        }
+        let msg = format!("{} of {} is private and can only be used from the impl blocks of {}", name, owner, owner);
+        let note = format!("{} is declared private here", name);
+        match origin{
+            Some((module, pos)) => self.imported_declaration_error(msg, idx, module, pos, note),
+            None => self.declaration_error(msg, idx, decl, note),
+        }
+    }
+
+    ///Check that the exported signature `sig` of the public declaration at `idx` only mentions public types.
+    fn check_exported_type(&mut self, sig: &TypeSignature, idx: usize) -> Result<(), ()>{
+        match sig{
+            TypeSignature::Struct(s) => {
+                if let Some((visibility, decl)) = self.structs.get(&s.name).map(|decl| (decl.visibility, decl.decl)){
+                    if visibility != Visibility::Public{
+                        return self.declaration_error(
+                            format!("Public declaration exposes the {} type {}", visibility, s.name),
+                            idx,
+                            decl,
+                            format!("{} is declared {} here", s.name, visibility)
+                        )
+                    }
+                }
+                for arg in s.type_arguments.iter(){
+                    self.check_exported_type(arg, idx)?;
+                }
+                Ok(())
+            },
+            TypeSignature::Function(f) => {
+                for param in f.parameters.iter(){
+                    self.check_exported_type(&param.sig, idx)?;
+                }
+                self.check_exported_type(&f.return_type_signature, idx)
+            },
+            TypeSignature::Array(element, _)
+            | TypeSignature::List(element)
+            | TypeSignature::Option(element)
+            | TypeSignature::Range(element) => self.check_exported_type(element, idx),
+            TypeSignature::Result(value, error) => {
+                self.check_exported_type(value, idx)?;
+                self.check_exported_type(error, idx)
+            },
+            _ => Ok(())
+        }
+    }
+
+    ///Check that nothing the module exports mentions a type that isn't exported with it.
+    ///The fields of public structs and the methods in impl blocks of public types are exported along with them.
+    pub(crate) fn check_exports(&mut self) -> Result<(), ()>{
+        //Whether the struct or impl block being walked belongs to a public type
+        let mut public_owner = false;
+        for idx in 0 .. self.ir_stack.len(){
+            let exported = self.visibility(idx) == Visibility::Public;
+            match &self.ir_stack[idx].ins{
+                HIRInstruction::Struct(_) => public_owner = exported,
+                HIRInstruction::Impl(_) => {
+                    public_owner = match &self.ir_stack[idx].sig{
+                        TypeSignature::Struct(s) => self.structs.get(&s.name).is_some_and(|decl| decl.visibility == Visibility::Public),
+                        _ => true,
+                    };
+                },
+                HIRInstruction::EndStruct | HIRInstruction::EndImpl => public_owner = false,
+                HIRInstruction::StructField(_) if exported && public_owner => {
+                    let sig = self.ir_stack[idx].sig.clone();
+                    self.check_exported_type(&sig, idx)?;
+                },
+                HIRInstruction::Fn(_)
+                | HIRInstruction::NativeFn(_)
+                | HIRInstruction::Property(_, _)
+                | HIRInstruction::Const(_)
+                | HIRInstruction::TypeAlias(_)
+                | HIRInstruction::Newtype(_) if exported => {
+                    let sig = self.ir_stack[idx].sig.clone();
+                    self.check_exported_type(&sig, idx)?;
+                    //An exported extension function exports the type it extends as well
+                    if let Some(receiver) = self.extension(idx).map(|extension| extension.receiver.clone()){
+                        self.check_exported_type(&receiver, idx)?;
+                    }
+                },
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}